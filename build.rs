@@ -0,0 +1,19 @@
+use std::env::var;
+
+fn main() {
+    println!("cargo:rerun-if-changed=build.rs");
+    println!("cargo:rustc-check-cfg=cfg(thumb_mode)");
+
+    // Detect whether the target uses Thumb mode on ARM, which affects the
+    // instruction encodings and registers the assembly code can use. Stable
+    // Rust doesn't expose the "thumb-mode" target feature, so also recognize
+    // Thumb targets by name.
+    let arch = var("CARGO_CFG_TARGET_ARCH").unwrap();
+    let target = var("TARGET").unwrap();
+    let features = var("CARGO_CFG_TARGET_FEATURE").unwrap_or_default();
+    if arch == "arm"
+        && (target.starts_with("thumb") || features.split(',').any(|f| f == "thumb-mode"))
+    {
+        println!("cargo:rustc-cfg=thumb_mode");
+    }
+}
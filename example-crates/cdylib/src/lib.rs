@@ -0,0 +1,32 @@
+use origin::thread;
+
+/// Create a thread with origin, and wait for it to finish.
+///
+/// This is exported for the host program to call after loading this library.
+#[no_mangle]
+pub extern "C" fn origin_example_run() -> i32 {
+    eprintln!("Hello from the library");
+
+    let thread = unsafe {
+        thread::create(
+            |_args| {
+                eprintln!("Hello from child thread");
+                thread::at_exit(Box::new(|| {
+                    eprintln!("Hello from child thread's `thread::at_exit` handler")
+                }));
+                None
+            },
+            &[],
+            thread::default_stack_size(),
+            thread::default_guard_size(),
+        )
+        .unwrap()
+    };
+
+    unsafe {
+        thread::join(thread);
+    }
+
+    eprintln!("Goodbye from the library");
+    0
+}
@@ -0,0 +1,25 @@
+//! A host program which loads the library with `dlopen`, the way a Python or
+//! C program would load an extension module.
+
+use std::ffi::{CStr, CString};
+
+fn main() {
+    eprintln!("Hello from host");
+
+    let exe = std::env::current_exe().unwrap();
+    let path = exe.with_file_name("libcdylib.so");
+    let path = CString::new(path.into_os_string().into_encoded_bytes()).unwrap();
+
+    unsafe {
+        let lib = libc::dlopen(path.as_ptr(), libc::RTLD_NOW | libc::RTLD_LOCAL);
+        if lib.is_null() {
+            panic!("dlopen: {:?}", CStr::from_ptr(libc::dlerror()));
+        }
+        let run = libc::dlsym(lib, c"origin_example_run".as_ptr());
+        assert!(!run.is_null());
+        let run = core::mem::transmute::<*mut libc::c_void, extern "C" fn() -> i32>(run);
+        assert_eq!(run(), 0);
+    }
+
+    eprintln!("Goodbye from host");
+}
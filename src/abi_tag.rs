@@ -0,0 +1,56 @@
+//! The `.note.ABI-tag` ELF note, for the "abi-tag" feature.
+//!
+//! C runtimes normally provide this note in their startup objects, which
+//! programs using "origin-start" don't link in. It records that the
+//! executable is for Linux, and the minimum Linux version it supports, for
+//! tools such as `file` and `ldconfig`.
+
+/// The minimum Linux version, which we take from glibc's ABI tag for each
+/// architecture.
+#[cfg(any(target_arch = "x86", target_arch = "x86_64", target_arch = "arm"))]
+macro_rules! abi_tag_version {
+    () => {
+        ".long 3, 2, 0"
+    };
+}
+
+/// The minimum Linux version, which we take from glibc's ABI tag for each
+/// architecture.
+#[cfg(target_arch = "aarch64")]
+macro_rules! abi_tag_version {
+    () => {
+        ".long 3, 7, 0"
+    };
+}
+
+/// The minimum Linux version, which we take from glibc's ABI tag for each
+/// architecture.
+#[cfg(target_arch = "riscv64")]
+macro_rules! abi_tag_version {
+    () => {
+        ".long 4, 15, 0"
+    };
+}
+
+/// Emit the note.
+///
+/// This is a macro, so that it can be expanded in the `program` module,
+/// rather than in its own module. With multiple codegen units, a module
+/// without any referenced symbols can end up in an object file which the
+/// linker doesn't link in, while `program` contains `entry`, which `_start`
+/// always references.
+macro_rules! abi_tag_note {
+    () => {
+        core::arch::global_asm!(
+            ".pushsection .note.ABI-tag, \"a\", %note",
+            ".p2align 2",
+            ".long 4",  // The size of the name, including the NUL.
+            ".long 16", // The size of the descriptor.
+            ".long 1",  // `NT_GNU_ABI_TAG`
+            ".asciz \"GNU\"",
+            ".long 0", // `ELF_NOTE_OS_LINUX`
+            abi_tag_version!(),
+            ".popsection",
+        );
+    };
+}
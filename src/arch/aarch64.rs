@@ -2,6 +2,7 @@
 
 #[cfg(any(
     feature = "take-charge",
+    feature = "timestamp-counter",
     all(not(feature = "unwinding"), feature = "panic-handler-trap")
 ))]
 use core::arch::asm;
@@ -9,19 +10,14 @@ use core::arch::asm;
 #[cfg(relocation_model = "pic")]
 use linux_raw_sys::elf::{Elf_Dyn, Elf_Ehdr};
 #[cfg(feature = "take-charge")]
-#[cfg(feature = "signal")]
-#[cfg(test)]
-use linux_raw_sys::general::__NR_rt_sigreturn;
-#[cfg(all(feature = "experimental-relocate", feature = "origin-start"))]
-#[cfg(relocation_model = "pic")]
-use linux_raw_sys::general::{__NR_mprotect, PROT_READ};
-#[cfg(feature = "take-charge")]
-#[cfg(feature = "thread")]
 use {
     core::ffi::c_void,
-    linux_raw_sys::general::{__NR_clone, __NR_exit, __NR_munmap},
-    rustix::thread::RawPid,
+    core::ptr::null_mut,
+    linux_raw_sys::general::{__NR_clone, __NR_execve, __NR_exit},
 };
+#[cfg(feature = "take-charge")]
+#[cfg(feature = "thread")]
+use {linux_raw_sys::general::__NR_munmap, rustix::thread::RawPid};
 
 #[cfg(feature = "origin-start")]
 naked_fn!(
@@ -39,13 +35,40 @@ naked_fn!(
     // Jump to `entry`, passing it the initial stack pointer value as an
     // argument, a null return address, a null frame pointer, and an aligned
     // stack pointer. On many architectures, the incoming frame pointer is
-    // already null.
+    // already null. Mark the return address as undefined in the CFI, so that
+    // debuggers and unwinders treat this as the outermost frame.
+    ".cfi_startproc",
+    ".cfi_undefined x30",
     "mov x0, sp",   // Pass the incoming `sp` as the arg to `entry`.
     "mov x30, xzr", // Set the return address to zero.
-    "b {entry}";    // Jump to `entry`.
+    "b {entry}",    // Jump to `entry`.
+    ".cfi_endproc";
     entry = sym super::program::entry
 );
 
+// Mark the executable as compatible with Branch Target Identification.
+//
+// `_start` begins with a landing pad from `naked_fn!`, and the rest of
+// origin's assembly code is only reached by direct branches, including the
+// child's jump to `entry` in `clone`, which continues after the `svc`
+// rather than being branched to. The linker only marks the executable if
+// every object file linked into it has this property, so the rest of the
+// program must be compiled with `-Z branch-protection=bti` too.
+#[cfg(all(feature = "bti", feature = "origin-start"))]
+core::arch::global_asm!(
+    ".pushsection .note.gnu.property, \"a\", %note",
+    ".p2align 3",
+    ".long 4",  // The size of the name, including the NUL.
+    ".long 16", // The size of the descriptor.
+    ".long 5",  // `NT_GNU_PROPERTY_TYPE_0`
+    ".asciz \"GNU\"",
+    ".long 0xc0000000", // `GNU_PROPERTY_AARCH64_FEATURE_1_AND`
+    ".long 4",          // The size of the property value.
+    ".long 1",          // `GNU_PROPERTY_AARCH64_FEATURE_1_BTI`
+    ".long 0",          // Padding to 8-byte alignment.
+    ".popsection",
+);
+
 /// Execute a trap instruction.
 ///
 /// This is roughly equivalent to `core::intrinsics::abort()`.
@@ -59,6 +82,21 @@ pub(super) fn trap() -> ! {
     }
 }
 
+/// Read the timestamp counter, using `cntvct_el0`, the virtual count register.
+#[cfg(feature = "timestamp-counter")]
+#[inline]
+pub(super) fn timestamp_counter() -> u64 {
+    let value: u64;
+    unsafe {
+        asm!(
+            "mrs {}, cntvct_el0",
+            out(reg) value,
+            options(nostack, preserves_flags)
+        );
+    }
+    value
+}
+
 /// Compute the dynamic address of `_DYNAMIC`.
 #[cfg(all(feature = "experimental-relocate", feature = "origin-start"))]
 #[cfg(relocation_model = "pic")]
@@ -146,47 +184,6 @@ pub(super) unsafe fn relocation_store(ptr: usize, value: usize) {
     );
 }
 
-/// Mark “relro” memory as readonly.
-///
-/// “relro” is a relocation feature in which memory can be readonly after
-/// relocations are applied.
-///
-/// This function conceptually casts `ptr` to a `*mut c_void` and does a
-/// `rustix::mm::mprotect(ptr, len, MprotectFlags::READ)`. However, it does
-/// this using `asm` and `usize` types which don't carry provenance, as it's
-/// used by `relocate` to implement the “relro” feature which cannot be
-/// expressed in the Rust memory model.
-///
-/// # Safety
-///
-/// This function must only be called during the relocation process, for
-/// relocation purposes. And, `ptr` must contain the address of a memory
-/// location that can be marked readonly.
-#[cfg(all(feature = "experimental-relocate", feature = "origin-start"))]
-#[cfg(relocation_model = "pic")]
-#[inline]
-pub(super) unsafe fn relocation_mprotect_readonly(ptr: usize, len: usize) {
-    let r0: usize;
-
-    // This is read-only but we don't use `readonly` because the side effects
-    // happen outside the Rust memory model. As far as Rust knows, this is
-    // just an arbitrary side-effecting opaque operation.
-    asm!(
-        "svc 0",
-        in("x8") __NR_mprotect,
-        inlateout("x0") ptr as usize => r0,
-        in("x1") len,
-        in("x2") PROT_READ,
-        options(nostack, preserves_flags),
-    );
-
-    if r0 != 0 {
-        // Do not panic here as libstd's panic handler needs TLS, which is not
-        // yet initialized at this point.
-        trap();
-    }
-}
-
 /// The required alignment for the stack pointer.
 #[cfg(feature = "take-charge")]
 #[cfg(feature = "thread")]
@@ -239,9 +236,63 @@ pub(super) unsafe fn clone(
     r0
 }
 
+/// A wrapper around the Linux `clone` system call for creating a
+/// `vfork`-style child which immediately calls `execve`.
+///
+/// `flags` should include `CLONE_VM` and `CLONE_VFORK`, and the child runs on
+/// the parent's stack, so the child does nothing except the `execve`, and if
+/// that fails, it stores the error code to `exec_errno` and exits. The child
+/// doesn't touch the stack at all.
+#[cfg(feature = "take-charge")]
+#[inline]
+pub(super) unsafe fn vfork_execve(
+    flags: u32,
+    path: *const u8,
+    argv: *const *const u8,
+    envp: *const *const u8,
+    exec_errno: *mut i32,
+) -> isize {
+    assert_eq!(__NR_execve, 221); // TODO: obviate this
+    assert_eq!(__NR_exit, 93); // TODO: obviate this
+    let r0;
+    asm!(
+        "svc 0",              // Do the `clone` system call.
+        "cbnz x0, 0f",        // Branch if we're in the parent.
+
+        // Child process.
+        "mov x0, {path}",     // Pass `path` as the first argument.
+        "mov x1, {argv}",     // Pass `argv` as the second argument.
+        "mov x2, {envp}",     // Pass `envp` as the third argument.
+        "mov x8, 221",        // TODO: use {__NR_execve}
+        "svc 0",              // Do the `execve` system call.
+        "str w0, [{exec_errno}]", // If we get here, `execve` failed.
+        "mov x0, 127",        // Exit with status 127, as shells do.
+        "mov x8, 93",         // TODO: use {__NR_exit}
+        "svc 0",
+        "udf #16",
+
+        // Parent process.
+        "0:",
+
+        path = in(reg) path,
+        argv = in(reg) argv,
+        envp = in(reg) envp,
+        exec_errno = in(reg) exec_errno,
+        in("x8") __NR_clone,
+        inlateout("x0") flags as usize => r0,
+        in("x1") null_mut::<c_void>(),
+        in("x2") null_mut::<c_void>(),
+        in("x3") null_mut::<c_void>(),
+        in("x4") null_mut::<c_void>(),
+        options(nostack)
+    );
+    r0
+}
+
 /// Write a value to the platform thread-pointer register.
 #[cfg(feature = "take-charge")]
 #[cfg(feature = "thread")]
+#[cfg(not(feature = "keep-main-thread-pointer"))]
 #[inline]
 pub(super) unsafe fn set_thread_pointer(ptr: *mut c_void) {
     asm!("msr tpidr_el0, {}", in(reg) ptr);
@@ -266,6 +317,174 @@ pub(super) fn thread_pointer() -> *mut c_void {
 #[cfg(feature = "thread")]
 pub(super) const TLS_OFFSET: usize = 0;
 
+/// The offset of `uc_sigmask` in the `ucontext` passed to `SA_SIGINFO` signal
+/// handlers, following `uc_flags`, `uc_link`, and `uc_stack`.
+#[cfg(feature = "take-charge")]
+#[cfg(all(feature = "signal", feature = "thread-registry"))]
+pub(super) const UCONTEXT_SIGMASK_OFFSET: usize = 40;
+
+/// The offsets of the saved program counter and stack pointer, `pc` and `sp` in
+/// `uc_mcontext`, in the `ucontext` passed to `SA_SIGINFO` signal handlers.
+#[cfg(feature = "signal")]
+pub(super) const UCONTEXT_PC_OFFSET: usize = 440;
+#[cfg(feature = "signal")]
+pub(super) const UCONTEXT_SP_OFFSET: usize = 432;
+
+// Raw system calls, for the few places where origin can't use rustix, such
+// as while relocating itself, or for system calls that rustix doesn't wrap.
+// These return the raw return value of the system call, which is a negated
+// errno value on failure.
+
+/// Make a system call with 1 argument.
+///
+/// # Safety
+///
+/// The system call must be safe to make with the given arguments.
+#[cfg(feature = "take-charge")]
+#[inline]
+pub(super) unsafe fn syscall1(nr: u32, a0: usize) -> isize {
+    let r0;
+    asm!(
+        "svc 0",
+        in("x8") nr as usize,
+        inlateout("x0") a0 => r0,
+        options(nostack, preserves_flags)
+    );
+    r0
+}
+
+/// Make a system call with 2 arguments.
+///
+/// # Safety
+///
+/// The system call must be safe to make with the given arguments.
+#[cfg(feature = "take-charge")]
+#[inline]
+pub(super) unsafe fn syscall2(nr: u32, a0: usize, a1: usize) -> isize {
+    let r0;
+    asm!(
+        "svc 0",
+        in("x8") nr as usize,
+        inlateout("x0") a0 => r0,
+        in("x1") a1,
+        options(nostack, preserves_flags)
+    );
+    r0
+}
+
+/// Make a system call with 3 arguments.
+///
+/// # Safety
+///
+/// The system call must be safe to make with the given arguments.
+#[cfg(feature = "take-charge")]
+#[cfg(any(
+    feature = "thread",
+    feature = "seccomp",
+    all(feature = "experimental-relocate", feature = "origin-start")
+))]
+#[cfg_attr(
+    not(any(feature = "thread", feature = "seccomp")),
+    cfg(relocation_model = "pic")
+)]
+#[inline]
+pub(super) unsafe fn syscall3(nr: u32, a0: usize, a1: usize, a2: usize) -> isize {
+    let r0;
+    asm!(
+        "svc 0",
+        in("x8") nr as usize,
+        inlateout("x0") a0 => r0,
+        in("x1") a1,
+        in("x2") a2,
+        options(nostack, preserves_flags)
+    );
+    r0
+}
+
+/// Make a system call with 4 arguments.
+///
+/// # Safety
+///
+/// The system call must be safe to make with the given arguments.
+#[cfg(feature = "take-charge")]
+#[cfg(feature = "thread")]
+#[inline]
+pub(super) unsafe fn syscall4(nr: u32, a0: usize, a1: usize, a2: usize, a3: usize) -> isize {
+    let r0;
+    asm!(
+        "svc 0",
+        in("x8") nr as usize,
+        inlateout("x0") a0 => r0,
+        in("x1") a1,
+        in("x2") a2,
+        in("x3") a3,
+        options(nostack, preserves_flags)
+    );
+    r0
+}
+
+/// Make a system call with 5 arguments.
+///
+/// # Safety
+///
+/// The system call must be safe to make with the given arguments.
+#[cfg(feature = "take-charge")]
+#[cfg(feature = "thread")]
+#[inline]
+pub(super) unsafe fn syscall5(
+    nr: u32,
+    a0: usize,
+    a1: usize,
+    a2: usize,
+    a3: usize,
+    a4: usize,
+) -> isize {
+    let r0;
+    asm!(
+        "svc 0",
+        in("x8") nr as usize,
+        inlateout("x0") a0 => r0,
+        in("x1") a1,
+        in("x2") a2,
+        in("x3") a3,
+        in("x4") a4,
+        options(nostack, preserves_flags)
+    );
+    r0
+}
+
+/// Make a system call with 6 arguments.
+///
+/// # Safety
+///
+/// The system call must be safe to make with the given arguments.
+#[cfg(feature = "take-charge")]
+#[cfg(feature = "thread")]
+#[inline]
+pub(super) unsafe fn syscall6(
+    nr: u32,
+    a0: usize,
+    a1: usize,
+    a2: usize,
+    a3: usize,
+    a4: usize,
+    a5: usize,
+) -> isize {
+    let r0;
+    asm!(
+        "svc 0",
+        in("x8") nr as usize,
+        inlateout("x0") a0 => r0,
+        in("x1") a1,
+        in("x2") a2,
+        in("x3") a3,
+        in("x4") a4,
+        in("x5") a5,
+        options(nostack, preserves_flags)
+    );
+    r0
+}
+
 /// `munmap` the current thread, then carefully exit the thread without
 /// touching the deallocated stack.
 #[cfg(feature = "take-charge")]
@@ -286,40 +505,3 @@ pub(super) unsafe fn munmap_and_exit_thread(map_addr: *mut c_void, map_len: usiz
         options(noreturn, nostack)
     );
 }
-
-#[cfg(feature = "take-charge")]
-#[cfg(feature = "signal")]
-naked_fn!(
-    "
-    Invoke the `__NR_rt_sigreturn` system call to return control from a signal
-    handler.
-
-    # Safety
-
-    This function must never be called other than by the `sa_restorer`
-    mechanism.
-    ";
-    pub(super) fn return_from_signal_handler() -> ();
-
-    "mov x8, 139", // TODO: use {__NR_rt_sigreturn}
-    "svc 0",
-    "udf #16";
-    //__NR_rt_sigreturn = const __NR_rt_sigreturn // TODO: Use this when `asm_const` is stabilized.
-);
-#[cfg(feature = "take-charge")]
-#[test] // TODO: obviate this
-fn test_rt_sigreturn() {
-    assert_eq!(__NR_rt_sigreturn, 139);
-}
-
-/// Invoke the appropriate system call to return control from a signal
-/// handler that does not use `SA_SIGINFO`. On aarch64, this uses the same
-/// sequence as the `SA_SIGINFO` case.
-///
-/// # Safety
-///
-/// This function must never be called other than by the `sa_restorer`
-/// mechanism.
-#[cfg(feature = "take-charge")]
-#[cfg(feature = "signal")]
-pub(super) use return_from_signal_handler as return_from_signal_handler_noinfo;
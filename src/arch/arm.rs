@@ -1,27 +1,33 @@
 //! Architecture-specific assembly code.
+//!
+//! This code is written to assemble and run correctly in both ARM and Thumb
+//! mode. In Thumb mode, `r7` is the frame pointer and can't be used as an
+//! `asm` operand, so system call numbers are passed in another register and
+//! moved into `r7` within the `asm`, saving and restoring its value around
+//! the call when control returns to Rust code.
 
 #[cfg(any(
     feature = "take-charge",
+    feature = "timestamp-counter",
     all(not(feature = "unwinding"), feature = "panic-handler-trap")
 ))]
 use core::arch::asm;
 #[cfg(all(feature = "experimental-relocate", feature = "origin-start"))]
 #[cfg(relocation_model = "pic")]
 use linux_raw_sys::elf::{Elf_Dyn, Elf_Ehdr};
-#[cfg(all(feature = "experimental-relocate", feature = "origin-start"))]
-#[cfg(relocation_model = "pic")]
-use linux_raw_sys::general::{__NR_mprotect, PROT_READ};
 #[cfg(feature = "take-charge")]
 #[cfg(feature = "signal")]
 #[cfg(test)]
 use linux_raw_sys::general::{__NR_rt_sigreturn, __NR_sigreturn};
 #[cfg(feature = "take-charge")]
-#[cfg(feature = "thread")]
 use {
     core::ffi::c_void,
-    linux_raw_sys::general::{__NR_clone, __NR_exit, __NR_munmap},
-    rustix::thread::RawPid,
+    core::ptr::null_mut,
+    linux_raw_sys::general::{__NR_clone, __NR_execve, __NR_exit},
 };
+#[cfg(feature = "take-charge")]
+#[cfg(feature = "thread")]
+use {linux_raw_sys::general::__NR_munmap, rustix::thread::RawPid};
 
 #[cfg(feature = "origin-start")]
 naked_fn!(
@@ -39,10 +45,14 @@ naked_fn!(
     // Jump to `entry`, passing it the initial stack pointer value as an
     // argument, a null return address, a null frame pointer, and an aligned
     // stack pointer. On many architectures, the incoming frame pointer is
-    // already null.
+    // already null. Mark the return address as undefined in the CFI, so that
+    // debuggers and unwinders treat this as the outermost frame.
+    ".cfi_startproc",
+    ".cfi_undefined lr",
     "mov r0, sp",   // Pass the incoming `sp` as the arg to `entry`.
     "mov lr, #0",   // Set the return address to zero.
-    "b {entry}";    // Jump to `entry`.
+    "b {entry}",    // Jump to `entry`.
+    ".cfi_endproc";
     entry = sym super::program::entry
 );
 
@@ -55,10 +65,36 @@ naked_fn!(
 ))]
 pub(super) fn trap() -> ! {
     unsafe {
+        // These are the encodings LLVM uses for traps.
+        #[cfg(not(thumb_mode))]
         asm!(".inst 0xe7ffdefe", options(noreturn, nostack));
+        #[cfg(thumb_mode)]
+        asm!(".inst.n 0xdefe", options(noreturn, nostack));
+    }
+}
+
+/// Read the timestamp counter, using `CNTVCT`, the virtual count register.
+#[cfg(feature = "timestamp-counter")]
+#[inline]
+pub(super) fn timestamp_counter() -> u64 {
+    let lo: u32;
+    let hi: u32;
+    unsafe {
+        asm!(
+            "mrrc p15, 1, {}, {}, c14",
+            out(reg) lo,
+            out(reg) hi,
+            options(nostack, preserves_flags)
+        );
     }
+    (u64::from(hi) << 32) | u64::from(lo)
 }
 
+/// How far ahead of an instruction `pc` reads as.
+#[cfg(all(feature = "experimental-relocate", feature = "origin-start"))]
+#[cfg(relocation_model = "pic")]
+const PC_OFFSET: usize = if cfg!(thumb_mode) { 4 } else { 8 };
+
 /// Compute the dynamic address of `_DYNAMIC`.
 #[cfg(all(feature = "experimental-relocate", feature = "origin-start"))]
 #[cfg(relocation_model = "pic")]
@@ -70,13 +106,14 @@ pub(super) fn dynamic_table_addr() -> *const Elf_Dyn {
             ".hidden _DYNAMIC",
             "ldr {0}, 1f",
             "0:",
-            "add {0}, pc, {0}",
+            "add {0}, pc",
             "b 2f",
             ".p2align 2",
             "1:",
-            ".word _DYNAMIC-(0b+8)",
+            ".word _DYNAMIC-(0b+{pc_offset})",
             "2:",
-            out(reg) addr
+            out(reg) addr,
+            pc_offset = const PC_OFFSET,
         );
     }
     addr
@@ -91,13 +128,14 @@ pub(super) fn ehdr_addr() -> *const Elf_Ehdr {
         asm!(
             "ldr {0}, 1f",
             "0:",
-            "add {0}, pc, {0}",
+            "add {0}, pc",
             "b 2f",
             ".p2align 2",
             "1:",
-            ".word __ehdr_start-(0b+8)",
+            ".word __ehdr_start-(0b+{pc_offset})",
             "2:",
-            out(reg) addr
+            out(reg) addr,
+            pc_offset = const PC_OFFSET,
         );
     }
     addr
@@ -134,69 +172,51 @@ pub(super) unsafe fn relocation_load(ptr: usize) -> usize {
     r0
 }
 
-/// Perform a single store operation, outside the Rust memory model.
+/// Perform a raw load operation to memory that Rust may consider out of bounds.
 ///
-/// This function conceptually casts `ptr` to a `*mut *mut c_void` and stores
-/// a `*mut c_void` value to it. However, it does this using `asm`, and `usize`
-/// types which don't carry provenance, as it's used by `relocate` to perform
-/// relocations which cannot be expressed in the Rust memory model.
+/// Data loaded from out-of-bounds bytes will have nondeterministic values.
 ///
 /// # Safety
 ///
-/// This function must only be called during the relocation process, for
-/// relocation purposes. And, `ptr` must contain the address of a memory
-/// location that can be stored to.
-#[cfg(all(feature = "experimental-relocate", feature = "origin-start"))]
-#[cfg(relocation_model = "pic")]
+/// `ptr` must be aligned for loading a `usize` and must point to enough readable
+/// memory for loading a `usize`.
+#[cfg(all(feature = "take-charge", not(feature = "optimize_for_size")))]
 #[inline]
-pub(super) unsafe fn relocation_store(ptr: usize, value: usize) {
+pub(super) unsafe fn oob_load(ptr: *const usize) -> usize {
+    let r0;
+
     asm!(
-        "str {}, [{}]",
-        in(reg) value,
+        "ldr {}, [{}]",
+        out(reg) r0,
         in(reg) ptr,
-        options(nostack, preserves_flags),
+        options(nostack, preserves_flags, readonly),
     );
+
+    r0
 }
 
-/// Mark “relro” memory as readonly.
-///
-/// “relro” is a relocation feature in which memory can be readonly after
-/// relocations are applied.
+/// Perform a single store operation, outside the Rust memory model.
 ///
-/// This function conceptually casts `ptr` to a `*mut c_void` and does a
-/// `rustix::mm::mprotect(ptr, len, MprotectFlags::READ)`. However, it does
-/// this using `asm` and `usize` types which don't carry provenance, as it's
-/// used by `relocate` to implement the “relro” feature which cannot be
-/// expressed in the Rust memory model.
+/// This function conceptually casts `ptr` to a `*mut *mut c_void` and stores
+/// a `*mut c_void` value to it. However, it does this using `asm`, and `usize`
+/// types which don't carry provenance, as it's used by `relocate` to perform
+/// relocations which cannot be expressed in the Rust memory model.
 ///
 /// # Safety
 ///
 /// This function must only be called during the relocation process, for
 /// relocation purposes. And, `ptr` must contain the address of a memory
-/// location that can be marked readonly.
+/// location that can be stored to.
 #[cfg(all(feature = "experimental-relocate", feature = "origin-start"))]
 #[cfg(relocation_model = "pic")]
 #[inline]
-pub(super) unsafe fn relocation_mprotect_readonly(ptr: usize, len: usize) {
-    let r0: usize;
-
-    // This is read-only but we don't use `readonly` because the side effects
-    // happen outside the Rust memory model. As far as Rust knows, this is
-    // just an arbitrary side-effecting opaque operation.
+pub(super) unsafe fn relocation_store(ptr: usize, value: usize) {
     asm!(
-        "svc 0",
-        in("r7") __NR_mprotect,
-        inlateout("r0") ptr as usize => r0,
-        in("r1") len,
-        in("r2") PROT_READ,
+        "str {}, [{}]",
+        in(reg) value,
+        in(reg) ptr,
         options(nostack, preserves_flags),
     );
-
-    if r0 != 0 {
-        // Do not panic here as libstd's panic handler needs TLS, which is not
-        // yet initialized at this point.
-        trap();
-    }
 }
 
 /// The required alignment for the stack pointer.
@@ -223,6 +243,8 @@ pub(super) unsafe fn clone(
 ) -> isize {
     let r0;
     asm!(
+        "mov {tmp}, r7",      // Save `r7`, which may be the frame pointer.
+        "mov r7, {nr}",
         "svc 0",              // Do the `clone` system call.
         "tst r0, r0",         // Branch if we're in the parent thread.
         "bne 0f",
@@ -231,17 +253,20 @@ pub(super) unsafe fn clone(
         "mov r0, {fn_}",      // Pass `fn_` as the first argument.
         "mov r1, sp",         // Pass the args pointer as the second argument.
         "mov r2, {num_args}", // Pass `num_args` as the third argument.
-        "mov fp, #0",         // Zero the frame address.
+        "mov r7, #0",         // Zero the frame address, which is in `r7` in
+        "mov r11, #0",        // Thumb mode and `r11` in ARM mode.
         "mov lr, #0",         // Zero the return address.
         "b {entry}",          // Call `entry`.
 
         // Parent thread.
         "0:",
+        "mov r7, {tmp}",      // Restore `r7`.
 
         entry = sym super::thread::entry,
         fn_ = in(reg) fn_,
         num_args = in(reg) num_args,
-        in("r7") __NR_clone,
+        nr = in(reg) __NR_clone,
+        tmp = out(reg) _,
         inlateout("r0") flags as usize => r0,
         in("r1") child_stack,
         in("r2") parent_tid,
@@ -252,9 +277,68 @@ pub(super) unsafe fn clone(
     r0
 }
 
+/// A wrapper around the Linux `clone` system call for creating a
+/// `vfork`-style child which immediately calls `execve`.
+///
+/// `flags` should include `CLONE_VM` and `CLONE_VFORK`, and the child runs on
+/// the parent's stack, so the child does nothing except the `execve`, and if
+/// that fails, it stores the error code to `exec_errno` and exits. The child
+/// doesn't touch the stack at all.
+#[cfg(feature = "take-charge")]
+#[inline]
+pub(super) unsafe fn vfork_execve(
+    flags: u32,
+    path: *const u8,
+    argv: *const *const u8,
+    envp: *const *const u8,
+    exec_errno: *mut i32,
+) -> isize {
+    assert_eq!(__NR_execve, 11); // TODO: obviate this
+    assert_eq!(__NR_exit, 1); // TODO: obviate this
+    let r0;
+    asm!(
+        "mov {tmp}, r7",      // Save `r7`, which may be the frame pointer.
+        "mov r7, {nr}",
+        "svc 0",              // Do the `clone` system call.
+        "tst r0, r0",         // Branch if we're in the parent.
+        "bne 0f",
+
+        // Child process.
+        "mov r0, {path}",     // Pass `path` as the first argument.
+        "mov r1, {argv}",     // Pass `argv` as the second argument.
+        "mov r2, {envp}",     // Pass `envp` as the third argument.
+        "mov r7, #11",        // TODO: use {__NR_execve}
+        "svc 0",              // Do the `execve` system call.
+        "str r0, [{exec_errno}]", // If we get here, `execve` failed.
+        "mov r0, #127",       // Exit with status 127, as shells do.
+        "mov r7, #1",         // TODO: use {__NR_exit}
+        "svc 0",
+        "udf #16",
+
+        // Parent process.
+        "0:",
+        "mov r7, {tmp}",      // Restore `r7`.
+
+        path = in(reg) path,
+        argv = in(reg) argv,
+        envp = in(reg) envp,
+        exec_errno = in(reg) exec_errno,
+        nr = in(reg) __NR_clone,
+        tmp = out(reg) _,
+        inlateout("r0") flags as usize => r0,
+        in("r1") null_mut::<c_void>(),
+        in("r2") null_mut::<c_void>(),
+        in("r3") null_mut::<c_void>(),
+        in("r4") null_mut::<c_void>(),
+        options(nostack)
+    );
+    r0
+}
+
 /// Write a value to the platform thread-pointer register.
 #[cfg(feature = "take-charge")]
 #[cfg(feature = "thread")]
+#[cfg(not(feature = "keep-main-thread-pointer"))]
 #[inline]
 pub(super) unsafe fn set_thread_pointer(ptr: *mut c_void) {
     let res = rustix::runtime::arm_set_tls(ptr);
@@ -280,6 +364,200 @@ pub(super) fn thread_pointer() -> *mut c_void {
 #[cfg(feature = "thread")]
 pub(super) const TLS_OFFSET: usize = 0;
 
+/// The offset of `uc_sigmask` in the `ucontext` passed to `SA_SIGINFO` signal
+/// handlers, following `uc_flags`, `uc_link`, `uc_stack`, and `uc_mcontext`.
+#[cfg(feature = "take-charge")]
+#[cfg(all(feature = "signal", feature = "thread-registry"))]
+pub(super) const UCONTEXT_SIGMASK_OFFSET: usize = 104;
+
+/// The offsets of the saved program counter and stack pointer, `arm_pc` and
+/// `arm_sp` in `uc_mcontext`, in the `ucontext` passed to `SA_SIGINFO` signal
+/// handlers.
+#[cfg(feature = "signal")]
+pub(super) const UCONTEXT_PC_OFFSET: usize = 92;
+#[cfg(feature = "signal")]
+pub(super) const UCONTEXT_SP_OFFSET: usize = 84;
+
+// Raw system calls, for the few places where origin can't use rustix, such as
+// while relocating itself, or for system calls that rustix doesn't wrap. These
+// return the raw return value of the system call, which is a negated errno
+// value on failure. `r7` may be the frame pointer, so it's saved and restored
+// around each call.
+
+/// Make a system call with 1 argument.
+///
+/// # Safety
+///
+/// The system call must be safe to make with the given arguments.
+#[cfg(feature = "take-charge")]
+#[inline]
+pub(super) unsafe fn syscall1(nr: u32, a0: usize) -> isize {
+    let r0;
+    asm!(
+        "mov {tmp}, r7",
+        "mov r7, {nr}",
+        "svc 0",
+        "mov r7, {tmp}",
+        nr = in(reg) nr,
+        tmp = out(reg) _,
+        inlateout("r0") a0 => r0,
+        options(nostack, preserves_flags)
+    );
+    r0
+}
+
+/// Make a system call with 2 arguments.
+///
+/// # Safety
+///
+/// The system call must be safe to make with the given arguments.
+#[cfg(feature = "take-charge")]
+#[inline]
+pub(super) unsafe fn syscall2(nr: u32, a0: usize, a1: usize) -> isize {
+    let r0;
+    asm!(
+        "mov {tmp}, r7",
+        "mov r7, {nr}",
+        "svc 0",
+        "mov r7, {tmp}",
+        nr = in(reg) nr,
+        tmp = out(reg) _,
+        inlateout("r0") a0 => r0,
+        in("r1") a1,
+        options(nostack, preserves_flags)
+    );
+    r0
+}
+
+/// Make a system call with 3 arguments.
+///
+/// # Safety
+///
+/// The system call must be safe to make with the given arguments.
+#[cfg(feature = "take-charge")]
+#[cfg(any(
+    feature = "thread",
+    feature = "seccomp",
+    all(feature = "experimental-relocate", feature = "origin-start")
+))]
+#[cfg_attr(
+    not(any(feature = "thread", feature = "seccomp")),
+    cfg(relocation_model = "pic")
+)]
+#[inline]
+pub(super) unsafe fn syscall3(nr: u32, a0: usize, a1: usize, a2: usize) -> isize {
+    let r0;
+    asm!(
+        "mov {tmp}, r7",
+        "mov r7, {nr}",
+        "svc 0",
+        "mov r7, {tmp}",
+        nr = in(reg) nr,
+        tmp = out(reg) _,
+        inlateout("r0") a0 => r0,
+        in("r1") a1,
+        in("r2") a2,
+        options(nostack, preserves_flags)
+    );
+    r0
+}
+
+/// Make a system call with 4 arguments.
+///
+/// # Safety
+///
+/// The system call must be safe to make with the given arguments.
+#[cfg(feature = "take-charge")]
+#[cfg(feature = "thread")]
+#[inline]
+pub(super) unsafe fn syscall4(nr: u32, a0: usize, a1: usize, a2: usize, a3: usize) -> isize {
+    let r0;
+    asm!(
+        "mov {tmp}, r7",
+        "mov r7, {nr}",
+        "svc 0",
+        "mov r7, {tmp}",
+        nr = in(reg) nr,
+        tmp = out(reg) _,
+        inlateout("r0") a0 => r0,
+        in("r1") a1,
+        in("r2") a2,
+        in("r3") a3,
+        options(nostack, preserves_flags)
+    );
+    r0
+}
+
+/// Make a system call with 5 arguments.
+///
+/// # Safety
+///
+/// The system call must be safe to make with the given arguments.
+#[cfg(feature = "take-charge")]
+#[cfg(feature = "thread")]
+#[inline]
+pub(super) unsafe fn syscall5(
+    nr: u32,
+    a0: usize,
+    a1: usize,
+    a2: usize,
+    a3: usize,
+    a4: usize,
+) -> isize {
+    let r0;
+    asm!(
+        "mov {tmp}, r7",
+        "mov r7, {nr}",
+        "svc 0",
+        "mov r7, {tmp}",
+        nr = in(reg) nr,
+        tmp = out(reg) _,
+        inlateout("r0") a0 => r0,
+        in("r1") a1,
+        in("r2") a2,
+        in("r3") a3,
+        in("r4") a4,
+        options(nostack, preserves_flags)
+    );
+    r0
+}
+
+/// Make a system call with 6 arguments.
+///
+/// # Safety
+///
+/// The system call must be safe to make with the given arguments.
+#[cfg(feature = "take-charge")]
+#[cfg(feature = "thread")]
+#[inline]
+pub(super) unsafe fn syscall6(
+    nr: u32,
+    a0: usize,
+    a1: usize,
+    a2: usize,
+    a3: usize,
+    a4: usize,
+    a5: usize,
+) -> isize {
+    let r0;
+    asm!(
+        "mov {tmp}, r7",
+        "mov r7, {nr}",
+        "svc 0",
+        "mov r7, {tmp}",
+        nr = in(reg) nr,
+        tmp = out(reg) _,
+        inlateout("r0") a0 => r0,
+        in("r1") a1,
+        in("r2") a2,
+        in("r3") a3,
+        in("r4") a4,
+        in("r5") a5,
+        options(nostack, preserves_flags)
+    );
+    r0
+}
+
 /// `munmap` the current thread, then carefully exit the thread without
 /// touching the deallocated stack.
 #[cfg(feature = "take-charge")]
@@ -288,13 +566,14 @@ pub(super) const TLS_OFFSET: usize = 0;
 pub(super) unsafe fn munmap_and_exit_thread(map_addr: *mut c_void, map_len: usize) -> ! {
     assert_eq!(__NR_exit, 1); // TODO: obviate this
     asm!(
+        "mov r7, {nr}",
         "svc 0",
         "mov r0, #0",
         "mov r7, #1", // TODO: use {__NR_exit}
         "svc 0",
         "udf #16",
         //__NR_exit = const __NR_exit, // TODO: Use this when `asm_const` is stabilized.
-        in("r7") __NR_munmap,
+        nr = in(reg) __NR_munmap,
         in("r0") map_addr,
         in("r1") map_len,
         options(noreturn, nostack)
@@ -2,22 +2,22 @@
 
 #[cfg(any(
     feature = "take-charge",
+    feature = "timestamp-counter",
     all(not(feature = "unwinding"), feature = "panic-handler-trap")
 ))]
 use core::arch::asm;
 #[cfg(all(feature = "experimental-relocate", feature = "origin-start"))]
 #[cfg(relocation_model = "pic")]
 use linux_raw_sys::elf::{Elf_Dyn, Elf_Ehdr};
-#[cfg(all(feature = "experimental-relocate", feature = "origin-start"))]
-#[cfg(relocation_model = "pic")]
-use linux_raw_sys::general::{__NR_mprotect, PROT_READ};
 #[cfg(feature = "take-charge")]
-#[cfg(feature = "thread")]
 use {
     core::ffi::c_void,
-    linux_raw_sys::general::{__NR_clone, __NR_exit, __NR_munmap},
-    rustix::thread::RawPid,
+    core::ptr::null_mut,
+    linux_raw_sys::general::{__NR_clone, __NR_execve, __NR_exit},
 };
+#[cfg(feature = "take-charge")]
+#[cfg(feature = "thread")]
+use {linux_raw_sys::general::__NR_munmap, rustix::thread::RawPid};
 
 #[cfg(feature = "origin-start")]
 naked_fn!(
@@ -35,11 +35,15 @@ naked_fn!(
     // Jump to `entry`, passing it the initial stack pointer value as an
     // argument, a null return address, a null frame pointer, and an aligned
     // stack pointer. On many architectures, the incoming frame pointer is
-    // already null.
+    // already null. Mark the return address as undefined in the CFI, so that
+    // debuggers and unwinders treat this as the outermost frame.
+    ".cfi_startproc",
+    ".cfi_undefined ra",
     "mv a0, sp",    // Pass the incoming `sp` as the arg to `entry`.
     "mv ra, zero",  // Set the return address to zero.
     "mv fp, zero",  // Set the frame address to zero.
-    "tail {entry}"; // Jump to `entry`.
+    "tail {entry}", // Jump to `entry`.
+    ".cfi_endproc";
     entry = sym super::program::entry
 );
 
@@ -56,6 +60,21 @@ pub(super) fn trap() -> ! {
     }
 }
 
+/// Read the timestamp counter, using the `time` CSR.
+#[cfg(feature = "timestamp-counter")]
+#[inline]
+pub(super) fn timestamp_counter() -> u64 {
+    let value: u64;
+    unsafe {
+        asm!(
+            "rdtime {}",
+            out(reg) value,
+            options(nostack, preserves_flags)
+        );
+    }
+    value
+}
+
 /// Compute the dynamic address of `_DYNAMIC`.
 #[cfg(all(feature = "experimental-relocate", feature = "origin-start"))]
 #[cfg(relocation_model = "pic")]
@@ -163,47 +182,6 @@ pub(super) unsafe fn relocation_store(ptr: usize, value: usize) {
     );
 }
 
-/// Mark “relro” memory as readonly.
-///
-/// “relro” is a relocation feature in which memory can be readonly after
-/// relocations are applied.
-///
-/// This function conceptually casts `ptr` to a `*mut c_void` and does a
-/// `rustix::mm::mprotect(ptr, len, MprotectFlags::READ)`. However, it does
-/// this using `asm` and `usize` types which don't carry provenance, as it's
-/// used by `relocate` to implement the “relro” feature which cannot be
-/// expressed in the Rust memory model.
-///
-/// # Safety
-///
-/// This function must only be called during the relocation process, for
-/// relocation purposes. And, `ptr` must contain the address of a memory
-/// location that can be marked readonly.
-#[cfg(all(feature = "experimental-relocate", feature = "origin-start"))]
-#[cfg(relocation_model = "pic")]
-#[inline]
-pub(super) unsafe fn relocation_mprotect_readonly(ptr: usize, len: usize) {
-    let r0: usize;
-
-    // This is read-only but we don't use `readonly` because the side effects
-    // happen outside the Rust memory model. As far as Rust knows, this is
-    // just an arbitrary side-effecting opaque operation.
-    asm!(
-        "ecall",
-        in("a7") __NR_mprotect,
-        inlateout("a0") ptr as usize => r0,
-        in("a1") len,
-        in("a2") PROT_READ,
-        options(nostack, preserves_flags),
-    );
-
-    if r0 != 0 {
-        // Do not panic here as libstd's panic handler needs TLS, which is not
-        // yet initialized at this point.
-        trap();
-    }
-}
-
 /// The required alignment for the stack pointer.
 #[cfg(feature = "take-charge")]
 #[cfg(feature = "thread")]
@@ -256,9 +234,63 @@ pub(super) unsafe fn clone(
     r0
 }
 
+/// A wrapper around the Linux `clone` system call for creating a
+/// `vfork`-style child which immediately calls `execve`.
+///
+/// `flags` should include `CLONE_VM` and `CLONE_VFORK`, and the child runs on
+/// the parent's stack, so the child does nothing except the `execve`, and if
+/// that fails, it stores the error code to `exec_errno` and exits. The child
+/// doesn't touch the stack at all.
+#[cfg(feature = "take-charge")]
+#[inline]
+pub(super) unsafe fn vfork_execve(
+    flags: u32,
+    path: *const u8,
+    argv: *const *const u8,
+    envp: *const *const u8,
+    exec_errno: *mut i32,
+) -> isize {
+    assert_eq!(__NR_execve, 221); // TODO: obviate this
+    assert_eq!(__NR_exit, 93); // TODO: obviate this
+    let r0;
+    asm!(
+        "ecall",              // Do the `clone` system call.
+        "bnez a0, 0f",        // Branch if we're in the parent.
+
+        // Child process.
+        "mv a0, {path}",      // Pass `path` as the first argument.
+        "mv a1, {argv}",      // Pass `argv` as the second argument.
+        "mv a2, {envp}",      // Pass `envp` as the third argument.
+        "li a7, 221",         // TODO: use {__NR_execve}
+        "ecall",              // Do the `execve` system call.
+        "sw a0, 0({exec_errno})", // If we get here, `execve` failed.
+        "li a0, 127",         // Exit with status 127, as shells do.
+        "li a7, 93",          // TODO: use {__NR_exit}
+        "ecall",
+        "unimp",
+
+        // Parent process.
+        "0:",
+
+        path = in(reg) path,
+        argv = in(reg) argv,
+        envp = in(reg) envp,
+        exec_errno = in(reg) exec_errno,
+        in("a7") __NR_clone,
+        inlateout("a0") flags as usize => r0,
+        in("a1") null_mut::<c_void>(),
+        in("a2") null_mut::<c_void>(),
+        in("a3") null_mut::<c_void>(),
+        in("a4") null_mut::<c_void>(),
+        options(nostack)
+    );
+    r0
+}
+
 /// Write a value to the platform thread-pointer register.
 #[cfg(feature = "take-charge")]
 #[cfg(feature = "thread")]
+#[cfg(not(feature = "keep-main-thread-pointer"))]
 #[inline]
 pub(super) unsafe fn set_thread_pointer(ptr: *mut c_void) {
     asm!("mv tp, {}", in(reg) ptr);
@@ -284,6 +316,174 @@ pub(super) fn thread_pointer() -> *mut c_void {
 #[cfg(feature = "thread")]
 pub(super) const TLS_OFFSET: usize = 0x800;
 
+/// The offset of `uc_sigmask` in the `ucontext` passed to `SA_SIGINFO` signal
+/// handlers, following `uc_flags`, `uc_link`, and `uc_stack`.
+#[cfg(feature = "take-charge")]
+#[cfg(all(feature = "signal", feature = "thread-registry"))]
+pub(super) const UCONTEXT_SIGMASK_OFFSET: usize = 40;
+
+/// The offsets of the saved program counter and stack pointer, `pc` and `sp` in
+/// `uc_mcontext`, in the `ucontext` passed to `SA_SIGINFO` signal handlers.
+#[cfg(feature = "signal")]
+pub(super) const UCONTEXT_PC_OFFSET: usize = 176;
+#[cfg(feature = "signal")]
+pub(super) const UCONTEXT_SP_OFFSET: usize = 192;
+
+// Raw system calls, for the few places where origin can't use rustix, such
+// as while relocating itself, or for system calls that rustix doesn't wrap.
+// These return the raw return value of the system call, which is a negated
+// errno value on failure.
+
+/// Make a system call with 1 argument.
+///
+/// # Safety
+///
+/// The system call must be safe to make with the given arguments.
+#[cfg(feature = "take-charge")]
+#[inline]
+pub(super) unsafe fn syscall1(nr: u32, a0: usize) -> isize {
+    let r0;
+    asm!(
+        "ecall",
+        in("a7") nr as usize,
+        inlateout("a0") a0 => r0,
+        options(nostack, preserves_flags)
+    );
+    r0
+}
+
+/// Make a system call with 2 arguments.
+///
+/// # Safety
+///
+/// The system call must be safe to make with the given arguments.
+#[cfg(feature = "take-charge")]
+#[inline]
+pub(super) unsafe fn syscall2(nr: u32, a0: usize, a1: usize) -> isize {
+    let r0;
+    asm!(
+        "ecall",
+        in("a7") nr as usize,
+        inlateout("a0") a0 => r0,
+        in("a1") a1,
+        options(nostack, preserves_flags)
+    );
+    r0
+}
+
+/// Make a system call with 3 arguments.
+///
+/// # Safety
+///
+/// The system call must be safe to make with the given arguments.
+#[cfg(feature = "take-charge")]
+#[cfg(any(
+    feature = "thread",
+    feature = "seccomp",
+    all(feature = "experimental-relocate", feature = "origin-start")
+))]
+#[cfg_attr(
+    not(any(feature = "thread", feature = "seccomp")),
+    cfg(relocation_model = "pic")
+)]
+#[inline]
+pub(super) unsafe fn syscall3(nr: u32, a0: usize, a1: usize, a2: usize) -> isize {
+    let r0;
+    asm!(
+        "ecall",
+        in("a7") nr as usize,
+        inlateout("a0") a0 => r0,
+        in("a1") a1,
+        in("a2") a2,
+        options(nostack, preserves_flags)
+    );
+    r0
+}
+
+/// Make a system call with 4 arguments.
+///
+/// # Safety
+///
+/// The system call must be safe to make with the given arguments.
+#[cfg(feature = "take-charge")]
+#[cfg(feature = "thread")]
+#[inline]
+pub(super) unsafe fn syscall4(nr: u32, a0: usize, a1: usize, a2: usize, a3: usize) -> isize {
+    let r0;
+    asm!(
+        "ecall",
+        in("a7") nr as usize,
+        inlateout("a0") a0 => r0,
+        in("a1") a1,
+        in("a2") a2,
+        in("a3") a3,
+        options(nostack, preserves_flags)
+    );
+    r0
+}
+
+/// Make a system call with 5 arguments.
+///
+/// # Safety
+///
+/// The system call must be safe to make with the given arguments.
+#[cfg(feature = "take-charge")]
+#[cfg(feature = "thread")]
+#[inline]
+pub(super) unsafe fn syscall5(
+    nr: u32,
+    a0: usize,
+    a1: usize,
+    a2: usize,
+    a3: usize,
+    a4: usize,
+) -> isize {
+    let r0;
+    asm!(
+        "ecall",
+        in("a7") nr as usize,
+        inlateout("a0") a0 => r0,
+        in("a1") a1,
+        in("a2") a2,
+        in("a3") a3,
+        in("a4") a4,
+        options(nostack, preserves_flags)
+    );
+    r0
+}
+
+/// Make a system call with 6 arguments.
+///
+/// # Safety
+///
+/// The system call must be safe to make with the given arguments.
+#[cfg(feature = "take-charge")]
+#[cfg(feature = "thread")]
+#[inline]
+pub(super) unsafe fn syscall6(
+    nr: u32,
+    a0: usize,
+    a1: usize,
+    a2: usize,
+    a3: usize,
+    a4: usize,
+    a5: usize,
+) -> isize {
+    let r0;
+    asm!(
+        "ecall",
+        in("a7") nr as usize,
+        inlateout("a0") a0 => r0,
+        in("a1") a1,
+        in("a2") a2,
+        in("a3") a3,
+        in("a4") a4,
+        in("a5") a5,
+        options(nostack, preserves_flags)
+    );
+    r0
+}
+
 /// `munmap` the current thread, then carefully exit the thread without
 /// touching the deallocated stack.
 #[cfg(feature = "take-charge")]
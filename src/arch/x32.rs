@@ -0,0 +1,555 @@
+//! Architecture-specific assembly code.
+//!
+//! This is for the x32 ABI, which runs on x86-64 hardware with 32-bit
+//! pointers. It's similar to x86-64, except that pointer-sized values are 32
+//! bits. Linux reads the full 64-bit registers, so we zero-extend values we
+//! pass to it. And system call numbers include `__X32_SYSCALL_BIT`.
+//!
+//! Note that origin's "take-charge" mode also depends on rustix's `linux_raw`
+//! backend, which doesn't yet support x32.
+
+#[cfg(any(
+    feature = "take-charge",
+    feature = "timestamp-counter",
+    all(not(feature = "unwinding"), feature = "panic-handler-trap")
+))]
+use core::arch::asm;
+#[cfg(all(feature = "experimental-relocate", feature = "origin-start"))]
+#[cfg(relocation_model = "pic")]
+use linux_raw_sys::elf::{Elf_Dyn, Elf_Ehdr};
+#[cfg(feature = "take-charge")]
+#[cfg(feature = "signal")]
+#[cfg(test)]
+use linux_raw_sys::general::__NR_rt_sigreturn;
+#[cfg(feature = "take-charge")]
+use {
+    core::ffi::c_void,
+    linux_raw_sys::general::{__NR_clone, __NR_execve, __NR_exit},
+};
+#[cfg(feature = "take-charge")]
+#[cfg(feature = "thread")]
+use {linux_raw_sys::general::__NR_munmap, rustix::thread::RawPid};
+
+#[cfg(feature = "origin-start")]
+naked_fn!(
+    "
+    The program entry point.
+
+    # Safety
+
+    This function must never be called explicitly. It is the first thing
+    executed in the program, and it assumes that memory is laid out according
+    to the operating system convention for starting a new program.
+    ";
+    pub(super) fn _start() -> !;
+
+    // Jump to `entry`, passing it the initial stack pointer value as an
+    // argument, a null return address, a null frame pointer, and an aligned
+    // stack pointer. On many architectures, the incoming frame pointer is
+    // already null. Mark the return address as undefined in the CFI, so that
+    // debuggers and unwinders treat this as the outermost frame.
+    ".cfi_startproc",
+    ".cfi_undefined rip",
+    "mov rdi, rsp", // Pass the incoming `rsp` as the arg to `entry`.
+    "push rbp",     // Set the return address to zero.
+    "jmp {entry}",  // Jump to `entry`.
+    ".cfi_endproc";
+    entry = sym super::program::entry
+);
+
+/// Execute a trap instruction.
+///
+/// This is roughly equivalent to `core::intrinsics::abort()`.
+#[cfg(any(
+    feature = "take-charge",
+    all(not(feature = "unwinding"), feature = "panic-handler-trap")
+))]
+pub(super) fn trap() -> ! {
+    unsafe {
+        asm!("ud2", options(noreturn, nostack));
+    }
+}
+
+/// Read the timestamp counter, using `rdtsc`.
+#[cfg(feature = "timestamp-counter")]
+#[inline]
+pub(super) fn timestamp_counter() -> u64 {
+    let lo: u32;
+    let hi: u32;
+    unsafe {
+        asm!(
+            "rdtsc",
+            out("eax") lo,
+            out("edx") hi,
+            options(nostack, preserves_flags)
+        );
+    }
+    (u64::from(hi) << 32) | u64::from(lo)
+}
+
+/// Compute the dynamic address of `_DYNAMIC`.
+#[cfg(all(feature = "experimental-relocate", feature = "origin-start"))]
+#[cfg(relocation_model = "pic")]
+pub(super) fn dynamic_table_addr() -> *const Elf_Dyn {
+    let addr;
+    unsafe {
+        asm!(
+            ".weak _DYNAMIC",
+            ".hidden _DYNAMIC",
+            "lea {:e}, [rip + _DYNAMIC]",
+            out(reg) addr
+        );
+    }
+    addr
+}
+
+/// Compute the dynamic address of `__ehdr_start`.
+#[cfg(all(feature = "experimental-relocate", feature = "origin-start"))]
+#[cfg(relocation_model = "pic")]
+pub(super) fn ehdr_addr() -> *const Elf_Ehdr {
+    let addr: *const Elf_Ehdr;
+    unsafe {
+        asm!(
+            "lea {:e}, [rip + __ehdr_start]",
+            out(reg) addr
+        );
+    }
+    addr
+}
+
+/// Perform a single load operation, outside the Rust memory model.
+///
+/// This function conceptually casts `ptr` to a `*const *mut c_void` and loads
+/// a `*mut c_void` value from it. However, it does this using `asm`, and
+/// `usize` types which don't carry provenance, as it's used by `relocate` to
+/// perform relocations which cannot be expressed in the Rust memory model.
+///
+/// # Safety
+///
+/// This function must only be called during the relocation process, for
+/// relocation purposes. And, `ptr` must contain the address of a memory
+/// location that can be loaded from.
+#[cfg(all(feature = "experimental-relocate", feature = "origin-start"))]
+#[cfg(relocation_model = "pic")]
+#[inline]
+pub(super) unsafe fn relocation_load(ptr: usize) -> usize {
+    let r0;
+
+    // This is read-only but we don't use `readonly` because this memory access
+    // happens outside the Rust memory model. As far as Rust knows, this is
+    // just an arbitrary side-effecting opaque operation.
+    asm!(
+        "mov {:e}, [{:e}]",
+        out(reg) r0,
+        in(reg) ptr,
+        options(nostack, preserves_flags),
+    );
+
+    r0
+}
+
+/// Perform a single store operation, outside the Rust memory model.
+///
+/// This function conceptually casts `ptr` to a `*mut *mut c_void` and stores
+/// `value` casted to `*mut c_void` through it. However, it does this using
+/// `asm`, and `usize` types which don't carry provenance, as it's used by
+/// `relocate` to perform relocations which cannot be expressed in the Rust
+/// memory model.
+///
+/// # Safety
+///
+/// This function must only be called during the relocation process, for
+/// relocation purposes. And, `ptr` must contain the address of a memory
+/// location that can be stored to.
+#[cfg(all(feature = "experimental-relocate", feature = "origin-start"))]
+#[cfg(relocation_model = "pic")]
+#[inline]
+pub(super) unsafe fn relocation_store(ptr: usize, value: usize) {
+    asm!(
+        "mov [{:e}], {:e}",
+        in(reg) ptr,
+        in(reg) value,
+        options(nostack, preserves_flags),
+    );
+}
+
+/// The required alignment for the stack pointer.
+#[cfg(feature = "take-charge")]
+#[cfg(feature = "thread")]
+pub(super) const STACK_ALIGNMENT: usize = 16;
+
+/// A wrapper around the Linux `clone` system call.
+///
+/// This can't be implemented in `rustix` because the child starts executing at
+/// the same point as the parent and we need to use inline asm to have the
+/// child jump to our new-thread entrypoint.
+#[cfg(feature = "take-charge")]
+#[cfg(feature = "thread")]
+#[inline]
+pub(super) unsafe fn clone(
+    flags: u32,
+    child_stack: *mut c_void,
+    parent_tid: *mut RawPid,
+    child_tid: *mut RawPid,
+    newtls: *mut c_void,
+    fn_: extern "C" fn(),
+    num_args: usize,
+) -> isize {
+    let r0: i64;
+    asm!(
+        "syscall",            // Do the `clone` system call.
+        "test eax, eax",      // Branch if we're in the parent thread.
+        "jnz 2f",
+
+        // Child thread.
+        "mov rdi, r9",        // Pass `fn_` as the first argument.
+        "mov rsi, rsp",       // Pass the args pointer as the second argument.
+        "mov rdx, r12",       // Pass `num_args` as the third argument.
+        "xor ebp, ebp",       // Zero the frame address.
+        "push rax",           // Zero the return address.
+        "jmp {entry}",        // Call `entry`.
+
+        // Parent thread.
+        "2:",
+
+        entry = sym super::thread::entry,
+        inlateout("rax") __NR_clone as u64 => r0,
+        in("rdi") flags as u64,
+        in("rsi") child_stack as u64,
+        in("rdx") parent_tid as u64,
+        in("r10") child_tid as u64,
+        in("r8") newtls as u64,
+        in("r9") fn_ as usize as u64,
+        in("r12") num_args as u64,
+        lateout("rcx") _,
+        lateout("r11") _,
+        options(nostack)
+    );
+    r0 as isize
+}
+
+/// A wrapper around the Linux `clone` system call for creating a
+/// `vfork`-style child which immediately calls `execve`.
+///
+/// `flags` should include `CLONE_VM` and `CLONE_VFORK`, and the child runs on
+/// the parent's stack, so the child does nothing except the `execve`, and if
+/// that fails, it stores the error code to `exec_errno` and exits. The child
+/// doesn't touch the stack at all.
+#[cfg(feature = "take-charge")]
+#[inline]
+pub(super) unsafe fn vfork_execve(
+    flags: u32,
+    path: *const u8,
+    argv: *const *const u8,
+    envp: *const *const u8,
+    exec_errno: *mut i32,
+) -> isize {
+    assert_eq!(__NR_execve, 0x4000_0208); // TODO: obviate this
+    assert_eq!(__NR_exit, 0x4000_003c); // TODO: obviate this
+    let r0: i64;
+    asm!(
+        "syscall",            // Do the `clone` system call.
+        "test eax, eax",      // Branch if we're in the parent.
+        "jnz 2f",
+
+        // Child process.
+        "mov rdi, {path}",    // Pass `path` as the first argument.
+        "mov rsi, {argv}",    // Pass `argv` as the second argument.
+        "mov rdx, {envp}",    // Pass `envp` as the third argument.
+        "mov eax, 0x40000208", // TODO: use {__NR_execve}
+        "syscall",            // Do the `execve` system call.
+        "mov [{exec_errno}], eax", // If we get here, `execve` failed.
+        "mov edi, 127",       // Exit with status 127, as shells do.
+        "mov eax, 0x4000003c", // TODO: use {__NR_exit}
+        "syscall",
+        "ud2",
+
+        // Parent process.
+        "2:",
+
+        path = in(reg) path as u64,
+        argv = in(reg) argv as u64,
+        envp = in(reg) envp as u64,
+        exec_errno = in(reg) exec_errno as u64,
+        inlateout("rax") __NR_clone as u64 => r0,
+        in("rdi") flags as u64,
+        in("rsi") 0_u64,
+        in("rdx") 0_u64,
+        in("r10") 0_u64,
+        in("r8") 0_u64,
+        out("rcx") _,
+        out("r11") _,
+        options(nostack)
+    );
+    r0 as isize
+}
+
+/// Write a value to the platform thread-pointer register.
+#[cfg(feature = "take-charge")]
+#[cfg(feature = "thread")]
+#[cfg(not(feature = "keep-main-thread-pointer"))]
+#[inline]
+pub(super) unsafe fn set_thread_pointer(ptr: *mut c_void) {
+    rustix::runtime::set_fs(ptr);
+    debug_assert_eq!(*ptr.cast::<*const c_void>(), ptr);
+    debug_assert_eq!(thread_pointer(), ptr);
+}
+
+/// Read the value of the platform thread-pointer register.
+#[cfg(feature = "take-charge")]
+#[cfg(feature = "thread")]
+#[inline]
+pub(super) fn thread_pointer() -> *mut c_void {
+    let ptr;
+    // SAFETY: On x32, reading the thread register itself is expensive, so
+    // the ABI specifies that the thread pointer value is also stored in memory
+    // at offset 0 from the thread pointer value, where it can be read with
+    // just a load.
+    unsafe {
+        asm!("mov {:e}, fs:0", out(reg) ptr, options(nostack, preserves_flags, readonly));
+    }
+    ptr
+}
+
+/// TLS data ends at the location pointed to by the thread pointer.
+#[cfg(feature = "take-charge")]
+#[cfg(feature = "thread")]
+pub(super) const TLS_OFFSET: usize = 0;
+
+/// The offset of `uc_sigmask` in the `ucontext` passed to `SA_SIGINFO` signal
+/// handlers, following `uc_flags`, `uc_link`, `uc_stack`, padding, and `uc_mcontext`.
+#[cfg(feature = "take-charge")]
+#[cfg(all(feature = "signal", feature = "thread-registry"))]
+pub(super) const UCONTEXT_SIGMASK_OFFSET: usize = 280;
+
+/// The offsets of the saved program counter and stack pointer, `rip` and `rsp`
+/// in `uc_mcontext`, in the `ucontext` passed to `SA_SIGINFO` signal handlers.
+#[cfg(feature = "signal")]
+pub(super) const UCONTEXT_PC_OFFSET: usize = 152;
+#[cfg(feature = "signal")]
+pub(super) const UCONTEXT_SP_OFFSET: usize = 144;
+
+// Raw system calls, for the few places where origin can't use rustix, such
+// as while relocating itself, or for system calls that rustix doesn't wrap.
+// These return the raw return value of the system call, which is a negated
+// errno value on failure.
+
+/// Make a system call with 1 argument.
+///
+/// # Safety
+///
+/// The system call must be safe to make with the given arguments.
+#[cfg(feature = "take-charge")]
+#[inline]
+pub(super) unsafe fn syscall1(nr: u32, a0: usize) -> isize {
+    let r0: i64;
+    asm!(
+        "syscall",
+        inlateout("rax") nr as u64 => r0,
+        in("rdi") a0 as u64,
+        lateout("rcx") _,
+        lateout("r11") _,
+        options(nostack, preserves_flags)
+    );
+    r0 as isize
+}
+
+/// Make a system call with 2 arguments.
+///
+/// # Safety
+///
+/// The system call must be safe to make with the given arguments.
+#[cfg(feature = "take-charge")]
+#[inline]
+pub(super) unsafe fn syscall2(nr: u32, a0: usize, a1: usize) -> isize {
+    let r0: i64;
+    asm!(
+        "syscall",
+        inlateout("rax") nr as u64 => r0,
+        in("rdi") a0 as u64,
+        in("rsi") a1 as u64,
+        lateout("rcx") _,
+        lateout("r11") _,
+        options(nostack, preserves_flags)
+    );
+    r0 as isize
+}
+
+/// Make a system call with 3 arguments.
+///
+/// # Safety
+///
+/// The system call must be safe to make with the given arguments.
+#[cfg(feature = "take-charge")]
+#[cfg(any(
+    feature = "thread",
+    feature = "seccomp",
+    all(feature = "experimental-relocate", feature = "origin-start")
+))]
+#[cfg_attr(
+    not(any(feature = "thread", feature = "seccomp")),
+    cfg(relocation_model = "pic")
+)]
+#[inline]
+pub(super) unsafe fn syscall3(nr: u32, a0: usize, a1: usize, a2: usize) -> isize {
+    let r0: i64;
+    asm!(
+        "syscall",
+        inlateout("rax") nr as u64 => r0,
+        in("rdi") a0 as u64,
+        in("rsi") a1 as u64,
+        in("rdx") a2 as u64,
+        lateout("rcx") _,
+        lateout("r11") _,
+        options(nostack, preserves_flags)
+    );
+    r0 as isize
+}
+
+/// Make a system call with 4 arguments.
+///
+/// # Safety
+///
+/// The system call must be safe to make with the given arguments.
+#[cfg(feature = "take-charge")]
+#[cfg(feature = "thread")]
+#[inline]
+pub(super) unsafe fn syscall4(nr: u32, a0: usize, a1: usize, a2: usize, a3: usize) -> isize {
+    let r0: i64;
+    asm!(
+        "syscall",
+        inlateout("rax") nr as u64 => r0,
+        in("rdi") a0 as u64,
+        in("rsi") a1 as u64,
+        in("rdx") a2 as u64,
+        in("r10") a3 as u64,
+        lateout("rcx") _,
+        lateout("r11") _,
+        options(nostack, preserves_flags)
+    );
+    r0 as isize
+}
+
+/// Make a system call with 5 arguments.
+///
+/// # Safety
+///
+/// The system call must be safe to make with the given arguments.
+#[cfg(feature = "take-charge")]
+#[cfg(feature = "thread")]
+#[inline]
+pub(super) unsafe fn syscall5(
+    nr: u32,
+    a0: usize,
+    a1: usize,
+    a2: usize,
+    a3: usize,
+    a4: usize,
+) -> isize {
+    let r0: i64;
+    asm!(
+        "syscall",
+        inlateout("rax") nr as u64 => r0,
+        in("rdi") a0 as u64,
+        in("rsi") a1 as u64,
+        in("rdx") a2 as u64,
+        in("r10") a3 as u64,
+        in("r8") a4 as u64,
+        lateout("rcx") _,
+        lateout("r11") _,
+        options(nostack, preserves_flags)
+    );
+    r0 as isize
+}
+
+/// Make a system call with 6 arguments.
+///
+/// # Safety
+///
+/// The system call must be safe to make with the given arguments.
+#[cfg(feature = "take-charge")]
+#[cfg(feature = "thread")]
+#[inline]
+pub(super) unsafe fn syscall6(
+    nr: u32,
+    a0: usize,
+    a1: usize,
+    a2: usize,
+    a3: usize,
+    a4: usize,
+    a5: usize,
+) -> isize {
+    let r0: i64;
+    asm!(
+        "syscall",
+        inlateout("rax") nr as u64 => r0,
+        in("rdi") a0 as u64,
+        in("rsi") a1 as u64,
+        in("rdx") a2 as u64,
+        in("r10") a3 as u64,
+        in("r8") a4 as u64,
+        in("r9") a5 as u64,
+        lateout("rcx") _,
+        lateout("r11") _,
+        options(nostack, preserves_flags)
+    );
+    r0 as isize
+}
+
+/// `munmap` the current thread, then carefully exit the thread without
+/// touching the deallocated stack.
+#[cfg(feature = "take-charge")]
+#[cfg(feature = "thread")]
+#[inline]
+pub(super) unsafe fn munmap_and_exit_thread(map_addr: *mut c_void, map_len: usize) -> ! {
+    assert_eq!(__NR_exit, 0x4000_003c); // TODO: obviate this
+    asm!(
+        "syscall",
+        "xor edi, edi",
+        "mov eax, 0x4000003c", // TODO: use {__NR_exit}
+        "syscall",
+        "ud2",
+        //__NR_exit = const __NR_exit, // TODO: Use this when `asm_const` is stabilized.
+        in("rax") __NR_munmap as u64,
+        in("rdi") map_addr as u64,
+        in("rsi") map_len as u64,
+        options(noreturn, nostack)
+    );
+}
+
+#[cfg(feature = "take-charge")]
+#[cfg(feature = "signal")]
+naked_fn!(
+    "
+    Invoke the `__NR_rt_sigreturn` system call to return control from a signal
+    handler.
+
+    # Safety
+
+    This function must never be called other than by the `sa_restorer`
+    mechanism.
+    ";
+    pub(super) fn return_from_signal_handler() -> ();
+
+    "mov eax, 0x40000201", // TODO: use {__NR_rt_sigreturn}
+    "syscall",
+    "ud2";
+    //__NR_rt_sigreturn = const __NR_rt_sigreturn // TODO: Use this when `asm_const` is stabilized.
+);
+#[cfg(feature = "take-charge")]
+#[test] // TODO: obviate this
+fn test_rt_sigreturn() {
+    assert_eq!(__NR_rt_sigreturn, 0x4000_0201);
+}
+
+/// Invoke the appropriate system call to return control from a signal
+/// handler that does not use `SA_SIGINFO`. On x32, this uses the same
+/// sequence as the `SA_SIGINFO` case.
+///
+/// # Safety
+///
+/// This function must never be called other than by the `sa_restorer`
+/// mechanism.
+#[cfg(feature = "take-charge")]
+#[cfg(feature = "signal")]
+pub(super) use return_from_signal_handler as return_from_signal_handler_noinfo;
@@ -6,6 +6,7 @@
 use crate::ptr::{without_provenance_mut, Polyfill as _};
 #[cfg(any(
     feature = "take-charge",
+    feature = "timestamp-counter",
     all(not(feature = "unwinding"), feature = "panic-handler-trap")
 ))]
 use core::arch::asm;
@@ -16,20 +17,19 @@ use core::ptr::without_provenance_mut;
 #[cfg(all(feature = "experimental-relocate", feature = "origin-start"))]
 #[cfg(relocation_model = "pic")]
 use linux_raw_sys::elf::{Elf_Dyn, Elf_Ehdr};
-#[cfg(all(feature = "experimental-relocate", feature = "origin-start"))]
-#[cfg(relocation_model = "pic")]
-use linux_raw_sys::general::{__NR_mprotect, PROT_READ};
 #[cfg(feature = "take-charge")]
 #[cfg(feature = "signal")]
 #[cfg(test)]
 use linux_raw_sys::general::{__NR_rt_sigreturn, __NR_sigreturn};
 #[cfg(feature = "take-charge")]
-#[cfg(feature = "thread")]
 use {
     core::ffi::c_void,
-    linux_raw_sys::general::{__NR_clone, __NR_exit, __NR_munmap},
-    rustix::thread::RawPid,
+    core::ptr::null_mut,
+    linux_raw_sys::general::{__NR_clone, __NR_execve, __NR_exit},
 };
+#[cfg(feature = "take-charge")]
+#[cfg(feature = "thread")]
+use {linux_raw_sys::general::__NR_munmap, rustix::thread::RawPid};
 
 #[cfg(feature = "origin-start")]
 naked_fn!(
@@ -47,14 +47,18 @@ naked_fn!(
     // Jump to `entry`, passing it the initial stack pointer value as an
     // argument, a null return address, a null frame pointer, and an aligned
     // stack pointer. On many architectures, the incoming frame pointer is
-    // already null.
+    // already null. Mark the return address as undefined in the CFI, so that
+    // debuggers and unwinders treat this as the outermost frame.
+    ".cfi_startproc",
+    ".cfi_undefined eip",
     "mov eax, esp", // Save the incoming `esp` value.
     "push ebp",     // Pad for stack pointer alignment.
     "push ebp",     // Pad for stack pointer alignment.
     "push ebp",     // Pad for stack pointer alignment.
     "push eax",     // Pass saved the incoming `esp` as the arg to `entry`.
     "push ebp",     // Set the return address to zero.
-    "jmp {entry}";  // Jump to `entry`.
+    "jmp {entry}",  // Jump to `entry`.
+    ".cfi_endproc";
     entry = sym super::program::entry
 );
 
@@ -71,6 +75,23 @@ pub(super) fn trap() -> ! {
     }
 }
 
+/// Read the timestamp counter, using `rdtsc`.
+#[cfg(feature = "timestamp-counter")]
+#[inline]
+pub(super) fn timestamp_counter() -> u64 {
+    let lo: u32;
+    let hi: u32;
+    unsafe {
+        asm!(
+            "rdtsc",
+            out("eax") lo,
+            out("edx") hi,
+            options(nostack, preserves_flags)
+        );
+    }
+    (u64::from(hi) << 32) | u64::from(lo)
+}
+
 /// Compute the dynamic address of `_DYNAMIC`.
 #[cfg(all(feature = "experimental-relocate", feature = "origin-start"))]
 #[cfg(relocation_model = "pic")]
@@ -182,47 +203,6 @@ pub(super) unsafe fn relocation_store(ptr: usize, value: usize) {
     );
 }
 
-/// Mark “relro” memory as readonly.
-///
-/// “relro” is a relocation feature in which memory can be readonly after
-/// relocations are applied.
-///
-/// This function conceptually casts `ptr` to a `*mut c_void` and does a
-/// `rustix::mm::mprotect(ptr, len, MprotectFlags::READ)`. However, it does
-/// this using `asm` and `usize` types which don't carry provenance, as it's
-/// used by `relocate` to implement the “relro” feature which cannot be
-/// expressed in the Rust memory model.
-///
-/// # Safety
-///
-/// This function must only be called during the relocation process, for
-/// relocation purposes. And, `ptr` must contain the address of a memory
-/// location that can be marked readonly.
-#[cfg(all(feature = "experimental-relocate", feature = "origin-start"))]
-#[cfg(relocation_model = "pic")]
-#[inline]
-pub(super) unsafe fn relocation_mprotect_readonly(ptr: usize, len: usize) {
-    let r0: usize;
-
-    // This is read-only but we don't use `readonly` because the side effects
-    // happen outside the Rust memory model. As far as Rust knows, this is
-    // just an arbitrary side-effecting opaque operation.
-    asm!(
-        "int 0x80",
-        inlateout("eax") __NR_mprotect as usize => r0,
-        in("ebx") ptr,
-        in("ecx") len,
-        in("edx") PROT_READ,
-        options(nostack, preserves_flags),
-    );
-
-    if r0 != 0 {
-        // Do not panic here as libstd's panic handler needs TLS, which is not
-        // yet initialized at this point.
-        trap();
-    }
-}
-
 /// The required alignment for the stack pointer.
 #[cfg(feature = "take-charge")]
 #[cfg(feature = "thread")]
@@ -321,9 +301,70 @@ pub(super) unsafe fn clone(
     r0
 }
 
+/// A wrapper around the Linux `clone` system call for creating a
+/// `vfork`-style child which immediately calls `execve`.
+///
+/// `flags` should include `CLONE_VM` and `CLONE_VFORK`, and the child runs on
+/// the parent's stack, so the child does nothing except the `execve`, and if
+/// that fails, it stores the error code to `exec_errno` and exits. The child
+/// doesn't touch the stack at all.
+#[cfg(feature = "take-charge")]
+#[inline]
+pub(super) unsafe fn vfork_execve(
+    flags: u32,
+    path: *const u8,
+    argv: *const *const u8,
+    envp: *const *const u8,
+    exec_errno: *mut i32,
+) -> isize {
+    assert_eq!(__NR_execve, 11); // TODO: obviate this
+    assert_eq!(__NR_exit, 1); // TODO: obviate this
+
+    // There aren't enough free registers to pass all the `execve` arguments
+    // through the `clone`, so pass them in memory. The child shares our
+    // memory, and we're suspended until it calls `execve` or exits, so it can
+    // read them from our stack.
+    let args: [*const c_void; 4] = [path.cast(), argv.cast(), envp.cast(), exec_errno.cast()];
+
+    let r0;
+    asm!(
+        // Use `int 0x80` instead of vsyscall, following `clone`'s
+        // documentation; vsyscall would attempt to return to the parent stack
+        // in the child.
+        "int 0x80",           // Do the `clone` system call.
+        "test eax, eax",      // Branch if we're in the parent.
+        "jnz 2f",
+
+        // Child process.
+        "mov ebx, [edi+0]",   // Pass `path` as the first argument.
+        "mov ecx, [edi+4]",   // Pass `argv` as the second argument.
+        "mov edx, [edi+8]",   // Pass `envp` as the third argument.
+        "mov eax, 11",        // TODO: use {__NR_execve}
+        "int 0x80",           // Do the `execve` system call.
+        "mov edi, [edi+12]",  // If we get here, `execve` failed.
+        "mov [edi], eax",
+        "mov ebx, 127",       // Exit with status 127, as shells do.
+        "mov eax, 1",         // TODO: use {__NR_exit}
+        "int 0x80",
+        "ud2",
+
+        // Parent process.
+        "2:",
+
+        inlateout("eax") __NR_clone as usize => r0,
+        in("ebx") flags,
+        in("ecx") null_mut::<c_void>(),
+        in("edx") null_mut::<c_void>(),
+        in("edi") args.as_ptr(),
+        options(nostack)
+    );
+    r0
+}
+
 /// Write a value to the platform thread-pointer register.
 #[cfg(feature = "take-charge")]
 #[cfg(feature = "thread")]
+#[cfg(not(feature = "keep-main-thread-pointer"))]
 #[inline]
 pub(super) unsafe fn set_thread_pointer(ptr: *mut c_void) {
     let mut user_desc = rustix::runtime::UserDesc {
@@ -344,9 +385,23 @@ pub(super) unsafe fn set_thread_pointer(ptr: *mut c_void) {
     debug_assert_eq!(res, Ok(()));
     debug_assert_ne!(user_desc.entry_number, !0);
 
+    // If we failed to get a segment, TLS accesses will crash in confusing
+    // ways later on, so with "checked-thread-pointer", stop now.
+    #[cfg(feature = "checked-thread-pointer")]
+    if res.is_err() || user_desc.entry_number == !0 {
+        trap();
+    }
+
     asm!("mov gs, {0:x}", in(reg) ((user_desc.entry_number << 3) | 3) as u16);
     debug_assert_eq!(*ptr.cast::<*const c_void>(), ptr);
     debug_assert_eq!(thread_pointer(), ptr);
+
+    // Check that the segment is usable, by reading the self-pointer at the
+    // start of the thread data through it.
+    #[cfg(feature = "checked-thread-pointer")]
+    if thread_pointer() != ptr {
+        trap();
+    }
 }
 
 /// Read the value of the platform thread-pointer register.
@@ -370,6 +425,185 @@ pub(super) fn thread_pointer() -> *mut c_void {
 #[cfg(feature = "thread")]
 pub(super) const TLS_OFFSET: usize = 0;
 
+/// The offset of `uc_sigmask` in the `ucontext` passed to `SA_SIGINFO` signal
+/// handlers, following `uc_flags`, `uc_link`, `uc_stack`, and `uc_mcontext`.
+#[cfg(feature = "take-charge")]
+#[cfg(all(feature = "signal", feature = "thread-registry"))]
+pub(super) const UCONTEXT_SIGMASK_OFFSET: usize = 108;
+
+/// The offsets of the saved program counter and stack pointer, `ip` and `sp` in
+/// `uc_mcontext`, in the `ucontext` passed to `SA_SIGINFO` signal handlers.
+#[cfg(feature = "signal")]
+pub(super) const UCONTEXT_PC_OFFSET: usize = 76;
+#[cfg(feature = "signal")]
+pub(super) const UCONTEXT_SP_OFFSET: usize = 48;
+
+// Raw system calls, for the few places where origin can't use rustix, such as
+// while relocating itself, or for system calls that rustix doesn't wrap. These
+// return the raw return value of the system call, which is a negated errno
+// value on failure. LLVM reserves `esi` and `ebp`, so arguments for those
+// registers are swapped or loaded in around the `int 0x80`.
+
+/// Make a system call with 1 argument.
+///
+/// # Safety
+///
+/// The system call must be safe to make with the given arguments.
+#[cfg(feature = "take-charge")]
+#[inline]
+pub(super) unsafe fn syscall1(nr: u32, a0: usize) -> isize {
+    let r0;
+    asm!(
+        "int 0x80",
+        inlateout("eax") nr as usize => r0,
+        in("ebx") a0,
+        options(nostack, preserves_flags)
+    );
+    r0
+}
+
+/// Make a system call with 2 arguments.
+///
+/// # Safety
+///
+/// The system call must be safe to make with the given arguments.
+#[cfg(feature = "take-charge")]
+#[inline]
+pub(super) unsafe fn syscall2(nr: u32, a0: usize, a1: usize) -> isize {
+    let r0;
+    asm!(
+        "int 0x80",
+        inlateout("eax") nr as usize => r0,
+        in("ebx") a0,
+        in("ecx") a1,
+        options(nostack, preserves_flags)
+    );
+    r0
+}
+
+/// Make a system call with 3 arguments.
+///
+/// # Safety
+///
+/// The system call must be safe to make with the given arguments.
+#[cfg(feature = "take-charge")]
+#[cfg(any(
+    feature = "thread",
+    feature = "seccomp",
+    all(feature = "experimental-relocate", feature = "origin-start")
+))]
+#[cfg_attr(
+    not(any(feature = "thread", feature = "seccomp")),
+    cfg(relocation_model = "pic")
+)]
+#[inline]
+pub(super) unsafe fn syscall3(nr: u32, a0: usize, a1: usize, a2: usize) -> isize {
+    let r0;
+    asm!(
+        "int 0x80",
+        inlateout("eax") nr as usize => r0,
+        in("ebx") a0,
+        in("ecx") a1,
+        in("edx") a2,
+        options(nostack, preserves_flags)
+    );
+    r0
+}
+
+/// Make a system call with 4 arguments.
+///
+/// # Safety
+///
+/// The system call must be safe to make with the given arguments.
+#[cfg(feature = "take-charge")]
+#[cfg(feature = "thread")]
+#[inline]
+pub(super) unsafe fn syscall4(nr: u32, a0: usize, a1: usize, a2: usize, a3: usize) -> isize {
+    let r0;
+    asm!(
+        "xchg esi, {a3}",
+        "int 0x80",
+        "xchg esi, {a3}",
+        a3 = in(reg) a3,
+        inlateout("eax") nr as usize => r0,
+        in("ebx") a0,
+        in("ecx") a1,
+        in("edx") a2,
+        options(nostack, preserves_flags)
+    );
+    r0
+}
+
+/// Make a system call with 5 arguments.
+///
+/// # Safety
+///
+/// The system call must be safe to make with the given arguments.
+#[cfg(feature = "take-charge")]
+#[cfg(feature = "thread")]
+#[inline]
+pub(super) unsafe fn syscall5(
+    nr: u32,
+    a0: usize,
+    a1: usize,
+    a2: usize,
+    a3: usize,
+    a4: usize,
+) -> isize {
+    let r0;
+    asm!(
+        "push esi",
+        "mov esi, [eax]",
+        "mov eax, [eax + 4]",
+        "int 0x80",
+        "pop esi",
+        inlateout("eax") [a3, nr as usize].as_ptr() => r0,
+        in("ebx") a0,
+        in("ecx") a1,
+        in("edx") a2,
+        in("edi") a4,
+        options(preserves_flags)
+    );
+    r0
+}
+
+/// Make a system call with 6 arguments.
+///
+/// # Safety
+///
+/// The system call must be safe to make with the given arguments.
+#[cfg(feature = "take-charge")]
+#[cfg(feature = "thread")]
+#[inline]
+pub(super) unsafe fn syscall6(
+    nr: u32,
+    a0: usize,
+    a1: usize,
+    a2: usize,
+    a3: usize,
+    a4: usize,
+    a5: usize,
+) -> isize {
+    let r0;
+    asm!(
+        "push ebp",
+        "push esi",
+        "mov esi, [eax]",
+        "mov ebp, [eax + 4]",
+        "mov eax, [eax + 8]",
+        "int 0x80",
+        "pop esi",
+        "pop ebp",
+        inlateout("eax") [a3, a5, nr as usize].as_ptr() => r0,
+        in("ebx") a0,
+        in("ecx") a1,
+        in("edx") a2,
+        in("edi") a4,
+        options(preserves_flags)
+    );
+    r0
+}
+
 /// `munmap` the current thread, then carefully exit the thread without
 /// touching the deallocated stack.
 #[cfg(feature = "take-charge")]
@@ -2,6 +2,7 @@
 
 #[cfg(any(
     feature = "take-charge",
+    feature = "timestamp-counter",
     all(not(feature = "unwinding"), feature = "panic-handler-trap")
 ))]
 use core::arch::asm;
@@ -12,16 +13,15 @@ use linux_raw_sys::elf::{Elf_Dyn, Elf_Ehdr};
 #[cfg(feature = "signal")]
 #[cfg(test)]
 use linux_raw_sys::general::__NR_rt_sigreturn;
-#[cfg(all(feature = "experimental-relocate", feature = "origin-start"))]
-#[cfg(relocation_model = "pic")]
-use linux_raw_sys::general::{__NR_mprotect, PROT_READ};
 #[cfg(feature = "take-charge")]
-#[cfg(feature = "thread")]
 use {
     core::ffi::c_void,
-    linux_raw_sys::general::{__NR_clone, __NR_exit, __NR_munmap},
-    rustix::thread::RawPid,
+    core::ptr::null_mut,
+    linux_raw_sys::general::{__NR_clone, __NR_execve, __NR_exit},
 };
+#[cfg(feature = "take-charge")]
+#[cfg(feature = "thread")]
+use {linux_raw_sys::general::__NR_munmap, rustix::thread::RawPid};
 
 #[cfg(feature = "origin-start")]
 naked_fn!(
@@ -39,13 +39,48 @@ naked_fn!(
     // Jump to `entry`, passing it the initial stack pointer value as an
     // argument, a null return address, a null frame pointer, and an aligned
     // stack pointer. On many architectures, the incoming frame pointer is
-    // already null.
+    // already null. Mark the return address as undefined in the CFI, so that
+    // debuggers and unwinders treat this as the outermost frame.
+    ".cfi_startproc",
+    ".cfi_undefined rip",
     "mov rdi, rsp", // Pass the incoming `rsp` as the arg to `entry`.
     "push rbp",     // Set the return address to zero.
-    "jmp {entry}";  // Jump to `entry`.
+    "jmp {entry}",  // Jump to `entry`.
+    ".cfi_endproc";
     entry = sym super::program::entry
 );
 
+// Mark the executable as compatible with CET Indirect Branch Tracking and
+// shadow stacks.
+//
+// `_start` and the signal return trampolines begin with landing pads from
+// `naked_fn!`. The rest of origin's assembly code is only reached by direct
+// branches, including the child's jump to `entry` in `clone`, which
+// continues after the `syscall` rather than being branched to. None of it
+// returns to an address that wasn't pushed by a `call`; `_start` and the
+// `clone` child push a zero return address, but `entry` never returns. The
+// kernel gives each thread created by `clone` its own shadow stack, and
+// `rt_sigreturn` restores the shadow stack when returning from a signal
+// handler.
+//
+// The linker only marks the executable if every object file linked into it
+// has these properties, so the rest of the program must be compiled with
+// `-Z cf-protection=full` too.
+#[cfg(all(feature = "cet", feature = "origin-start"))]
+core::arch::global_asm!(
+    ".pushsection .note.gnu.property, \"a\", @note",
+    ".p2align 3",
+    ".long 4",  // The size of the name, including the NUL.
+    ".long 16", // The size of the descriptor.
+    ".long 5",  // `NT_GNU_PROPERTY_TYPE_0`
+    ".asciz \"GNU\"",
+    ".long 0xc0000002", // `GNU_PROPERTY_X86_FEATURE_1_AND`
+    ".long 4",          // The size of the property value.
+    ".long 3",          // `GNU_PROPERTY_X86_FEATURE_1_IBT | GNU_PROPERTY_X86_FEATURE_1_SHSTK`
+    ".long 0",          // Padding to 8-byte alignment.
+    ".popsection",
+);
+
 /// Execute a trap instruction.
 ///
 /// This is roughly equivalent to `core::intrinsics::abort()`.
@@ -59,6 +94,23 @@ pub(super) fn trap() -> ! {
     }
 }
 
+/// Read the timestamp counter, using `rdtsc`.
+#[cfg(feature = "timestamp-counter")]
+#[inline]
+pub(super) fn timestamp_counter() -> u64 {
+    let lo: u32;
+    let hi: u32;
+    unsafe {
+        asm!(
+            "rdtsc",
+            out("eax") lo,
+            out("edx") hi,
+            options(nostack, preserves_flags)
+        );
+    }
+    (u64::from(hi) << 32) | u64::from(lo)
+}
+
 /// Compute the dynamic address of `_DYNAMIC`.
 #[cfg(all(feature = "experimental-relocate", feature = "origin-start"))]
 #[cfg(relocation_model = "pic")]
@@ -145,49 +197,6 @@ pub(super) unsafe fn relocation_store(ptr: usize, value: usize) {
     );
 }
 
-/// Mark “relro” memory as readonly.
-///
-/// “relro” is a relocation feature in which memory can be readonly after
-/// relocations are applied.
-///
-/// This function conceptually casts `ptr` to a `*mut c_void` and does a
-/// `rustix::mm::mprotect(ptr, len, MprotectFlags::READ)`. However, it does
-/// this using `asm` and `usize` types which don't carry provenance, as it's
-/// used by `relocate` to implement the “relro” feature which cannot be
-/// expressed in the Rust memory model.
-///
-/// # Safety
-///
-/// This function must only be called during the relocation process, for
-/// relocation purposes. And, `ptr` must contain the address of a memory
-/// location that can be marked readonly.
-#[cfg(all(feature = "experimental-relocate", feature = "origin-start"))]
-#[cfg(relocation_model = "pic")]
-#[inline]
-pub(super) unsafe fn relocation_mprotect_readonly(ptr: usize, len: usize) {
-    let r0: usize;
-
-    // This is read-only but we don't use `readonly` because the side effects
-    // happen outside the Rust memory model. As far as Rust knows, this is
-    // just an arbitrary side-effecting opaque operation.
-    asm!(
-        "syscall",
-        inlateout("rax") __NR_mprotect as usize => r0,
-        in("rdi") ptr,
-        in("rsi") len,
-        in("rdx") PROT_READ,
-        lateout("rcx") _,
-        lateout("r11") _,
-        options(nostack, preserves_flags),
-    );
-
-    if r0 != 0 {
-        // Do not panic here as libstd's panic handler needs TLS, which is not
-        // yet initialized at this point.
-        trap();
-    }
-}
-
 /// The required alignment for the stack pointer.
 #[cfg(feature = "take-charge")]
 #[cfg(feature = "thread")]
@@ -243,9 +252,66 @@ pub(super) unsafe fn clone(
     r0
 }
 
+/// A wrapper around the Linux `clone` system call for creating a
+/// `vfork`-style child which immediately calls `execve`.
+///
+/// `flags` should include `CLONE_VM` and `CLONE_VFORK`, and the child runs on
+/// the parent's stack, so the child does nothing except the `execve`, and if
+/// that fails, it stores the error code to `exec_errno` and exits. The child
+/// doesn't touch the stack at all.
+#[cfg(feature = "take-charge")]
+#[inline]
+pub(super) unsafe fn vfork_execve(
+    flags: u32,
+    path: *const u8,
+    argv: *const *const u8,
+    envp: *const *const u8,
+    exec_errno: *mut i32,
+) -> isize {
+    assert_eq!(__NR_execve, 59); // TODO: obviate this
+    assert_eq!(__NR_exit, 60); // TODO: obviate this
+    let r0;
+    asm!(
+        "syscall",            // Do the `clone` system call.
+        "test eax, eax",      // Branch if we're in the parent.
+        "jnz 2f",
+
+        // Child process.
+        "mov rdi, {path}",    // Pass `path` as the first argument.
+        "mov rsi, {argv}",    // Pass `argv` as the second argument.
+        "mov rdx, {envp}",    // Pass `envp` as the third argument.
+        "mov eax, 59",        // TODO: use {__NR_execve}
+        "syscall",            // Do the `execve` system call.
+        "mov [{exec_errno}], eax", // If we get here, `execve` failed.
+        "mov edi, 127",       // Exit with status 127, as shells do.
+        "mov eax, 60",        // TODO: use {__NR_exit}
+        "syscall",
+        "ud2",
+
+        // Parent process.
+        "2:",
+
+        path = in(reg) path,
+        argv = in(reg) argv,
+        envp = in(reg) envp,
+        exec_errno = in(reg) exec_errno,
+        inlateout("rax") __NR_clone as usize => r0,
+        in("rdi") flags,
+        in("rsi") null_mut::<c_void>(),
+        in("rdx") null_mut::<c_void>(),
+        in("r10") null_mut::<c_void>(),
+        in("r8") null_mut::<c_void>(),
+        out("rcx") _,
+        out("r11") _,
+        options(nostack)
+    );
+    r0
+}
+
 /// Write a value to the platform thread-pointer register.
 #[cfg(feature = "take-charge")]
 #[cfg(feature = "thread")]
+#[cfg(not(feature = "keep-main-thread-pointer"))]
 #[inline]
 pub(super) unsafe fn set_thread_pointer(ptr: *mut c_void) {
     rustix::runtime::set_fs(ptr);
@@ -274,6 +340,186 @@ pub(super) fn thread_pointer() -> *mut c_void {
 #[cfg(feature = "thread")]
 pub(super) const TLS_OFFSET: usize = 0;
 
+/// The offset of `uc_sigmask` in the `ucontext` passed to `SA_SIGINFO` signal
+/// handlers, following `uc_flags`, `uc_link`, `uc_stack`, and `uc_mcontext`.
+#[cfg(feature = "take-charge")]
+#[cfg(all(feature = "signal", feature = "thread-registry"))]
+pub(super) const UCONTEXT_SIGMASK_OFFSET: usize = 296;
+
+/// The offsets of the saved program counter and stack pointer, `rip` and `rsp`
+/// in `uc_mcontext`, in the `ucontext` passed to `SA_SIGINFO` signal handlers.
+#[cfg(feature = "signal")]
+pub(super) const UCONTEXT_PC_OFFSET: usize = 168;
+#[cfg(feature = "signal")]
+pub(super) const UCONTEXT_SP_OFFSET: usize = 160;
+
+// Raw system calls, for the few places where origin can't use rustix, such
+// as while relocating itself, or for system calls that rustix doesn't wrap.
+// These return the raw return value of the system call, which is a negated
+// errno value on failure.
+
+/// Make a system call with 1 argument.
+///
+/// # Safety
+///
+/// The system call must be safe to make with the given arguments.
+#[cfg(feature = "take-charge")]
+#[inline]
+pub(super) unsafe fn syscall1(nr: u32, a0: usize) -> isize {
+    let r0;
+    asm!(
+        "syscall",
+        inlateout("rax") nr as usize => r0,
+        in("rdi") a0,
+        lateout("rcx") _,
+        lateout("r11") _,
+        options(nostack, preserves_flags)
+    );
+    r0
+}
+
+/// Make a system call with 2 arguments.
+///
+/// # Safety
+///
+/// The system call must be safe to make with the given arguments.
+#[cfg(feature = "take-charge")]
+#[inline]
+pub(super) unsafe fn syscall2(nr: u32, a0: usize, a1: usize) -> isize {
+    let r0;
+    asm!(
+        "syscall",
+        inlateout("rax") nr as usize => r0,
+        in("rdi") a0,
+        in("rsi") a1,
+        lateout("rcx") _,
+        lateout("r11") _,
+        options(nostack, preserves_flags)
+    );
+    r0
+}
+
+/// Make a system call with 3 arguments.
+///
+/// # Safety
+///
+/// The system call must be safe to make with the given arguments.
+#[cfg(feature = "take-charge")]
+#[cfg(any(
+    feature = "thread",
+    feature = "seccomp",
+    all(feature = "experimental-relocate", feature = "origin-start")
+))]
+#[cfg_attr(
+    not(any(feature = "thread", feature = "seccomp")),
+    cfg(relocation_model = "pic")
+)]
+#[inline]
+pub(super) unsafe fn syscall3(nr: u32, a0: usize, a1: usize, a2: usize) -> isize {
+    let r0;
+    asm!(
+        "syscall",
+        inlateout("rax") nr as usize => r0,
+        in("rdi") a0,
+        in("rsi") a1,
+        in("rdx") a2,
+        lateout("rcx") _,
+        lateout("r11") _,
+        options(nostack, preserves_flags)
+    );
+    r0
+}
+
+/// Make a system call with 4 arguments.
+///
+/// # Safety
+///
+/// The system call must be safe to make with the given arguments.
+#[cfg(feature = "take-charge")]
+#[cfg(feature = "thread")]
+#[inline]
+pub(super) unsafe fn syscall4(nr: u32, a0: usize, a1: usize, a2: usize, a3: usize) -> isize {
+    let r0;
+    asm!(
+        "syscall",
+        inlateout("rax") nr as usize => r0,
+        in("rdi") a0,
+        in("rsi") a1,
+        in("rdx") a2,
+        in("r10") a3,
+        lateout("rcx") _,
+        lateout("r11") _,
+        options(nostack, preserves_flags)
+    );
+    r0
+}
+
+/// Make a system call with 5 arguments.
+///
+/// # Safety
+///
+/// The system call must be safe to make with the given arguments.
+#[cfg(feature = "take-charge")]
+#[cfg(feature = "thread")]
+#[inline]
+pub(super) unsafe fn syscall5(
+    nr: u32,
+    a0: usize,
+    a1: usize,
+    a2: usize,
+    a3: usize,
+    a4: usize,
+) -> isize {
+    let r0;
+    asm!(
+        "syscall",
+        inlateout("rax") nr as usize => r0,
+        in("rdi") a0,
+        in("rsi") a1,
+        in("rdx") a2,
+        in("r10") a3,
+        in("r8") a4,
+        lateout("rcx") _,
+        lateout("r11") _,
+        options(nostack, preserves_flags)
+    );
+    r0
+}
+
+/// Make a system call with 6 arguments.
+///
+/// # Safety
+///
+/// The system call must be safe to make with the given arguments.
+#[cfg(feature = "take-charge")]
+#[cfg(feature = "thread")]
+#[inline]
+pub(super) unsafe fn syscall6(
+    nr: u32,
+    a0: usize,
+    a1: usize,
+    a2: usize,
+    a3: usize,
+    a4: usize,
+    a5: usize,
+) -> isize {
+    let r0;
+    asm!(
+        "syscall",
+        inlateout("rax") nr as usize => r0,
+        in("rdi") a0,
+        in("rsi") a1,
+        in("rdx") a2,
+        in("r10") a3,
+        in("r8") a4,
+        in("r9") a5,
+        lateout("rcx") _,
+        lateout("r11") _,
+        options(nostack, preserves_flags)
+    );
+    r0
+}
+
 /// `munmap` the current thread, then carefully exit the thread without
 /// touching the deallocated stack.
 #[cfg(feature = "take-charge")]
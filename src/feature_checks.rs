@@ -0,0 +1,93 @@
+//! Compile-time checks that the enabled features make sense together.
+//!
+//! Cargo features are additive, so Cargo can't express that some features
+//! conflict with each other, or only do anything in combination with others.
+//! Check for those combinations here, so that they produce an error saying
+//! what to change, rather than a confusing error from deeper in the crate, or
+//! a feature that silently has no effect.
+
+// Origin either takes charge of program startup itself, or coexists with
+// libc, so one of those needs to be configured.
+#[cfg(all(
+    feature = "take-charge",
+    not(any(feature = "origin-start", feature = "external-start"))
+))]
+compile_error!(
+    "The \"take-charge\" feature requires either \"origin-start\" or \"external-start\"; enable one of those instead of enabling \"take-charge\" directly."
+);
+
+#[cfg(all(feature = "origin-start", feature = "external-start"))]
+compile_error!(
+    "\"origin-start\" and \"external-start\" can't both be enabled; \"origin-start\" defines the program entrypoint, while \"external-start\" is for programs where something else does and then calls origin's `start`. Note that \"keep-main-thread-pointer\" enables \"external-start\"."
+);
+
+#[cfg(not(any(feature = "take-charge", all(feature = "libc", feature = "errno"))))]
+compile_error!(
+    "Without \"origin-start\" or \"external-start\", origin uses libc, which requires the \"libc\" and \"errno\" features. These are enabled by default; if default features are disabled, either enable them, or enable \"origin-start\" or \"external-start\"."
+);
+
+// Origin's own startup, thread, and signal code are only implemented for
+// Linux on these architectures. This also covers the signal return
+// trampolines, which are per-architecture.
+#[cfg(all(feature = "take-charge", not(target_os = "linux")))]
+compile_error!("\"origin-start\" and \"external-start\" are only supported on Linux.");
+
+#[cfg(all(
+    feature = "take-charge",
+    not(any(
+        target_arch = "x86_64",
+        target_arch = "x86",
+        target_arch = "aarch64",
+        target_arch = "arm",
+        target_arch = "riscv64"
+    ))
+))]
+compile_error!(
+    "\"origin-start\" and \"external-start\" are only supported on x86_64, x86, aarch64, arm, and riscv64."
+);
+
+// Relocation happens in origin's `_start`, before anything else runs, so
+// it's not available when something else starts the program.
+#[cfg(all(feature = "experimental-relocate", not(feature = "origin-start")))]
+compile_error!(
+    "\"experimental-relocate\" requires \"origin-start\", since relocations are performed by origin's program entrypoint."
+);
+
+// BTI and CET landing pads go in origin's `_start`, so they only matter when
+// origin defines it.
+#[cfg(all(feature = "bti", not(feature = "origin-start")))]
+compile_error!(
+    "\"bti\" requires \"origin-start\", since it marks origin's program entrypoint as a branch target."
+);
+
+#[cfg(all(feature = "cet", not(feature = "origin-start")))]
+compile_error!(
+    "\"cet\" requires \"origin-start\", since it marks origin's program entrypoint as a branch target."
+);
+
+/// Error out if `$feature` is enabled without "take-charge", since these
+/// features configure origin's own implementations of things, which libc
+/// provides otherwise.
+macro_rules! requires_take_charge {
+    ($($feature:literal),* $(,)?) => {
+        $(
+            #[cfg(all(feature = $feature, not(feature = "take-charge")))]
+            compile_error!(concat!(
+                "\"",
+                $feature,
+                "\" only has an effect when origin takes charge of the program; enable \"origin-start\" or \"external-start\" too, or disable it."
+            ));
+        )*
+    };
+}
+
+requires_take_charge!(
+    "thread-stats",
+    "thread-registry",
+    "lock-main-tls",
+    "single-threaded",
+    "checked-thread-pointer",
+    "quiesce-threads-before-exit",
+    "getauxval",
+    "ignore-sigpipe",
+);
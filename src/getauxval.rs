@@ -1,12 +1,35 @@
-//! Define a C-compatible `getauxv`.
+//! Define a C-compatible `getauxval`.
 //!
-//! This may be needed to satisfy `compiler_builtins` or other low-level code.
+//! This may be needed to satisfy `compiler_builtins` or other low-level code,
+//! including linked C code, which expects libc to provide it.
 
 #[cfg(not(feature = "nightly"))]
-use crate::ptr::without_provenance_mut;
+use crate::ptr::Polyfill as _;
 use core::ffi::{c_ulong, c_void};
-#[cfg(feature = "nightly")]
-use core::ptr::without_provenance_mut;
+use core::ptr::null_mut;
+use core::sync::atomic::AtomicPtr;
+use core::sync::atomic::Ordering::Relaxed;
+use linux_raw_sys::general::AT_NULL;
+
+/// The address of the aux vector the kernel provided at startup.
+///
+/// Each entry is a pair of a type and a value, and the vector is terminated
+/// by an entry with type `AT_NULL`.
+static AUXV: AtomicPtr<*mut c_void> = AtomicPtr::new(null_mut());
+
+/// Record the address of the aux vector, which follows `envp` in memory.
+///
+/// # Safety
+///
+/// `envp` must point to the NULL-terminated array of environment variables
+/// as provided by the operating system.
+pub(crate) unsafe fn init(envp: *mut *mut u8) {
+    let mut entry = envp;
+    while !(*entry).is_null() {
+        entry = entry.add(1);
+    }
+    AUXV.store(entry.add(1).cast(), Relaxed);
+}
 
 // `getauxval` usually returns `unsigned long`, but we make it a pointer type
 // so that it preserves provenance.
@@ -21,13 +44,30 @@ unsafe extern "C" fn __getauxval(type_: c_ulong) -> *mut c_void {
     _getauxval(type_)
 }
 
+/// Scan the aux vector for an entry with type `type_` and return its value,
+/// or return 0 if there is no such entry.
+///
+/// Unlike libc's `getauxval`, this doesn't set `errno` when there's no
+/// entry.
 fn _getauxval(type_: c_ulong) -> *mut c_void {
-    match type_ as _ {
-        linux_raw_sys::general::AT_HWCAP => without_provenance_mut(rustix::param::linux_hwcap().0),
-        linux_raw_sys::general::AT_HWCAP2 => without_provenance_mut(rustix::param::linux_hwcap().1),
-        linux_raw_sys::general::AT_MINSIGSTKSZ => {
-            without_provenance_mut(rustix::param::linux_minsigstksz())
+    let mut entry = AUXV.load(Relaxed);
+    if entry.is_null() {
+        return null_mut();
+    }
+
+    // SAFETY: `init` recorded the address of the aux vector the kernel
+    // provided, which is never modified and lives for the rest of the
+    // program, and which is terminated by an `AT_NULL` entry.
+    unsafe {
+        loop {
+            let key = (*entry).addr();
+            if key == AT_NULL as usize {
+                return null_mut();
+            }
+            if key == type_ as usize {
+                return *entry.add(1);
+            }
+            entry = entry.add(2);
         }
-        _ => todo!("unrecognized __getauxval {}", type_),
     }
 }
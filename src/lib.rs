@@ -3,6 +3,8 @@
 #![no_std]
 #![cfg_attr(docsrs, feature(doc_cfg))]
 #![cfg_attr(feature = "experimental-relocate", feature(cfg_relocation_model))]
+// With "origin-alloc-error-handler", define an `#[alloc_error_handler]`.
+#![cfg_attr(feature = "origin-alloc-error-handler", feature(alloc_error_handler))]
 // On nightly, enable `#[naked]` functions.
 #![cfg_attr(feature = "nightly", feature(naked_functions))]
 // On nightly, enable llvm intrinsics for additional debug asserts.
@@ -23,6 +25,9 @@ extern crate alloc;
 #[cfg(not(feature = "nightly"))]
 pub(crate) mod ptr;
 
+// Check that the enabled features are consistent with each other.
+mod feature_checks;
+
 // Wrapper/polyfill for `#[naked]`.
 #[macro_use]
 pub(crate) mod naked;
@@ -40,7 +45,14 @@ mod unwind_unimplemented;
 mod stubs;
 
 #[cfg_attr(target_arch = "aarch64", path = "arch/aarch64.rs")]
-#[cfg_attr(target_arch = "x86_64", path = "arch/x86_64.rs")]
+#[cfg_attr(
+    all(target_arch = "x86_64", target_pointer_width = "64"),
+    path = "arch/x86_64.rs"
+)]
+#[cfg_attr(
+    all(target_arch = "x86_64", target_pointer_width = "32"),
+    path = "arch/x32.rs"
+)]
 #[cfg_attr(target_arch = "x86", path = "arch/x86.rs")]
 #[cfg_attr(target_arch = "riscv64", path = "arch/riscv64.rs")]
 #[cfg_attr(target_arch = "arm", path = "arch/arm.rs")]
@@ -51,6 +63,10 @@ mod log;
 #[cfg(relocation_model = "pic")]
 mod relocate;
 
+#[cfg(all(feature = "abi-tag", feature = "origin-start"))]
+#[macro_use]
+mod abi_tag;
+
 #[cfg_attr(feature = "take-charge", path = "program/linux_raw.rs")]
 #[cfg_attr(not(feature = "take-charge"), path = "program/libc.rs")]
 pub mod program;
@@ -10,8 +10,14 @@
 use crate::ptr::Polyfill as _;
 
 // memcpy/memmove/memset have optimized implementations on some architectures
-#[cfg_attr(target_arch = "x86_64", path = "x86_64.rs")]
-#[cfg_attr(not(target_arch = "x86_64"), path = "impls.rs")]
+#[cfg_attr(
+    all(target_arch = "x86_64", target_pointer_width = "64"),
+    path = "x86_64.rs"
+)]
+#[cfg_attr(
+    not(all(target_arch = "x86_64", target_pointer_width = "64")),
+    path = "impls.rs"
+)]
 mod impls;
 
 #[no_mangle]
@@ -289,17 +289,127 @@ pub unsafe fn set_bytes(mut s: *mut u8, c: u8, mut n: usize) {
 }
 
 #[inline(always)]
-pub unsafe fn compare_bytes(s1: *const u8, s2: *const u8, n: usize) -> i32 {
-    let mut i = 0;
-    while i < n {
-        let a = *s1.add(i);
-        let b = *s2.add(i);
-        if a != b {
-            return a as i32 - b as i32;
+pub unsafe fn compare_bytes(mut s1: *const u8, mut s2: *const u8, mut n: usize) -> i32 {
+    #[inline(always)]
+    unsafe fn compare_bytes_bytes(s1: *const u8, s2: *const u8, n: usize) -> i32 {
+        let mut i = 0;
+        while i < n {
+            let a = *s1.add(i);
+            let b = *s2.add(i);
+            if a != b {
+                return a as i32 - b as i32;
+            }
+            i += 1;
+        }
+        0
+    }
+
+    // The word-wise comparisons return the number of bytes before the first
+    // word which differs, or `n` if all of the words are equal.
+
+    #[inline(always)]
+    unsafe fn compare_aligned_words(s1: *const u8, s2: *const u8, n: usize) -> usize {
+        let mut s1_usize = s1 as *const usize;
+        let mut s2_usize = s2 as *const usize;
+        let s1_end = s1.add(n) as *const usize;
+
+        while s1_usize < s1_end {
+            if *s1_usize != *s2_usize {
+                break;
+            }
+            s1_usize = s1_usize.add(1);
+            s2_usize = s2_usize.add(1);
         }
-        i += 1;
+        s1_usize.cast::<u8>().offset_from(s1) as usize
+    }
+
+    #[cfg(not(any(
+        target_arch = "x86_64",
+        target_arch = "x86",
+        target_arch = "aarch64",
+        target_arch = "bpf"
+    )))]
+    #[inline(always)]
+    unsafe fn compare_misaligned_words(s1: *const u8, s2: *const u8, n: usize) -> usize {
+        let mut s1_usize = s1 as *const usize;
+        let s1_end = s1.add(n) as *const usize;
+
+        // Calculate the misalignment offset and shift needed to reassemble value.
+        let offset = s2.addr() & WORD_MASK;
+        let shift = offset * 8;
+
+        // Realign s2
+        let mut s2_aligned = s2.with_addr(s2.addr() & !WORD_MASK).cast::<usize>();
+        // This will read (but won't use) bytes out of bound.
+        let mut prev_word = crate::arch::oob_load(s2_aligned);
+
+        while s1_usize < s1_end {
+            s2_aligned = s2_aligned.add(1);
+            let cur_word = *s2_aligned;
+            #[cfg(target_endian = "little")]
+            let resembled = prev_word >> shift | cur_word << (WORD_SIZE * 8 - shift);
+            #[cfg(target_endian = "big")]
+            let resembled = prev_word << shift | cur_word >> (WORD_SIZE * 8 - shift);
+            prev_word = cur_word;
+
+            if *s1_usize != resembled {
+                break;
+            }
+            s1_usize = s1_usize.add(1);
+        }
+        s1_usize.cast::<u8>().offset_from(s1) as usize
+    }
+
+    #[cfg(any(
+        target_arch = "x86_64",
+        target_arch = "x86",
+        target_arch = "aarch64",
+        target_arch = "bpf"
+    ))]
+    #[inline(always)]
+    unsafe fn compare_misaligned_words(s1: *const u8, s2: *const u8, n: usize) -> usize {
+        let mut s1_usize = s1 as *const usize;
+        let mut s2_usize = s2 as *const usize;
+        let s1_end = s1.add(n) as *const usize;
+
+        while s1_usize < s1_end {
+            if *s1_usize != read_usize_unaligned(s2_usize) {
+                break;
+            }
+            s1_usize = s1_usize.add(1);
+            s2_usize = s2_usize.add(1);
+        }
+        s1_usize.cast::<u8>().offset_from(s1) as usize
+    }
+
+    if n >= WORD_COPY_THRESHOLD {
+        // Align s1
+        // Because of n >= 2 * WORD_SIZE, s1_misalignment < n
+        let s1_misalignment = (s1.addr()).wrapping_neg() & WORD_MASK;
+        let result = compare_bytes_bytes(s1, s2, s1_misalignment);
+        if result != 0 {
+            return result;
+        }
+        s1 = s1.add(s1_misalignment);
+        s2 = s2.add(s1_misalignment);
+        n -= s1_misalignment;
+
+        let n_words = n & !WORD_MASK;
+        let s2_misalignment = s2.addr() & WORD_MASK;
+        let n_equal = if s2_misalignment == 0 {
+            compare_aligned_words(s1, s2, n_words)
+        } else {
+            compare_misaligned_words(s1, s2, n_words)
+        };
+        s1 = s1.add(n_equal);
+        s2 = s2.add(n_equal);
+        n -= n_equal;
+
+        // If a word differed, the bytewise comparison below finds the first
+        // byte in it which differs, which determines the sign of the result
+        // independent of the byte order.
     }
-    0
+    compare_bytes_bytes(s1, s2, n)
 }
 
 #[inline(always)]
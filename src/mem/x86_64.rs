@@ -18,9 +18,13 @@
 //  - Fast Zero-Length MOVSB (On no current hardware)
 //  - Fast Short STOSB (On no current hardware)
 //
-// To simplify things, we switch to using the byte-based variants if the "ermsb"
-// feature is present at compile-time. We don't bother detecting other features.
-// Note that ERMSB does not enhance the backwards (DF=1) "rep movsb".
+// Unlike compiler_builtins, we use the byte-based variants unconditionally,
+// rather than only when the "ermsb" feature is present at compile time. Few
+// builds enable that feature, but nearly all x86_64 processors in use have
+// ERMSB, and on them a single "rep movsb" or "rep stosb" is several times
+// faster than a "rep movsq" or "rep stosq" sequence for sizes up to a few
+// KiB, and about the same for larger sizes. Note that ERMSB does not enhance
+// the backwards (DF=1) "rep movsb", so `copy_backward` still uses "rep movsq".
 
 #[cfg(not(feature = "nightly"))]
 use crate::ptr::Polyfill as _;
@@ -28,7 +32,6 @@ use core::arch::asm;
 use core::mem;
 
 #[inline(always)]
-#[cfg(target_feature = "ermsb")]
 pub unsafe fn copy_forward(dest: *mut u8, src: *const u8, count: usize) {
     // FIXME: Use the Intel syntax once we drop LLVM 9 support on rust-lang/rust.
     core::arch::asm!(
@@ -40,34 +43,6 @@ pub unsafe fn copy_forward(dest: *mut u8, src: *const u8, count: usize) {
     );
 }
 
-#[inline(always)]
-#[cfg(not(target_feature = "ermsb"))]
-pub unsafe fn copy_forward(mut dest: *mut u8, mut src: *const u8, count: usize) {
-    let (pre_byte_count, qword_count, byte_count) = rep_param(dest, count);
-    // Separating the blocks gives the compiler more freedom to reorder instructions.
-    asm!(
-        "rep movsb",
-        inout("ecx") pre_byte_count => _,
-        inout("rdi") dest => dest,
-        inout("rsi") src => src,
-        options(att_syntax, nostack, preserves_flags)
-    );
-    asm!(
-        "rep movsq",
-        inout("rcx") qword_count => _,
-        inout("rdi") dest => dest,
-        inout("rsi") src => src,
-        options(att_syntax, nostack, preserves_flags)
-    );
-    asm!(
-        "rep movsb",
-        inout("ecx") byte_count => _,
-        inout("rdi") dest => _,
-        inout("rsi") src => _,
-        options(att_syntax, nostack, preserves_flags)
-    );
-}
-
 #[inline(always)]
 pub unsafe fn copy_backward(dest: *mut u8, src: *const u8, count: usize) {
     let (pre_byte_count, qword_count, byte_count) = rep_param(dest, count);
@@ -96,7 +71,6 @@ pub unsafe fn copy_backward(dest: *mut u8, src: *const u8, count: usize) {
 }
 
 #[inline(always)]
-#[cfg(target_feature = "ermsb")]
 pub unsafe fn set_bytes(dest: *mut u8, c: u8, count: usize) {
     // FIXME: Use the Intel syntax once we drop LLVM 9 support on rust-lang/rust.
     core::arch::asm!(
@@ -108,35 +82,6 @@ pub unsafe fn set_bytes(dest: *mut u8, c: u8, count: usize) {
     )
 }
 
-#[inline(always)]
-#[cfg(not(target_feature = "ermsb"))]
-pub unsafe fn set_bytes(mut dest: *mut u8, c: u8, count: usize) {
-    let c = c as u64 * 0x0101_0101_0101_0101;
-    let (pre_byte_count, qword_count, byte_count) = rep_param(dest, count);
-    // Separating the blocks gives the compiler more freedom to reorder instructions.
-    asm!(
-        "rep stosb",
-        inout("ecx") pre_byte_count => _,
-        inout("rdi") dest => dest,
-        in("rax") c,
-        options(att_syntax, nostack, preserves_flags)
-    );
-    asm!(
-        "rep stosq",
-        inout("rcx") qword_count => _,
-        inout("rdi") dest => dest,
-        in("rax") c,
-        options(att_syntax, nostack, preserves_flags)
-    );
-    asm!(
-        "rep stosb",
-        inout("ecx") byte_count => _,
-        inout("rdi") dest => _,
-        in("rax") c,
-        options(att_syntax, nostack, preserves_flags)
-    );
-}
-
 #[inline(always)]
 pub unsafe fn compare_bytes(a: *const u8, b: *const u8, n: usize) -> i32 {
     #[inline(always)]
@@ -44,6 +44,7 @@ macro_rules! naked_fn {
         #[no_mangle]
         $vis unsafe extern "C" fn $name $args -> $ret {
             core::arch::naked_asm!(
+                landing_pad!(),
                 $($code),*,
                 $($label = $kind $path),*
             )
@@ -68,11 +69,64 @@ macro_rules! naked_fn {
         }
         core::arch::global_asm!(
             concat!(".global ", stringify!($name)),
-            concat!(".type ", stringify!($name), ", @function"),
+            concat!(".type ", stringify!($name), ", %function"),
+            thumb_func!(),
             concat!(stringify!($name), ":"),
+            landing_pad!(),
             $($code),*,
             concat!(".size ", stringify!($name), ", .-", stringify!($name)),
             $($label = $kind $path),*
         );
     };
 }
+
+/// In Thumb mode on ARM, function symbols need to be marked as Thumb code so
+/// that their addresses have the low bit set and callers switch modes
+/// correctly.
+#[cfg(not(feature = "nightly"))]
+#[cfg(all(target_arch = "arm", thumb_mode))]
+macro_rules! thumb_func {
+    () => {
+        ".thumb_func"
+    };
+}
+
+/// On other targets, there's nothing to do.
+#[cfg(not(feature = "nightly"))]
+#[cfg(not(all(target_arch = "arm", thumb_mode)))]
+macro_rules! thumb_func {
+    () => {
+        ""
+    };
+}
+
+/// With the "bti" feature on aarch64, functions start with a landing pad for
+/// Branch Target Identification, so that they can be the targets of indirect
+/// calls on systems which enforce it.
+#[cfg(all(target_arch = "aarch64", feature = "bti"))]
+macro_rules! landing_pad {
+    () => {
+        "bti c"
+    };
+}
+
+/// With the "cet" feature on x86-64, functions start with a landing pad for
+/// CET Indirect Branch Tracking, so that they can be the targets of indirect
+/// calls on systems which enforce it.
+#[cfg(all(target_arch = "x86_64", feature = "cet"))]
+macro_rules! landing_pad {
+    () => {
+        "endbr64"
+    };
+}
+
+/// On other targets, there's nothing to do.
+#[cfg(not(any(
+    all(target_arch = "aarch64", feature = "bti"),
+    all(target_arch = "x86_64", feature = "cet")
+)))]
+macro_rules! landing_pad {
+    () => {
+        ""
+    };
+}
@@ -0,0 +1,40 @@
+//! The `#[alloc_error_handler]` provided by the
+//! "origin-alloc-error-handler" feature.
+
+use super::{trap, Stderr};
+use core::alloc::Layout;
+use core::fmt::Write;
+use core::ptr::null_mut;
+use core::sync::atomic::{AtomicPtr, Ordering};
+
+/// The function set with [`set_alloc_error_handler`], or null if none has
+/// been set.
+static HANDLER: AtomicPtr<()> = AtomicPtr::new(null_mut());
+
+/// Set the function to call when a memory allocation fails.
+///
+/// By default, origin prints a message to stderr and calls [`trap`]. The
+/// function is passed the layout of the allocation that failed, and must not
+/// return. It's called from within the allocating code, so it should avoid
+/// allocating memory itself.
+#[cfg_attr(docsrs, doc(cfg(feature = "origin-alloc-error-handler")))]
+pub fn set_alloc_error_handler(handler: fn(Layout) -> !) {
+    HANDLER.store(handler as *mut (), Ordering::Relaxed);
+}
+
+#[alloc_error_handler]
+fn alloc_error_handler(layout: Layout) -> ! {
+    let handler = HANDLER.load(Ordering::Relaxed);
+    if !handler.is_null() {
+        // SAFETY: `set_alloc_error_handler` only stores `fn(Layout) -> !`s.
+        let handler = unsafe { core::mem::transmute::<*mut (), fn(Layout) -> !>(handler) };
+        handler(layout);
+    }
+
+    let _ = writeln!(
+        Stderr,
+        "origin: memory allocation of {} bytes failed",
+        layout.size()
+    );
+    trap()
+}
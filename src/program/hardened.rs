@@ -0,0 +1,110 @@
+//! Validation of the incoming `argv` and `envp` arrays, for the
+//! "hardened-main" feature.
+//!
+//! A well-behaved kernel always gives us well-formed arrays, however programs
+//! that run with elevated privileges, or which are launched by untrusted
+//! parents, may wish to turn a corrupted initial stack into a controlled
+//! abort, rather than reading out of bounds in user code.
+
+use super::abort_startup;
+#[cfg(not(feature = "nightly"))]
+use crate::ptr::Polyfill as _;
+use linux_raw_sys::ctypes::c_int;
+use rustix::io;
+use rustix::mm::{madvise, Advice};
+use rustix::param::page_size;
+
+/// The maximum number of entries we accept in `argv` or `envp`.
+///
+/// Linux limits the total size of the arguments and environment variables to
+/// a quarter of the stack size limit, so this is much more than any
+/// well-formed array will have.
+const MAX_ENTRIES: usize = 1 << 24;
+
+/// The maximum length we accept for an argument or environment variable
+/// string, including its NUL terminator, in pages. This is the same as
+/// Linux's `MAX_ARG_STRLEN`.
+const MAX_STRLEN_PAGES: usize = 32;
+
+/// Check that `argv` and `envp` are terminated within [`MAX_ENTRIES`].
+///
+/// This reads only the arrays themselves, so it can be called before
+/// anything else looks for the end of `envp`.
+///
+/// # Safety
+///
+/// `mem` must point to the stack as provided by the operating system.
+pub(super) unsafe fn check_arrays(mem: *mut usize) {
+    let argc = *mem;
+    if argc > c_int::MAX as usize || argc >= MAX_ENTRIES {
+        abort_startup("argc is out of bounds");
+    }
+
+    let argv = mem.add(1).cast::<*mut u8>();
+    if !(*argv.add(argc)).is_null() {
+        abort_startup("argv is not NULL-terminated");
+    }
+
+    let envp = argv.add(argc + 1);
+    if array_len(envp).is_none() {
+        abort_startup("envp is not NULL-terminated");
+    }
+}
+
+/// Check that every string in `argv` and `envp` is in mapped memory and is
+/// NUL-terminated within [`MAX_STRLEN_PAGES`] pages.
+///
+/// # Safety
+///
+/// `argv` and `envp` must have been checked by [`check_arrays`], and
+/// `rustix::param::init` must have been called.
+pub(super) unsafe fn check_strings(argv: *mut *mut u8, envp: *mut *mut u8) {
+    for array in [argv, envp] {
+        let mut entry = array;
+        while !(*entry).is_null() {
+            check_string(*entry);
+            entry = entry.add(1);
+        }
+    }
+}
+
+/// Return the number of entries in the NULL-terminated array `array`, or
+/// `None` if it has more than [`MAX_ENTRIES`].
+unsafe fn array_len(array: *mut *mut u8) -> Option<usize> {
+    (0..MAX_ENTRIES).find(|i| (*array.add(*i)).is_null())
+}
+
+/// Check that `s` is in mapped memory and is NUL-terminated within
+/// [`MAX_STRLEN_PAGES`] pages.
+unsafe fn check_string(s: *mut u8) {
+    let page_size = page_size();
+
+    // The number of bytes starting at `s` that we know to be mapped.
+    let mut mapped = 0;
+
+    for i in 0..MAX_STRLEN_PAGES * page_size {
+        if i == mapped {
+            // Ask the kernel whether the page containing `s.add(i)` is mapped.
+            // `madvise` with `Normal` has no effect on memory we haven't
+            // given any other advice for, and fails with `NOMEM` if any part
+            // of the range isn't mapped. Other errors mean we can't check,
+            // such as when a sandbox doesn't allow `madvise`.
+            let offset = s.wrapping_add(i).addr() & (page_size - 1);
+            let page = s.wrapping_add(i).wrapping_sub(offset);
+            match madvise(page.cast(), page_size, Advice::Normal) {
+                Ok(()) => {}
+                Err(io::Errno::NOMEM) => {
+                    abort_startup("argv or envp contains a pointer to unmapped memory")
+                }
+                Err(_) => abort_startup("unable to check argv and envp without `madvise`"),
+            }
+            mapped = i + (page_size - offset);
+        }
+
+        if *s.add(i) == b'\0' {
+            return;
+        }
+    }
+
+    abort_startup("argv or envp contains a string which is too long");
+}
@@ -0,0 +1,93 @@
+//! Functions called even by `exit_immediately`.
+
+use core::ptr::null_mut;
+use core::sync::atomic::{AtomicPtr, AtomicUsize, Ordering};
+use rustix::io;
+
+/// The maximum number of functions that may be registered with
+/// [`at_immediate_exit`].
+pub const MAX_IMMEDIATE_EXIT_FUNCTIONS: usize = 32;
+
+/// The functions registered with [`at_immediate_exit`], as `fn()`s, in order
+/// of registration. Each slot is cleared when its function is called, so that
+/// each function is only called once.
+static FUNCS: [AtomicPtr<()>; MAX_IMMEDIATE_EXIT_FUNCTIONS] = {
+    // An inline `const` block would avoid this, but it needs Rust 1.79.
+    #[allow(clippy::declare_interior_mutable_const)]
+    const EMPTY: AtomicPtr<()> = AtomicPtr::new(null_mut());
+    [EMPTY; MAX_IMMEDIATE_EXIT_FUNCTIONS]
+};
+
+/// The number of slots in `FUNCS` that have been claimed.
+static LEN: AtomicUsize = AtomicUsize::new(0);
+
+/// Register a function to be called when the program exits, even if it exits
+/// with [`exit_immediately`].
+///
+/// This is a separate, smaller tier of cleanup from `at_exit`, for things
+/// which must happen however the program exits, such as removing a pid file
+/// or a lock file. Functions registered with `at_exit` are only called by
+/// [`exit`]; functions registered with `at_immediate_exit` are called by both
+/// `exit`, after everything else it calls, and `exit_immediately`. They are
+/// not called by [`exit_silent`], [`exit_vfork_child`], or when the program
+/// is terminated by a signal.
+///
+/// Functions are called in the reverse order of their registration, and each
+/// is called at most once, even if `exit_immediately` is called again while
+/// they're being called. Since `exit_immediately` may be called at times when
+/// the program is in an inconsistent state, these functions should be kept
+/// minimal, and should avoid allocating, taking locks, and depending on
+/// thread-local storage, as in a signal handler. Registering them doesn't
+/// allocate.
+///
+/// At most [`MAX_IMMEDIATE_EXIT_FUNCTIONS`] functions may be registered.
+/// Registering more fails with [`io::Errno::NOMEM`].
+///
+/// With the libc backend, when the program exits with `exit`, these are
+/// called from an `atexit` function registered by the first call to this
+/// function, so functions registered with `at_exit` after that are called
+/// before them, and functions registered before it are called after them.
+///
+/// [`exit`]: super::exit
+/// [`exit_immediately`]: super::exit_immediately
+/// [`exit_silent`]: super::exit_silent
+/// [`exit_vfork_child`]: super::exit_vfork_child
+pub fn at_immediate_exit(func: fn()) -> io::Result<()> {
+    let index = LEN.fetch_add(1, Ordering::AcqRel);
+    if index >= MAX_IMMEDIATE_EXIT_FUNCTIONS {
+        LEN.fetch_sub(1, Ordering::AcqRel);
+        return Err(io::Errno::NOMEM);
+    }
+    FUNCS[index].store(func as *mut (), Ordering::Release);
+
+    #[cfg(not(feature = "take-charge"))]
+    if index == 0 {
+        extern "C" fn call() {
+            call_immediate_exit_functions();
+        }
+
+        // SAFETY: `call` is an `extern "C" fn` with no arguments, as `atexit`
+        // expects.
+        unsafe {
+            assert_eq!(libc::atexit(call), 0);
+        }
+    }
+
+    Ok(())
+}
+
+/// Call the functions registered with [`at_immediate_exit`] that haven't been
+/// called yet, in reverse order.
+pub(super) fn call_immediate_exit_functions() {
+    let len = LEN
+        .load(Ordering::Acquire)
+        .min(MAX_IMMEDIATE_EXIT_FUNCTIONS);
+    for slot in FUNCS[..len].iter().rev() {
+        let func = slot.swap(null_mut(), Ordering::AcqRel);
+        if !func.is_null() {
+            // SAFETY: `at_immediate_exit` only stores `fn()`s.
+            let func = unsafe { core::mem::transmute::<*mut (), fn()>(func) };
+            func();
+        }
+    }
+}
@@ -22,7 +22,10 @@
 //! pointer to a NULL-terminated array of pointers to NUL-terminated C strings
 //! containing a key followed by `b'='` followed by a value. It describes the
 //! environment variables. The function should return a value for the program
-//! exit status.
+//! exit status. [`Termination`] can be used to convert richer values, such as
+//! `Result`s and [`ExitCode`]s, into an exit status. The
+//! [`main!`](crate::main) macro can define `origin_main` to call a function
+//! with another name, or to dispatch on the name the program was invoked with.
 //!
 //! This is a low-level and somewhat C-flavored interface, which is in tension
 //! with origin's goal of providing Rust-idiomatic interfaces, however it does
@@ -32,7 +35,48 @@
 use alloc::boxed::Box;
 #[cfg(feature = "program-at-exit")]
 use core::ptr::null_mut;
-use linux_raw_sys::ctypes::c_int;
+use linux_raw_sys::ctypes::{c_int, c_ulong};
+use rustix::io;
+
+#[cfg(feature = "origin-alloc-error-handler")]
+mod alloc_error;
+#[cfg(feature = "program-at-immediate-exit")]
+mod immediate_exit;
+#[cfg(feature = "log")]
+mod log_flush;
+mod main_macro;
+mod personality;
+mod privileges;
+mod rlimit;
+#[cfg(feature = "signal")]
+mod shutdown;
+mod stdio;
+mod termination;
+mod timer_slack;
+#[cfg(feature = "timestamp-counter")]
+mod timestamp_counter;
+
+#[cfg(feature = "origin-alloc-error-handler")]
+pub use alloc_error::set_alloc_error_handler;
+#[cfg(feature = "program-at-immediate-exit")]
+pub use immediate_exit::{at_immediate_exit, MAX_IMMEDIATE_EXIT_FUNCTIONS};
+#[cfg(feature = "log")]
+pub use log_flush::{flush_logs, set_log_flush_hook};
+#[doc(hidden)]
+pub use main_macro::__invoked_name;
+pub use personality::{disable_aslr_and_reexec, personality, set_personality, ADDR_NO_RANDOMIZE};
+pub use privileges::{chroot, drop_privileges};
+pub use rlimit::{get_rlimit, set_rlimit};
+pub use rustix::process::{Gid, Resource, Rlimit, Uid};
+#[cfg(feature = "signal")]
+pub use shutdown::{install_shutdown_handler, ShutdownToken};
+#[cfg(feature = "program-at-exit")]
+pub use stdio::BufferedStdout;
+pub use stdio::{stderr_lock, Stderr, StderrLock, Stdout};
+pub use termination::{ExitCode, Termination};
+pub use timer_slack::{set_timer_slack, timer_slack};
+#[cfg(feature = "timestamp-counter")]
+pub use timestamp_counter::timestamp_counter;
 
 /// Register a function to be called when [`exit`] is called.
 #[cfg(feature = "program-at-exit")]
@@ -70,14 +114,152 @@ pub fn exit(status: c_int) -> ! {
 
 /// Exit the program without calling functions registered with [`at_exit`] or
 /// with the `.fini_array` section.
+///
+/// With the "program-at-immediate-exit" feature, this does call functions
+/// registered with `at_immediate_exit`.
 #[inline]
 pub fn exit_immediately(status: c_int) -> ! {
+    // Call functions registered with `at_immediate_exit`.
+    #[cfg(feature = "program-at-immediate-exit")]
+    immediate_exit::call_immediate_exit_functions();
+
+    unsafe {
+        // Call `libc` to exit the program.
+        libc::_exit(status)
+    }
+}
+
+/// Exit the program without calling functions registered with [`at_exit`] or
+/// with the `.fini_array` section, and without logging, regardless of which
+/// features are enabled.
+///
+/// This just calls `_exit`, so it's suitable for use in signal handlers, or
+/// after the logger has been torn down.
+#[inline]
+pub fn exit_silent(status: c_int) -> ! {
     unsafe {
         // Call `libc` to exit the program.
         libc::_exit(status)
     }
 }
 
+/// Exit the child process of a `vfork`, without doing anything else.
+///
+/// Between `vfork` and `execve`, the child shares the parent's memory, and
+/// the parent is suspended, so the child must not run exit functions, free
+/// memory, take locks, or touch thread-local state, any of which could
+/// corrupt the parent. This just calls `_exit`, which POSIX permits in that
+/// state, such as when `execve` fails.
+///
+/// Outside of a `vfork` child, this behaves like [`exit_silent`].
+#[inline]
+pub fn exit_vfork_child(status: c_int) -> ! {
+    exit_silent(status)
+}
+
+/// Terminate the program as if it had been killed by `sig`.
+///
+/// This resets `sig`'s disposition to the default, unblocks it, and sends it
+/// to the current thread, so that a parent waiting for this process sees it
+/// as terminated by `sig`, which an exit status can't express. Functions
+/// registered with [`at_exit`] or with the `.fini_array` section are not
+/// called.
+///
+/// If `sig`'s [default action] isn't to terminate the process, such as for
+/// `Signal::Child`, this exits with status `128 + sig` instead, following the
+/// shell convention.
+///
+/// [default action]: crate::signal::default_action
+#[cfg(feature = "signal")]
+#[cfg_attr(docsrs, doc(cfg(feature = "signal")))]
+pub fn terminate_by_signal(sig: crate::signal::Signal) -> ! {
+    use crate::signal::{default_action, DefaultAction};
+
+    #[cfg(feature = "log")]
+    log::trace!("Program terminating by signal `{:?}`", sig);
+
+    unsafe {
+        // Reset the signal to its default disposition.
+        libc::signal(sig as c_int, libc::SIG_DFL);
+
+        // Unblock it.
+        let mut set: libc::sigset_t = core::mem::zeroed();
+        libc::sigemptyset(&mut set);
+        libc::sigaddset(&mut set, sig as c_int);
+        libc::pthread_sigmask(libc::SIG_UNBLOCK, &set, core::ptr::null_mut());
+
+        // Send it to ourselves. An unblocked signal sent to the current thread
+        // is delivered before `raise` returns, however another thread could
+        // change the disposition in the meantime, so keep trying.
+        loop {
+            libc::raise(sig as c_int);
+
+            if !matches!(
+                default_action(sig),
+                DefaultAction::Terminate | DefaultAction::CoreDump
+            ) {
+                exit_immediately(128 + sig as c_int);
+            }
+
+            libc::signal(sig as c_int, libc::SIG_DFL);
+        }
+    }
+}
+
+/// Test whether the program is running in “secure execution” mode.
+///
+/// The kernel sets `AT_SECURE` in the aux vector when the program was
+/// started with elevated privileges, such as by a setuid or setgid
+/// executable, or one with file capabilities, or when a Linux Security Module
+/// requests it. In that case, the environment variables were chosen by a
+/// less-privileged user, so the program should not trust them, and in
+/// particular should not use them to choose files to load or execute. libc
+/// ignores variables such as `LD_PRELOAD` in this mode for the same reason.
+#[inline]
+#[must_use]
+pub fn is_secure() -> bool {
+    unsafe { libc::getauxval(libc::AT_SECURE) != 0 }
+}
+
+/// Set whether the process is “dumpable”.
+///
+/// A process which isn't dumpable doesn't produce core dumps, and can't be
+/// attached to with `ptrace` or have its `/proc/<pid>` files such as `mem`
+/// read by other unprivileged processes. Linux resets this to dumpable on
+/// `execve`, and clears it when the process's credentials change, such as
+/// when it drops privileges with `setuid`. It applies to the whole process.
+#[doc(alias = "PR_SET_DUMPABLE")]
+pub fn set_dumpable(dumpable: bool) -> io::Result<()> {
+    prctl(libc::PR_SET_DUMPABLE, c_ulong::from(dumpable))
+}
+
+/// Set the `no_new_privs` attribute.
+///
+/// Once this is set, `execve` can't grant privileges, such as by running a
+/// setuid or setgid executable, or one with file capabilities, and it can't
+/// be unset. Unprivileged processes must set it before installing seccomp
+/// filters.
+///
+/// This is a per-thread attribute, which is inherited by threads created
+/// afterward and by child processes, so to cover the whole process, call
+/// this before creating any threads, for example at the start of
+/// `origin_main` or from an `.init_array` function.
+#[doc(alias = "PR_SET_NO_NEW_PRIVS")]
+pub fn set_no_new_privs() -> io::Result<()> {
+    prctl(libc::PR_SET_NO_NEW_PRIVS, 1)
+}
+
+/// Call `prctl` with `option` and one argument, with zeros for the other
+/// arguments.
+fn prctl(option: c_int, arg: c_ulong) -> io::Result<()> {
+    let zero: c_ulong = 0;
+    if unsafe { libc::prctl(option, arg, zero, zero, zero) } == 0 {
+        Ok(())
+    } else {
+        Err(io::Errno::from_raw_os_error(errno::errno().0))
+    }
+}
+
 /// Execute a trap instruction.
 ///
 /// This will produce a `Signal::Ill`, which by default will immediately
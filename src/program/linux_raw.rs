@@ -22,24 +22,105 @@
 //! pointer to a NULL-terminated array of pointers to NUL-terminated C strings
 //! containing a key followed by `b'='` followed by a value. It describes the
 //! environment variables. The function should return a value for the program
-//! exit status.
+//! exit status. [`Termination`] can be used to convert richer values, such as
+//! `Result`s and [`ExitCode`]s, into an exit status. The
+//! [`main!`](crate::main) macro can define `origin_main` to call a function
+//! with another name, or to dispatch on the name the program was invoked with.
 //!
 //! This is a low-level and somewhat C-flavored interface, which is in tension
 //! with origin's goal of providing Rust-idiomatic interfaces, however it does
 //! mean that origin can avoid doing any work that users might not need.
 
+#[cfg(not(feature = "nightly"))]
+use crate::ptr::{without_provenance_mut, Polyfill as _};
 #[cfg(feature = "thread")]
 use crate::thread;
 #[cfg(feature = "program-at-exit")]
 use alloc::boxed::Box;
-#[cfg(all(feature = "program-at-exit", not(feature = "thread")))]
+#[cfg(all(
+    feature = "program-at-exit",
+    any(not(feature = "thread"), feature = "single-threaded")
+))]
 use core::cell::UnsafeCell;
+use core::ffi::{c_void, CStr};
+#[cfg(feature = "nightly")]
+use core::ptr::without_provenance_mut;
+use core::slice;
+use core::sync::atomic::AtomicPtr;
+#[cfg(any(feature = "param", feature = "program-at-exit"))]
+use core::sync::atomic::AtomicUsize;
+#[cfg(feature = "program-at-exit")]
+use core::sync::atomic::{AtomicBool, Ordering};
 use linux_raw_sys::ctypes::c_int;
-#[cfg(all(feature = "program-at-exit", feature = "thread"))]
+#[cfg(feature = "preinit-array")]
+use linux_raw_sys::elf::PT_INTERP;
+use linux_raw_sys::elf::{Elf_Phdr, PT_DYNAMIC, PT_LOAD, PT_PHDR};
+use rustix::io;
+use rustix::process::{Pid, RawPid};
+use rustix::runtime::exe_phdrs;
+#[cfg(all(
+    feature = "program-at-exit",
+    feature = "thread",
+    not(feature = "single-threaded")
+))]
 use rustix_futex_sync::Mutex;
 
-#[cfg(not(any(feature = "origin-start", feature = "external-start")))]
-compile_error!("\"origin-program\" depends on either \"origin-start\" or \"external-start\".");
+#[cfg(feature = "origin-alloc-error-handler")]
+mod alloc_error;
+#[cfg(feature = "hardened-main")]
+mod hardened;
+#[cfg(feature = "program-at-immediate-exit")]
+mod immediate_exit;
+#[cfg(feature = "log")]
+mod log_flush;
+mod main_macro;
+mod personality;
+mod privileges;
+mod rlimit;
+#[cfg(feature = "seccomp")]
+mod seccomp;
+#[cfg(feature = "signal")]
+mod shutdown;
+mod stdio;
+mod termination;
+mod timer_slack;
+#[cfg(feature = "timestamp-counter")]
+mod timestamp_counter;
+#[cfg(feature = "vdso")]
+mod vdso;
+
+#[cfg(feature = "origin-alloc-error-handler")]
+pub use alloc_error::set_alloc_error_handler;
+#[cfg(feature = "program-at-immediate-exit")]
+pub use immediate_exit::{at_immediate_exit, MAX_IMMEDIATE_EXIT_FUNCTIONS};
+#[cfg(feature = "log")]
+pub use log_flush::{flush_logs, set_log_flush_hook};
+#[doc(hidden)]
+pub use main_macro::__invoked_name;
+pub use personality::{disable_aslr_and_reexec, personality, set_personality, ADDR_NO_RANDOMIZE};
+pub use privileges::{chroot, drop_privileges};
+pub use rlimit::{get_rlimit, set_rlimit};
+pub use rustix::process::{Gid, Resource, Rlimit, Uid};
+#[cfg(feature = "seccomp")]
+pub use seccomp::{
+    install_seccomp_filter, seccomp_allow_origin_syscalls, SockFilter, ORIGIN_SYSCALLS,
+};
+#[cfg(feature = "signal")]
+pub use shutdown::{install_shutdown_handler, ShutdownToken};
+#[cfg(feature = "program-at-exit")]
+pub use stdio::BufferedStdout;
+pub use stdio::{stderr_lock, Stderr, StderrLock, Stdout};
+pub use termination::{ExitCode, Termination};
+pub use timer_slack::{set_timer_slack, timer_slack};
+#[cfg(feature = "timestamp-counter")]
+pub use timestamp_counter::timestamp_counter;
+#[cfg(feature = "vdso")]
+pub use vdso::{vdso, ClockGettime, Gettimeofday, Vdso};
+
+// Emit the `.note.ABI-tag` note. See the comments in `abi_tag.rs` for why
+// this is here.
+#[cfg(all(feature = "abi-tag", feature = "origin-start"))]
+abi_tag_note!();
 
 /// The entrypoint where Rust code is first executed when the program starts.
 ///
@@ -93,6 +174,11 @@ pub(super) unsafe extern "C" fn entry(mem: *mut usize) -> ! {
         }
     }
 
+    // With "timestamp-counter" and "log", read the timestamp counter between
+    // the startup phases, to log how long they took once logging is set up.
+    #[cfg(all(feature = "timestamp-counter", feature = "log"))]
+    let start_time = crate::arch::timestamp_counter();
+
     // Compute `argc`, `argv`, and `envp`.
     let (argc, argv, envp) = compute_args(mem);
 
@@ -103,9 +189,56 @@ pub(super) unsafe extern "C" fn entry(mem: *mut usize) -> ! {
         crate::relocate::relocate(envp);
     }
 
+    #[cfg(all(feature = "timestamp-counter", feature = "log"))]
+    let relocated_time = crate::arch::timestamp_counter();
+
     // Initialize program state before running any user code.
     init_runtime(mem, envp);
 
+    #[cfg(all(feature = "timestamp-counter", feature = "log"))]
+    let initialized_time = crate::arch::timestamp_counter();
+
+    // Call the functions registered via `.preinit_array`, which run before
+    // those registered via `.init_array`. If the executable has a dynamic
+    // linker, that has already called them, as glibc's does, so only do this
+    // for statically-linked executables.
+    #[cfg(feature = "preinit-array")]
+    if !exe_phdr_iter().any(|phdr| phdr.p_type == PT_INTERP) {
+        use core::arch::asm;
+        use core::ffi::c_void;
+
+        // The linker-generated symbols that mark the start and end of the
+        // `.preinit_array` section.
+        extern "C" {
+            static __preinit_array_start: c_void;
+            static __preinit_array_end: c_void;
+        }
+
+        // Call the `.preinit_array` functions, passing the same arguments as
+        // for `.init_array` functions, as glibc does.
+        type InitFn = unsafe extern "C" fn(c_int, *mut *mut u8, *mut *mut u8);
+        let mut init = core::ptr::addr_of!(__preinit_array_start).cast::<InitFn>();
+        let init_end = core::ptr::addr_of!(__preinit_array_end).cast::<InitFn>();
+        // Prevent the optimizer from optimizing the `!=` comparison to true;
+        // `init` and `init_start` may have the same address.
+        asm!("# {}", inout(reg) init, options(pure, nomem, nostack, preserves_flags));
+
+        while init != init_end {
+            #[cfg(feature = "log")]
+            log::trace!(
+                "Calling `.preinit_array`-registered function `{:?}({:?}, {:?}, {:?})`",
+                *init,
+                argc,
+                argv,
+                envp
+            );
+
+            (*init)(argc, argv, envp);
+
+            init = init.add(1);
+        }
+    }
+
     // Call the functions registered via `.init_array`.
     #[cfg(feature = "init-array")]
     {
@@ -151,17 +284,32 @@ pub(super) unsafe extern "C" fn entry(mem: *mut usize) -> ! {
             fn origin_main(argc: usize, argv: *mut *mut u8, envp: *mut *mut u8) -> i32;
         }
 
+        #[cfg(all(feature = "timestamp-counter", feature = "log"))]
+        {
+            let main_time = crate::arch::timestamp_counter();
+            log::trace!(
+                "Startup took {} timestamp counter ticks: {} computing arguments and relocating, {} initializing the runtime, and {} calling `.preinit_array` and `.init_array` functions",
+                main_time.wrapping_sub(start_time),
+                relocated_time.wrapping_sub(start_time),
+                initialized_time.wrapping_sub(relocated_time),
+                main_time.wrapping_sub(initialized_time)
+            );
+        }
+
         #[cfg(feature = "log")]
         log::trace!("Calling `origin_main({:?}, {:?}, {:?})`", argc, argv, envp);
 
-        // Call `origin_main`.
-        let status = origin_main(argc as usize, argv, envp);
+        // Call `origin_main`, and convert its return value into an exit
+        // status.
+        let status = origin_main(argc as usize, argv, envp).report();
 
         #[cfg(feature = "log")]
         log::trace!("`origin_main` returned `{:?}`", status);
 
         // Run functions registered with `at_exit`, and exit with
-        // `origin_main`'s return value.
+        // `origin_main`'s return value. If `origin_main` called `exit`
+        // itself, it never returns, so we don't get here and the functions
+        // are only called once.
         exit(status)
     }
 }
@@ -169,6 +317,15 @@ pub(super) unsafe extern "C" fn entry(mem: *mut usize) -> ! {
 /// A program entry point similar to `_start`, but which is meant to be called
 /// by something else in the program rather than the OS.
 ///
+/// With the "thread" feature, this points the thread pointer at origin's own
+/// thread data for the main thread. If a libc started the program, this
+/// replaces libc's thread pointer, so any libc code that accesses libc's
+/// thread-local variables, such as `errno`, after this is called accesses the
+/// wrong memory. Enable the "keep-main-thread-pointer" feature to have origin
+/// leave libc's thread pointer in place on the main thread. In either case,
+/// libc functions that use thread-local variables must not be called on
+/// threads created by origin.
+///
 /// # Safety
 ///
 /// `mem` must point to a stack with the contents that the OS would provide
@@ -178,6 +335,21 @@ pub unsafe fn start(mem: *mut usize) -> ! {
     entry(mem)
 }
 
+/// Print a message to stderr and abort the process, when startup can't
+/// continue.
+///
+/// This doesn't depend on TLS or the allocator, so it can be used at any
+/// point during startup.
+#[cfg(any(feature = "thread", feature = "hardened-main"))]
+#[cold]
+pub(crate) fn abort_startup(msg: &str) -> ! {
+    use core::fmt::Write as _;
+
+    let _ = writeln!(Stderr, "origin: {}", msg);
+
+    crate::arch::trap()
+}
+
 /// Compute `argc`, `argv`, and `envp`.
 ///
 /// # Safety
@@ -207,11 +379,46 @@ unsafe fn compute_args(mem: *mut usize) -> (i32, *mut *mut u8, *mut *mut u8) {
 /// must point to the incoming environment variables.
 #[allow(unused_variables)]
 unsafe fn init_runtime(mem: *mut usize, envp: *mut *mut u8) {
+    // Check that `argv` and `envp` are NULL-terminated before anything looks
+    // for the end of `envp`.
+    #[cfg(feature = "hardened-main")]
+    hardened::check_arrays(mem);
+
+    // Record where the aux vector is, for `getauxval`.
+    #[cfg(any(feature = "getauxval", target_arch = "aarch64"))]
+    crate::getauxval::init(envp);
+
     // Explicitly initialize `rustix`. This is needed for things like
     // `page_size()` to work.
     #[cfg(feature = "param")]
     rustix::param::init(envp);
 
+    // Check that the strings `argv` and `envp` point to are valid.
+    #[cfg(feature = "hardened-main")]
+    hardened::check_strings(mem.add(1).cast(), envp);
+
+    // Locate the vDSO's time functions, for `vdso`.
+    #[cfg(feature = "vdso")]
+    vdso::init(envp);
+
+    // Ignore `SIGPIPE`, as std does, so that writes to closed pipes and
+    // sockets fail with `EPIPE` instead of terminating the program.
+    #[cfg(feature = "ignore-sigpipe")]
+    {
+        use rustix::runtime::{sigaction, Sigaction, Signal};
+
+        let mut action: Sigaction = core::mem::zeroed();
+        action.sa_handler_kernel = linux_raw_sys::signal_macros::sig_ign();
+        sigaction(Signal::Pipe, Some(action)).unwrap();
+    }
+
+    // Remember where `argv[0]` is, for `set_name`.
+    #[cfg(feature = "param")]
+    ARGV0.store(
+        *mem.add(1) as *mut u8,
+        core::sync::atomic::Ordering::Relaxed,
+    );
+
     // Read the program headers and extract the TLS info.
     #[cfg(feature = "thread")]
     thread::initialize_startup_info();
@@ -219,6 +426,16 @@ unsafe fn init_runtime(mem: *mut usize, envp: *mut *mut u8) {
     // Initialize the main thread.
     #[cfg(feature = "thread")]
     thread::initialize_main(mem.cast());
+
+    // Lock the main thread's TLS data and metadata into memory, before any
+    // user code has a chance to store anything there. Programs which need to
+    // know whether this succeeded can call `thread::lock_tls_in_memory`
+    // themselves.
+    #[cfg(feature = "lock-main-tls")]
+    if let Err(_err) = thread::lock_tls_in_memory() {
+        #[cfg(feature = "log")]
+        log::warn!("Unable to lock the main thread's TLS in memory: {:?}", _err);
+    }
 }
 
 /// Functions registered with [`at_exit`].
@@ -227,70 +444,275 @@ unsafe fn init_runtime(mem: *mut usize, envp: *mut *mut u8) {
 /// `SmallVec` to ensure we can register that many without allocating.
 ///
 /// [POSIX guarantees]: https://pubs.opengroup.org/onlinepubs/9699919799/functions/atexit.html
-#[cfg(all(feature = "program-at-exit", feature = "thread"))]
+#[cfg(all(
+    feature = "program-at-exit",
+    feature = "thread",
+    not(feature = "single-threaded")
+))]
 static DTORS: Mutex<smallvec::SmallVec<[Box<dyn FnOnce() + Send>; 32]>> =
     Mutex::new(smallvec::SmallVec::new_const());
 
 /// A type for `DTORS` in the single-threaded case that we can mark as `Sync`.
-#[cfg(all(feature = "program-at-exit", not(feature = "thread")))]
-struct Dtors(UnsafeCell<smallvec::SmallVec<[Box<dyn FnOnce() + Send>; 32]>>);
+#[cfg(all(
+    feature = "program-at-exit",
+    any(not(feature = "thread"), feature = "single-threaded")
+))]
+struct Dtors {
+    /// With "external-start", the program may have been started by a libc
+    /// which can create threads of its own, so use a simple spin lock.
+    #[cfg(feature = "external-start")]
+    locked: AtomicBool,
 
-/// SAFETY: With `feature = "take-charge"`, we can assume that Origin is
+    list: UnsafeCell<smallvec::SmallVec<[Box<dyn FnOnce() + Send>; 32]>>,
+}
+
+/// SAFETY: With `feature = "origin-start"`, we can assume that Origin is
 /// responsible for creating all threads in the program, and with
-/// `not(feature = "thread")` mode, Origin can't create any new threads, so we
-/// don't need to synchronize.
-#[cfg(all(feature = "program-at-exit", not(feature = "thread")))]
+/// `not(feature = "thread")` or `feature = "single-threaded"` mode, Origin
+/// can't create any new threads, so we don't need to synchronize. With
+/// `feature = "external-start"`, other code may have created threads, so
+/// `Dtors::with` synchronizes with `locked`.
+#[cfg(all(
+    feature = "program-at-exit",
+    any(not(feature = "thread"), feature = "single-threaded")
+))]
 unsafe impl Sync for Dtors {}
 
+#[cfg(all(
+    feature = "program-at-exit",
+    any(not(feature = "thread"), feature = "single-threaded")
+))]
+impl Dtors {
+    /// Call `f` with exclusive access to the list.
+    fn with<R>(
+        &self,
+        f: impl FnOnce(&mut smallvec::SmallVec<[Box<dyn FnOnce() + Send>; 32]>) -> R,
+    ) -> R {
+        #[cfg(feature = "external-start")]
+        while self
+            .locked
+            .compare_exchange_weak(false, true, Ordering::Acquire, Ordering::Relaxed)
+            .is_err()
+        {
+            core::hint::spin_loop();
+        }
+
+        // In "single-threaded" mode, check that the program really does have
+        // just the one thread, which origin starts with the process id as
+        // its thread id.
+        #[cfg(all(
+            feature = "thread",
+            feature = "single-threaded",
+            not(feature = "external-start")
+        ))]
+        debug_assert_eq!(
+            thread::current_id(),
+            rustix::process::getpid(),
+            "\"single-threaded\" programs must only have one thread"
+        );
+
+        // SAFETY: See the safety comments on the `unsafe impl Sync for Dtors`.
+        let result = f(unsafe { &mut *self.list.get() });
+
+        #[cfg(feature = "external-start")]
+        self.locked.store(false, Ordering::Release);
+
+        result
+    }
+}
+
 /// The single-threaded version of `DTORS`.
-#[cfg(all(feature = "program-at-exit", not(feature = "thread")))]
-static DTORS: Dtors = Dtors(UnsafeCell::new(smallvec::SmallVec::new_const()));
+#[cfg(all(
+    feature = "program-at-exit",
+    any(not(feature = "thread"), feature = "single-threaded")
+))]
+static DTORS: Dtors = Dtors {
+    #[cfg(feature = "external-start")]
+    locked: AtomicBool::new(false),
+    list: UnsafeCell::new(smallvec::SmallVec::new_const()),
+};
+
+/// Set once [`exit`] has started calling the functions registered with
+/// [`at_exit`].
+#[cfg(feature = "program-at-exit")]
+static EXITING: AtomicBool = AtomicBool::new(false);
+
+/// The number of functions registered with [`at_exit`] since [`exit`] started
+/// calling them.
+#[cfg(feature = "program-at-exit")]
+static EXIT_REGISTRATIONS: AtomicUsize = AtomicUsize::new(0);
+
+/// The limit set by [`set_max_exit_registrations`].
+#[cfg(feature = "program-at-exit")]
+static MAX_EXIT_REGISTRATIONS: AtomicUsize = AtomicUsize::new(DEFAULT_MAX_EXIT_REGISTRATIONS);
+
+/// The default limit on the number of functions which may be registered with
+/// [`at_exit`] while [`exit`] is calling the registered functions.
+#[cfg(feature = "program-at-exit")]
+#[cfg_attr(
+    docsrs,
+    doc(cfg(all(feature = "take-charge", feature = "program-at-exit")))
+)]
+pub const DEFAULT_MAX_EXIT_REGISTRATIONS: usize = 1024;
 
 /// Register a function to be called when [`exit`] is called.
+///
+/// Functions are called in the reverse order of their registration. A
+/// function may itself call `at_exit`, and the newly registered function is
+/// called next, before any functions registered earlier. To ensure that
+/// `exit` finishes even if a function always registers another, at most
+/// [`DEFAULT_MAX_EXIT_REGISTRATIONS`] functions, or the limit set with
+/// [`set_max_exit_registrations`], may be registered this way. Registering
+/// more prints a message to stderr and aborts the process.
 #[cfg(feature = "program-at-exit")]
 #[cfg_attr(docsrs, doc(cfg(feature = "program-at-exit")))]
 pub fn at_exit(func: Box<dyn FnOnce() + Send>) {
-    #[cfg(feature = "thread")]
-    let mut dtors = DTORS.lock();
-    // SAFETY: See the safety comments on the `unsafe impl Sync for Dtors`.
-    #[cfg(not(feature = "thread"))]
-    let dtors = unsafe { &mut *DTORS.0.get() };
+    if EXITING.load(Ordering::Relaxed)
+        && EXIT_REGISTRATIONS.fetch_add(1, Ordering::Relaxed)
+            >= MAX_EXIT_REGISTRATIONS.load(Ordering::Relaxed)
+    {
+        too_many_exit_registrations();
+    }
+
+    #[cfg(all(feature = "thread", not(feature = "single-threaded")))]
+    DTORS.lock().push(func);
+    #[cfg(any(not(feature = "thread"), feature = "single-threaded"))]
+    DTORS.with(|dtors| dtors.push(func));
+}
+
+/// Set the limit on the number of functions which may be registered with
+/// [`at_exit`] while [`exit`] is calling the registered functions.
+///
+/// The default is [`DEFAULT_MAX_EXIT_REGISTRATIONS`].
+#[cfg(feature = "program-at-exit")]
+#[cfg_attr(
+    docsrs,
+    doc(cfg(all(feature = "take-charge", feature = "program-at-exit")))
+)]
+pub fn set_max_exit_registrations(max: usize) {
+    MAX_EXIT_REGISTRATIONS.store(max, Ordering::Relaxed);
+}
+
+/// Report that the limit set by [`set_max_exit_registrations`] was exceeded,
+/// and abort the process.
+#[cfg(feature = "program-at-exit")]
+#[cold]
+fn too_many_exit_registrations() -> ! {
+    use core::fmt::Write as _;
+
+    let _ = Stderr.write_str(
+        "origin: too many functions registered with `at_exit` while exiting; \
+         see `origin::program::set_max_exit_registrations`\n",
+    );
 
-    dtors.push(func);
+    crate::arch::trap()
 }
 
 /// Call all the functions registered with [`at_exit`] or with the
 /// `.fini_array` section, and exit the program.
+///
+/// Those functions are called on the current thread while any other threads
+/// are still running, and other threads are only terminated when the program
+/// exits afterward. So they must not wait for other threads, which may never
+/// respond, and must not assume they have exclusive access to shared state.
+/// With the "quiesce-threads-before-exit" feature, origin first asks its
+/// other threads to stop, via `thread::exit_requested`, and waits a bounded
+/// time for them to return.
+///
+/// The current thread's thread-local storage isn't torn down before or while
+/// those functions are called, so they, and functions registered with
+/// `thread::at_exit`, can use `#[thread_local]` data on the current thread
+/// until the program exits.
+///
+/// `at_exit` needs an allocator, but the `.fini_array` functions don't; with
+/// the "fini-array" feature, they're called even when "alloc" is disabled,
+/// so programs without an allocator can still link C code which relies on
+/// finalizers.
+///
+/// After calling those functions, this writes any output buffered by
+/// `BufferedStdout`, and, with the "log" feature, calls `flush_logs`.
+///
+/// In debug builds with the "thread-stats" and "log" features, this logs a
+/// warning if there are other threads still running, which often means the
+/// program forgot to join them.
+///
+/// If one of those functions calls `exit` itself, the nested call continues
+/// with the functions that haven't been called yet, rather than starting
+/// over, and exits with the nested call's status.
+///
+/// When none of "program-at-exit", "thread-at-exit", and "fini-array" are
+/// enabled, there's nothing else to call, so without "log" this is just
+/// [`exit_immediately`].
+#[cfg_attr(
+    not(any(
+        feature = "program-at-exit",
+        feature = "thread-at-exit",
+        feature = "fini-array"
+    )),
+    inline
+)]
 pub fn exit(status: c_int) -> ! {
-    // Call functions registered with `at_thread_exit`.
+    // In debug builds, warn about threads which are still running, since
+    // they'll be terminated abruptly when we exit.
+    #[cfg(all(debug_assertions, feature = "thread-stats", feature = "log"))]
+    crate::thread::warn_outstanding();
+
+    // Ask other threads to stop before calling any exit functions.
+    #[cfg(feature = "quiesce-threads-before-exit")]
+    crate::thread::quiesce_others();
+
+    // Call functions registered with `thread::at_exit`, and then functions
+    // registered with `thread::register_global_exit_hook`.
     #[cfg(feature = "thread-at-exit")]
-    crate::thread::call_dtors(crate::thread::current());
+    {
+        // The index of the next global exit hook to call, so that if a
+        // `.fini_array` function calls `exit`, the nested call doesn't call
+        // the hooks again. `call_dtors` pops each function before calling it,
+        // so it resumes on its own.
+        static HOOKS_NEXT: AtomicUsize = AtomicUsize::new(0);
+
+        crate::thread::call_dtors(crate::thread::current());
+        crate::thread::call_global_exit_hooks(crate::thread::current(), &HOOKS_NEXT);
+    }
 
     // Call all the registered functions, in reverse order. Leave `DTORS`
     // unlocked while making the call so that functions can add more functions
     // to the end of the list.
     #[cfg(feature = "program-at-exit")]
-    loop {
-        #[cfg(feature = "thread")]
-        let mut dtors = DTORS.lock();
-        // SAFETY: See the safety comments on the `unsafe impl Sync for Dtors`.
-        #[cfg(not(feature = "thread"))]
-        let dtors = unsafe { &mut *DTORS.0.get() };
+    {
+        // Set once we're done processing `DTORS`, so that if a `.fini_array`
+        // function calls `exit`, the nested call doesn't try to lock it again.
+        static DTORS_DONE: AtomicBool = AtomicBool::new(false);
 
-        if let Some(func) = dtors.pop() {
-            // Unlock `DTORS` before calling `func`.
-            drop(dtors);
+        // From here on, count the functions registered by the functions we
+        // call, so that `at_exit` can bound them.
+        EXITING.store(true, Ordering::Relaxed);
 
-            #[cfg(feature = "log")]
-            log::trace!("Calling `at_exit`-registered function");
-
-            func();
-        } else {
-            // Now that we're done processing `DTORS`, leak the lock, since
-            // from this point on, nothing should try to add anything to it.
-            #[cfg(feature = "thread")]
-            core::mem::forget(dtors);
-            break;
+        while !DTORS_DONE.load(Ordering::Relaxed) {
+            #[cfg(all(feature = "thread", not(feature = "single-threaded")))]
+            let mut dtors = DTORS.lock();
+            #[cfg(all(feature = "thread", not(feature = "single-threaded")))]
+            let func = dtors.pop();
+            #[cfg(any(not(feature = "thread"), feature = "single-threaded"))]
+            let func = DTORS.with(|dtors| dtors.pop());
+
+            if let Some(func) = func {
+                // Unlock `DTORS` before calling `func`.
+                #[cfg(all(feature = "thread", not(feature = "single-threaded")))]
+                drop(dtors);
+
+                #[cfg(feature = "log")]
+                log::trace!("Calling `at_exit`-registered function");
+
+                func();
+            } else {
+                // Now that we're done processing `DTORS`, leak the lock, since
+                // from this point on, nothing should try to add anything to
+                // it.
+                #[cfg(all(feature = "thread", not(feature = "single-threaded")))]
+                core::mem::forget(dtors);
+                DTORS_DONE.store(true, Ordering::Relaxed);
+            }
         }
     }
 
@@ -299,6 +721,7 @@ pub fn exit(status: c_int) -> ! {
     unsafe {
         use core::arch::asm;
         use core::ffi::c_void;
+        use core::sync::atomic::{AtomicPtr, Ordering};
 
         // The linker-generated symbols that mark the start and end of the
         // `.fini_array` section.
@@ -307,9 +730,21 @@ pub fn exit(status: c_int) -> ! {
             static __fini_array_end: c_void;
         }
 
+        // The last `.fini_array` function we started calling, or null if we
+        // haven't started yet. If a `.fini_array` function calls `exit`, this
+        // lets the nested call resume where we left off, rather than calling
+        // the functions that have already been called again.
+        static FINI_NEXT: AtomicPtr<c_void> = AtomicPtr::new(core::ptr::null_mut());
+
         // Call the `.fini_array` functions.
         type FiniFn = extern "C" fn();
-        let mut fini = core::ptr::addr_of!(__fini_array_end).cast::<FiniFn>();
+        let mut fini = FINI_NEXT
+            .load(Ordering::Relaxed)
+            .cast::<FiniFn>()
+            .cast_const();
+        if fini.is_null() {
+            fini = core::ptr::addr_of!(__fini_array_end).cast::<FiniFn>();
+        }
         let fini_start = core::ptr::addr_of!(__fini_array_start).cast::<FiniFn>();
         // Prevent the optimizer from optimizing the `!=` comparison to true;
         // `fini` and `fini_start` may have the same address.
@@ -317,6 +752,7 @@ pub fn exit(status: c_int) -> ! {
 
         while fini != fini_start {
             fini = fini.sub(1);
+            FINI_NEXT.store(fini.cast::<c_void>().cast_mut(), Ordering::Relaxed);
 
             #[cfg(feature = "log")]
             log::trace!("Calling `.fini_array`-registered function `{:?}()`", *fini);
@@ -325,21 +761,522 @@ pub fn exit(status: c_int) -> ! {
         }
     }
 
+    // Write any output buffered by `BufferedStdout`.
+    #[cfg(feature = "program-at-exit")]
+    stdio::flush_at_exit();
+
+    // Write any buffered log output.
+    #[cfg(feature = "log")]
+    log_flush::flush_logs();
+
     // Call `exit_immediately` to exit the program.
     exit_immediately(status)
 }
 
 /// Exit the program without calling functions registered with [`at_exit`] or
 /// with the `.fini_array` section.
+///
+/// With the "program-at-immediate-exit" feature, this does call functions
+/// registered with `at_immediate_exit`. With the "log" feature, this emits a
+/// trace-level log message before exiting, which calls into the logger.
+/// Without either, this is just the `exit_group` system call. To exit without
+/// any possibility of logging or calling other functions, use
+/// [`exit_silent`].
 #[inline]
 pub fn exit_immediately(status: c_int) -> ! {
     #[cfg(feature = "log")]
     log::trace!("Program exiting with status `{:?}`", status);
 
+    // Call functions registered with `at_immediate_exit`.
+    #[cfg(feature = "program-at-immediate-exit")]
+    immediate_exit::call_immediate_exit_functions();
+
     // Call `rustix` to exit the program.
     rustix::runtime::exit_group(status)
 }
 
+/// Exit the program without calling functions registered with [`at_exit`] or
+/// with the `.fini_array` section, and without logging, regardless of which
+/// features are enabled.
+///
+/// This is just the `exit_group` system call, so it's suitable for use in
+/// signal handlers, or after the logger has been torn down.
+#[inline]
+pub fn exit_silent(status: c_int) -> ! {
+    rustix::runtime::exit_group(status)
+}
+
+/// Exit the child process of a `vfork`, without doing anything else.
+///
+/// Between `vfork` and `execve`, the child shares the parent's memory, and
+/// the parent is suspended, so the child must not run exit functions, free
+/// memory, take locks, log, or touch thread-local state, any of which could
+/// corrupt the parent. This just makes the raw `exit_group` system call, so
+/// it's safe to use in that state, such as when `execve` fails.
+///
+/// Outside of a `vfork` child, this behaves like [`exit_silent`].
+#[inline]
+pub fn exit_vfork_child(status: c_int) -> ! {
+    exit_silent(status)
+}
+
+/// Terminate the program as if it had been killed by `sig`.
+///
+/// This resets `sig`'s disposition to the default, unblocks it, and sends it
+/// to the current thread, so that a parent waiting for this process sees it
+/// as terminated by `sig`, which an exit status can't express. Functions
+/// registered with [`at_exit`] or with the `.fini_array` section are not
+/// called.
+///
+/// If `sig`'s [default action] isn't to terminate the process, such as for
+/// `Signal::Child`, this exits with status `128 + sig` instead, following the
+/// shell convention.
+///
+/// [default action]: crate::signal::default_action
+#[cfg(feature = "signal")]
+#[cfg_attr(docsrs, doc(cfg(feature = "signal")))]
+pub fn terminate_by_signal(sig: crate::signal::Signal) -> ! {
+    use crate::signal::{default_action, sigaction, DefaultAction, SigDfl, Sigaction};
+    use rustix::runtime::{sigprocmask, tkill, How, Sigset};
+    use rustix::thread::gettid;
+
+    #[cfg(feature = "log")]
+    log::trace!("Program terminating by signal `{:?}`", sig);
+
+    unsafe {
+        // Reset the signal to its default disposition.
+        let mut action: Sigaction = core::mem::zeroed();
+        action.sa_handler_kernel = SigDfl;
+        sigaction(sig, Some(action)).ok();
+
+        // Unblock it.
+        let mut set: Sigset = core::mem::zeroed();
+        let word_bits = 8 * core::mem::size_of_val(&set.sig[0]);
+        let bit = sig as usize - 1;
+        set.sig[bit / word_bits] |= 1 << (bit % word_bits);
+        sigprocmask(How::UNBLOCK, Some(&set)).ok();
+
+        // Send it to ourselves. An unblocked signal sent to the current thread
+        // is delivered before `tkill` returns, however another thread could
+        // change the disposition in the meantime, so keep trying.
+        loop {
+            tkill(gettid(), sig).ok();
+
+            if !matches!(
+                default_action(sig),
+                DefaultAction::Terminate | DefaultAction::CoreDump
+            ) {
+                exit_immediately(128 + sig as c_int);
+            }
+
+            sigaction(sig, Some(action)).ok();
+        }
+    }
+}
+
+/// Return the filename the program was executed with.
+///
+/// This is the same as [`exec_path`]. It isn't affected by [`set_name`].
+#[cfg(feature = "param")]
+#[cfg_attr(docsrs, doc(cfg(all(feature = "take-charge", feature = "param"))))]
+#[inline]
+#[must_use]
+pub fn name() -> &'static CStr {
+    exec_path()
+}
+
+/// Return the path the program was executed with.
+///
+/// This is the `AT_EXECFN` value that Linux provides at startup, which is
+/// the path passed to `execve`. It may be relative to the working directory
+/// the program started in, and if the program was started through a
+/// symlink, it names the symlink. To re-execute the program itself
+/// regardless of how it was started, use `/proc/self/exe` instead.
+#[cfg(feature = "param")]
+#[cfg_attr(docsrs, doc(cfg(all(feature = "take-charge", feature = "param"))))]
+#[inline]
+#[must_use]
+pub fn exec_path() -> &'static CStr {
+    rustix::param::linux_execfn()
+}
+
+/// Write the path of the current working directory into `buf`, and return
+/// it.
+///
+/// This fails with [`io::Errno::RANGE`] if `buf` is too small to hold the
+/// path and its NUL terminator; `PATH_MAX`, 4096 bytes, is always enough. If
+/// the working directory isn't reachable from the root directory, such as
+/// after a [`chroot`] that leaves it outside the new root, this fails with
+/// [`io::Errno::NOENT`], as `getcwd` in libc does.
+#[cfg_attr(docsrs, doc(cfg(feature = "take-charge")))]
+pub fn current_dir(buf: &mut [u8]) -> io::Result<&CStr> {
+    use linux_raw_sys::general::__NR_getcwd;
+
+    // SAFETY: The kernel writes at most `buf.len()` bytes into `buf`.
+    let r0 = unsafe { crate::arch::syscall2(__NR_getcwd, buf.as_mut_ptr() as usize, buf.len()) };
+    if r0 < 0 {
+        return Err(io::Errno::from_raw_os_error(-r0 as i32));
+    }
+
+    // On success, Linux returns the length of the path including the NUL
+    // terminator. It reports an unreachable directory with a path that
+    // doesn't start with `/`.
+    let path = &buf[..r0 as usize];
+    if path.first() != Some(&b'/') {
+        return Err(io::Errno::NOENT);
+    }
+    Ok(CStr::from_bytes_with_nul(path).unwrap())
+}
+
+/// The address of the program's `argv[0]` string, for [`set_name`].
+#[cfg(feature = "param")]
+static ARGV0: AtomicPtr<u8> = AtomicPtr::new(core::ptr::null_mut());
+
+/// The original length of the program's `argv[0]` string, or `usize::MAX` if
+/// it hasn't been computed yet.
+#[cfg(feature = "param")]
+static ARGV0_LEN: AtomicUsize = AtomicUsize::new(usize::MAX);
+
+/// Set the name of the process, as shown by tools like `ps` and `top`.
+///
+/// This sets the name of the calling thread with `PR_SET_NAME`, which Linux
+/// truncates to 15 bytes, and so should be called from the main thread to
+/// rename the process. It also overwrites the `argv[0]` string in place, so
+/// that the name appears in `/proc/self/cmdline`. This can't grow the string,
+/// so `name` is truncated to the original length of `argv[0]`, and any
+/// remaining bytes are filled with NULs.
+///
+/// The `argv` pointer passed to `origin_main` refers to the same string, so
+/// this changes what it points to too. Callers should ensure that nothing
+/// else is reading or writing `argv[0]` concurrently.
+#[cfg(feature = "param")]
+#[cfg_attr(docsrs, doc(cfg(all(feature = "take-charge", feature = "param"))))]
+pub fn set_name(name: &CStr) -> io::Result<()> {
+    rustix::thread::set_name(name)?;
+
+    use core::sync::atomic::Ordering::Relaxed;
+
+    let argv0 = ARGV0.load(Relaxed);
+    if argv0.is_null() {
+        return Ok(());
+    }
+
+    // SAFETY: `argv0` points to the NUL-terminated `argv[0]` string the OS
+    // provided, which remains valid for the life of the program. We compute
+    // its original length before the first time we overwrite it, and never
+    // write past that.
+    unsafe {
+        let mut len = ARGV0_LEN.load(Relaxed);
+        if len == usize::MAX {
+            len = CStr::from_ptr(argv0.cast()).to_bytes().len();
+            ARGV0_LEN.store(len, Relaxed);
+        }
+
+        let name = name.to_bytes();
+        let copy_len = core::cmp::min(name.len(), len);
+        core::ptr::copy_nonoverlapping(name.as_ptr(), argv0, copy_len);
+        core::ptr::write_bytes(argv0.add(copy_len), 0, len - copy_len);
+    }
+
+    Ok(())
+}
+
+/// Return the program break as it was the first time this was called.
+///
+/// Origin itself never moves the program break, so unless something else in
+/// the program has called `brk`, this is where the kernel initially placed
+/// it, just past the end of the executable's data. Allocators can use this
+/// as a starting point for managing the heap with `brk`.
+///
+/// This returns a null pointer if the program break can't be queried.
+#[cfg_attr(docsrs, doc(cfg(feature = "take-charge")))]
+#[must_use]
+pub fn initial_brk() -> *mut c_void {
+    use core::sync::atomic::Ordering::Relaxed;
+
+    static INITIAL_BRK: AtomicPtr<c_void> = AtomicPtr::new(core::ptr::null_mut());
+
+    let brk = INITIAL_BRK.load(Relaxed);
+    if !brk.is_null() {
+        return brk;
+    }
+
+    // SAFETY: Passing a null pointer to `brk` just queries the current
+    // program break without changing it.
+    let brk =
+        unsafe { rustix::runtime::brk(core::ptr::null_mut()) }.unwrap_or(core::ptr::null_mut());
+    match INITIAL_BRK.compare_exchange(core::ptr::null_mut(), brk, Relaxed, Relaxed) {
+        Ok(_) => brk,
+        Err(existing) => existing,
+    }
+}
+
+/// Return the address of the end of the executable's loaded segments.
+///
+/// This is computed from the executable's segment headers (“phdrs”), and is
+/// the highest address covered by any `PT_LOAD` segment, which is normally
+/// the end of the `.bss` section. It's not necessarily page-aligned.
+///
+/// The returned pointer is only an address; it isn't valid for reading or
+/// writing.
+#[cfg_attr(docsrs, doc(cfg(feature = "take-charge")))]
+#[must_use]
+pub fn data_end() -> *mut c_void {
+    let mut end = 0;
+
+    for phdr in exe_phdr_iter() {
+        if phdr.p_type == PT_LOAD {
+            end = core::cmp::max(end, phdr.p_vaddr + phdr.p_memsz);
+        }
+    }
+
+    without_provenance_mut(exe_load_offset().wrapping_add(end))
+}
+
+/// Return the contents of the executable's GNU build ID note, if it has one.
+///
+/// Debuggers, `coredumpctl`, and symbol servers use the build ID to identify
+/// the executable. The linker computes it when linking with `--build-id`,
+/// which is the default with many toolchains; with "origin-start", nothing
+/// else provides it.
+#[cfg_attr(docsrs, doc(cfg(feature = "take-charge")))]
+#[must_use]
+pub fn build_id() -> Option<&'static [u8]> {
+    /// The type of the `PT_NOTE` segment header.
+    const PT_NOTE: u32 = 4;
+    /// The type of the build ID note.
+    const NT_GNU_BUILD_ID: u32 = 3;
+
+    let offset = exe_load_offset();
+    let (first_phdr, _phent, _phnum) = exe_phdrs();
+
+    for phdr in exe_phdr_iter() {
+        if phdr.p_type != PT_NOTE {
+            continue;
+        }
+
+        // Notes are padded to 4 bytes, or to 8 bytes in segments aligned to
+        // 8 bytes.
+        let align = if phdr.p_align == 8 { 8 } else { 4 };
+        let size = phdr.p_memsz;
+        let base = first_phdr
+            .with_addr(offset.wrapping_add(phdr.p_vaddr))
+            .cast::<u8>();
+
+        // Each note has a 12-byte header followed by the name and the
+        // descriptor. Check each note's extent with integer offsets before
+        // forming any pointers into it, so that a malformed note can't lead
+        // us outside the segment.
+        let mut pos = 0_usize;
+        while size - pos >= 12 {
+            // SAFETY: The `PT_NOTE` segment is part of a loaded segment, and
+            // we checked that the header is within it.
+            let (name_size, desc_size, type_) = unsafe {
+                let header = base.add(pos).cast::<u32>();
+                (*header, *header.add(1), *header.add(2))
+            };
+            let name_pos = pos + 12;
+            let Some(desc_pos) = name_pos
+                .checked_add(name_size as usize)
+                .and_then(|n| align_note(n, align))
+            else {
+                break;
+            };
+            let Some(desc_end) = desc_pos.checked_add(desc_size as usize) else {
+                break;
+            };
+            if desc_end > size {
+                break;
+            }
+
+            // SAFETY: We checked that the name and descriptor are within the
+            // segment.
+            unsafe {
+                if type_ == NT_GNU_BUILD_ID
+                    && name_size == 4
+                    && slice::from_raw_parts(base.add(name_pos), 4) == b"GNU\0"
+                {
+                    return Some(slice::from_raw_parts(
+                        base.add(desc_pos),
+                        desc_size as usize,
+                    ));
+                }
+            }
+
+            // The last note's padding may be omitted.
+            pos = match align_note(desc_end, align) {
+                Some(next) if next <= size => next,
+                _ => break,
+            };
+        }
+    }
+
+    None
+}
+
+/// Round `n` up to a multiple of `align`, the alignment of ELF note fields,
+/// or return `None` if that overflows.
+const fn align_note(n: usize, align: usize) -> Option<usize> {
+    match n.checked_add(align - 1) {
+        Some(n) => Some(n & !(align - 1)),
+        None => None,
+    }
+}
+
+/// Iterate over the executable's segment headers (“phdrs”).
+fn exe_phdr_iter() -> impl Iterator<Item = &'static Elf_Phdr> {
+    let (first_phdr, phent, phnum) = exe_phdrs();
+
+    // SAFETY: We assume that the phdr array pointer and length the kernel
+    // provided to the process describe a valid phdr array.
+    (0..phnum).map(move |i| unsafe { &*first_phdr.byte_add(i * phent).cast::<Elf_Phdr>() })
+}
+
+/// Compute the offset from the static virtual addresses in the executable's
+/// segment headers to the dynamic addresses.
+fn exe_load_offset() -> usize {
+    let (first_phdr, _phent, _phnum) = exe_phdrs();
+
+    // SAFETY: We're just taking the address of `_DYNAMIC` for arithmetic
+    // purposes, not dereferencing it.
+    let dynamic_addr: *const c_void = unsafe { &_DYNAMIC };
+
+    // As in `thread::initialize_startup_info`, use whichever of `PT_PHDR` or
+    // `PT_DYNAMIC` we get.
+    let mut offset = 0;
+    for phdr in exe_phdr_iter() {
+        match phdr.p_type {
+            PT_PHDR => offset = first_phdr.addr().wrapping_sub(phdr.p_vaddr),
+            PT_DYNAMIC => offset = dynamic_addr.addr().wrapping_sub(phdr.p_vaddr),
+            _ => {}
+        }
+    }
+    offset
+}
+
+extern "C" {
+    /// Declare the `_DYNAMIC` symbol so that we can compare its address with
+    /// the static address in the `PT_DYNAMIC` header to learn our offset. Use
+    /// a weak symbol because `_DYNAMIC` is not always present.
+    pub(crate) static _DYNAMIC: c_void;
+}
+// Rust has `extern_weak` but it isn't stable, so use a `global_asm`.
+core::arch::global_asm!(".weak _DYNAMIC");
+
+/// Launch a program in a new child process, using a `vfork`-style `clone`.
+///
+/// The child shares the parent's memory and the parent is suspended until the
+/// child calls `execve` or exits, so this avoids the cost of copying the
+/// parent's address space that `fork` has. The child never returns to Rust
+/// code; it calls `execve` directly from assembly code, and if that fails, it
+/// exits with status 127 and the `execve` error is returned from this
+/// function.
+///
+/// Signal handlers are not reset in the child, so a signal delivered to the
+/// child before it calls `execve` runs the parent's handler in the child,
+/// sharing the parent's memory. Programs with handlers that can't tolerate
+/// that should block those signals around this call, keeping in mind that the
+/// signal mask is inherited by the new program.
+///
+/// Ignored signals stay ignored in the new program, so with the
+/// "ignore-sigpipe" feature, the new program starts with `SIGPIPE` ignored,
+/// unlike with std's `Command`, which resets it.
+///
+/// # Safety
+///
+/// `argv` and `envp` must point to NULL-terminated arrays of pointers to
+/// NUL-terminated C strings.
+#[cfg_attr(docsrs, doc(cfg(feature = "take-charge")))]
+pub unsafe fn vfork_exec(
+    path: &CStr,
+    argv: *const *const u8,
+    envp: *const *const u8,
+) -> io::Result<Pid> {
+    use linux_raw_sys::general::{CLONE_VFORK, CLONE_VM, SIGCHLD};
+    use rustix::process::{waitpid, WaitOptions};
+
+    let flags = CLONE_VM | CLONE_VFORK | SIGCHLD;
+
+    // The child stores the `execve` error code here if it fails.
+    let mut exec_errno = 0;
+
+    let clone_res =
+        crate::arch::vfork_execve(flags, path.as_ptr().cast(), argv, envp, &mut exec_errno);
+    if clone_res < 0 {
+        return Err(io::Errno::from_raw_os_error(-clone_res as i32));
+    }
+    let pid = Pid::from_raw_unchecked(clone_res as RawPid);
+
+    // We're resumed once the child has either called `execve` successfully or
+    // exited. If it exited, reap it and report the error.
+    if exec_errno != 0 {
+        let _ = waitpid(Some(pid), WaitOptions::empty());
+        return Err(io::Errno::from_raw_os_error(-exec_errno));
+    }
+
+    #[cfg(feature = "log")]
+    log::trace!(
+        "Launched process {:?} with `vfork_exec`",
+        pid.as_raw_nonzero()
+    );
+
+    Ok(pid)
+}
+
+/// Test whether the program is running in “secure execution” mode.
+///
+/// The kernel sets `AT_SECURE` in the aux vector when the program was
+/// started with elevated privileges, such as by a setuid or setgid
+/// executable, or one with file capabilities, or when a Linux Security Module
+/// requests it. In that case, the environment variables were chosen by a
+/// less-privileged user, so the program should not trust them, and in
+/// particular should not use them to choose files to load or execute. libc
+/// ignores variables such as `LD_PRELOAD` in this mode for the same reason.
+#[cfg(feature = "param")]
+#[cfg_attr(docsrs, doc(cfg(all(feature = "take-charge", feature = "param"))))]
+#[inline]
+#[must_use]
+pub fn is_secure() -> bool {
+    // `rustix::param::init` records `AT_SECURE` for us.
+    rustix::runtime::linux_secure()
+}
+
+/// Set whether the process is “dumpable”.
+///
+/// A process which isn't dumpable doesn't produce core dumps, and can't be
+/// attached to with `ptrace` or have its `/proc/<pid>` files such as `mem`
+/// read by other unprivileged processes. Linux resets this to dumpable on
+/// `execve`, and clears it when the process's credentials change, such as
+/// when it drops privileges with `setuid`. It applies to the whole process.
+#[cfg_attr(docsrs, doc(cfg(feature = "take-charge")))]
+#[doc(alias = "PR_SET_DUMPABLE")]
+pub fn set_dumpable(dumpable: bool) -> io::Result<()> {
+    use rustix::process::{set_dumpable_behavior, DumpableBehavior};
+
+    set_dumpable_behavior(if dumpable {
+        DumpableBehavior::Dumpable
+    } else {
+        DumpableBehavior::NotDumpable
+    })
+}
+
+/// Set the `no_new_privs` attribute.
+///
+/// Once this is set, `execve` can't grant privileges, such as by running a
+/// setuid or setgid executable, or one with file capabilities, and it can't
+/// be unset. Unprivileged processes must set it before installing seccomp
+/// filters.
+///
+/// This is a per-thread attribute, which is inherited by threads created
+/// afterward and by child processes, so to cover the whole process, call
+/// this before creating any threads, for example at the start of
+/// `origin_main` or from an `.init_array` function.
+#[cfg_attr(docsrs, doc(cfg(feature = "take-charge")))]
+#[doc(alias = "PR_SET_NO_NEW_PRIVS")]
+pub fn set_no_new_privs() -> io::Result<()> {
+    rustix::thread::set_no_new_privs(true)
+}
+
 /// Execute a trap instruction.
 ///
 /// This will produce a `Signal::Ill`, which by default will immediately
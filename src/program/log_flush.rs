@@ -0,0 +1,59 @@
+//! Flushing buffered log output when the program exits.
+
+use core::ptr::null_mut;
+use core::sync::atomic::{AtomicPtr, Ordering};
+
+/// The function registered with [`set_log_flush_hook`], as a `fn()`, or null.
+static HOOK: AtomicPtr<()> = AtomicPtr::new(null_mut());
+
+/// Set a function to be called by [`flush_logs`], to write out any log
+/// output that a logger has buffered.
+///
+/// This is for loggers, and `tracing` subscribers, which buffer their output
+/// and don't flush it when the program exits on their own. The function is
+/// called by [`exit`] after everything else it calls, so it also sees log
+/// messages from functions registered with `at_exit`. Only one function is
+/// kept; setting another replaces it.
+///
+/// With the libc backend, [`exit`] calls it from an `atexit` function
+/// registered by the first call to this function, so functions registered
+/// with `at_exit` after that are called before it, and functions registered
+/// before it are called after it.
+///
+/// [`exit`]: super::exit
+pub fn set_log_flush_hook(hook: fn()) {
+    let _prev = HOOK.swap(hook as *mut (), Ordering::AcqRel);
+
+    #[cfg(not(feature = "take-charge"))]
+    if _prev.is_null() {
+        extern "C" fn call() {
+            flush_logs();
+        }
+
+        // SAFETY: `call` is an `extern "C" fn` with no arguments, as `atexit`
+        // expects.
+        unsafe {
+            assert_eq!(libc::atexit(call), 0);
+        }
+    }
+}
+
+/// Flush any buffered log output.
+///
+/// This flushes the `log` crate's logger, and then calls the function
+/// registered with [`set_log_flush_hook`], if any.
+/// [`exit`] calls this, so programs only need to call it themselves before
+/// exiting in other ways, such as with [`exit_immediately`].
+///
+/// [`exit`]: super::exit
+/// [`exit_immediately`]: super::exit_immediately
+pub fn flush_logs() {
+    log::logger().flush();
+
+    let hook = HOOK.load(Ordering::Acquire);
+    if !hook.is_null() {
+        // SAFETY: `set_log_flush_hook` only stores `fn()`s.
+        let hook = unsafe { core::mem::transmute::<*mut (), fn()>(hook) };
+        hook();
+    }
+}
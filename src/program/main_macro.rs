@@ -0,0 +1,82 @@
+//! The `main!` macro, for defining `origin_main` in terms of other functions.
+
+/// Define `origin_main` to call a function with a different name, or to
+/// call one of several functions depending on the name the program was
+/// invoked with.
+///
+/// Origin calls a function with the symbol name `origin_main`, as described
+/// in the [`program`] module documentation. This macro defines it, so that
+/// the program's main function can be named something else:
+///
+/// ```no_run
+/// unsafe fn my_main(argc: usize, argv: *mut *mut u8, envp: *mut *mut u8) -> i32 {
+///     todo!("Run the program and return the program exit status.")
+/// }
+///
+/// origin::main!(my_main);
+/// ```
+///
+/// For multi-call programs, which act as different programs depending on the
+/// name they're invoked with, this can also dispatch on the file name in
+/// `argv[0]`, after its last `/`. Names are compared as byte strings, and the
+/// `_` case is used if none of them match, including when `argc` is zero:
+///
+/// ```no_run
+/// # unsafe fn true_main(_: usize, _: *mut *mut u8, _: *mut *mut u8) -> i32 { 0 }
+/// # unsafe fn false_main(_: usize, _: *mut *mut u8, _: *mut *mut u8) -> i32 { 1 }
+/// # unsafe fn usage_main(_: usize, _: *mut *mut u8, _: *mut *mut u8) -> i32 { 2 }
+/// origin::main! {
+///     "true" => true_main,
+///     "false" => false_main,
+///     _ => usage_main,
+/// }
+/// ```
+///
+/// Each function takes the same arguments as `origin_main`, and may return
+/// anything that implements [`Termination`], such as `i32`, [`ExitCode`], or
+/// a `Result`, which is converted into the program exit status. Since the
+/// symbol name is fixed, this can only be used once in a program.
+///
+/// [`program`]: crate::program
+/// [`Termination`]: crate::program::Termination
+/// [`ExitCode`]: crate::program::ExitCode
+#[macro_export]
+macro_rules! main {
+    ($($name:literal => $main:path,)* _ => $default:path $(,)?) => {
+        #[no_mangle]
+        unsafe fn origin_main(argc: usize, argv: *mut *mut u8, envp: *mut *mut u8) -> i32 {
+            let name = $crate::program::__invoked_name(argc, argv);
+            $(
+                if name == $name.as_bytes() {
+                    return $crate::program::Termination::report($main(argc, argv, envp));
+                }
+            )*
+            $crate::program::Termination::report($default(argc, argv, envp))
+        }
+    };
+    ($main:path $(,)?) => {
+        #[no_mangle]
+        unsafe fn origin_main(argc: usize, argv: *mut *mut u8, envp: *mut *mut u8) -> i32 {
+            $crate::program::Termination::report($main(argc, argv, envp))
+        }
+    };
+}
+
+/// Return the file name in `argv[0]`, after its last `/`, or an empty slice
+/// if `argc` is zero, for `main!`.
+///
+/// # Safety
+///
+/// `argc` and `argv` must be the values passed to `origin_main`.
+#[doc(hidden)]
+pub unsafe fn __invoked_name(argc: usize, argv: *mut *mut u8) -> &'static [u8] {
+    if argc == 0 || argv.is_null() || (*argv).is_null() {
+        return &[];
+    }
+
+    let path = core::ffi::CStr::from_ptr((*argv).cast()).to_bytes();
+    match path.iter().rposition(|byte| *byte == b'/') {
+        Some(slash) => &path[slash + 1..],
+        None => path,
+    }
+}
@@ -0,0 +1,106 @@
+//! Getting and setting the process's execution domain and flags.
+
+use rustix::io;
+
+/// The [`set_personality`] flag which disables address-space layout
+/// randomization for programs executed afterward.
+pub const ADDR_NO_RANDOMIZE: u32 = 0x0040000;
+
+/// The `personality` argument which queries the current persona without
+/// changing it.
+const PERSONALITY_QUERY: u32 = 0xffff_ffff;
+
+/// Set the process's execution domain and flags, with `personality`, and
+/// return the previous value.
+///
+/// `persona` holds an execution domain in its low byte, which is 0 for
+/// ordinary Linux programs, and flags such as [`ADDR_NO_RANDOMIZE`] in its
+/// upper bits. To add a flag, use [`personality`] to get the current value
+/// and set it with the flag added. Most flags, including
+/// `ADDR_NO_RANDOMIZE`, only take effect for programs executed afterward,
+/// and they are inherited across `execve` and by child processes.
+pub fn set_personality(persona: u32) -> io::Result<u32> {
+    #[cfg(feature = "take-charge")]
+    {
+        use linux_raw_sys::general::__NR_personality;
+
+        // SAFETY: `personality` doesn't access memory.
+        let r0 = unsafe { crate::arch::syscall1(__NR_personality, persona as usize) };
+        if r0 < 0 {
+            Err(io::Errno::from_raw_os_error(-r0 as i32))
+        } else {
+            Ok(r0 as u32)
+        }
+    }
+
+    #[cfg(not(feature = "take-charge"))]
+    {
+        // SAFETY: `personality` doesn't access memory.
+        let r = unsafe { libc::personality(persona as libc::c_ulong) };
+        if r == -1 {
+            Err(io::Errno::from_raw_os_error(errno::errno().0))
+        } else {
+            Ok(r as u32)
+        }
+    }
+}
+
+/// Return the process's current execution domain and flags, as passed to
+/// [`set_personality`].
+pub fn personality() -> io::Result<u32> {
+    set_personality(PERSONALITY_QUERY)
+}
+
+/// Ensure the program is running with address-space layout randomization
+/// disabled, by setting [`ADDR_NO_RANDOMIZE`] and executing the program
+/// again, if it isn't set already.
+///
+/// This is meant to be called at the start of the program, with the `argv`
+/// and `envp` it was passed, so that the addresses of the program, its
+/// stack, and its allocations are the same from one run to the next, for
+/// reproducible debugging. If the flag is already set, because this has
+/// already executed the program again or because it was started that way,
+/// such as with `setarch -R`, this returns `Ok(())`, and the caller should
+/// proceed. Otherwise, it doesn't return unless it fails.
+///
+/// The program is executed again from `/proc/self/exe`, so this requires
+/// `/proc` to be mounted. Anything the program has done before this, such
+/// as creating threads or setting signal handlers, is lost, except for the
+/// things `execve` preserves, such as ignored signals and the signal mask.
+///
+/// # Safety
+///
+/// `argv` and `envp` must point to NULL-terminated arrays of pointers to
+/// NUL-terminated C strings.
+pub unsafe fn disable_aslr_and_reexec(
+    argv: *const *const u8,
+    envp: *const *const u8,
+) -> io::Result<()> {
+    let persona = personality()?;
+    if persona & ADDR_NO_RANDOMIZE != 0 {
+        return Ok(());
+    }
+
+    set_personality(persona | ADDR_NO_RANDOMIZE)?;
+
+    #[cfg(feature = "log")]
+    log::trace!("Executing the program again with ASLR disabled");
+
+    #[cfg(feature = "take-charge")]
+    let err = rustix::runtime::execve(c"/proc/self/exe", argv, envp);
+
+    #[cfg(not(feature = "take-charge"))]
+    let err = {
+        libc::execve(
+            c"/proc/self/exe".as_ptr(),
+            argv.cast::<*const libc::c_char>(),
+            envp.cast::<*const libc::c_char>(),
+        );
+        io::Errno::from_raw_os_error(errno::errno().0)
+    };
+
+    // Restore the previous persona, so that programs this one executes later
+    // aren't affected.
+    let _ = set_personality(persona);
+    Err(err)
+}
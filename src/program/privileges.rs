@@ -0,0 +1,83 @@
+//! Dropping privileges.
+
+use core::ffi::CStr;
+use rustix::io;
+use rustix::process::{Gid, Uid};
+
+/// Change the process's root directory to `path`, and change the current
+/// directory to the new root.
+///
+/// Changing the current directory too means that relative paths can't be
+/// used to reach files outside of the new root, which they otherwise could,
+/// since `chroot` alone leaves the current directory where it was.
+///
+/// This requires `CAP_SYS_CHROOT`, so a program which drops its privileges
+/// should call this first, before [`drop_privileges`]. Note that a process
+/// which keeps `CAP_SYS_CHROOT`, or has file descriptors open for
+/// directories outside the new root, can escape it.
+pub fn chroot(path: &CStr) -> io::Result<()> {
+    rustix::process::chroot(path)?;
+    rustix::process::chdir(c"/")
+}
+
+/// Permanently drop the process's privileges, by setting its supplementary
+/// groups to `supplementary`, and its real, effective, and saved group and
+/// user ids to `gid` and `uid`.
+///
+/// These are set in that order, since setting the user id first would take
+/// away the privilege needed to set the groups, and each step is checked,
+/// since continuing after a failure would leave the program running with
+/// privileges it meant to give up. Once everything is set, this checks that
+/// the ids took effect, and fails with [`io::Errno::PERM`] if not.
+///
+/// Linux keeps credentials per thread. With the libc backend, libc
+/// propagates the changes to all of the process's threads. With origin
+/// taking charge, nothing does, so this only changes the calling thread and
+/// threads it creates afterward; call it before creating any threads, for
+/// example at the start of `origin_main`. Since this needs `CAP_SETUID` and
+/// `CAP_SETGID`, call [`chroot`] first, if the program uses it.
+pub fn drop_privileges(uid: Uid, gid: Gid, supplementary: &[Gid]) -> io::Result<()> {
+    use rustix::process::{getegid, geteuid, getgid, getuid};
+
+    #[cfg(feature = "take-charge")]
+    {
+        use rustix::thread::{set_thread_groups, set_thread_res_gid, set_thread_res_uid};
+
+        set_thread_groups(supplementary)?;
+        set_thread_res_gid(gid, gid, gid)?;
+        set_thread_res_uid(uid, uid, uid)?;
+    }
+
+    #[cfg(not(feature = "take-charge"))]
+    unsafe {
+        let check = |r: libc::c_int| {
+            if r == 0 {
+                Ok(())
+            } else {
+                Err(io::Errno::from_raw_os_error(errno::errno().0))
+            }
+        };
+
+        // `Gid` is a transparent wrapper around `gid_t`.
+        check(libc::setgroups(
+            supplementary.len(),
+            supplementary.as_ptr().cast::<libc::gid_t>(),
+        ))?;
+        let (gid_raw, uid_raw) = (gid.as_raw(), uid.as_raw());
+        check(libc::setresgid(gid_raw, gid_raw, gid_raw))?;
+        check(libc::setresuid(uid_raw, uid_raw, uid_raw))?;
+    }
+
+    if getgid() != gid || getegid() != gid || getuid() != uid || geteuid() != uid {
+        return Err(io::Errno::PERM);
+    }
+
+    #[cfg(feature = "log")]
+    log::trace!(
+        "Dropped privileges to uid {} and gid {}",
+        uid.as_raw(),
+        gid.as_raw()
+    );
+
+    Ok(())
+}
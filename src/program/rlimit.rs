@@ -0,0 +1,32 @@
+//! Getting and setting resource limits.
+
+use rustix::io;
+use rustix::process::{Resource, Rlimit};
+
+/// Return the current and maximum values of the resource limit `resource`.
+///
+/// A `None` value means the limit is unlimited.
+#[doc(alias = "getrlimit")]
+#[inline]
+#[must_use]
+pub fn get_rlimit(resource: Resource) -> Rlimit {
+    rustix::process::getrlimit(resource)
+}
+
+/// Set the current and maximum values of the resource limit `resource`.
+///
+/// Unprivileged processes may lower their maximums, and set their current
+/// values anywhere up to their maximums, but not raise their maximums. Limits
+/// apply to the whole process and are inherited by child processes, so a
+/// program can, for example, raise its `Resource::Nofile` limit to its
+/// maximum, or set its `Resource::Core` limit to zero to disable core dumps,
+/// early in `main`.
+///
+/// `Resource::Nproc` limits the number of processes and threads the user may
+/// have; when it's reached, `thread::create` fails with
+/// [`io::Errno::AGAIN`].
+#[doc(alias = "setrlimit")]
+#[inline]
+pub fn set_rlimit(resource: Resource, limit: Rlimit) -> io::Result<()> {
+    rustix::process::setrlimit(resource, limit)
+}
@@ -0,0 +1,173 @@
+//! Installing seccomp filters.
+
+use alloc::vec::Vec;
+use linux_raw_sys::general::*;
+use linux_raw_sys::ptrace::{
+    sock_fprog, BPF_ABS, BPF_JEQ, BPF_JMP, BPF_K, BPF_LD, BPF_RET, BPF_W, SECCOMP_RET_ALLOW,
+    SECCOMP_RET_KILL_PROCESS, SECCOMP_SET_MODE_FILTER,
+};
+use rustix::io;
+
+/// A classic BPF instruction, for use in a filter passed to
+/// [`install_seccomp_filter`].
+pub use linux_raw_sys::ptrace::sock_filter as SockFilter;
+
+/// The `AUDIT_ARCH_*` value for the current architecture, which the kernel
+/// passes to filters in `seccomp_data::arch`.
+#[cfg(target_arch = "x86_64")]
+const AUDIT_ARCH: u32 = linux_raw_sys::ptrace::AUDIT_ARCH_X86_64;
+#[cfg(target_arch = "x86")]
+const AUDIT_ARCH: u32 = linux_raw_sys::ptrace::AUDIT_ARCH_I386;
+#[cfg(target_arch = "aarch64")]
+const AUDIT_ARCH: u32 = linux_raw_sys::ptrace::AUDIT_ARCH_AARCH64;
+#[cfg(target_arch = "arm")]
+const AUDIT_ARCH: u32 = linux_raw_sys::ptrace::AUDIT_ARCH_ARM;
+#[cfg(target_arch = "riscv64")]
+const AUDIT_ARCH: u32 = linux_raw_sys::ptrace::AUDIT_ARCH_RISCV64;
+
+/// The offsets of the `nr` and `arch` fields in `struct seccomp_data`.
+const SECCOMP_DATA_NR: u32 = 0;
+const SECCOMP_DATA_ARCH: u32 = 4;
+
+/// The system calls that origin itself may make after `origin_main` is
+/// called, which [`seccomp_allow_origin_syscalls`] allows.
+///
+/// This covers exiting the program, creating, joining, and exiting threads,
+/// including VM workers, signal handling, including waiting for signals in a
+/// thread set up with `signal::dedicate_thread_to_signals`, writing messages
+/// to stderr, installing further filters, and the system calls made on
+/// demand by functions such as `thread::stack_committed_range`,
+/// `program::set_name`, and `thread::Builder::numa_node`, which reads the
+/// NUMA node's CPU list from sysfs. It doesn't include the system calls made
+/// at startup, since a filter can only be installed after that.
+pub const ORIGIN_SYSCALLS: &[u32] = &[
+    __NR_exit,
+    __NR_exit_group,
+    __NR_write,
+    __NR_read,
+    __NR_openat,
+    __NR_close,
+    #[cfg(not(any(target_arch = "x86", target_arch = "arm")))]
+    __NR_mmap,
+    #[cfg(any(target_arch = "x86", target_arch = "arm"))]
+    __NR_mmap2,
+    __NR_munmap,
+    __NR_mprotect,
+    __NR_madvise,
+    __NR_mlock,
+    __NR_msync,
+    __NR_mbind,
+    __NR_clone,
+    __NR_set_tid_address,
+    __NR_waitid,
+    __NR_futex,
+    __NR_futex_waitv,
+    #[cfg(any(target_arch = "x86", target_arch = "arm"))]
+    __NR_futex_time64,
+    __NR_rt_sigaction,
+    __NR_rt_sigprocmask,
+    __NR_rt_sigreturn,
+    __NR_rt_sigtimedwait,
+    #[cfg(any(target_arch = "x86", target_arch = "arm"))]
+    __NR_rt_sigtimedwait_time64,
+    __NR_getpid,
+    __NR_gettid,
+    __NR_tkill,
+    __NR_tgkill,
+    __NR_sched_yield,
+    __NR_sched_getaffinity,
+    __NR_sched_setaffinity,
+    __NR_prlimit64,
+    __NR_prctl,
+    __NR_seccomp,
+];
+
+/// Install a seccomp filter for the calling thread.
+///
+/// `prog` is a classic BPF program, which the kernel runs on every system
+/// call to decide whether to allow it. The filter applies to the calling
+/// thread and to threads and processes it creates afterward, and can't be
+/// removed, so to cover the whole process, call this before creating any
+/// threads. If more than one filter is installed, every filter is run, and
+/// the most restrictive result is used.
+///
+/// Unless the process has `CAP_SYS_ADMIN`, [`set_no_new_privs`] must be
+/// called first, or this fails with [`io::Errno::ACCESS`].
+///
+/// [`set_no_new_privs`]: super::set_no_new_privs
+#[cfg_attr(docsrs, doc(cfg(all(feature = "take-charge", feature = "seccomp"))))]
+#[doc(alias = "seccomp")]
+#[doc(alias = "SECCOMP_SET_MODE_FILTER")]
+pub fn install_seccomp_filter(prog: &[SockFilter]) -> io::Result<()> {
+    let mut fprog = sock_fprog {
+        len: prog.len().try_into().map_err(|_| io::Errno::INVAL)?,
+        filter: prog.as_ptr().cast_mut(),
+    };
+
+    // SAFETY: `fprog` points to `prog`, which the kernel only reads.
+    let r0 = unsafe {
+        crate::arch::syscall3(
+            __NR_seccomp,
+            SECCOMP_SET_MODE_FILTER as usize,
+            0,
+            (&mut fprog as *mut sock_fprog) as usize,
+        )
+    };
+    if r0 < 0 {
+        Err(io::Errno::from_raw_os_error(-r0 as i32))
+    } else {
+        Ok(())
+    }
+}
+
+/// Install a seccomp filter which allows only the system calls in
+/// [`ORIGIN_SYSCALLS`] and `extra`, and kills the process if it makes any
+/// other system call.
+///
+/// This is a starting point for sandboxing a program; `extra` should list
+/// the system calls, by number, that the rest of the program needs, such as
+/// `__NR_read` for reading from its input. Like with
+/// [`install_seccomp_filter`], [`set_no_new_privs`] must be called first
+/// unless the process has `CAP_SYS_ADMIN`.
+///
+/// [`set_no_new_privs`]: super::set_no_new_privs
+#[cfg_attr(docsrs, doc(cfg(all(feature = "take-charge", feature = "seccomp"))))]
+pub fn seccomp_allow_origin_syscalls(extra: &[u32]) -> io::Result<()> {
+    let stmt = |code: u32, k: u32| SockFilter {
+        code: code as u16,
+        jt: 0,
+        jf: 0,
+        k,
+    };
+
+    let mut prog = Vec::with_capacity(5 + 2 * (ORIGIN_SYSCALLS.len() + extra.len()));
+
+    // Kill the process if it makes system calls for a different
+    // architecture, such as 32-bit system calls on x86-64, since their
+    // numbers mean different things.
+    prog.push(stmt(BPF_LD | BPF_W | BPF_ABS, SECCOMP_DATA_ARCH));
+    prog.push(SockFilter {
+        code: (BPF_JMP | BPF_JEQ | BPF_K) as u16,
+        jt: 1,
+        jf: 0,
+        k: AUDIT_ARCH,
+    });
+    prog.push(stmt(BPF_RET | BPF_K, SECCOMP_RET_KILL_PROCESS));
+
+    // Allow each of the listed system calls.
+    prog.push(stmt(BPF_LD | BPF_W | BPF_ABS, SECCOMP_DATA_NR));
+    for nr in ORIGIN_SYSCALLS.iter().chain(extra) {
+        prog.push(SockFilter {
+            code: (BPF_JMP | BPF_JEQ | BPF_K) as u16,
+            jt: 0,
+            jf: 1,
+            k: *nr,
+        });
+        prog.push(stmt(BPF_RET | BPF_K, SECCOMP_RET_ALLOW));
+    }
+
+    // Kill the process for anything else.
+    prog.push(stmt(BPF_RET | BPF_K, SECCOMP_RET_KILL_PROCESS));
+
+    install_seccomp_filter(&prog)
+}
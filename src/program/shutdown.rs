@@ -0,0 +1,121 @@
+//! Coordinating a graceful shutdown on `SIGINT`, `SIGTERM`, and `SIGHUP`.
+
+use crate::signal::{set_handler, sig_ign, sigaction, Handler, Sighandler, Signal};
+use core::ffi::c_int;
+use core::sync::atomic::{AtomicI32, Ordering};
+
+/// The signals which request a shutdown.
+const SIGNALS: [Signal; 3] = [Signal::Int, Signal::Term, Signal::Hup];
+
+/// The first shutdown signal received, or 0 if none has been received yet.
+static RECEIVED: AtomicI32 = AtomicI32::new(0);
+
+/// Install handlers for `SIGINT`, `SIGTERM`, and `SIGHUP` which record that
+/// a shutdown has been requested, and return a [`ShutdownToken`] for
+/// checking whether it has.
+///
+/// This is for programs, such as daemons, which handle these signals by
+/// finishing their current work and exiting, rather than being killed
+/// immediately. The handlers only store to an atomic variable, so they're
+/// async-signal-safe; the program's main loop polls the token to notice the
+/// request and shuts down at its own pace.
+///
+/// Signals which are ignored when this is called are left ignored, so that
+/// programs started with `nohup`, or in the background by a shell which
+/// ignores `SIGINT` for background jobs, keep behaving as their parent
+/// intended. Handlers for the other signals replace any actions previously
+/// installed for them.
+///
+/// The handlers are installed without `SA_RESTART`, so that blocking system
+/// calls interrupted by a shutdown signal fail with [`io::Errno::INTR`],
+/// giving a main loop blocked in `read` or `accept` a chance to check the
+/// token. Calling this more than once is harmless; all the tokens observe
+/// the same process-wide state.
+///
+/// [`io::Errno::INTR`]: rustix::io::Errno::INTR
+#[cfg_attr(docsrs, doc(cfg(feature = "signal")))]
+pub fn install_shutdown_handler() -> ShutdownToken {
+    for sig in SIGNALS {
+        // SAFETY: Querying the current action doesn't change anything, and
+        // `handle` only stores to an atomic variable, which is
+        // async-signal-safe.
+        unsafe {
+            // `sigaction` only fails for invalid signals, and these are
+            // valid.
+            let old = sigaction(sig, None).unwrap();
+            if handler_addr(action_handler(&old)) == handler_addr(sig_ign()) {
+                continue;
+            }
+            set_handler(sig, Handler::new(handle_handler()).no_restart()).unwrap();
+        }
+    }
+
+    ShutdownToken { _private: () }
+}
+
+/// A token for checking whether a shutdown has been requested, returned by
+/// [`install_shutdown_handler`].
+#[cfg_attr(docsrs, doc(cfg(feature = "signal")))]
+#[derive(Debug, Copy, Clone)]
+pub struct ShutdownToken {
+    _private: (),
+}
+
+impl ShutdownToken {
+    /// Return whether a shutdown signal has been received.
+    #[inline]
+    #[must_use]
+    pub fn is_shutting_down(&self) -> bool {
+        RECEIVED.load(Ordering::Acquire) != 0
+    }
+
+    /// Return the first shutdown signal received, if any.
+    ///
+    /// Later signals don't replace it, so a program which shuts down
+    /// differently depending on the signal, for example by re-raising it
+    /// with `terminate_by_signal` once it's done, sees the signal which
+    /// started the shutdown.
+    #[inline]
+    #[must_use]
+    pub fn which_signal(&self) -> Option<Signal> {
+        Signal::from_raw(RECEIVED.load(Ordering::Acquire))
+    }
+}
+
+/// The handler for the shutdown signals.
+extern "C" fn handle(sig: c_int) {
+    let _ = RECEIVED.compare_exchange(0, sig, Ordering::AcqRel, Ordering::Relaxed);
+}
+
+// The take-charge backend represents handlers as `Option`s of function
+// pointers, and libc represents them as addresses.
+
+#[cfg(feature = "take-charge")]
+fn handle_handler() -> Sighandler {
+    Some(handle)
+}
+
+#[cfg(not(feature = "take-charge"))]
+fn handle_handler() -> Sighandler {
+    handle as extern "C" fn(c_int) as Sighandler
+}
+
+#[cfg(feature = "take-charge")]
+fn handler_addr(handler: Sighandler) -> usize {
+    handler.map_or(0, |handler| handler as usize)
+}
+
+#[cfg(not(feature = "take-charge"))]
+fn handler_addr(handler: Sighandler) -> usize {
+    handler
+}
+
+#[cfg(feature = "take-charge")]
+fn action_handler(action: &crate::signal::Sigaction) -> Sighandler {
+    action.sa_handler_kernel
+}
+
+#[cfg(not(feature = "take-charge"))]
+fn action_handler(action: &crate::signal::Sigaction) -> Sighandler {
+    action.sa_sigaction
+}
@@ -0,0 +1,315 @@
+//! [`Stdout`] and [`Stderr`], for formatted output without allocation.
+
+use core::fmt;
+#[cfg(all(feature = "program-at-exit", not(feature = "take-charge")))]
+use core::sync::atomic::{AtomicBool, Ordering};
+use rustix::fd::BorrowedFd;
+use rustix::io;
+#[cfg(any(feature = "thread", not(feature = "origin-start")))]
+use rustix_futex_sync::{Mutex, MutexGuard};
+#[cfg(not(any(feature = "thread", not(feature = "origin-start"))))]
+use unsync::{Mutex, MutexGuard};
+
+/// A [`fmt::Write`] implementation which writes to the standard output
+/// stream.
+///
+/// This writes directly to file descriptor 1, with no buffering, locking, or
+/// allocation, so it can be used with `write!` and `writeln!` in programs
+/// without an allocator:
+///
+/// ```no_run
+/// use core::fmt::Write;
+/// use origin::program::Stdout;
+///
+/// writeln!(Stdout, "Hello, {}!", "world").unwrap();
+/// ```
+///
+/// Since there's no locking, output from multiple threads may be interleaved.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Stdout;
+
+/// A [`fmt::Write`] implementation which writes to the standard error
+/// stream.
+///
+/// This is like [`Stdout`], but writes to file descriptor 2. To keep output
+/// from multiple threads from being interleaved, use [`stderr_lock`].
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Stderr;
+
+impl fmt::Write for Stdout {
+    #[inline]
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        // SAFETY: We assume the program hasn't closed file descriptor 1, or
+        // has opened something else in its place.
+        write_all(unsafe { BorrowedFd::borrow_raw(1) }, s.as_bytes())
+    }
+}
+
+impl fmt::Write for Stderr {
+    #[inline]
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        // SAFETY: We assume the program hasn't closed file descriptor 2, or
+        // has opened something else in its place.
+        write_all(unsafe { BorrowedFd::borrow_raw(2) }, s.as_bytes())
+    }
+}
+
+/// Lock the standard error stream, for writing to it without output from
+/// other threads being interleaved.
+///
+/// This blocks until no other thread holds the lock, and returns a guard
+/// which writes to file descriptor 2 like [`Stderr`] and releases the lock
+/// when it's dropped. Everything written through one guard, such as all the
+/// pieces of a `writeln!`, or several `writeln!`s in a row, appears together,
+/// and a thread which acquires the lock after another has released it writes
+/// after all of that thread's output:
+///
+/// ```no_run
+/// use core::fmt::Write;
+/// use origin::program::stderr_lock;
+///
+/// let mut stderr = stderr_lock();
+/// writeln!(stderr, "Hello, {}!", "world").unwrap();
+/// writeln!(stderr, "Goodbye!").unwrap();
+/// ```
+///
+/// This only orders output written through `stderr_lock`; writes with
+/// [`Stderr`], including origin's own messages when it aborts the process,
+/// don't take the lock. The lock isn't reentrant, so a thread which calls
+/// this while holding the lock deadlocks, and signal handlers must not call
+/// it, since the thread they interrupt may hold the lock.
+pub fn stderr_lock() -> StderrLock {
+    StderrLock {
+        _guard: STDERR_LOCK.lock(),
+    }
+}
+
+/// A guard returned by [`stderr_lock`], which holds the lock on the standard
+/// error stream until it's dropped.
+pub struct StderrLock {
+    _guard: MutexGuard<'static, ()>,
+}
+
+impl fmt::Debug for StderrLock {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("StderrLock").finish_non_exhaustive()
+    }
+}
+
+impl fmt::Write for StderrLock {
+    #[inline]
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        Stderr.write_str(s)
+    }
+}
+
+/// The lock held by a [`StderrLock`].
+static STDERR_LOCK: Mutex<()> = Mutex::new(());
+
+/// A [`fmt::Write`] implementation which writes to the standard output
+/// stream through a buffer.
+///
+/// [`Stdout`] makes a system call for every piece of a `write!`, which is
+/// slow for many small writes. `BufferedStdout` accumulates them in a
+/// process-wide buffer instead, and writes the buffer to file descriptor 1
+/// when it fills up, when [`BufferedStdout::flush`] is called, and when the
+/// program exits with [`exit`], after the functions registered with
+/// [`at_exit`] have been called. Exiting with [`exit_immediately`], or being
+/// terminated by a signal, discards any output still in the buffer.
+///
+/// Writes from multiple threads are serialized with a lock, but as with
+/// `Stdout`, the pieces of `write!`s from different threads may be
+/// interleaved. Signal handlers must not use `BufferedStdout`, since the
+/// thread they interrupt may hold the lock; they can write with `Stdout`
+/// directly, bypassing the buffer.
+///
+/// [`exit`]: super::exit
+/// [`at_exit`]: super::at_exit
+/// [`exit_immediately`]: super::exit_immediately
+#[cfg(feature = "program-at-exit")]
+#[cfg_attr(docsrs, doc(cfg(feature = "program-at-exit")))]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct BufferedStdout;
+
+#[cfg(feature = "program-at-exit")]
+impl BufferedStdout {
+    /// Write any buffered output to the standard output stream.
+    ///
+    /// If this fails, the buffered output is discarded.
+    pub fn flush(&mut self) -> fmt::Result {
+        BUFFER.lock().flush()
+    }
+}
+
+#[cfg(feature = "program-at-exit")]
+impl fmt::Write for BufferedStdout {
+    #[inline]
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        // With the take-charge backend, `exit` flushes the buffer itself.
+        // With libc, have libc's `exit` call us. Register before taking the
+        // lock, since `at_exit` takes libc's own lock. If more output is
+        // written after the flush, this registers another function to flush
+        // it.
+        #[cfg(not(feature = "take-charge"))]
+        if !FLUSH_REGISTERED.swap(true, Ordering::AcqRel) {
+            super::at_exit(alloc::boxed::Box::new(|| {
+                FLUSH_REGISTERED.store(false, Ordering::Release);
+                if let Some(mut buffer) = BUFFER.try_lock() {
+                    let _ = buffer.flush();
+                }
+            }));
+        }
+
+        BUFFER.lock().write(s.as_bytes())
+    }
+}
+
+/// With the libc backend, whether we've registered a function with `at_exit`
+/// to flush the buffer.
+#[cfg(all(feature = "program-at-exit", not(feature = "take-charge")))]
+static FLUSH_REGISTERED: AtomicBool = AtomicBool::new(false);
+
+/// The size of the buffer used by [`BufferedStdout`].
+#[cfg(feature = "program-at-exit")]
+const BUFFER_SIZE: usize = 4096;
+
+/// The buffer used by [`BufferedStdout`].
+#[cfg(feature = "program-at-exit")]
+struct Buffer {
+    bytes: [u8; BUFFER_SIZE],
+    len: usize,
+}
+
+#[cfg(feature = "program-at-exit")]
+impl Buffer {
+    fn write(&mut self, bytes: &[u8]) -> fmt::Result {
+        if self.len + bytes.len() > BUFFER_SIZE {
+            self.flush()?;
+        }
+        if bytes.len() >= BUFFER_SIZE {
+            return write_all(stdout(), bytes);
+        }
+
+        self.bytes[self.len..][..bytes.len()].copy_from_slice(bytes);
+        self.len += bytes.len();
+        Ok(())
+    }
+
+    fn flush(&mut self) -> fmt::Result {
+        let len = core::mem::take(&mut self.len);
+        write_all(stdout(), &self.bytes[..len])
+    }
+}
+
+/// The buffer used by [`BufferedStdout`].
+#[cfg(feature = "program-at-exit")]
+static BUFFER: Mutex<Buffer> = Mutex::new(Buffer {
+    bytes: [0; BUFFER_SIZE],
+    len: 0,
+});
+
+/// Write any output buffered by [`BufferedStdout`], for [`exit`] to call
+/// once it's called all the other functions it calls.
+///
+/// If another thread holds the buffer's lock, don't wait for it, and discard
+/// the output.
+///
+/// [`exit`]: super::exit
+#[cfg(all(feature = "take-charge", feature = "program-at-exit"))]
+pub(super) fn flush_at_exit() {
+    if let Some(mut buffer) = BUFFER.try_lock() {
+        let _ = buffer.flush();
+    }
+}
+
+/// Return file descriptor 1.
+#[cfg(feature = "program-at-exit")]
+fn stdout() -> BorrowedFd<'static> {
+    // SAFETY: We assume the program hasn't closed file descriptor 1, or has
+    // opened something else in its place.
+    unsafe { BorrowedFd::borrow_raw(1) }
+}
+
+/// Write all of `buf` to `fd`, retrying on partial writes and `EINTR`.
+fn write_all(fd: BorrowedFd<'_>, mut buf: &[u8]) -> fmt::Result {
+    while !buf.is_empty() {
+        match io::write(fd, buf) {
+            // A zero-length write means we can't make progress.
+            Ok(0) => return Err(fmt::Error),
+            Ok(n) => buf = &buf[n..],
+            Err(io::Errno::INTR) => {}
+            Err(_) => return Err(fmt::Error),
+        }
+    }
+    Ok(())
+}
+
+/// A lock with the same API as `rustix_futex_sync::Mutex`, for programs using
+/// "origin-start" without "thread", which only ever have one thread.
+#[cfg(not(any(feature = "thread", not(feature = "origin-start"))))]
+mod unsync {
+    use core::cell::{Cell, UnsafeCell};
+    use core::ops::{Deref, DerefMut};
+
+    pub(super) struct Mutex<T> {
+        locked: Cell<bool>,
+        value: UnsafeCell<T>,
+    }
+
+    /// SAFETY: With "origin-start" and without "thread", origin is
+    /// responsible for creating all threads in the program, and can't create
+    /// any new ones, so there's only ever one thread.
+    unsafe impl<T> Sync for Mutex<T> {}
+
+    impl<T> Mutex<T> {
+        pub(super) const fn new(value: T) -> Self {
+            Self {
+                locked: Cell::new(false),
+                value: UnsafeCell::new(value),
+            }
+        }
+
+        /// Lock the lock. Since there's only one thread, the lock can only
+        /// be held if we're reentering it, which would deadlock with a real
+        /// lock, so trap.
+        pub(super) fn lock(&self) -> MutexGuard<'_, T> {
+            match self.try_lock() {
+                Some(guard) => guard,
+                None => crate::arch::trap(),
+            }
+        }
+
+        pub(super) fn try_lock(&self) -> Option<MutexGuard<'_, T>> {
+            if self.locked.replace(true) {
+                return None;
+            }
+            Some(MutexGuard { mutex: self })
+        }
+    }
+
+    pub(super) struct MutexGuard<'a, T> {
+        mutex: &'a Mutex<T>,
+    }
+
+    impl<T> Deref for MutexGuard<'_, T> {
+        type Target = T;
+
+        fn deref(&self) -> &T {
+            // SAFETY: We hold the lock.
+            unsafe { &*self.mutex.value.get() }
+        }
+    }
+
+    impl<T> DerefMut for MutexGuard<'_, T> {
+        fn deref_mut(&mut self) -> &mut T {
+            // SAFETY: We hold the lock.
+            unsafe { &mut *self.mutex.value.get() }
+        }
+    }
+
+    impl<T> Drop for MutexGuard<'_, T> {
+        fn drop(&mut self) {
+            self.mutex.locked.set(false);
+        }
+    }
+}
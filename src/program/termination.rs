@@ -0,0 +1,105 @@
+//! The [`Termination`] trait and [`ExitCode`].
+
+use core::fmt::{Debug, Write as _};
+use linux_raw_sys::ctypes::c_int;
+
+/// A trait for converting the result of a program's main function into a
+/// program exit status.
+///
+/// This is similar to `std::process::Termination`, but doesn't depend on
+/// `std`. `origin_main` still returns a plain `i32`, so this is meant to be
+/// used to convert a richer return value into that `i32`, like this:
+///
+/// ```no_run
+/// use origin::program::Termination;
+///
+/// #[no_mangle]
+/// unsafe fn origin_main(_argc: usize, _argv: *mut *mut u8, _envp: *mut *mut u8) -> i32 {
+///     run().report()
+/// }
+///
+/// fn run() -> Result<(), &'static str> {
+///     todo!("Run the program.")
+/// }
+/// ```
+pub trait Termination {
+    /// Convert `self` into a program exit status.
+    fn report(self) -> c_int;
+}
+
+impl Termination for () {
+    #[inline]
+    fn report(self) -> c_int {
+        0
+    }
+}
+
+/// `c_int` is `i32` on all platforms origin supports, so this covers `c_int`
+/// too.
+impl Termination for i32 {
+    #[inline]
+    fn report(self) -> c_int {
+        self
+    }
+}
+
+impl Termination for ExitCode {
+    #[inline]
+    fn report(self) -> c_int {
+        self.0.into()
+    }
+}
+
+/// `Ok` values are reported using their own `Termination` implementation.
+/// `Err` values are printed to stderr with their `Debug` implementation, and
+/// reported as [`ExitCode::FAILURE`], as `std` does.
+impl<T: Termination, E: Debug> Termination for Result<T, E> {
+    fn report(self) -> c_int {
+        match self {
+            Ok(value) => value.report(),
+            Err(err) => {
+                let _ = writeln!(super::Stderr, "Error: {:?}", err);
+                ExitCode::FAILURE.report()
+            }
+        }
+    }
+}
+
+/// A program exit status, for returning from a program's main function with
+/// [`Termination`].
+///
+/// This is similar to `std::process::ExitCode`, but doesn't depend on `std`.
+/// Exit statuses are reported to the parent process modulo 256, so this only
+/// holds values that are reported as-is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ExitCode(u8);
+
+impl ExitCode {
+    /// The exit status for successful termination, `0`.
+    pub const SUCCESS: Self = Self(0);
+
+    /// The exit status for unsuccessful termination, `1`.
+    pub const FAILURE: Self = Self(1);
+
+    /// Create an `ExitCode` with the exit status `code`.
+    #[inline]
+    #[must_use]
+    pub const fn from_raw(code: u8) -> Self {
+        Self(code)
+    }
+
+    /// Return the exit status, as a value that can be returned from
+    /// `origin_main`.
+    #[inline]
+    #[must_use]
+    pub const fn to_i32(self) -> i32 {
+        self.0 as i32
+    }
+}
+
+impl From<u8> for ExitCode {
+    #[inline]
+    fn from(code: u8) -> Self {
+        Self::from_raw(code)
+    }
+}
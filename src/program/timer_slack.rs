@@ -0,0 +1,29 @@
+//! Getting and setting the timer slack.
+
+use rustix::io;
+
+/// Set the timer slack, in nanoseconds.
+///
+/// Linux may delay the expiration of timers, including `nanosleep` and
+/// timeouts for `futex` and `poll`, by up to this much, so that it can
+/// batch wakeups together. The default is 50 microseconds, which is
+/// noticeable for latency-sensitive programs; a smaller value makes
+/// timeouts more precise, at some cost in power. A value of 0 restores the
+/// value the program started with. Threads with a real-time scheduling
+/// policy don't use timer slack.
+///
+/// This is a per-thread attribute, which is inherited by threads created
+/// afterward and by child processes, so to cover the whole process, call
+/// this before creating any threads.
+#[doc(alias = "PR_SET_TIMERSLACK")]
+pub fn set_timer_slack(ns: u64) -> io::Result<()> {
+    rustix::thread::set_current_timer_slack(core::num::NonZeroU64::new(ns))
+}
+
+/// Return the timer slack, in nanoseconds, as set by [`set_timer_slack`].
+///
+/// This is the value for the current thread.
+#[doc(alias = "PR_GET_TIMERSLACK")]
+pub fn timer_slack() -> io::Result<u64> {
+    rustix::thread::current_timer_slack()
+}
@@ -0,0 +1,25 @@
+//! Reading the CPU's timestamp counter.
+
+/// Return the current value of the CPU's timestamp counter.
+///
+/// This is a raw, cheap-to-read counter for fine-grained timing, such as
+/// measuring how long a short piece of code takes. It's not wall-clock time:
+/// its frequency depends on the hardware, it starts at an arbitrary value,
+/// and it may not be synchronized between CPU cores, so differences between
+/// readings taken on different cores, or across a migration between cores,
+/// may not be meaningful. Use `rustix::time::clock_gettime` for time that
+/// means something outside the program.
+///
+/// This reads `rdtsc` on x86-64 and x86, `cntvct_el0` on aarch64, `CNTVCT`
+/// on arm, and the `time` CSR on riscv64. The `cycle` CSR isn't used on
+/// riscv64, since recent Linux versions don't allow reading it from
+/// userspace by default. Some systems disable userspace access to these
+/// counters altogether, in which case this traps.
+#[cfg_attr(docsrs, doc(cfg(feature = "timestamp-counter")))]
+#[doc(alias = "rdtsc")]
+#[doc(alias = "cntvct")]
+#[inline]
+#[must_use]
+pub fn timestamp_counter() -> u64 {
+    crate::arch::timestamp_counter()
+}
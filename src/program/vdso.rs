@@ -0,0 +1,341 @@
+//! Locating functions in the vDSO.
+//!
+//! Linux maps a small shared library, the vDSO, into every process, with
+//! implementations of time functions that read the time from memory shared
+//! with the kernel instead of making a system call. Its address is passed in
+//! the aux vector as `AT_SYSINFO_EHDR`. This parses its ELF headers and
+//! symbol table at startup, the same way a dynamic linker would, and records
+//! the addresses of the functions we know about.
+
+use core::ffi::{c_int, c_void, CStr};
+use core::mem::size_of;
+use core::ptr::null_mut;
+use core::sync::atomic::AtomicPtr;
+use core::sync::atomic::Ordering::Relaxed;
+use linux_raw_sys::elf::*;
+use linux_raw_sys::general::{
+    __kernel_old_timeval, __kernel_timespec, timezone, AT_NULL, AT_SYSINFO_EHDR,
+};
+
+/// The type of the vDSO's `clock_gettime` function.
+///
+/// It takes a clock id such as `CLOCK_MONOTONIC` and a pointer to a
+/// timespec to fill in, and returns 0 on success, or a negated errno value
+/// on failure. On 32-bit platforms, this is the 64-bit-time variant,
+/// `clock_gettime64`.
+pub type ClockGettime = unsafe extern "C" fn(c_int, *mut __kernel_timespec) -> c_int;
+
+/// The type of the vDSO's `gettimeofday` function.
+///
+/// It takes a pointer to a timeval to fill in and a pointer to a timezone to
+/// fill in, either of which may be null, and returns 0 on success, or a
+/// negated errno value on failure.
+pub type Gettimeofday = unsafe extern "C" fn(*mut __kernel_old_timeval, *mut timezone) -> c_int;
+
+/// The functions found in the vDSO, as returned by [`vdso`].
+#[derive(Debug, Copy, Clone)]
+#[non_exhaustive]
+pub struct Vdso {
+    /// The vDSO's `clock_gettime`, if it has one.
+    pub clock_gettime: Option<ClockGettime>,
+
+    /// The vDSO's `gettimeofday`, if it has one.
+    pub gettimeofday: Option<Gettimeofday>,
+}
+
+/// The address of the vDSO's ELF header, or null if the kernel didn't
+/// provide one or it couldn't be parsed.
+static BASE: AtomicPtr<c_void> = AtomicPtr::new(null_mut());
+
+/// The addresses of the functions found by `init`, or null.
+static CLOCK_GETTIME: AtomicPtr<c_void> = AtomicPtr::new(null_mut());
+static GETTIMEOFDAY: AtomicPtr<c_void> = AtomicPtr::new(null_mut());
+
+/// The names of the functions, and the symbol version they're defined with,
+/// which differ between architectures.
+#[cfg(target_arch = "x86_64")]
+const NAMES: (&CStr, &CStr, &CStr) = (
+    c"__vdso_clock_gettime",
+    c"__vdso_gettimeofday",
+    c"LINUX_2.6",
+);
+#[cfg(any(target_arch = "x86", target_arch = "arm"))]
+const NAMES: (&CStr, &CStr, &CStr) = (
+    c"__vdso_clock_gettime64",
+    c"__vdso_gettimeofday",
+    c"LINUX_2.6",
+);
+#[cfg(target_arch = "aarch64")]
+const NAMES: (&CStr, &CStr, &CStr) = (
+    c"__kernel_clock_gettime",
+    c"__kernel_gettimeofday",
+    c"LINUX_2.6.39",
+);
+#[cfg(target_arch = "riscv64")]
+const NAMES: (&CStr, &CStr, &CStr) = (
+    c"__vdso_clock_gettime",
+    c"__vdso_gettimeofday",
+    c"LINUX_4.15",
+);
+
+/// Return the functions found in the vDSO, or `None` if the kernel didn't
+/// provide a vDSO.
+///
+/// These are the same functions libc uses to implement `clock_gettime` and
+/// `gettimeofday` without making a system call for clocks the vDSO supports.
+/// For other clocks, and when the kernel's clock source can't be read from
+/// userspace, they fall back to making the system call themselves.
+///
+/// The vDSO is located and parsed once, at startup.
+#[cfg_attr(docsrs, doc(cfg(all(feature = "take-charge", feature = "vdso"))))]
+#[must_use]
+pub fn vdso() -> Option<Vdso> {
+    if BASE.load(Relaxed).is_null() {
+        return None;
+    }
+
+    // SAFETY: `init` only stores addresses of functions of these types.
+    unsafe {
+        Some(Vdso {
+            clock_gettime: as_fn(CLOCK_GETTIME.load(Relaxed)),
+            gettimeofday: as_fn(GETTIMEOFDAY.load(Relaxed)),
+        })
+    }
+}
+
+/// Convert a possibly-null function address into an `Option` of a function
+/// pointer.
+///
+/// # Safety
+///
+/// `ptr` must be null or the address of a function of type `F`.
+unsafe fn as_fn<F: Copy>(ptr: *mut c_void) -> Option<F> {
+    debug_assert_eq!(size_of::<F>(), size_of::<*mut c_void>());
+    if ptr.is_null() {
+        None
+    } else {
+        Some(core::mem::transmute_copy::<*mut c_void, F>(&ptr))
+    }
+}
+
+/// Locate the vDSO and the functions in it.
+///
+/// # Safety
+///
+/// `envp` must point to the NULL-terminated array of environment variables
+/// as provided by the operating system, which is followed by the aux vector.
+pub(super) unsafe fn init(envp: *mut *mut u8) {
+    let mut auxp = envp;
+    while !(*auxp).is_null() {
+        auxp = auxp.add(1);
+    }
+    let mut auxp = auxp.add(1).cast::<Elf_auxv_t>();
+
+    let mut base: *const c_void = null_mut();
+    loop {
+        let Elf_auxv_t { a_type, a_val } = *auxp;
+        match a_type as u32 {
+            AT_SYSINFO_EHDR => base = a_val,
+            AT_NULL => break,
+            _ => {}
+        }
+        auxp = auxp.add(1);
+    }
+    if base.is_null() {
+        return;
+    }
+
+    let Some(image) = Image::parse(base) else {
+        #[cfg(feature = "log")]
+        log::warn!("Unable to parse the vDSO");
+        return;
+    };
+
+    let (clock_gettime, gettimeofday, version) = NAMES;
+    CLOCK_GETTIME.store(image.lookup(clock_gettime, version), Relaxed);
+    GETTIMEOFDAY.store(image.lookup(gettimeofday, version), Relaxed);
+    BASE.store(base.cast_mut(), Relaxed);
+}
+
+/// The parts of a vDSO image needed to look up symbols.
+struct Image {
+    /// The address corresponding to static address 0 in the image, which
+    /// all other addresses are derived from.
+    load_base: *const u8,
+    strtab: *const u8,
+    symtab: *const Elf_Sym,
+    nsyms: usize,
+    /// The symbol version table and version definitions, which may be null.
+    versym: *const u16,
+    verdef: *const Elf_Verdef,
+}
+
+impl Image {
+    /// Parse the ELF headers and dynamic section of the vDSO at `base`.
+    unsafe fn parse(base: *const c_void) -> Option<Self> {
+        let ehdr = &*base.cast::<Elf_Ehdr>();
+        if ehdr.e_ident[..SELFMAG] != ELFMAG
+            || ehdr.e_ident[EI_CLASS] != ELFCLASS
+            || ehdr.e_ident[EI_DATA] != ELFDATA
+            || ehdr.e_ident[EI_VERSION] != EV_CURRENT
+            || ehdr.e_type != ET_DYN
+            || ehdr.e_machine != EM_CURRENT
+            || usize::from(ehdr.e_phentsize) != size_of::<Elf_Phdr>()
+        {
+            return None;
+        }
+
+        // Find the load address from the first `PT_LOAD` segment, and the
+        // dynamic section.
+        let mut load_base = None;
+        let mut dynamic = None;
+        let phdrs = base.byte_add(ehdr.e_phoff).cast::<Elf_Phdr>();
+        for i in 0..usize::from(ehdr.e_phnum) {
+            let phdr = &*phdrs.add(i);
+            match phdr.p_type {
+                PT_LOAD if load_base.is_none() => {
+                    load_base = Some(
+                        base.cast::<u8>()
+                            .wrapping_add(phdr.p_offset)
+                            .wrapping_sub(phdr.p_vaddr),
+                    );
+                }
+                PT_DYNAMIC => dynamic = Some(base.byte_add(phdr.p_offset).cast::<Elf_Dyn>()),
+                _ => {}
+            }
+        }
+        let (load_base, mut dyn_) = (load_base?, dynamic?);
+
+        let mut image = Self {
+            load_base,
+            strtab: null_mut(),
+            symtab: null_mut(),
+            nsyms: 0,
+            versym: null_mut(),
+            verdef: null_mut(),
+        };
+        let mut hash: *const u32 = null_mut();
+        let mut gnu_hash: *const u32 = null_mut();
+        loop {
+            let Elf_Dyn { d_tag, d_un } = *dyn_;
+            dyn_ = dyn_.add(1);
+
+            let ptr = d_un.d_ptr;
+            match d_tag {
+                DT_NULL => break,
+                DT_STRTAB => image.strtab = image.addr(ptr),
+                DT_SYMTAB => image.symtab = image.addr(ptr),
+                DT_SYMENT if ptr != size_of::<Elf_Sym>() => return None,
+                DT_HASH => hash = image.addr(ptr),
+                DT_GNU_HASH => gnu_hash = image.addr(ptr),
+                DT_VERSYM => image.versym = image.addr(ptr),
+                DT_VERDEF => image.verdef = image.addr(ptr),
+                _ => {}
+            }
+        }
+        if image.strtab.is_null() || image.symtab.is_null() {
+            return None;
+        }
+
+        // The hash tables are the only way to find how many symbols there
+        // are. The vDSO only has a handful, so we scan them all rather than
+        // hashing the names we're looking for.
+        image.nsyms = if !hash.is_null() {
+            // `DT_HASH` tables start with the bucket and chain counts, and
+            // there's one chain entry per symbol.
+            *hash.add(1) as usize
+        } else if !gnu_hash.is_null() {
+            gnu_hash_nsyms(gnu_hash)
+        } else {
+            return None;
+        };
+
+        Some(image)
+    }
+
+    /// Compute the address of the static address `vaddr` in the image.
+    fn addr<T>(&self, vaddr: usize) -> *const T {
+        self.load_base.wrapping_add(vaddr).cast::<T>()
+    }
+
+    /// Find the address of the function `name` with version `version`, or
+    /// null if there isn't one.
+    unsafe fn lookup(&self, name: &CStr, version: &CStr) -> *mut c_void {
+        for i in 0..self.nsyms {
+            let sym = &*self.symtab.add(i);
+            let type_ = ELF_ST_TYPE(sym.st_info);
+            let bind = ELF_ST_BIND(sym.st_info);
+            if (type_ != STT_FUNC && type_ != STT_NOTYPE)
+                || (bind != STB_GLOBAL && bind != STB_WEAK)
+                || sym.st_shndx == SHN_UNDEF
+                || ELF_ST_VISIBILITY(sym.st_other) != STV_DEFAULT
+            {
+                continue;
+            }
+            if self.str(sym.st_name) != name {
+                continue;
+            }
+            if !self.versym.is_null() && !self.version_matches(*self.versym.add(i), version) {
+                continue;
+            }
+            return self.addr::<c_void>(sym.st_value).cast_mut();
+        }
+        null_mut()
+    }
+
+    /// Test whether the version with index `ver` in the version definitions
+    /// is named `version`.
+    unsafe fn version_matches(&self, ver: u16, version: &CStr) -> bool {
+        // The high bit marks hidden symbols, which are still usable here.
+        let ver = ver & 0x7fff;
+        if self.verdef.is_null() {
+            return true;
+        }
+
+        let mut def = self.verdef;
+        loop {
+            let d = &*def;
+            if d.vd_version != VER_DEF_CURRENT {
+                return false;
+            }
+            if d.vd_flags & VER_FLG_BASE == 0 && d.vd_ndx & 0x7fff == ver {
+                let aux = &*def.byte_add(d.vd_aux as usize).cast::<Elf_Verdaux>();
+                return self.str(aux.vda_name) == version;
+            }
+            if d.vd_next == 0 {
+                return false;
+            }
+            def = def.byte_add(d.vd_next as usize);
+        }
+    }
+
+    /// Return the string at offset `offset` in the string table.
+    unsafe fn str(&self, offset: u32) -> &CStr {
+        CStr::from_ptr(self.strtab.add(offset as usize).cast())
+    }
+}
+
+/// Compute the number of symbols covered by a `DT_GNU_HASH` table.
+///
+/// The table doesn't record this directly; the last symbol is the end of the
+/// chain which starts at the highest bucket.
+unsafe fn gnu_hash_nsyms(table: *const u32) -> usize {
+    let nbuckets = *table as usize;
+    let symoffset = *table.add(1) as usize;
+    let bloom_size = *table.add(2) as usize;
+    let buckets = table.add(4).cast::<usize>().add(bloom_size).cast::<u32>();
+    let chains = buckets.add(nbuckets);
+
+    let mut last = 0;
+    for i in 0..nbuckets {
+        last = last.max(*buckets.add(i) as usize);
+    }
+    if last < symoffset {
+        return symoffset;
+    }
+    // The low bit of a chain entry marks the end of the chain.
+    while *chains.add(last - symoffset) & 1 == 0 {
+        last += 1;
+    }
+    last + 1
+}
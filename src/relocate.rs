@@ -15,8 +15,7 @@
 #![allow(clippy::cmp_null)]
 
 use crate::arch::{
-    dynamic_table_addr, ehdr_addr, relocation_load, relocation_mprotect_readonly, relocation_store,
-    trap,
+    dynamic_table_addr, ehdr_addr, relocation_load, relocation_store, syscall3, trap,
 };
 #[cfg(not(feature = "nightly"))]
 use crate::ptr::addr;
@@ -24,7 +23,7 @@ use core::ffi::c_void;
 use core::mem;
 use core::ptr::{null, null_mut};
 use linux_raw_sys::elf::*;
-use linux_raw_sys::general::{AT_BASE, AT_ENTRY, AT_NULL, AT_PAGESZ};
+use linux_raw_sys::general::{__NR_mprotect, AT_BASE, AT_ENTRY, AT_NULL, AT_PAGESZ, PROT_READ};
 
 /// Wrapper around `.addr()` for pointers, because we can't use the polyfill
 /// in the relocation code because that might emit calls to things that aren't
@@ -38,6 +37,10 @@ fn addr<T>(addr: *const T) -> usize {
 #[allow(non_camel_case_types)]
 type Elf_Relr = usize;
 
+// Linux UAPI headers don't define the `NONE` relocation type either. It's 0
+// on all architectures, and some linkers use it to pad relocation tables.
+const R_NONE: u32 = 0;
+
 const DT_RELRSZ: usize = 35;
 const DT_RELR: usize = 36;
 #[cfg(debug_assertions)]
@@ -47,6 +50,14 @@ const DT_RELRENT: usize = 37;
 // panic as panicking doesn't work this early on. See the docs of [relocate]
 // for more info.
 #[cfg(debug_assertions)]
+macro_rules! debug_assert {
+    ($e:expr) => {
+        if !$e {
+            trap();
+        }
+    };
+}
+#[cfg(debug_assertions)]
 macro_rules! debug_assert_eq {
     ($l:expr, $r:expr) => {
         if !($l == $r) {
@@ -230,6 +241,8 @@ pub(super) unsafe fn relocate(envp: *mut *mut u8) {
                 let reloc_value = addend.wrapping_add(offset);
                 relocation_store(reloc_addr, reloc_value);
             }
+            // Skip padding entries.
+            R_NONE => (),
             // Trap the process without panicking as panicking requires
             // relocations to be performed first.
             _ => trap(),
@@ -253,6 +266,8 @@ pub(super) unsafe fn relocate(envp: *mut *mut u8) {
                 let reloc_value = addend.wrapping_add(offset);
                 relocation_store(reloc_addr, reloc_value);
             }
+            // Skip padding entries.
+            R_NONE => (),
             // Trap the process without panicking as panicking requires
             // relocations to be performed first.
             _ => trap(),
@@ -349,7 +364,7 @@ pub(super) unsafe fn relocate(envp: *mut *mut u8) {
         match phdr.p_type {
             #[cfg(debug_assertions)]
             PT_DYNAMIC => {
-                assert_eq!(dynv, base.byte_add(phdr.p_vaddr).cast::<Elf_Dyn>());
+                debug_assert_eq!(dynv, base.byte_add(phdr.p_vaddr).cast::<Elf_Dyn>());
             }
             PT_GNU_RELRO => {
                 // A relro description is present. Make a note of it so that we
@@ -401,3 +416,29 @@ fn load_static_start() -> usize {
     let static_start_addr: *const *const c_void = &STATIC_START.0;
     unsafe { relocation_load(addr(static_start_addr)) }
 }
+
+/// Mark “relro” memory as readonly.
+///
+/// “relro” is a relocation feature in which memory can be readonly after
+/// relocations are applied.
+///
+/// This function conceptually casts `ptr` to a `*mut c_void` and does a
+/// `rustix::mm::mprotect(ptr, len, MprotectFlags::READ)`. However, it does
+/// this using a raw system call and `usize` types which don't carry
+/// provenance, as the “relro” feature cannot be expressed in the Rust memory
+/// model.
+///
+/// # Safety
+///
+/// This function must only be called during the relocation process, for
+/// relocation purposes. And, `ptr` must contain the address of a memory
+/// location that can be marked readonly.
+unsafe fn relocation_mprotect_readonly(ptr: usize, len: usize) {
+    // This is read-only but as far as Rust knows, this is just an arbitrary
+    // side-effecting opaque operation.
+    if syscall3(__NR_mprotect, ptr, len, PROT_READ as usize) != 0 {
+        // Do not panic here as libstd's panic handler needs TLS, which is not
+        // yet initialized at this point.
+        trap();
+    }
+}
@@ -0,0 +1,62 @@
+//! The default actions for signals.
+
+use rustix::runtime::Signal;
+
+/// The action taken for a signal when its handler is [`SigDfl`].
+///
+/// [`SigDfl`]: super::SigDfl
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum DefaultAction {
+    /// Terminate the process.
+    Terminate,
+    /// Terminate the process and produce a core dump.
+    CoreDump,
+    /// Ignore the signal.
+    Ignore,
+    /// Stop the process.
+    Stop,
+    /// Continue the process if it's stopped.
+    Continue,
+}
+
+/// Return the action taken for `sig` when its handler is [`SigDfl`].
+///
+/// This lets a handler determine whether resetting a signal to its default
+/// handler and re-raising it will terminate the process, or do nothing.
+///
+/// [`SigDfl`]: super::SigDfl
+#[must_use]
+pub const fn default_action(sig: Signal) -> DefaultAction {
+    match sig {
+        Signal::Hup
+        | Signal::Int
+        | Signal::Kill
+        | Signal::Usr1
+        | Signal::Usr2
+        | Signal::Pipe
+        | Signal::Alarm
+        | Signal::Term
+        | Signal::Stkflt
+        | Signal::Vtalarm
+        | Signal::Prof
+        | Signal::Io
+        | Signal::Power => DefaultAction::Terminate,
+
+        Signal::Quit
+        | Signal::Ill
+        | Signal::Trap
+        | Signal::Abort
+        | Signal::Bus
+        | Signal::Fpe
+        | Signal::Segv
+        | Signal::Xcpu
+        | Signal::Xfsz
+        | Signal::Sys => DefaultAction::CoreDump,
+
+        Signal::Child | Signal::Urg | Signal::Winch => DefaultAction::Ignore,
+
+        Signal::Stop | Signal::Tstp | Signal::Ttin | Signal::Ttou => DefaultAction::Stop,
+
+        Signal::Cont => DefaultAction::Continue,
+    }
+}
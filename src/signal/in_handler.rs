@@ -0,0 +1,208 @@
+//! Detecting code running in a signal handler, in debug builds.
+
+#[cfg(debug_assertions)]
+use {
+    super::{sig_ign, sigaction, SigDfl, Sigaction, Sighandler},
+    core::ffi::c_int,
+    core::ptr::null_mut,
+    core::sync::atomic::{AtomicI32, AtomicPtr, AtomicUsize, Ordering},
+    rustix::io,
+    rustix::runtime::Signal,
+};
+
+/// The number of handlers installed by `set_handler` that are running, on
+/// any thread.
+#[cfg(debug_assertions)]
+static DEPTH: AtomicUsize = AtomicUsize::new(0);
+
+/// The maximum number of handler invocations that are tracked at once.
+#[cfg(debug_assertions)]
+const MAX_TRACKED: usize = 16;
+
+/// The ids of the threads running handlers, or 0 for unused slots. Origin
+/// doesn't depend on thread-local storage here, so that this works in all
+/// configurations.
+#[cfg(debug_assertions)]
+static TRACKED: [AtomicI32; MAX_TRACKED] = {
+    // An inline `const` block would avoid this, but it needs Rust 1.79.
+    #[allow(clippy::declare_interior_mutable_const)]
+    const EMPTY: AtomicI32 = AtomicI32::new(0);
+    [EMPTY; MAX_TRACKED]
+};
+
+/// The handlers installed by `set_handler`, indexed by signal number, which
+/// `call_handler` calls.
+#[cfg(debug_assertions)]
+static HANDLERS: [AtomicPtr<()>; 65] = {
+    #[allow(clippy::declare_interior_mutable_const)]
+    const EMPTY: AtomicPtr<()> = AtomicPtr::new(null_mut());
+    [EMPTY; 65]
+};
+
+/// Return whether the current thread is running a signal handler installed
+/// with [`set_handler`].
+///
+/// Code which isn't async-signal-safe, such as a global allocator which
+/// takes a lock, can check this to catch being called from a signal handler,
+/// which can deadlock if the signal interrupted the thread while it held the
+/// lock. [`assert_not_in_handler`] does this check and traps with a message.
+///
+/// This is only tracked in debug builds, where `set_handler` installs the
+/// handler behind a trampoline which records when it's running; in release
+/// builds, this always returns `false`. Handlers installed with [`sigaction`]
+/// aren't tracked. If more than 16 handler invocations are running at once,
+/// across all threads, the additional ones aren't tracked.
+///
+/// [`set_handler`]: super::set_handler
+/// [`sigaction`]: super::sigaction
+#[inline]
+#[must_use]
+pub fn in_handler() -> bool {
+    #[cfg(debug_assertions)]
+    {
+        // Check the count first, so that the common case doesn't need to ask
+        // the OS for the thread id.
+        if DEPTH.load(Ordering::Acquire) == 0 {
+            return false;
+        }
+        let tid = rustix::thread::gettid().as_raw_nonzero().get();
+        TRACKED
+            .iter()
+            .any(|slot| slot.load(Ordering::Acquire) == tid)
+    }
+
+    #[cfg(not(debug_assertions))]
+    false
+}
+
+/// In debug builds, if the current thread is running a signal handler
+/// installed with [`set_handler`], print a message to stderr naming the
+/// caller's location and trap.
+///
+/// This is meant to be called at the start of operations which aren't
+/// async-signal-safe, such as memory allocation in a global allocator.
+/// Origin calls it itself when creating threads. See [`in_handler`] for
+/// details.
+///
+/// [`set_handler`]: super::set_handler
+#[inline]
+#[track_caller]
+pub fn assert_not_in_handler() {
+    #[cfg(debug_assertions)]
+    if in_handler() {
+        not_in_handler_failed(core::panic::Location::caller());
+    }
+}
+
+#[cfg(debug_assertions)]
+#[cold]
+fn not_in_handler_failed(location: &core::panic::Location<'_>) -> ! {
+    use core::fmt::Write;
+
+    let _ = writeln!(
+        crate::program::Stderr,
+        "origin: async-signal-unsafe operation at {} called from a signal handler",
+        location
+    );
+    crate::program::trap()
+}
+
+/// Install `action`, with a handler that isn't `SIG_DFL` or `SIG_IGN`,
+/// behind `call_handler`, so that [`in_handler`] can tell when it's running.
+///
+/// The previous action is returned with its handler as it was passed to
+/// this function, so that reinstalling it with `sigaction` restores the
+/// previous handler.
+#[cfg(debug_assertions)]
+pub(super) unsafe fn sigaction_tracked(
+    sig: Signal,
+    mut action: Sigaction,
+) -> io::Result<Sigaction> {
+    let addr = handler_addr(action_handler(&action));
+    if addr == handler_addr(SigDfl) || addr == handler_addr(sig_ign()) {
+        return sigaction(sig, Some(action));
+    }
+
+    let trampoline = call_handler as unsafe extern "C" fn(c_int) as usize;
+    let slot = &HANDLERS[sig as usize];
+    let prev = slot.swap(addr as *mut (), Ordering::AcqRel);
+    set_action_handler(&mut action, addr_handler(trampoline));
+
+    match sigaction(sig, Some(action)) {
+        Ok(mut old) => {
+            if handler_addr(action_handler(&old)) == trampoline {
+                set_action_handler(&mut old, addr_handler(prev as usize));
+            }
+            Ok(old)
+        }
+        Err(err) => {
+            slot.store(prev, Ordering::Release);
+            Err(err)
+        }
+    }
+}
+
+/// The trampoline `sigaction_tracked` installs, which records that the
+/// handler is running and calls it.
+#[cfg(debug_assertions)]
+unsafe extern "C" fn call_handler(sig: c_int) {
+    let handler = HANDLERS[sig as usize].load(Ordering::Acquire);
+    let handler = core::mem::transmute::<*mut (), unsafe extern "C" fn(c_int)>(handler);
+
+    DEPTH.fetch_add(1, Ordering::AcqRel);
+    let tid = rustix::thread::gettid().as_raw_nonzero().get();
+    let tracked = TRACKED.iter().find(|slot| {
+        slot.compare_exchange(0, tid, Ordering::AcqRel, Ordering::Relaxed)
+            .is_ok()
+    });
+
+    handler(sig);
+
+    if let Some(slot) = tracked {
+        slot.store(0, Ordering::Release);
+    }
+    DEPTH.fetch_sub(1, Ordering::AcqRel);
+}
+
+// `Sighandler` is an `Option` of a function pointer with the take-charge
+// backend, and an integer with libc.
+
+#[cfg(all(debug_assertions, feature = "take-charge"))]
+fn handler_addr(handler: Sighandler) -> usize {
+    handler.map_or(0, |handler| handler as usize)
+}
+
+#[cfg(all(debug_assertions, not(feature = "take-charge")))]
+fn handler_addr(handler: Sighandler) -> usize {
+    handler
+}
+
+#[cfg(all(debug_assertions, feature = "take-charge"))]
+unsafe fn addr_handler(addr: usize) -> Sighandler {
+    core::mem::transmute::<usize, Sighandler>(addr)
+}
+
+#[cfg(all(debug_assertions, not(feature = "take-charge")))]
+unsafe fn addr_handler(addr: usize) -> Sighandler {
+    addr
+}
+
+#[cfg(all(debug_assertions, feature = "take-charge"))]
+fn action_handler(action: &Sigaction) -> Sighandler {
+    action.sa_handler_kernel
+}
+
+#[cfg(all(debug_assertions, not(feature = "take-charge")))]
+fn action_handler(action: &Sigaction) -> Sighandler {
+    action.sa_sigaction
+}
+
+#[cfg(all(debug_assertions, feature = "take-charge"))]
+fn set_action_handler(action: &mut Sigaction, handler: Sighandler) {
+    action.sa_handler_kernel = handler;
+}
+
+#[cfg(all(debug_assertions, not(feature = "take-charge")))]
+fn set_action_handler(action: &mut Sigaction, handler: Sighandler) {
+    action.sa_sigaction = handler;
+}
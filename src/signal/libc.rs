@@ -4,6 +4,18 @@ use core::mem::MaybeUninit;
 use core::ptr::null;
 use rustix::io;
 
+mod default_action;
+mod in_handler;
+mod mcontext;
+mod set_handler;
+mod with_handler;
+
+pub use default_action::{default_action, DefaultAction};
+pub use in_handler::{assert_not_in_handler, in_handler};
+pub use mcontext::{mcontext_pc, mcontext_sp, set_mcontext_pc, set_mcontext_sp};
+pub use set_handler::{set_handler, Handler};
+pub use with_handler::with_handler;
+
 /// A signal action record for use with [`sigaction`].
 pub type Sigaction = libc::sigaction;
 
@@ -22,6 +34,12 @@ pub use linux_raw_sys::ctypes::c_int as Sigflags;
 
 /// Register a signal handler.
 ///
+/// A signal's handler and flags are shared by all the threads in the
+/// process, however each thread has its own signal mask, and a signal sent to
+/// the process as a whole is delivered to one of the threads which doesn't
+/// have it blocked. To handle signals on a dedicated thread, block them in
+/// all the other threads.
+///
 /// # Safety
 ///
 /// yolo. At least this function handles `sa_restorer` automatically though.
@@ -39,6 +57,33 @@ pub unsafe fn sigaction(sig: Signal, action: Option<Sigaction>) -> io::Result<Si
     }
 }
 
+/// Send the signal `sig` to the thread `thread`.
+///
+/// Unlike sending a signal to the process, which may be delivered to any
+/// thread which doesn't have it blocked, this always delivers it to `thread`,
+/// which is useful for interrupting a blocking system call in a particular
+/// thread, for example.
+///
+/// This uses `pthread_kill`, which may fail with [`io::Errno::SRCH`] if
+/// `thread` has exited.
+///
+/// # Safety
+///
+/// `thread` must point to a valid thread record that has not been joined or
+/// detached, and the thread's handler for `sig` must be prepared to run on
+/// it.
+#[cfg(feature = "thread")]
+#[doc(alias = "tgkill")]
+#[doc(alias = "pthread_kill")]
+pub unsafe fn send_to_thread(thread: crate::thread::Thread, sig: Signal) -> io::Result<()> {
+    let thread = thread.to_raw() as usize as libc::pthread_t;
+
+    match libc::pthread_kill(thread, sig as libc::c_int) {
+        0 => Ok(()),
+        err => Err(io::Errno::from_raw_os_error(err)),
+    }
+}
+
 /// Return a special “ignore” signal handler for ignoring signals.
 ///
 /// If you're looking for `sig_dfl`; use [`SigDfl`].
@@ -1,9 +1,21 @@
 //! Signal handlers.
 
 use rustix::io;
-#[cfg(not(target_arch = "riscv64"))]
+#[cfg(any(target_arch = "x86_64", target_arch = "x86", target_arch = "arm"))]
 use {crate::arch, linux_raw_sys::ctypes::c_ulong, linux_raw_sys::general::SA_RESTORER};
 
+mod default_action;
+mod in_handler;
+mod mcontext;
+mod set_handler;
+mod with_handler;
+
+pub use default_action::{default_action, DefaultAction};
+pub use in_handler::{assert_not_in_handler, in_handler};
+pub use mcontext::{mcontext_pc, mcontext_sp, set_mcontext_pc, set_mcontext_sp};
+pub use set_handler::{set_handler, Handler};
+pub use with_handler::with_handler;
+
 /// A signal action record for use with [`sigaction`].
 pub use rustix::runtime::Sigaction;
 
@@ -25,6 +37,19 @@ pub use linux_raw_sys::ctypes::c_ulong as Sigflags;
 
 /// Register a signal handler.
 ///
+/// On x86-64, x86, and arm, this sets `SA_RESTORER` and `sa_restorer` to
+/// origin's own trampolines, as the libc ABIs on those platforms do; x86-64
+/// requires it. On aarch64 and riscv64, this leaves them as provided, so that
+/// by default the kernel uses the restorer in the vDSO, which also has unwind
+/// info describing the signal frame.
+///
+/// A signal's handler and flags are shared by all the threads in the
+/// process, however each thread has its own signal mask, and a signal sent to
+/// the process as a whole is delivered to one of the threads which doesn't
+/// have it blocked. To handle signals on a dedicated thread, block them in
+/// all the other threads, such as with `dedicate_thread_to_signals` with
+/// the "thread-registry" feature.
+///
 /// # Safety
 ///
 /// yolo. At least this function handles `sa_restorer` automatically though.
@@ -32,7 +57,7 @@ pub unsafe fn sigaction(sig: Signal, action: Option<Sigaction>) -> io::Result<Si
     #[allow(unused_mut)]
     let mut action = action;
 
-    #[cfg(not(target_arch = "riscv64"))]
+    #[cfg(any(target_arch = "x86_64", target_arch = "x86", target_arch = "arm"))]
     if let Some(action) = &mut action {
         action.sa_flags |= SA_RESTORER as c_ulong;
 
@@ -46,6 +71,280 @@ pub unsafe fn sigaction(sig: Signal, action: Option<Sigaction>) -> io::Result<Si
     rustix::runtime::sigaction(sig, action)
 }
 
+/// Send the signal `sig` to the thread `thread`.
+///
+/// Unlike sending a signal to the process, which may be delivered to any
+/// thread which doesn't have it blocked, this always delivers it to `thread`,
+/// which is useful for interrupting a blocking system call in a particular
+/// thread, for example.
+///
+/// This uses `tgkill` with the current process id, so it can't signal a
+/// thread in another process. If `thread` has already exited, this usually
+/// fails with [`io::Errno::SRCH`]. However, if `thread` exits after its id is
+/// read and the id is reused by a new thread before the `tgkill`, the signal
+/// is delivered to the new thread instead. To avoid this, make sure `thread`
+/// doesn't exit while it's being signaled.
+///
+/// # Safety
+///
+/// `thread` must point to a valid thread record, and the thread's handler for
+/// `sig` must be prepared to run on it.
+#[cfg(feature = "thread")]
+#[doc(alias = "tgkill")]
+#[doc(alias = "pthread_kill")]
+pub unsafe fn send_to_thread(thread: crate::thread::Thread, sig: Signal) -> io::Result<()> {
+    use linux_raw_sys::general::__NR_tgkill;
+
+    let tid = crate::thread::id(thread).ok_or(io::Errno::SRCH)?;
+    let pid = rustix::process::getpid();
+
+    let r0 = crate::arch::syscall3(
+        __NR_tgkill,
+        pid.as_raw_nonzero().get() as usize,
+        tid.as_raw_nonzero().get() as usize,
+        sig as usize,
+    );
+    if r0 < 0 {
+        Err(io::Errno::from_raw_os_error(-r0 as i32))
+    } else {
+        Ok(())
+    }
+}
+
+/// Arrange for the signals in `sigs` to be delivered only to the calling
+/// thread.
+///
+/// This blocks `sigs` in the main thread and in every other thread created by
+/// origin, and unblocks them in the calling thread, so that signals in `sigs`
+/// sent to the process are delivered to the calling thread. This is the usual
+/// setup for a thread which waits for signals with `sigwaitinfo`, or which
+/// runs handlers that aren't safe to run on arbitrary threads.
+///
+/// A thread's signal mask can only be changed by the thread itself, so this
+/// takes over `SIGUSR2` and sends it to each other thread, with a handler
+/// which adds `sigs` to the mask that the thread returns to. Once every
+/// thread has responded, the previous `SIGUSR2` action is restored.
+///
+/// If any thread doesn't respond, for example because it has `SIGUSR2`
+/// blocked, this gives up after about a second without any progress, and
+/// fails with [`io::Errno::TIMEDOUT`]. Before returning, it signals the
+/// threads which did respond again, to remove the signals they added to their
+/// masks, discards the `SIGUSR2` still pending in the threads which didn't,
+/// and restores the previous `SIGUSR2` action. A thread which responded the
+/// first time but not the second may be left with `sigs` blocked.
+///
+/// New threads inherit the signal mask of the thread that creates them, so
+/// threads created by other threads later on also have `sigs` blocked, but
+/// threads created by the calling thread don't. Threads which are being
+/// created while this is running may not be covered.
+///
+/// # Safety
+///
+/// No other code may use `SIGUSR2` while this is running, and `sigs` must not
+/// contain `SIGUSR2`.
+#[cfg(feature = "thread-registry")]
+#[cfg_attr(
+    docsrs,
+    doc(cfg(all(feature = "take-charge", feature = "thread-registry")))
+)]
+pub unsafe fn dedicate_thread_to_signals(sigs: &Sigset) -> io::Result<()> {
+    use crate::thread::{self, ThreadId};
+    use alloc::vec::Vec;
+    use core::ffi::{c_int, c_void};
+    use core::ptr::null_mut;
+    use core::sync::atomic::Ordering::SeqCst;
+    use core::sync::atomic::{AtomicPtr, AtomicU32};
+    use rustix::runtime::{sigprocmask, tkill, How};
+    use rustix::thread::{futex, gettid};
+
+    /// The signals to add to the mask of each thread which is signaled.
+    static mut SIGS: Sigset = unsafe { core::mem::zeroed() };
+
+    /// The bit in `STATE` which is set once we've started undoing.
+    const UNDO: u32 = 1 << 31;
+
+    /// The number of entries in `ADDED` which threads have claimed, and the
+    /// `UNDO` bit.
+    static STATE: AtomicU32 = AtomicU32::new(0);
+
+    /// For each thread which has updated its mask, its id and the signals it
+    /// added which weren't already blocked. This is null when we're not
+    /// running, so that stray signals are ignored.
+    static ADDED: AtomicPtr<(ThreadId, Sigset)> = AtomicPtr::new(null_mut());
+
+    /// The number of entries `ADDED` has room for.
+    static CAPACITY: AtomicU32 = AtomicU32::new(0);
+
+    /// The number of handlers currently using `ADDED`, so that we don't free
+    /// it out from under them.
+    static ACTIVE: AtomicU32 = AtomicU32::new(0);
+
+    /// The number of threads which have finished updating their masks.
+    static RESPONDED: AtomicU32 = AtomicU32::new(0);
+
+    /// Add `SIGS` to the mask that the kernel restores when this handler
+    /// returns, or, once we've started undoing, remove the signals that this
+    /// thread added.
+    unsafe extern "C" fn block(_sig: c_int, _info: *mut Siginfo, ucontext: *mut c_void) {
+        ACTIVE.fetch_add(1, SeqCst);
+        let entries = ADDED.load(SeqCst);
+        if !entries.is_null() {
+            let mask = &mut *ucontext
+                .byte_add(crate::arch::UCONTEXT_SIGMASK_OFFSET)
+                .cast::<Sigset>();
+            update_mask(entries, mask);
+        }
+        ACTIVE.fetch_sub(1, SeqCst);
+    }
+
+    /// The body of `block`, once it has checked that `entries`, the value of
+    /// `ADDED`, is non-null.
+    unsafe fn update_mask(entries: *mut (ThreadId, Sigset), mask: &mut Sigset) {
+        let mut state = STATE.load(SeqCst);
+        loop {
+            if state & UNDO != 0 {
+                let id = gettid();
+                for i in 0..state & !UNDO {
+                    let (added_id, added) = &*entries.add(i as usize);
+                    if *added_id == id {
+                        for (word, sig) in mask.sig.iter_mut().zip(added.sig.iter()) {
+                            *word &= !*sig;
+                        }
+                        RESPONDED.fetch_add(1, SeqCst);
+                        let _ = futex::wake(&RESPONDED, futex::Flags::PRIVATE, 1);
+                    }
+                }
+                return;
+            }
+            // Ignore any signals beyond the ones we sent, rather than writing
+            // past the end of `ADDED`.
+            if state >= CAPACITY.load(SeqCst) {
+                return;
+            }
+            match STATE.compare_exchange(state, state + 1, SeqCst, SeqCst) {
+                Ok(_) => break,
+                Err(current) => state = current,
+            }
+        }
+
+        let mut added = SIGS;
+        for (sig, word) in added.sig.iter_mut().zip(mask.sig.iter_mut()) {
+            *sig &= !*word;
+            *word |= *sig;
+        }
+        entries.add(state as usize).write((gettid(), added));
+
+        RESPONDED.fetch_add(1, SeqCst);
+        let _ = futex::wake(&RESPONDED, futex::Flags::PRIVATE, 1);
+    }
+
+    /// Wait for `count` threads to respond, giving up if we go a second
+    /// without any progress.
+    fn wait_for_responses(count: u32) -> bool {
+        const TIMEOUT: futex::Timespec = futex::Timespec {
+            tv_sec: 0,
+            tv_nsec: 10_000_000,
+        };
+        let mut timeouts = 0;
+        loop {
+            let responded = RESPONDED.load(SeqCst);
+            if responded >= count {
+                return true;
+            }
+            if timeouts == 100 {
+                return false;
+            }
+            match futex::wait(&RESPONDED, futex::Flags::PRIVATE, responded, Some(TIMEOUT)) {
+                Err(io::Errno::TIMEDOUT) => timeouts += 1,
+                _ => timeouts = 0,
+            }
+        }
+    }
+
+    // Lock the registry for the duration, so that threads don't start or
+    // exit while we're signaling them, and so that concurrent calls to this
+    // function are serialized.
+    let registry = thread::lock_registry();
+
+    // Allocate an entry in `ADDED` for each thread we might signal, up front,
+    // since the handler can't allocate.
+    let mut added = Vec::<(ThreadId, Sigset)>::with_capacity(registry.len() + 1);
+    SIGS = *sigs;
+    CAPACITY.store(added.capacity() as u32, SeqCst);
+    STATE.store(0, SeqCst);
+    RESPONDED.store(0, SeqCst);
+
+    // Install our handler, with all signals blocked while it runs.
+    let mut action: Sigaction = core::mem::zeroed();
+    action.sa_handler_kernel = Some(core::mem::transmute::<
+        unsafe extern "C" fn(c_int, *mut Siginfo, *mut c_void),
+        unsafe extern "C" fn(c_int),
+    >(block));
+    action.sa_flags = SA_SIGINFO;
+    action.sa_mask.sig.fill(!0);
+    let old_action = sigaction(Signal::Usr2, Some(action))?;
+    ADDED.store(added.as_mut_ptr(), SeqCst);
+
+    // Signal the main thread, whose id is the process id, and the threads in
+    // the registry, other than ourselves.
+    let current_id = thread::current_id();
+    let main_id = rustix::process::getpid();
+    let mut signaled = 0;
+    for id in core::iter::once(&main_id).chain(registry.iter()) {
+        if *id != current_id && tkill(*id, Signal::Usr2).is_ok() {
+            signaled += 1;
+        }
+    }
+
+    let result = if wait_for_responses(signaled) {
+        Ok(())
+    } else {
+        // Stop any more threads from adding to their masks, and wait for the
+        // handlers which have already claimed an entry to finish.
+        let claimed = STATE.fetch_or(UNDO, SeqCst);
+        while !wait_for_responses(claimed) {}
+
+        // Signal the threads which added to their masks again, to remove
+        // what they added.
+        RESPONDED.store(0, SeqCst);
+        let mut undoing = 0;
+        for i in 0..claimed {
+            let (id, _) = *added.as_ptr().add(i as usize);
+            if tkill(id, Signal::Usr2).is_ok() {
+                undoing += 1;
+            }
+        }
+        wait_for_responses(undoing);
+
+        // Setting the action to ignore discards the `SIGUSR2` still pending
+        // in threads which never responded, so that it doesn't invoke the
+        // previous action once they unblock it.
+        let mut ignore: Sigaction = core::mem::zeroed();
+        ignore.sa_handler_kernel = sig_ign();
+        let _ = sigaction(Signal::Usr2, Some(ignore));
+
+        Err(io::Errno::TIMEDOUT)
+    };
+
+    // Clean up before reporting any errors. Even if the previous action can't
+    // be restored, once `ADDED` is null and no handlers are using it, our
+    // handler ignores any further signals, so `added` can be freed.
+    let restored = sigaction(Signal::Usr2, Some(old_action));
+    ADDED.store(null_mut(), SeqCst);
+    while ACTIVE.load(SeqCst) != 0 {
+        core::hint::spin_loop();
+    }
+    drop(added);
+    drop(registry);
+
+    result?;
+    restored?;
+
+    sigprocmask(How::UNBLOCK, Some(sigs))?;
+
+    Ok(())
+}
+
 /// Return a special “ignore” signal handler for ignoring signals.
 ///
 /// If you're looking for `sig_dfl`; use [`SigDfl`].
@@ -0,0 +1,94 @@
+//! Reading and modifying the registers saved in a signal handler's
+//! `ucontext`.
+
+use crate::arch::{UCONTEXT_PC_OFFSET, UCONTEXT_SP_OFFSET};
+use core::ffi::c_void;
+
+/// Return the program counter saved in `ucontext`, which is where the thread
+/// resumes when the signal handler returns.
+///
+/// For a synchronous signal such as `SIGSEGV` or `SIGILL` raised by an
+/// instruction, this is usually the address of that instruction.
+///
+/// # Safety
+///
+/// `ucontext` must be the `ucontext` pointer passed as the third argument to
+/// a signal handler installed with `SA_SIGINFO`, and the handler must still
+/// be running.
+#[inline]
+#[must_use]
+pub unsafe fn mcontext_pc(ucontext: *const c_void) -> usize {
+    read_reg(ucontext, UCONTEXT_PC_OFFSET)
+}
+
+/// Return the stack pointer saved in `ucontext`.
+///
+/// # Safety
+///
+/// The requirements of [`mcontext_pc`] apply.
+#[inline]
+#[must_use]
+pub unsafe fn mcontext_sp(ucontext: *const c_void) -> usize {
+    read_reg(ucontext, UCONTEXT_SP_OFFSET)
+}
+
+/// Set the program counter saved in `ucontext`, so that the thread resumes
+/// at `pc` when the signal handler returns.
+///
+/// This is the mechanism for recovering from a fault, such as a JIT
+/// skipping over a trapping instruction, or jumping to recovery code for a
+/// userspace page-fault handler. It's extremely architecture-specific: the
+/// handler needs to know the length and meaning of the instructions it's
+/// skipping, and on arm, `pc` must not have the low bit set; whether it's
+/// Thumb code is determined by the saved `cpsr`, which isn't changed. All
+/// other registers are restored from `ucontext` as they were when the signal
+/// was delivered, so the code at `pc` must be prepared to run with them.
+///
+/// # Safety
+///
+/// In addition to the requirements of [`mcontext_pc`], `pc` must be the
+/// address of code which has defined behavior when resumed with the register
+/// state in `ucontext`.
+#[inline]
+pub unsafe fn set_mcontext_pc(ucontext: *mut c_void, pc: usize) {
+    write_reg(ucontext, UCONTEXT_PC_OFFSET, pc)
+}
+
+/// Set the stack pointer saved in `ucontext`, so that the thread resumes
+/// with its stack pointer set to `sp` when the signal handler returns.
+///
+/// This is usually used along with [`set_mcontext_pc`], to resume on a
+/// different stack, or to unwind the stack of the code that faulted. `sp`
+/// must satisfy the architecture's alignment requirements at the point the
+/// thread resumes.
+///
+/// # Safety
+///
+/// In addition to the requirements of [`mcontext_pc`], `sp` must be a valid
+/// stack pointer for the code the thread resumes at.
+#[inline]
+pub unsafe fn set_mcontext_sp(ucontext: *mut c_void, sp: usize) {
+    write_reg(ucontext, UCONTEXT_SP_OFFSET, sp)
+}
+
+// On x32, the saved registers are 64-bit, though pointers are 32-bit.
+
+#[cfg(not(all(target_arch = "x86_64", target_pointer_width = "32")))]
+unsafe fn read_reg(ucontext: *const c_void, offset: usize) -> usize {
+    ucontext.byte_add(offset).cast::<usize>().read()
+}
+
+#[cfg(not(all(target_arch = "x86_64", target_pointer_width = "32")))]
+unsafe fn write_reg(ucontext: *mut c_void, offset: usize, value: usize) {
+    ucontext.byte_add(offset).cast::<usize>().write(value)
+}
+
+#[cfg(all(target_arch = "x86_64", target_pointer_width = "32"))]
+unsafe fn read_reg(ucontext: *const c_void, offset: usize) -> usize {
+    ucontext.byte_add(offset).cast::<u64>().read() as usize
+}
+
+#[cfg(all(target_arch = "x86_64", target_pointer_width = "32"))]
+unsafe fn write_reg(ucontext: *mut c_void, offset: usize, value: usize) {
+    ucontext.byte_add(offset).cast::<u64>().write(value as u64)
+}
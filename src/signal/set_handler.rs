@@ -0,0 +1,101 @@
+//! Installing a signal handler with common defaults.
+
+use super::{sigaction, Sigaction, Sigflags, Sighandler, SA_RESTART};
+use rustix::io;
+use rustix::runtime::Signal;
+
+/// A signal handler to install with [`set_handler`].
+///
+/// By default, the handler is installed with `SA_RESTART`, so that system
+/// calls which are interrupted by the signal are restarted by the kernel
+/// once the handler returns, rather than failing with [`io::Errno::INTR`].
+/// Use [`Handler::no_restart`] for handlers which are meant to interrupt
+/// blocking calls.
+#[derive(Clone, Copy)]
+pub struct Handler {
+    handler: Sighandler,
+    flags: Sigflags,
+}
+
+impl Handler {
+    /// Return a `Handler` which calls `handler` and sets `SA_RESTART`.
+    #[inline]
+    #[must_use]
+    pub const fn new(handler: Sighandler) -> Self {
+        Self {
+            handler,
+            flags: SA_RESTART,
+        }
+    }
+
+    /// Don't set `SA_RESTART`.
+    ///
+    /// Blocking system calls interrupted by the signal then fail with
+    /// [`io::Errno::INTR`] after the handler returns, which lets a handler
+    /// that sets a flag, for example on `SIGINT`, wake up a thread blocked in
+    /// `read` so that it can check the flag. Some system calls, such as
+    /// `nanosleep` and `poll`, fail with `INTR` even with `SA_RESTART`.
+    #[inline]
+    #[must_use]
+    pub const fn no_restart(mut self) -> Self {
+        self.flags &= !SA_RESTART;
+        self
+    }
+
+    /// Return the [`Sigaction`] that [`set_handler`] installs for this
+    /// `Handler`.
+    ///
+    /// In debug builds, `set_handler` installs the handler behind a
+    /// trampoline, for [`in_handler`], so the action it installs differs in
+    /// its handler.
+    ///
+    /// [`in_handler`]: super::in_handler
+    #[must_use]
+    pub fn to_sigaction(&self) -> Sigaction {
+        // SAFETY: `Sigaction` is a C struct, for which all zeros is an empty
+        // mask and no flags.
+        let mut action: Sigaction = unsafe { core::mem::zeroed() };
+        #[cfg(feature = "take-charge")]
+        {
+            action.sa_handler_kernel = self.handler;
+        }
+        #[cfg(not(feature = "take-charge"))]
+        {
+            action.sa_sigaction = self.handler;
+        }
+        action.sa_flags = self.flags;
+        action
+    }
+}
+
+/// Install `handler` for `sig`, and return the previous action.
+///
+/// Unlike [`sigaction`], which installs exactly the flags it's given, this
+/// sets `SA_RESTART` unless [`Handler::no_restart`] is used. Since a signal's
+/// action is shared by all the threads in the process, this affects every
+/// blocking system call in the program which the signal interrupts.
+///
+/// In debug builds, the handler is called from a trampoline which records
+/// that it's running, so that [`in_handler`] and [`assert_not_in_handler`]
+/// can detect code running in it. The returned previous action has the
+/// handler it was installed with, rather than the trampoline, so that it can
+/// be reinstalled with [`sigaction`], though it isn't tracked then.
+///
+/// [`in_handler`]: super::in_handler
+/// [`assert_not_in_handler`]: super::assert_not_in_handler
+///
+/// # Safety
+///
+/// The requirements of [`sigaction`] apply. In particular, the handler must
+/// only do things which are safe to do in a signal handler.
+pub unsafe fn set_handler(sig: Signal, handler: Handler) -> io::Result<Sigaction> {
+    #[cfg(debug_assertions)]
+    {
+        super::in_handler::sigaction_tracked(sig, handler.to_sigaction())
+    }
+
+    #[cfg(not(debug_assertions))]
+    {
+        sigaction(sig, Some(handler.to_sigaction()))
+    }
+}
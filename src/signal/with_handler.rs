@@ -0,0 +1,42 @@
+//! Temporarily installing a signal handler.
+
+use super::{sigaction, Sigaction};
+use rustix::io;
+use rustix::runtime::Signal;
+
+/// Install `action` for `sig`, call `f`, and then restore the previous
+/// action.
+///
+/// This is for the common pattern of handling a signal, such as `SIGPIPE` or
+/// `SIGCHLD`, only during a particular operation. The previous action is
+/// restored when `f` returns, so it's restored on every path out of `f`,
+/// however there's no `Drop`-based cleanup, so if `f` unwinds, it isn't
+/// restored.
+///
+/// This fails without calling `f` if `action` can't be installed. Restoring
+/// the previous action, which the OS gave us, is not expected to fail.
+///
+/// # Safety
+///
+/// The requirements of [`sigaction`] apply. In addition, a signal's action
+/// is shared by all the threads in the process, so other threads must not
+/// install actions for `sig` while this is running, or they may be
+/// overwritten when the previous action is restored.
+pub unsafe fn with_handler<R>(
+    sig: Signal,
+    action: Sigaction,
+    f: impl FnOnce() -> R,
+) -> io::Result<R> {
+    let old = sigaction(sig, Some(action))?;
+
+    let result = f();
+
+    let restored = sigaction(sig, Some(old));
+    debug_assert!(
+        restored.is_ok(),
+        "failed to restore the previous action for {:?}",
+        sig
+    );
+
+    Ok(result)
+}
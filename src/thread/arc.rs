@@ -0,0 +1,109 @@
+//! Reference-counted thread handles.
+
+use super::{detach, wait_for_exit, Thread, ThreadId};
+use core::ffi::c_void;
+use core::ptr::NonNull;
+use core::sync::atomic::Ordering::SeqCst;
+
+/// A reference-counted handle to a thread.
+///
+/// Unlike [`Thread`], which is a plain pointer that must be joined or
+/// detached exactly once, an `ArcThread` can be cloned and dropped freely.
+/// The count of `ArcThread`s is stored in the thread's record, and the record
+/// is freed once the thread has exited and the last `ArcThread` for it has
+/// been dropped. This makes [`ArcThread::join`] and [`ArcThread::detach`]
+/// safe to call.
+#[cfg_attr(docsrs, doc(cfg(feature = "take-charge")))]
+pub struct ArcThread {
+    thread: Thread,
+}
+
+impl ArcThread {
+    /// Take ownership of `thread`, returning the first `ArcThread` for it.
+    ///
+    /// # Safety
+    ///
+    /// `thread` must point to a valid thread record for a thread created by
+    /// [`create`], which has not been detached or joined, and which won't be
+    /// detached or joined other than through `ArcThread`s.
+    ///
+    /// [`create`]: super::create
+    pub unsafe fn new(thread: Thread) -> Self {
+        let prev = thread.0.as_ref().handles.swap(1, SeqCst);
+        debug_assert_eq!(prev, 0, "thread is already owned by an `ArcThread`");
+        Self { thread }
+    }
+
+    /// Return the underlying [`Thread`].
+    ///
+    /// The returned `Thread` is only valid while `self`, or a clone of it, is
+    /// live.
+    #[inline]
+    #[must_use]
+    pub fn thread(&self) -> Thread {
+        self.thread
+    }
+
+    /// Return the id of the thread, or `None` if the thread has exited.
+    #[inline]
+    #[must_use]
+    pub fn id(&self) -> Option<ThreadId> {
+        // SAFETY: `self` keeps the thread record live.
+        unsafe { super::id(self.thread) }
+    }
+
+    /// Wait for the thread to finish, and return the value returned from the
+    /// call to the `fn_` passed to `create`.
+    ///
+    /// Any number of clones of an `ArcThread` may be joined; each one waits
+    /// for the thread to finish and sees the same return value.
+    pub fn join(self) -> Option<NonNull<c_void>> {
+        // SAFETY: `self` keeps the thread record live, and since it's never
+        // detached while an `ArcThread` exists, it'll store its return value
+        // in the record when it exits.
+        unsafe {
+            wait_for_exit(self.thread);
+            NonNull::new(self.thread.0.as_ref().return_value.load(SeqCst))
+        }
+    }
+
+    /// Release this handle without waiting for the thread to finish.
+    ///
+    /// This is the same as dropping `self`. Once the thread has exited and
+    /// every `ArcThread` for it has been released, its resources are freed.
+    #[inline]
+    pub fn detach(self) {
+        drop(self)
+    }
+}
+
+impl Clone for ArcThread {
+    #[inline]
+    fn clone(&self) -> Self {
+        // SAFETY: `self` keeps the thread record live.
+        let prev = unsafe { self.thread.0.as_ref().handles.fetch_add(1, SeqCst) };
+        assert!(prev < usize::MAX / 2, "too many `ArcThread`s");
+        Self {
+            thread: self.thread,
+        }
+    }
+}
+
+impl Drop for ArcThread {
+    fn drop(&mut self) {
+        // SAFETY: `self` keeps the thread record live until we release it
+        // here. When the last handle is released, nothing else refers to the
+        // thread, so we can detach it, which frees the record immediately if
+        // the thread has exited, or has the thread free it when it exits.
+        unsafe {
+            if self.thread.0.as_ref().handles.fetch_sub(1, SeqCst) == 1 {
+                detach(self.thread);
+            }
+        }
+    }
+}
+
+// SAFETY: The thread record is only accessed through atomics, and the count
+// of handles ensures that it stays live while any `ArcThread` exists.
+unsafe impl Send for ArcThread {}
+unsafe impl Sync for ArcThread {}
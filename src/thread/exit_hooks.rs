@@ -0,0 +1,79 @@
+//! Functions called when any thread exits.
+
+use super::Thread;
+use alloc::vec::Vec;
+use core::sync::atomic::AtomicUsize;
+use core::sync::atomic::Ordering::SeqCst;
+use rustix_futex_sync::Mutex;
+
+/// The functions registered with [`register_global_exit_hook`], in order of
+/// registration.
+static HOOKS: Mutex<Vec<fn(Thread)>> = Mutex::new(Vec::new());
+
+/// Registers a function to call whenever any thread exits, including the main
+/// thread when the program exits.
+///
+/// The function is passed the exiting thread, and is called on that thread,
+/// so it may also use [`current`] and the thread's thread-local storage.
+/// Functions are called in the order of their registration, after the
+/// thread's own functions registered with [`at_exit`] have been called, so
+/// that they see the thread in its final state. A function registered while
+/// these functions are being called is also called before the thread
+/// finishes exiting.
+///
+/// On the main thread, they are called by `program::exit`, after the
+/// functions registered with [`at_exit`] and, with the take-charge backend,
+/// before functions registered with `program::at_exit`.
+///
+/// With the libc backend, this covers threads created by [`create`], which
+/// call these functions from a `__cxa_thread_atexit_impl` destructor
+/// registered when they start, and the thread which calls `program::exit`,
+/// which calls them from an `atexit` function registered by the first call
+/// to this function. Threads created by other means don't call them.
+///
+/// [`current`]: super::current
+/// [`at_exit`]: super::at_exit
+/// [`create`]: super::create
+pub fn register_global_exit_hook(hook: fn(Thread)) {
+    HOOKS.lock().push(hook);
+
+    #[cfg(not(feature = "take-charge"))]
+    {
+        use core::sync::atomic::AtomicBool;
+
+        static REGISTERED: AtomicBool = AtomicBool::new(false);
+
+        extern "C" fn call() {
+            call_global_exit_hooks(super::current(), &AtomicUsize::new(0));
+        }
+
+        if !REGISTERED.swap(true, SeqCst) {
+            // SAFETY: `call` is an `extern "C" fn` with no arguments, as
+            // `atexit` expects.
+            unsafe {
+                assert_eq!(libc::atexit(call), 0);
+            }
+        }
+    }
+}
+
+/// Call the functions registered with [`register_global_exit_hook`] for the
+/// exiting thread `current`.
+///
+/// `next` holds the index of the next function to call, and is advanced
+/// before each call, so that if a call re-enters the exit path, the nested
+/// call can continue where this one left off rather than starting over.
+pub(crate) fn call_global_exit_hooks(current: Thread, next: &AtomicUsize) {
+    // Leave `HOOKS` unlocked while making each call, so that hooks can
+    // register more hooks, and index into it each time rather than holding
+    // an iterator, since it may have been reallocated.
+    loop {
+        let index = next.load(SeqCst);
+        let hook = match HOOKS.lock().get(index) {
+            Some(hook) => *hook,
+            None => break,
+        };
+        next.store(index + 1, SeqCst);
+        hook(current);
+    }
+}
@@ -19,7 +19,18 @@ use core::ptr::{with_exposed_provenance_mut, without_provenance_mut};
 use core::slice;
 use rustix::io;
 
+#[cfg(feature = "thread-at-exit")]
+mod exit_hooks;
+#[cfg(feature = "alloc")]
+mod spawn;
+
+#[cfg(feature = "thread-at-exit")]
+pub(crate) use exit_hooks::call_global_exit_hooks;
+#[cfg(feature = "thread-at-exit")]
+pub use exit_hooks::register_global_exit_hook;
 pub use rustix::thread::Pid as ThreadId;
+#[cfg(feature = "alloc")]
+pub use spawn::{spawn_unchecked, JoinHandle};
 
 // Symbols defined in libc but not declared in the libc crate.
 extern "C" {
@@ -35,6 +46,10 @@ extern "C" {
     static __dso_handle: *const c_void;
 }
 
+/// The type of a thread function passed to [`create`], which takes the
+/// thread's arguments and returns its return value.
+type ThreadFn = unsafe fn(&mut [Option<NonNull<c_void>>]) -> Option<NonNull<c_void>>;
+
 /// An opaque pointer to a thread.
 ///
 /// This type does not detach or free resources on drop. It just leaks the
@@ -103,12 +118,133 @@ impl Thread {
 /// The values of `args` must be valid to send to the new thread, `fn_(args)`
 /// on the new thread must have defined behavior, and the return value must be
 /// valid to send to other threads.
+///
+/// If the limit on the number of processes and threads the user may have,
+/// `RLIMIT_NPROC`, has been reached, this fails with [`io::Errno::AGAIN`],
+/// and it may succeed if retried after other threads have exited.
+///
+/// If `stack_size` is less than [`MIN_STACK_SIZE`], or libc's own minimum,
+/// this fails with [`io::Errno::INVAL`].
+///
+/// In debug builds with the "signal" feature, this traps with a message if
+/// it's called from a signal handler installed with `signal::set_handler`,
+/// since creating a thread isn't async-signal-safe.
+#[cfg_attr(all(debug_assertions, feature = "signal"), track_caller)]
 pub unsafe fn create(
-    fn_: unsafe fn(&mut [Option<NonNull<c_void>>]) -> Option<NonNull<c_void>>,
+    fn_: ThreadFn,
     args: &[Option<NonNull<c_void>>],
     stack_size: usize,
     guard_size: usize,
 ) -> io::Result<Thread> {
+    create_impl(fn_, args, stack_size, guard_size, false)
+}
+
+/// Options for creating a new thread.
+///
+/// This is an alternative to [`create`] for when non-default options are
+/// needed. [`Builder::new`] starts with the [`default_stack_size`] and
+/// [`default_guard_size`].
+#[derive(Debug, Clone)]
+pub struct Builder {
+    stack_size: usize,
+    guard_size: usize,
+    block_all_signals: bool,
+}
+
+impl Builder {
+    /// Create a new `Builder` with default options.
+    #[inline]
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            stack_size: default_stack_size(),
+            guard_size: default_guard_size(),
+            block_all_signals: false,
+        }
+    }
+
+    /// Set the size of the new thread's stack.
+    ///
+    /// Creating the thread fails if this is less than [`MIN_STACK_SIZE`].
+    #[inline]
+    #[must_use]
+    pub fn stack_size(mut self, stack_size: usize) -> Self {
+        self.stack_size = stack_size;
+        self
+    }
+
+    /// Set the size of the new thread's stack guard region.
+    #[inline]
+    #[must_use]
+    pub fn guard_size(mut self, guard_size: usize) -> Self {
+        self.guard_size = guard_size;
+        self
+    }
+
+    /// Set whether the new thread starts with all signals blocked.
+    ///
+    /// By default, a new thread inherits the signal mask of the thread that
+    /// creates it. With this set, it instead starts with all signals blocked,
+    /// which is useful for helper threads that should never run signal
+    /// handlers. The thread can unblock signals itself once it's ready.
+    ///
+    /// The mask is applied before the new thread runs any code, so there's no
+    /// window in which a signal can be delivered to it. `SIGKILL` and
+    /// `SIGSTOP` can't be blocked.
+    #[inline]
+    #[must_use]
+    pub fn block_all_signals(mut self, block_all_signals: bool) -> Self {
+        self.block_all_signals = block_all_signals;
+        self
+    }
+
+    /// Creates a new thread with these options.
+    ///
+    /// This is like [`create`], with the options from this `Builder`.
+    ///
+    /// # Safety
+    ///
+    /// The requirements of [`create`] apply.
+    #[cfg_attr(all(debug_assertions, feature = "signal"), track_caller)]
+    pub unsafe fn create(
+        self,
+        fn_: ThreadFn,
+        args: &[Option<NonNull<c_void>>],
+    ) -> io::Result<Thread> {
+        create_impl(
+            fn_,
+            args,
+            self.stack_size,
+            self.guard_size,
+            self.block_all_signals,
+        )
+    }
+}
+
+impl Default for Builder {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// The implementation of [`create`] and [`Builder::create`].
+///
+/// If `block_all_signals` is true, the new thread starts with all signals
+/// blocked.
+#[cfg_attr(all(debug_assertions, feature = "signal"), track_caller)]
+unsafe fn create_impl(
+    fn_: ThreadFn,
+    args: &[Option<NonNull<c_void>>],
+    stack_size: usize,
+    guard_size: usize,
+    block_all_signals: bool,
+) -> io::Result<Thread> {
+    // Creating a thread allocates and takes locks, so it isn't
+    // async-signal-safe.
+    #[cfg(feature = "signal")]
+    crate::signal::assert_not_in_handler();
+
     // This should really be an `unsafe` function, but `libc::pthread_create`
     // doesn't have `unsafe` in its signature.
     extern "C" fn start(thread_arg_ptr: *mut c_void) -> *mut c_void {
@@ -120,10 +256,21 @@ pub unsafe fn create(
                 None => 0,
             };
             let thread_args = slice::from_raw_parts_mut(thread_arg_ptr, num_args + 2);
-            let fn_: unsafe fn(&mut [Option<NonNull<c_void>>]) -> Option<NonNull<c_void>> =
-                transmute(thread_args[1]);
+            let fn_: ThreadFn = transmute(thread_args[1]);
             let args = &mut thread_args[2..];
 
+            // Have the functions registered with `register_global_exit_hook`
+            // called when this thread exits. Register this before the user's
+            // function runs, so that it's called after any functions the user
+            // registers with `at_exit`.
+            #[cfg(feature = "thread-at-exit")]
+            {
+                unsafe extern "C" fn call(_arg: *mut c_void) {
+                    call_global_exit_hooks(current(), &core::sync::atomic::AtomicUsize::new(0));
+                }
+                assert_eq!(__cxa_thread_atexit_impl(call, null_mut(), dso_handle()), 0);
+            }
+
             // Call the user's function.
             let return_value = fn_(args);
 
@@ -138,6 +285,12 @@ pub unsafe fn create(
         }
     }
 
+    // `pthread_attr_setstacksize` checks libc's own minimum, which may be
+    // smaller, so check ours first.
+    if stack_size < MIN_STACK_SIZE {
+        return Err(io::Errno::INVAL);
+    }
+
     unsafe {
         let mut attr: libc::pthread_attr_t = zeroed();
         match libc::pthread_attr_init(&mut attr) {
@@ -168,10 +321,37 @@ pub unsafe fn create(
         thread_args[1] = NonNull::new(fn_ as _);
         thread_args[2..].copy_from_slice(args);
 
+        // The new thread inherits our signal mask. If it's meant to start
+        // with all signals blocked, block them here around the
+        // `pthread_create`, so that there's no window in which a signal could
+        // be delivered to it before it's blocked.
+        let mut old_mask: libc::sigset_t = zeroed();
+        if block_all_signals {
+            let mut all: libc::sigset_t = zeroed();
+            libc::sigfillset(&mut all);
+            match libc::pthread_sigmask(libc::SIG_SETMASK, &all, &mut old_mask) {
+                0 => (),
+                err => {
+                    libc::free(thread_arg_ptr);
+                    return Err(io::Errno::from_raw_os_error(err));
+                }
+            }
+        }
+
         // Call libc to create the thread.
-        match libc::pthread_create(&mut new_thread, &attr, start, thread_arg_ptr) {
+        let res = libc::pthread_create(&mut new_thread, &attr, start, thread_arg_ptr);
+
+        // Restore our own signal mask.
+        if block_all_signals {
+            libc::pthread_sigmask(libc::SIG_SETMASK, &old_mask, null_mut());
+        }
+
+        match res {
             0 => (),
-            err => return Err(io::Errno::from_raw_os_error(err)),
+            err => {
+                libc::free(thread_arg_ptr);
+                return Err(io::Errno::from_raw_os_error(err));
+            }
         }
 
         Ok(Thread(new_thread))
@@ -212,7 +392,73 @@ pub unsafe fn join(thread: Thread) -> Option<NonNull<c_void>> {
     NonNull::new(return_value)
 }
 
+/// Waits for any of several threads to finish.
+///
+/// This returns the index in `threads` of a thread that has exited, and that
+/// thread's return value, as [`join`] would. That thread is joined; the others
+/// are left as they were, and still need to be joined or detached.
+///
+/// pthreads can only wait for one thread to exit at a time, so this waits for
+/// each of the threads in turn, with a short timeout, and it may take up to
+/// that long to notice that a thread other than the one being waited for has
+/// exited.
+///
+/// This uses the GNU extensions `pthread_tryjoin_np` and
+/// `pthread_timedjoin_np`, so it's only available on glibc targets.
+///
+/// # Panics
+///
+/// This panics if `threads` is empty.
+///
+/// # Safety
+///
+/// Each element of `threads` must point to a valid thread record that has not
+/// already been detached or joined, and `threads` must not contain duplicates.
+#[cfg(target_env = "gnu")]
+#[cfg_attr(docsrs, doc(cfg(target_env = "gnu")))]
+pub unsafe fn join_any(threads: &[Thread]) -> (usize, Option<NonNull<c_void>>) {
+    // How long to wait for each thread before checking the others.
+    const TIMEOUT_NSEC: libc::c_long = 1_000_000;
+
+    assert!(
+        !threads.is_empty(),
+        "`join_any` requires at least one thread"
+    );
+
+    let mut next = 0;
+    loop {
+        for (index, thread) in threads.iter().enumerate() {
+            let mut return_value: *mut c_void = null_mut();
+            match libc::pthread_tryjoin_np(thread.0, &mut return_value) {
+                0 => return (index, NonNull::new(return_value)),
+                err => assert_eq!(err, libc::EBUSY),
+            }
+        }
+
+        // `pthread_timedjoin_np` takes an absolute `CLOCK_REALTIME` deadline.
+        let mut deadline: libc::timespec = zeroed();
+        assert_eq!(libc::clock_gettime(libc::CLOCK_REALTIME, &mut deadline), 0);
+        deadline.tv_nsec += TIMEOUT_NSEC;
+        if deadline.tv_nsec >= 1_000_000_000 {
+            deadline.tv_sec += 1;
+            deadline.tv_nsec -= 1_000_000_000;
+        }
+
+        let mut return_value: *mut c_void = null_mut();
+        match libc::pthread_timedjoin_np(threads[next].0, &mut return_value, &deadline) {
+            0 => return (next, NonNull::new(return_value)),
+            err => assert_eq!(err, libc::ETIMEDOUT),
+        }
+
+        next = (next + 1) % threads.len();
+    }
+}
+
 /// Registers a function to call when the current thread exits.
+///
+/// This uses libc's `__cxa_thread_atexit_impl`, so functions are called in
+/// the reverse order of their registration, along with C++ `thread_local`
+/// destructors, and before `pthread_key_create` destructors.
 #[cfg(feature = "thread-at-exit")]
 pub fn at_exit(func: Box<dyn FnOnce()>) {
     unsafe extern "C" fn call(arg: *mut c_void) {
@@ -231,6 +477,9 @@ pub fn at_exit(func: Box<dyn FnOnce()>) {
 }
 
 /// Return a raw pointer to the data associated with the current thread.
+///
+/// This is stable for the lifetime of the thread, and on a thread created by
+/// [`create`], it is equal to the `Thread` that `create` returned.
 #[inline]
 #[must_use]
 pub fn current() -> Thread {
@@ -268,6 +517,18 @@ pub unsafe fn set_current_id_after_a_fork(tid: ThreadId) {
     let _ = tid;
 }
 
+/// Reset origin's view of the program's threads in the child of a `fork`.
+///
+/// With the libc backend, origin doesn't keep records of threads, and libc
+/// updates its own in its `fork`, so this does nothing.
+///
+/// # Safety
+///
+/// This must only be called in the child of a `fork`, before any other
+/// threads are created.
+#[inline]
+pub unsafe fn reset_after_fork() {}
+
 /// Return the address of the thread-local `errno` state.
 ///
 /// This is equivalent to `__errno_location()` in glibc and musl.
@@ -277,6 +538,29 @@ pub fn errno_location() -> *mut i32 {
     unsafe { __errno_location() }
 }
 
+/// Return the current thread's `errno` value.
+///
+/// This reads the value at [`errno_location`].
+#[cfg(feature = "unstable-errno")]
+#[inline]
+#[must_use]
+pub fn errno() -> i32 {
+    // SAFETY: `errno_location` returns a valid pointer for the current
+    // thread.
+    unsafe { *errno_location() }
+}
+
+/// Set the current thread's `errno` value.
+///
+/// This writes the value at [`errno_location`].
+#[cfg(feature = "unstable-errno")]
+#[inline]
+pub fn set_errno(value: i32) {
+    // SAFETY: `errno_location` returns a valid pointer for the current
+    // thread.
+    unsafe { *errno_location() = value }
+}
+
 /// Return the TLS address for the given `module` and `offset` for the current
 /// thread.
 #[inline]
@@ -312,6 +596,24 @@ pub unsafe fn stack(thread: Thread) -> (*mut c_void, usize, usize) {
     (stack_addr, stack_size, guard_size)
 }
 
+/// A default stack size for new threads, known at compile time.
+///
+/// Unlike [`default_stack_size`], this doesn't take into account libc's
+/// defaults, which may depend on `RLIMIT_STACK`.
+pub const DEFAULT_STACK_SIZE: usize = 0x20000;
+
+/// The smallest stack size [`create`] accepts.
+///
+/// This is 16 KiB, the same as `PTHREAD_STACK_MIN` on many platforms. libc
+/// may require more on some platforms.
+pub const MIN_STACK_SIZE: usize = 0x4000;
+
+/// A default guard size for new threads, known at compile time.
+///
+/// This is four 4 KiB pages. Unlike [`default_guard_size`], this doesn't take
+/// into account libc's defaults or the actual page size.
+pub const DEFAULT_GUARD_SIZE: usize = 4 * 4096;
+
 /// Return the default stack size for new threads.
 #[inline]
 #[must_use]
@@ -348,6 +650,21 @@ pub fn yield_current() {
     let _ = unsafe { libc::sched_yield() };
 }
 
+/// Yield the current thread, hinting that `thread` should run instead.
+///
+/// This is only a hint; callers must not depend on `thread` having made
+/// progress when this returns. libc doesn't provide a way to wait on another
+/// thread's id, so this just calls [`yield_current`].
+///
+/// # Safety
+///
+/// `thread` must point to a valid thread record.
+#[inline]
+pub unsafe fn yield_to(thread: Thread) {
+    let _ = thread;
+    yield_current()
+}
+
 /// Return the address of `__dso_handle`, appropriately casted.
 #[cfg(feature = "thread-at-exit")]
 unsafe fn dso_handle() -> *mut c_void {
@@ -7,38 +7,57 @@
 //! more control when creating efficient higher-level abstractions like
 //! pthreads or `std::thread::Thread`.
 
-use crate::arch::{
-    clone, munmap_and_exit_thread, set_thread_pointer, thread_pointer, STACK_ALIGNMENT, TLS_OFFSET,
-};
+#[cfg(not(feature = "keep-main-thread-pointer"))]
+use crate::arch::set_thread_pointer;
+use crate::arch::{clone, munmap_and_exit_thread, thread_pointer, STACK_ALIGNMENT, TLS_OFFSET};
+use crate::program::_DYNAMIC;
 #[cfg(not(feature = "nightly"))]
 use crate::ptr::Polyfill as _;
 #[cfg(feature = "thread-at-exit")]
 use alloc::boxed::Box;
-#[cfg(feature = "unstable-errno")]
 use core::cell::Cell;
 use core::cmp::max;
 use core::ffi::c_void;
 use core::mem::{align_of, offset_of, size_of};
-use core::ptr::{copy_nonoverlapping, drop_in_place, null, null_mut, NonNull};
+use core::ptr::{copy_nonoverlapping, drop_in_place, null, null_mut, write_bytes, NonNull};
 use core::slice;
 use core::sync::atomic::Ordering::SeqCst;
-use core::sync::atomic::{AtomicI32, AtomicPtr, AtomicU32, AtomicU8};
+use core::sync::atomic::{AtomicI32, AtomicPtr, AtomicU32, AtomicU8, AtomicUsize};
 use linux_raw_sys::elf::*;
 use rustix::io;
 use rustix::mm::{mmap_anonymous, mprotect, MapFlags, MprotectFlags, ProtFlags};
-use rustix::param::{linux_execfn, page_size};
+use rustix::param::page_size;
 use rustix::process::{getrlimit, Resource};
 use rustix::runtime::{exe_phdrs, set_tid_address};
-#[cfg(feature = "signal")]
 use rustix::runtime::{sigprocmask, How, Sigset};
 use rustix::thread::gettid;
 
+mod arc;
+#[cfg(feature = "thread-at-exit")]
+mod exit_hooks;
+mod numa;
+#[cfg(feature = "alloc")]
+mod spawn;
+
+pub use arc::ArcThread;
+#[cfg(feature = "thread-at-exit")]
+pub(crate) use exit_hooks::call_global_exit_hooks;
+#[cfg(feature = "thread-at-exit")]
+pub use exit_hooks::register_global_exit_hook;
 pub use rustix::thread::Pid as ThreadId;
+#[cfg(feature = "alloc")]
+pub use spawn::{spawn_unchecked, JoinHandle};
+
+/// The type of a thread function passed to [`create`], which takes the
+/// thread's arguments and returns its return value.
+type ThreadFn = unsafe fn(&mut [Option<NonNull<c_void>>]) -> Option<NonNull<c_void>>;
 
 /// An opaque pointer to a thread.
 ///
 /// This type does not detach or free resources on drop. It just leaks the
-/// thread. To detach or join, call [`detach`] or [`join`] explicitly.
+/// thread. To detach or join, call [`detach`] or [`join`] explicitly, or use
+/// [`ArcThread`] to have this done automatically when the last handle is
+/// dropped.
 #[derive(Copy, Clone, Eq, PartialEq)]
 pub struct Thread(NonNull<ThreadData>);
 
@@ -96,11 +115,25 @@ struct ThreadData {
     #[cfg(feature = "unstable-errno")]
     errno_val: Cell<i32>,
     detached: AtomicU8,
+    handles: AtomicUsize,
     stack_addr: *mut c_void,
     stack_size: usize,
     guard_size: usize,
     map_size: usize,
+    tls_mem_size: usize,
+    tls_align: usize,
+    tls_base: *mut c_void,
     return_value: AtomicPtr<c_void>,
+    /// The value passed to `set_return_value`, if it's been called. This is
+    /// only accessed by the thread itself.
+    set_return_value: Cell<Option<Option<NonNull<c_void>>>>,
+    clear_addr: *mut u32,
+    /// Whether this is a VM worker outside our thread group.
+    vm_worker: bool,
+    /// For a VM worker outside our thread group, its id, for `join` to reap
+    /// it with; otherwise 0.
+    worker_id: AtomicI32,
+    stack_measured: bool,
 
     // Support a few dtors before using dynamic allocation.
     #[cfg(feature = "thread-at-exit")]
@@ -114,17 +147,32 @@ const ABANDONED: u8 = 2;
 
 impl ThreadData {
     #[inline]
-    fn new(stack_addr: *mut c_void, stack_size: usize, guard_size: usize, map_size: usize) -> Self {
+    fn new(
+        stack_addr: *mut c_void,
+        stack_size: usize,
+        guard_size: usize,
+        map_size: usize,
+        tls: &TlsTemplate<'_>,
+    ) -> Self {
         Self {
             thread_id: AtomicI32::new(0),
             #[cfg(feature = "unstable-errno")]
             errno_val: Cell::new(0),
             detached: AtomicU8::new(INITIAL),
+            handles: AtomicUsize::new(0),
             stack_addr,
             stack_size,
             guard_size,
             map_size,
+            tls_mem_size: tls.mem_size,
+            tls_align: tls.align,
+            tls_base: null_mut(),
             return_value: AtomicPtr::new(null_mut()),
+            set_return_value: Cell::new(None),
+            clear_addr: null_mut(),
+            vm_worker: false,
+            worker_id: AtomicI32::new(0),
+            stack_measured: false,
             #[cfg(feature = "thread-at-exit")]
             dtors: smallvec::SmallVec::new(),
         }
@@ -196,9 +244,18 @@ struct Abi {
     dtv: *const c_void,
 
     /// Padding to put the `canary` field at its well-known offset.
-    #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+    #[cfg(any(
+        target_arch = "x86",
+        all(target_arch = "x86_64", target_pointer_width = "64")
+    ))]
     _pad: [usize; 3],
 
+    /// Padding to put the `canary` field at its well-known offset, which on
+    /// x32 is the same as on x86-64 except that the preceding fields are
+    /// smaller.
+    #[cfg(all(target_arch = "x86_64", target_pointer_width = "32"))]
+    _pad: [usize; 4],
+
     /// The ABI-exposed `canary` field.
     #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
     canary: usize,
@@ -207,12 +264,13 @@ struct Abi {
 /// Information obtained from the `DT_TLS` segment of the executable.
 ///
 /// This variable must be initialized with [`initialize_startup_info`] before
-/// use.
+/// use. Until then, and if there's no `PT_TLS` segment, it describes an empty
+/// TLS.
 static mut STARTUP_TLS_INFO: StartupTlsInfo = StartupTlsInfo {
-    addr: null(),
+    addr: NonNull::dangling().as_ptr(),
     mem_size: 0,
     file_size: 0,
-    align: 0,
+    align: 1,
 };
 
 /// The type of [`STARTUP_TLS_INFO`].
@@ -235,14 +293,113 @@ struct StartupTlsInfo {
 /// The requested minimum size for stacks.
 static mut STARTUP_STACK_SIZE: usize = 0;
 
+/// A description of the initial contents of a thread's TLS data.
+///
+/// By default, new threads' TLS data is initialized from the executable's
+/// `PT_TLS` segment, which is described by [`TlsTemplate::startup`].
+/// Runtimes which load additional modules with TLS of their own can compose
+/// a larger template and use [`create_with_tls_template`] to create threads
+/// with it.
+#[cfg_attr(docsrs, doc(cfg(feature = "take-charge")))]
+#[derive(Debug, Clone, Copy)]
+pub struct TlsTemplate<'a> {
+    data: &'a [u8],
+    mem_size: usize,
+    align: usize,
+}
+
+impl<'a> TlsTemplate<'a> {
+    /// Create a new `TlsTemplate`.
+    ///
+    /// The TLS data is `mem_size` bytes, aligned to `align`. The first
+    /// `data.len()` bytes are initialized from `data`, and the rest are
+    /// zero-initialized.
+    ///
+    /// This fails with [`io::Errno::INVAL`] if `align` isn't a power of two,
+    /// or if `data` is longer than `mem_size`.
+    pub fn new(data: &'a [u8], mem_size: usize, align: usize) -> io::Result<Self> {
+        if !align.is_power_of_two() || data.len() > mem_size {
+            return Err(io::Errno::INVAL);
+        }
+        Ok(Self {
+            data,
+            mem_size,
+            align,
+        })
+    }
+
+    /// Return the template for the executable's own TLS data, from its
+    /// `PT_TLS` segment.
+    #[must_use]
+    pub fn startup() -> TlsTemplate<'static> {
+        // SAFETY: `STARTUP_TLS_INFO` is initialized at program startup, and
+        // its `addr` is always non-null and points to `file_size` bytes of
+        // initializer data.
+        unsafe {
+            TlsTemplate {
+                data: slice::from_raw_parts(
+                    STARTUP_TLS_INFO.addr.cast::<u8>(),
+                    STARTUP_TLS_INFO.file_size,
+                ),
+                mem_size: STARTUP_TLS_INFO.mem_size,
+                align: STARTUP_TLS_INFO.align,
+            }
+        }
+    }
+
+    /// Return the initializer data.
+    #[inline]
+    #[must_use]
+    pub fn data(&self) -> &'a [u8] {
+        self.data
+    }
+
+    /// Return the size of the TLS data, including the zero-initialized part.
+    #[inline]
+    #[must_use]
+    pub fn mem_size(&self) -> usize {
+        self.mem_size
+    }
+
+    /// Return the required alignment of the TLS data.
+    #[inline]
+    #[must_use]
+    pub fn align(&self) -> usize {
+        self.align
+    }
+}
+
+/// The main thread's initial stack pointer, for finding its committed stack
+/// in [`stack_committed_range`].
+static mut MAIN_STACK_POINTER: *mut c_void = null_mut();
+
+/// The stack size limit to assume for the main thread if `RLIMIT_STACK` is
+/// unlimited. This is Linux's default limit.
+const DEFAULT_MAIN_STACK_LIMIT: usize = 8 * 1024 * 1024;
+
+/// With "keep-main-thread-pointer", the main thread's thread pointer, as
+/// libc set it up.
+#[cfg(feature = "keep-main-thread-pointer")]
+static mut MAIN_THREAD_POINTER: *mut c_void = null_mut();
+
+/// With "keep-main-thread-pointer", the main thread's [`Metadata`], which we
+/// can't find from the thread pointer.
+#[cfg(feature = "keep-main-thread-pointer")]
+static mut MAIN_METADATA: *mut Metadata = null_mut();
+
 /// Initialize `STARTUP_TLS_INFO` and `STARTUP_STACK_SIZE`.
 ///
 /// Read values from the main executable segment headers (“phdrs”) relevant
 /// to initializing TLS provided to the program at startup, and store them in
 /// `STARTUP_TLS_INFO`.
+///
+/// With "no-tls", this only looks for the requested stack size, and leaves
+/// `STARTUP_TLS_INFO` describing an empty TLS.
 pub(super) fn initialize_startup_info() {
+    #[cfg(not(feature = "no-tls"))]
     let mut tls_phdr = null();
     let mut stack_size = 0;
+    #[cfg(not(feature = "no-tls"))]
     let mut offset = 0;
 
     let (first_phdr, phent, phnum) = exe_phdrs();
@@ -253,6 +410,7 @@ pub(super) fn initialize_startup_info() {
     //
     // SAFETY: We're just taking the address of `_DYNAMIC` for arithmetic
     // purposes, not dereferencing it.
+    #[cfg(not(feature = "no-tls"))]
     let dynamic_addr: *const c_void = unsafe { &_DYNAMIC };
 
     // SAFETY: We assume that the phdr array pointer and length the kernel
@@ -270,48 +428,41 @@ pub(super) fn initialize_startup_info() {
                 // `p_vaddr` fields to the dynamic addresses. We don't always
                 // get a `PT_PHDR` or `PT_DYNAMIC` header, so use whichever one
                 // we get.
+                #[cfg(not(feature = "no-tls"))]
                 PT_PHDR => offset = first_phdr.addr().wrapping_sub(phdr.p_vaddr),
+                #[cfg(not(feature = "no-tls"))]
                 PT_DYNAMIC => offset = dynamic_addr.addr().wrapping_sub(phdr.p_vaddr),
 
+                #[cfg(not(feature = "no-tls"))]
                 PT_TLS => tls_phdr = phdr,
+                #[cfg(feature = "no-tls")]
+                PT_TLS => debug_assert_eq!(
+                    phdr.p_memsz, 0,
+                    "\"no-tls\" is enabled, but the executable has TLS data"
+                ),
                 PT_GNU_STACK => stack_size = phdr.p_memsz,
 
                 _ => {}
             }
         }
 
-        STARTUP_TLS_INFO = if tls_phdr.is_null() {
-            // No `PT_TLS` section. Assume an empty TLS.
-            StartupTlsInfo {
-                addr: NonNull::dangling().as_ptr(),
-                mem_size: 0,
-                file_size: 0,
-                align: 1,
-            }
-        } else {
-            // We saw a `PT_TLS` section. Initialize the fields.
+        // If we saw a `PT_TLS` section, initialize the fields. Otherwise,
+        // leave `STARTUP_TLS_INFO` describing an empty TLS.
+        #[cfg(not(feature = "no-tls"))]
+        if !tls_phdr.is_null() {
             let tls_phdr = &*tls_phdr;
-            StartupTlsInfo {
+            STARTUP_TLS_INFO = StartupTlsInfo {
                 addr: first_phdr.with_addr(offset.wrapping_add(tls_phdr.p_vaddr)),
                 mem_size: tls_phdr.p_memsz,
                 file_size: tls_phdr.p_filesz,
                 align: tls_phdr.p_align,
-            }
-        };
+            };
+        }
 
         STARTUP_STACK_SIZE = stack_size;
     }
 }
 
-extern "C" {
-    /// Declare the `_DYNAMIC` symbol so that we can compare its address with
-    /// the static address in the `PT_DYNAMIC` header to learn our offset. Use
-    /// a weak symbol because `_DYNAMIC` is not always present.
-    static _DYNAMIC: c_void;
-}
-// Rust has `extern_weak` but it isn't stable, so use a `global_asm`.
-core::arch::global_asm!(".weak _DYNAMIC");
-
 /// Initialize the main thread.
 ///
 /// This function is similar to `create_thread` except that the OS thread is
@@ -320,50 +471,57 @@ core::arch::global_asm!(".weak _DYNAMIC");
 /// the TLS initializers, and point the thread pointer to it so that it follows
 /// the thread ABI that all the other threads follow.
 ///
+/// With "keep-main-thread-pointer", we leave the thread pointer that libc set
+/// up in place, so that libc's TLS keeps working, and record where the
+/// metadata is instead.
+///
 /// # Safety
 ///
 /// `initialize_startup_info` must be called before this. And `mem` must be the
 /// initial value of the stack pointer in a new process, pointing to the
 /// initial contents of the stack.
 pub(super) unsafe fn initialize_main(mem: *mut c_void) {
-    // Determine the top of the stack. Linux puts the `AT_EXECFN` string at
-    // the top, so find the end of that, and then round up to the page size.
-    // See <https://lwn.net/Articles/631631/> for details.
-    let execfn = linux_execfn().to_bytes_with_nul();
-    let stack_base = execfn.as_ptr().add(execfn.len());
-    let stack_base = stack_base
-        .map_addr(|ptr| round_up(ptr, page_size()))
-        .cast_mut();
+    // Determine the top of the stack.
+    MAIN_STACK_POINTER = mem;
+    let stack_base = main_stack_base();
 
     // We're running before any user code, so the startup soft stack limit is
     // the effective stack size. Linux sets up inaccessible memory at the end
     // of the stack.
-    let stack_map_size = getrlimit(Resource::Stack).current.unwrap() as usize;
-    let stack_least = stack_base.sub(stack_map_size);
-    let stack_size = stack_least.offset_from(mem.cast::<u8>()) as usize;
+    let stack_least = stack_base.sub(startup_stack_limit());
+    let stack_size = mem.cast::<u8>().offset_from(stack_least) as usize;
     let guard_size = page_size();
 
     // Initialize the canary value from the OS-provided random bytes.
     let random_ptr = rustix::runtime::random().cast::<usize>();
     let canary = random_ptr.read_unaligned();
-    __stack_chk_guard = canary;
+
+    // With "keep-main-thread-pointer", libc has already initialized the
+    // canary, and functions on the stack may have saved its value.
+    #[cfg(not(feature = "keep-main-thread-pointer"))]
+    {
+        __stack_chk_guard = canary;
+    }
+
+    let tls = TlsTemplate::startup();
 
     let mut alloc_size = 0;
-    let (tls_data_bottom, header) = calculate_tls_size(&mut alloc_size);
+    let (tls_data_bottom, header) = calculate_tls_size(&mut alloc_size, tls.mem_size, tls.align);
+
+    let metadata_align = max(tls.align, align_of::<Metadata>());
 
     // Allocate the thread data. Use `mmap_anonymous` rather than `alloc` here
     // as the allocator may depend on thread-local data, which is what we're
     // initializing here.
-    let new = mmap_anonymous(
-        null_mut(),
+    let new = match mmap_aligned(
         alloc_size,
+        metadata_align,
         ProtFlags::READ | ProtFlags::WRITE,
         MapFlags::PRIVATE,
-    )
-    .unwrap()
-    .cast::<u8>();
-
-    let metadata_align = max(unsafe { STARTUP_TLS_INFO.align }, align_of::<Metadata>());
+    ) {
+        Ok(new) => new,
+        Err(_) => crate::program::abort_startup("unable to allocate the main thread's TLS"),
+    };
     debug_assert_eq!(new.addr() % metadata_align, 0);
 
     let tls_data = new.add(tls_data_bottom);
@@ -372,31 +530,79 @@ pub(super) unsafe fn initialize_main(mem: *mut c_void) {
     let (newtls, thread_id_ptr) = initialize_tls(
         tls_data,
         metadata,
+        &tls,
         canary,
-        stack_least,
-        stack_size,
-        guard_size,
-        0,
+        ThreadData::new(stack_least.cast(), stack_size, guard_size, 0, &tls),
     );
-    let tid = rustix::runtime::set_tid_address(thread_id_ptr.cast());
-    *thread_id_ptr = tid.as_raw_nonzero().get();
 
-    // Point the platform thread-pointer register at the new thread metadata.
-    set_thread_pointer(newtls);
+    #[cfg(not(feature = "keep-main-thread-pointer"))]
+    {
+        let tid = rustix::runtime::set_tid_address(thread_id_ptr.cast());
+        *thread_id_ptr = tid.as_raw_nonzero().get();
+
+        // Point the platform thread-pointer register at the new thread
+        // metadata.
+        set_thread_pointer(newtls);
+    }
+
+    // Leave libc's thread pointer and tid address in place, and remember
+    // where our metadata is.
+    #[cfg(feature = "keep-main-thread-pointer")]
+    {
+        let _ = newtls;
+        *thread_id_ptr = gettid().as_raw_nonzero().get();
+        MAIN_THREAD_POINTER = thread_pointer();
+        MAIN_METADATA = metadata;
+    }
+
+    #[cfg(feature = "thread-stats")]
+    {
+        let live = LIVE_THREADS.fetch_add(1, SeqCst) + 1;
+        PEAK_THREADS.fetch_max(live, SeqCst);
+        TOTAL_THREADS.fetch_add(1, SeqCst);
+    }
 }
 
-fn calculate_tls_size(map_size: &mut usize) -> (usize, usize) {
-    // SAFETY: `STARTUP_TLS_INFO` is initialized at program startup before
-    // we come here creating new threads.
-    let (startup_tls_align, startup_tls_mem_size) =
-        unsafe { (STARTUP_TLS_INFO.align, STARTUP_TLS_INFO.mem_size) };
+/// Return the main thread's stack size limit at startup.
+///
+/// This makes the `prlimit64` system call directly, rather than using
+/// [`getrlimit`], so that it can fall back to [`DEFAULT_MAIN_STACK_LIMIT`] if
+/// a sandbox doesn't allow it, as well as if the limit is unlimited.
+unsafe fn startup_stack_limit() -> usize {
+    use linux_raw_sys::general::{__NR_prlimit64, rlimit64, RLIM64_INFINITY, RLIMIT_STACK};
+
+    let mut limit = rlimit64 {
+        rlim_cur: 0,
+        rlim_max: 0,
+    };
+    let r0 = crate::arch::syscall4(
+        __NR_prlimit64,
+        0,
+        RLIMIT_STACK as usize,
+        0,
+        core::ptr::addr_of_mut!(limit) as usize,
+    );
+    if r0 < 0 || limit.rlim_cur == RLIM64_INFINITY as u64 {
+        DEFAULT_MAIN_STACK_LIMIT
+    } else {
+        limit.rlim_cur as usize
+    }
+}
 
-    // Compute relevant alignments.
-    let tls_data_align = startup_tls_align;
-    let page_align = page_size();
+/// Add space for TLS data with the given size and alignment, and the thread
+/// metadata, to `map_size`, and return the offsets of the TLS data and the
+/// metadata.
+fn calculate_tls_size(
+    map_size: &mut usize,
+    tls_mem_size: usize,
+    tls_align: usize,
+) -> (usize, usize) {
+    // Compute relevant alignments. The metadata alignment may be greater
+    // than the page size, in which case the memory is allocated with
+    // `mmap_aligned`.
+    let tls_data_align = tls_align;
     let header_align = align_of::<Metadata>();
     let metadata_align = max(tls_data_align, header_align);
-    debug_assert!(metadata_align <= page_align);
 
     *map_size = round_up(*map_size, metadata_align);
 
@@ -406,7 +612,7 @@ fn calculate_tls_size(map_size: &mut usize) -> (usize, usize) {
 
     #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
     {
-        *map_size += round_up(startup_tls_mem_size, tls_data_align);
+        *map_size += round_up(tls_mem_size, tls_data_align);
     }
 
     let header = *map_size;
@@ -424,22 +630,59 @@ fn calculate_tls_size(map_size: &mut usize) -> (usize, usize) {
 
     #[cfg(any(target_arch = "aarch64", target_arch = "arm", target_arch = "riscv64"))]
     {
-        *map_size += round_up(startup_tls_mem_size, tls_data_align);
+        *map_size += round_up(tls_mem_size, tls_data_align);
     }
     (tls_data_bottom, header)
 }
 
+/// Allocate `size` bytes of memory with `mmap_anonymous`, aligned to `align`.
+///
+/// `mmap` only guarantees page alignment, so if `align` is greater than the
+/// page size, this over-allocates and then unmaps the excess on either side,
+/// leaving a mapping which starts at the returned address, so that it can be
+/// freed with a single `munmap` of `size` bytes.
+unsafe fn mmap_aligned(
+    size: usize,
+    align: usize,
+    prot: ProtFlags,
+    flags: MapFlags,
+) -> io::Result<*mut u8> {
+    use rustix::mm::munmap;
+
+    let page_align = page_size();
+    if align <= page_align {
+        return Ok(mmap_anonymous(null_mut(), size, prot, flags)?.cast());
+    }
+
+    let size = round_up(size, page_align);
+    let extra = align - page_align;
+    let map = mmap_anonymous(null_mut(), size + extra, prot, flags)?.cast::<u8>();
+
+    let aligned = map.map_addr(|addr| round_up(addr, align));
+    let front = aligned.offset_from(map) as usize;
+    let back = extra - front;
+    if front != 0 {
+        munmap(map.cast(), front).unwrap();
+    }
+    if back != 0 {
+        munmap(aligned.add(size).cast(), back).unwrap();
+    }
+
+    Ok(aligned)
+}
+
 unsafe fn initialize_tls(
     tls_data: *mut u8,
     metadata: *mut Metadata,
+    tls: &TlsTemplate<'_>,
     canary: usize,
-    stack_least: *mut u8,
-    stack_size: usize,
-    guard_size: usize,
-    map_size: usize,
+    mut thread: ThreadData,
 ) -> (*mut c_void, *mut i32) {
     let newtls: *mut c_void = (*metadata).abi.thread_pointee.as_mut_ptr().cast();
 
+    // Remember where the TLS data is, for `tls_addr_of`.
+    thread.tls_base = tls_data.cast();
+
     // Initialize the thread metadata.
     metadata.write(Metadata {
         abi: Abi {
@@ -450,23 +693,18 @@ unsafe fn initialize_tls(
             _pad: Default::default(),
             thread_pointee: [],
         },
-        thread: ThreadData::new(stack_least.cast(), stack_size, guard_size, map_size),
+        thread,
     });
 
-    // Initialize the TLS data with explicit initializer data.
-    slice::from_raw_parts_mut(tls_data, STARTUP_TLS_INFO.file_size).copy_from_slice(
-        slice::from_raw_parts(
-            STARTUP_TLS_INFO.addr.cast::<u8>(),
-            STARTUP_TLS_INFO.file_size,
-        ),
-    );
+    // Initialize the TLS data with explicit initializer data. This and the
+    // `fill` below may be lowered to calls to `memcpy` and `memset`, which is
+    // fine because we're only called from `initialize_main`, which
+    // `init_runtime` calls after any relocations have been performed, and
+    // from `create`, which can only be called after that.
+    slice::from_raw_parts_mut(tls_data, tls.data.len()).copy_from_slice(tls.data);
 
     // Initialize the TLS data beyond `file_size` which is zero-filled.
-    slice::from_raw_parts_mut(
-        tls_data.add(STARTUP_TLS_INFO.file_size),
-        STARTUP_TLS_INFO.mem_size - STARTUP_TLS_INFO.file_size,
-    )
-    .fill(0);
+    slice::from_raw_parts_mut(tls_data.add(tls.data.len()), tls.mem_size - tls.data.len()).fill(0);
 
     let thread_id_ptr = (*metadata).thread.thread_id.as_ptr().cast::<i32>();
 
@@ -483,12 +721,452 @@ unsafe fn initialize_tls(
 /// The values of `args` must be valid to send to the new thread, `fn_(args)`
 /// on the new thread must have defined behavior, and the return value must be
 /// valid to send to other threads.
+///
+/// If the limit on the number of processes and threads the user may have,
+/// `RLIMIT_NPROC`, has been reached, this fails with [`io::Errno::AGAIN`],
+/// and it may succeed if retried after other threads have exited. With the
+/// "thread-stats" feature, [`can_spawn`] can be used to check for this ahead
+/// of time.
+///
+/// If `stack_size` is less than [`MIN_STACK_SIZE`], this fails with
+/// [`io::Errno::INVAL`]. The thread's TLS data and origin's record for the
+/// thread are allocated alongside the stack, so `stack_size` doesn't need to
+/// include space for them.
+///
+/// With the "single-threaded" feature, this fails with
+/// [`io::Errno::NOTSUP`].
+///
+/// In debug builds with the "signal" feature, this traps with a message if
+/// it's called from a signal handler installed with `signal::set_handler`,
+/// since creating a thread isn't async-signal-safe.
+#[cfg_attr(all(debug_assertions, feature = "signal"), track_caller)]
 pub unsafe fn create(
-    fn_: unsafe fn(&mut [Option<NonNull<c_void>>]) -> Option<NonNull<c_void>>,
+    fn_: ThreadFn,
+    args: &[Option<NonNull<c_void>>],
+    stack_size: usize,
+    guard_size: usize,
+) -> io::Result<Thread> {
+    create_with_clear_tid(null_mut(), fn_, args, stack_size, guard_size)
+}
+
+/// Creates a new thread, and arranges for `clear_addr` to be cleared and
+/// woken when it exits.
+///
+/// This is like [`create`], but when the new thread exits, after any
+/// functions registered with [`at_exit`] have been called, the `u32` at
+/// `clear_addr` is set to zero and a futex wake is performed on it. This
+/// lets runtimes build their own join logic on a futex word they own.
+///
+/// Linux only supports one `CLONE_CHILD_CLEARTID` address per thread, and
+/// origin uses it for its own [`join`], so `clear_addr` is cleared by the
+/// thread itself just before it exits rather than by the kernel. It therefore
+/// indicates that the thread is finished running user code, but not that it
+/// has fully exited; the thread must still be joined or detached to free its
+/// resources. The caller should initialize the word to a non-zero value
+/// before calling this, so that waiters can distinguish it from the cleared
+/// state.
+///
+/// If `clear_addr` is null, this behaves exactly like [`create`].
+///
+/// # Safety
+///
+/// In addition to the requirements of [`create`], `clear_addr` must either be
+/// null or point to a `u32` which is valid for atomic access until the thread
+/// exits.
+#[cfg_attr(docsrs, doc(cfg(feature = "take-charge")))]
+#[cfg_attr(all(debug_assertions, feature = "signal"), track_caller)]
+pub unsafe fn create_with_clear_tid(
+    clear_addr: *mut u32,
+    fn_: ThreadFn,
+    args: &[Option<NonNull<c_void>>],
+    stack_size: usize,
+    guard_size: usize,
+) -> io::Result<Thread> {
+    let options = Builder {
+        stack_size,
+        guard_size,
+        block_all_signals: false,
+        share_io: false,
+        measure_stack_usage: false,
+        worker_flags: VmWorkerFlags::all(),
+        numa_node: None,
+    };
+    create_impl(clear_addr, fn_, args, &options, &TlsTemplate::startup())
+}
+
+/// Creates a new thread with TLS data initialized from `template`, instead of
+/// from the executable's `PT_TLS` segment.
+///
+/// This is like [`create`], but the new thread's TLS data is laid out and
+/// initialized according to `template`. The template's data is copied, so it
+/// only needs to live for the duration of this call.
+///
+/// # Safety
+///
+/// In addition to the requirements of [`create`], code running on the new
+/// thread which accesses thread-local variables, which includes the
+/// executable's own and may include origin's and the global allocator's,
+/// requires `template` to contain the executable's TLS data at the offsets
+/// the executable expects. On targets where TLS data goes after the thread
+/// pointer (aarch64, arm, and riscv64), that's at the start of the template,
+/// and on targets where it goes before it (x86 and x86_64), that's at the
+/// end, ending at `mem_size` rounded up to `align`. [`TlsTemplate::startup`]
+/// describes the executable's TLS data.
+#[cfg_attr(docsrs, doc(cfg(feature = "take-charge")))]
+#[cfg_attr(all(debug_assertions, feature = "signal"), track_caller)]
+pub unsafe fn create_with_tls_template(
+    template: &TlsTemplate<'_>,
+    fn_: ThreadFn,
+    args: &[Option<NonNull<c_void>>],
+    stack_size: usize,
+    guard_size: usize,
+) -> io::Result<Thread> {
+    let options = Builder {
+        stack_size,
+        guard_size,
+        block_all_signals: false,
+        share_io: false,
+        measure_stack_usage: false,
+        worker_flags: VmWorkerFlags::all(),
+        numa_node: None,
+    };
+    create_impl(null_mut(), fn_, args, &options, template)
+}
+
+/// Creates a new thread, and returns a description of its memory along with
+/// it.
+///
+/// This is like [`create`], but also returns a snapshot of the new thread's
+/// stack and memory layout, taken before the caller can detach or join it,
+/// so that it doesn't depend on when the caller gets around to asking.
+///
+/// # Safety
+///
+/// The requirements of [`create`] apply.
+#[cfg_attr(docsrs, doc(cfg(feature = "take-charge")))]
+#[cfg_attr(all(debug_assertions, feature = "signal"), track_caller)]
+pub unsafe fn create_detailed(
+    fn_: ThreadFn,
+    args: &[Option<NonNull<c_void>>],
+    stack_size: usize,
+    guard_size: usize,
+) -> io::Result<(Thread, StackInfo)> {
+    let thread = create(fn_, args, stack_size, guard_size)?;
+
+    // The thread hasn't been detached or joined yet, so its record is valid
+    // even if it has already exited.
+    let data = thread.0.as_ref();
+    let guard_size = round_up(data.guard_size, page_size());
+    let info = StackInfo {
+        stack_addr: data.stack_addr,
+        stack_size: data.stack_size,
+        guard_size,
+        map_addr: data.stack_addr.byte_sub(guard_size),
+        map_size: data.map_size,
+    };
+    Ok((thread, info))
+}
+
+/// The memory layout of a thread created by [`create_detailed`].
+///
+/// The thread's memory is one `mmap` region, starting with the guard region,
+/// followed by the stack, followed by the thread's TLS data and origin's
+/// record for the thread. It's freed when the thread is joined, or when it
+/// exits if it's detached.
+#[cfg_attr(docsrs, doc(cfg(feature = "take-charge")))]
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+#[non_exhaustive]
+pub struct StackInfo {
+    /// The lowest address of the stack, just above the guard region.
+    pub stack_addr: *mut c_void,
+
+    /// The size of the stack, as requested.
+    pub stack_size: usize,
+
+    /// The size of the inaccessible guard region below the stack, rounded up
+    /// to the page size.
+    pub guard_size: usize,
+
+    /// The start of the thread's `mmap` region, which is the start of the
+    /// guard region.
+    pub map_addr: *mut c_void,
+
+    /// The size of the thread's `mmap` region.
+    pub map_size: usize,
+}
+
+/// Creates a new "VM worker", which shares the address space with the
+/// current process, but which may have its own signal handlers and its own
+/// thread group, according to `flags`.
+///
+/// **A VM worker that leaves out any of `flags` is not a thread**, and most
+/// things that assume threads don't work with it. This is for specialized
+/// runtimes, such as some garbage collectors and sandboxes, that need this
+/// specific arrangement. Origin sets up the worker's stack, TLS data, and
+/// entry as it does for [`create`], and it shares memory, file descriptors,
+/// the filesystem context, and System V semaphore adjustments with the
+/// current process, but:
+///
+///  - Without [`VmWorkerFlags::SIGHAND`], the worker gets a copy of the
+///    current signal handlers, and changes to handlers made by either side
+///    afterward aren't seen by the other. Origin's own bookkeeping for
+///    `signal::set_handler` is in shared memory, so it may be confused by
+///    handlers that differ between the two.
+///
+///  - Without [`VmWorkerFlags::THREAD`], the worker is in its own thread
+///    group, which is to say it's a separate process as far as Linux is
+///    concerned. `getpid` returns its own id, and signals sent to the
+///    creating process, including ones from the terminal, aren't delivered
+///    to it. It isn't added to origin's thread registry, so
+///    "quiesce-threads-before-exit" doesn't wait for it. Calling
+///    `program::exit` in the worker runs the program's exit functions, which
+///    live in shared memory, and then terminates only the worker's thread
+///    group; the rest of the program keeps running, with those exit functions
+///    consumed. Exiting the program elsewhere doesn't terminate the worker.
+///
+///  - Without [`VmWorkerFlags::THREAD`], once the worker exits, it stays a
+///    zombie child process until it's reaped. [`join`] reaps it, and must be
+///    called from the thread group that created it. [`detach`]ed workers
+///    that exit after being detached stay zombies until the creating process
+///    waits for them, using `waitid` with `__WALL`, or exits.
+///
+/// `VmWorkerFlags::THREAD` requires `VmWorkerFlags::SIGHAND`; this fails with
+/// [`io::Errno::INVAL`] otherwise. With all of `flags`, this creates an
+/// ordinary thread, exactly like [`create`].
+///
+/// # Safety
+///
+/// The requirements of [`create`] apply, and the worker must not rely on any
+/// of the thread semantics described above which `flags` leaves out.
+#[cfg_attr(docsrs, doc(cfg(feature = "take-charge")))]
+#[cfg_attr(all(debug_assertions, feature = "signal"), track_caller)]
+pub unsafe fn create_vm_worker(
+    flags: VmWorkerFlags,
+    fn_: ThreadFn,
     args: &[Option<NonNull<c_void>>],
     stack_size: usize,
     guard_size: usize,
 ) -> io::Result<Thread> {
+    let options = Builder {
+        stack_size,
+        guard_size,
+        block_all_signals: false,
+        share_io: false,
+        measure_stack_usage: false,
+        worker_flags: flags,
+        numa_node: None,
+    };
+    create_impl(null_mut(), fn_, args, &options, &TlsTemplate::startup())
+}
+
+bitflags::bitflags! {
+    /// Which thread semantics a worker created by [`create_vm_worker`] keeps.
+    #[cfg_attr(docsrs, doc(cfg(feature = "take-charge")))]
+    #[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+    pub struct VmWorkerFlags: u32 {
+        /// Share signal handlers with the current process, as with
+        /// `CLONE_SIGHAND`.
+        const SIGHAND = linux_raw_sys::general::CLONE_SIGHAND;
+
+        /// Join the current process' thread group, as with `CLONE_THREAD`.
+        /// This requires `SIGHAND`.
+        const THREAD = linux_raw_sys::general::CLONE_THREAD;
+    }
+}
+
+/// Options for creating a new thread.
+///
+/// This is an alternative to [`create`] for when non-default options are
+/// needed. [`Builder::new`] starts with the [`default_stack_size`] and
+/// [`default_guard_size`].
+#[derive(Debug, Clone)]
+pub struct Builder {
+    stack_size: usize,
+    guard_size: usize,
+    block_all_signals: bool,
+    share_io: bool,
+    measure_stack_usage: bool,
+    worker_flags: VmWorkerFlags,
+    numa_node: Option<u32>,
+}
+
+impl Builder {
+    /// Create a new `Builder` with default options.
+    #[inline]
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            stack_size: default_stack_size(),
+            guard_size: default_guard_size(),
+            block_all_signals: false,
+            share_io: false,
+            measure_stack_usage: false,
+            worker_flags: VmWorkerFlags::all(),
+            numa_node: None,
+        }
+    }
+
+    /// Set the size of the new thread's stack.
+    ///
+    /// Creating the thread fails if this is less than [`MIN_STACK_SIZE`].
+    #[inline]
+    #[must_use]
+    pub fn stack_size(mut self, stack_size: usize) -> Self {
+        self.stack_size = stack_size;
+        self
+    }
+
+    /// Set the size of the new thread's stack guard region.
+    #[inline]
+    #[must_use]
+    pub fn guard_size(mut self, guard_size: usize) -> Self {
+        self.guard_size = guard_size;
+        self
+    }
+
+    /// Set whether the new thread starts with all signals blocked.
+    ///
+    /// By default, a new thread inherits the signal mask of the thread that
+    /// creates it. With this set, it instead starts with all signals blocked,
+    /// which is useful for helper threads that should never run signal
+    /// handlers. The thread can unblock signals itself once it's ready.
+    ///
+    /// The mask is applied before the new thread runs any code, so there's no
+    /// window in which a signal can be delivered to it. `SIGKILL` and
+    /// `SIGSTOP` can't be blocked.
+    #[inline]
+    #[must_use]
+    pub fn block_all_signals(mut self, block_all_signals: bool) -> Self {
+        self.block_all_signals = block_all_signals;
+        self
+    }
+
+    /// Set whether the new thread shares the I/O context of the thread that
+    /// creates it, using `CLONE_IO`.
+    ///
+    /// Linux's I/O schedulers track the I/O context of each thread, and by
+    /// default each thread has its own, so the block layer sees I/O from
+    /// different threads as independent streams. Threads which cooperate on
+    /// the same I/O, such as a pool of threads issuing reads from the same
+    /// file, can share an I/O context, so that the scheduler treats them as a
+    /// single stream. This can improve throughput with schedulers like BFQ,
+    /// which idle briefly waiting for a stream's next request, but it also
+    /// means that the threads share a single I/O priority and time slice.
+    ///
+    /// The new thread is otherwise an ordinary thread; this doesn't change
+    /// any of the other resources it shares with the process.
+    #[cfg_attr(docsrs, doc(cfg(feature = "take-charge")))]
+    #[inline]
+    #[must_use]
+    pub fn share_io(mut self, share_io: bool) -> Self {
+        self.share_io = share_io;
+        self
+    }
+
+    /// Set whether to fill the new thread's stack with a sentinel pattern,
+    /// so that [`peak_stack_usage`] can measure how much of it the thread
+    /// has used.
+    ///
+    /// This is for tuning stack sizes. Filling the stack writes to every page
+    /// of it, so it costs time proportional to the stack size when creating
+    /// the thread, and the whole stack is committed to memory up front,
+    /// rather than as the thread uses it.
+    #[cfg_attr(docsrs, doc(cfg(feature = "take-charge")))]
+    #[inline]
+    #[must_use]
+    pub fn measure_stack_usage(mut self, measure_stack_usage: bool) -> Self {
+        self.measure_stack_usage = measure_stack_usage;
+        self
+    }
+
+    /// Place the new thread on NUMA node `node`.
+    ///
+    /// The thread's memory, including its stack, TLS data, and origin's
+    /// record for it, is bound to the node with `mbind`, so that it's
+    /// allocated from the node's memory as the thread touches it. The
+    /// thread also starts with its CPU affinity restricted to the CPUs on the
+    /// node that the creating thread is allowed to run on, which it can
+    /// change afterward. If none of them are allowed, or the node's CPUs
+    /// can't be read from sysfs, the thread inherits the creating thread's
+    /// affinity, and only its memory is placed.
+    ///
+    /// On systems with a single node, and when Linux is built without NUMA
+    /// support, this has no effect. Otherwise, creating the thread fails with
+    /// [`io::Errno::INVAL`] if `node` isn't a node on the system, and with
+    /// [`io::Errno::PERM`] if binding memory isn't permitted, as in some
+    /// containers.
+    #[cfg_attr(docsrs, doc(cfg(feature = "take-charge")))]
+    #[inline]
+    #[must_use]
+    pub fn numa_node(mut self, node: u32) -> Self {
+        self.numa_node = Some(node);
+        self
+    }
+
+    /// Creates a new thread with these options.
+    ///
+    /// This is like [`create`], with the options from this `Builder`.
+    ///
+    /// # Safety
+    ///
+    /// The requirements of [`create`] apply.
+    #[cfg_attr(all(debug_assertions, feature = "signal"), track_caller)]
+    pub unsafe fn create(
+        self,
+        fn_: ThreadFn,
+        args: &[Option<NonNull<c_void>>],
+    ) -> io::Result<Thread> {
+        create_impl(null_mut(), fn_, args, &self, &TlsTemplate::startup())
+    }
+}
+
+impl Default for Builder {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// The implementation of [`create_with_clear_tid`],
+/// [`create_with_tls_template`], [`create_vm_worker`], and
+/// [`Builder::create`].
+///
+/// The new thread is created with the options in `options`, and its TLS data
+/// is initialized from `tls`.
+#[cfg_attr(all(debug_assertions, feature = "signal"), track_caller)]
+unsafe fn create_impl(
+    clear_addr: *mut u32,
+    fn_: ThreadFn,
+    args: &[Option<NonNull<c_void>>],
+    options: &Builder,
+    tls: &TlsTemplate<'_>,
+) -> io::Result<Thread> {
+    // Creating a thread allocates and takes locks, so it isn't
+    // async-signal-safe.
+    #[cfg(feature = "signal")]
+    crate::signal::assert_not_in_handler();
+
+    let Builder {
+        stack_size,
+        guard_size,
+        block_all_signals,
+        share_io,
+        measure_stack_usage,
+        worker_flags,
+        numa_node,
+    } = *options;
+
+    // "single-threaded" builds don't synchronize origin's global state, so
+    // they don't support creating threads.
+    if cfg!(feature = "single-threaded") {
+        return Err(io::Errno::NOTSUP);
+    }
+
+    // Reject stacks too small to run anything, like `pthread_create` does.
+    if stack_size < MIN_STACK_SIZE {
+        return Err(io::Errno::INVAL);
+    }
+
     // Compute relevant alignments.
     let page_align = page_size();
     let stack_align = 16;
@@ -504,26 +1182,40 @@ pub unsafe fn create(
 
     let stack_top = map_size;
 
-    let (tls_data_bottom, header) = calculate_tls_size(&mut map_size);
+    let (tls_data_bottom, header) = calculate_tls_size(&mut map_size, tls.mem_size, tls.align);
+
+    // The offsets computed above assume the memory is aligned for the TLS
+    // data and the metadata.
+    let metadata_align = max(tls.align, align_of::<Metadata>());
 
     // Now we'll `mmap` the memory, initialize it, and create the OS thread.
     unsafe {
-        // Allocate address space for the thread, including guard pages.
-        let map = mmap_anonymous(
-            null_mut(),
+        // Allocate memory for the thread, including guard pages. Map it all
+        // readable and writable, and then make just the guard region at the
+        // bottom inaccessible, so that there's no `mprotect` at all when
+        // there's no guard region, and otherwise it only covers the guard.
+        let map = mmap_aligned(
             map_size,
-            ProtFlags::empty(),
+            metadata_align,
+            ProtFlags::READ | ProtFlags::WRITE,
             MapFlags::PRIVATE | MapFlags::STACK,
-        )?
-        .cast::<u8>();
-
-        // Make the thread metadata and stack readable and writable, leaving
-        // the guard region inaccessible.
-        mprotect(
-            map.add(stack_bottom).cast(),
-            map_size - stack_bottom,
-            MprotectFlags::READ | MprotectFlags::WRITE,
         )?;
+        if stack_bottom != 0 {
+            if let Err(err) = mprotect(map.cast(), stack_bottom, MprotectFlags::empty()) {
+                rustix::mm::munmap(map.cast(), map_size).unwrap();
+                return Err(err);
+            }
+        }
+
+        // Bind the memory to the requested NUMA node before anything touches
+        // it, so that its pages are allocated there.
+        let numa_node = numa_node.filter(|node| !numa::is_only_node(*node));
+        if let Some(node) = numa_node {
+            if let Err(err) = numa::bind_memory(map.cast(), map_size, node) {
+                rustix::mm::munmap(map.cast(), map_size).unwrap();
+                return Err(err);
+            }
+        }
 
         // Compute specific pointers into the thread's memory.
         let stack = map.add(stack_top);
@@ -538,12 +1230,23 @@ pub unsafe fn create(
         let (newtls, thread_id_ptr) = initialize_tls(
             tls_data,
             metadata,
+            tls,
             canary,
-            stack_least,
-            stack_size,
-            guard_size,
-            map_size,
+            ThreadData::new(stack_least.cast(), stack_size, guard_size, map_size, tls),
         );
+        (*metadata).thread.clear_addr = clear_addr;
+        (*metadata).thread.vm_worker = !worker_flags.contains(VmWorkerFlags::THREAD);
+
+        // Fill the stack with the sentinel pattern before anything is stored
+        // on it, for `peak_stack_usage`.
+        if measure_stack_usage {
+            write_bytes(
+                stack_least.cast::<u8>(),
+                STACK_SENTINEL,
+                stack_top - stack_bottom,
+            );
+            (*metadata).thread.stack_measured = true;
+        }
 
         // Allocate space for the thread arguments on the child's stack.
         let stack = stack.cast::<Option<NonNull<c_void>>>().sub(args.len());
@@ -572,16 +1275,57 @@ pub unsafe fn create(
         // We receive the tid in the same memory for the parent and the child,
         // but we set both `PARENT_SETTID` and `CHILD_SETTID` to ensure that
         // the store completes before either the parent or child reads the tid.
-        let flags = CloneFlags::VM
+        let mut flags = CloneFlags::VM
             | CloneFlags::FS
             | CloneFlags::FILES
-            | CloneFlags::SIGHAND
-            | CloneFlags::THREAD
             | CloneFlags::SYSVSEM
             | CloneFlags::SETTLS
             | CloneFlags::CHILD_CLEARTID
             | CloneFlags::CHILD_SETTID
             | CloneFlags::PARENT_SETTID;
+
+        // Optional flags only add to what the new thread shares with us, so
+        // it's still a thread in our process.
+        if share_io {
+            flags |= CloneFlags::IO;
+        }
+
+        // Ordinary threads share signal handlers and the thread group. VM
+        // workers created by `create_vm_worker` may leave these out.
+        if worker_flags.contains(VmWorkerFlags::SIGHAND) {
+            flags |= CloneFlags::SIGHAND;
+        }
+        if worker_flags.contains(VmWorkerFlags::THREAD) {
+            flags |= CloneFlags::THREAD;
+        }
+        debug_assert!(flags.contains(CloneFlags::VM));
+
+        // The new thread inherits our signal mask. If it's meant to start
+        // with all signals blocked, block them here around the `clone`, rather
+        // than in the new thread, so that there's no window in which a signal
+        // could be delivered to it before it's blocked.
+        let old_mask = if block_all_signals {
+            match sigprocmask(How::SETMASK, Some(&all_signals())) {
+                Ok(old_mask) => Some(old_mask),
+                Err(err) => {
+                    drop_in_place(&mut (*metadata).thread);
+                    rustix::mm::munmap(map.cast(), map_size).unwrap();
+                    return Err(err);
+                }
+            }
+        } else {
+            None
+        };
+
+        // Similarly, the new thread inherits our CPU affinity, so restrict
+        // ours to the NUMA node's CPUs around the `clone`.
+        let old_affinity = numa_node.and_then(numa::enter_node);
+
+        // Count the new thread as live before it starts running, so that if
+        // it exits quickly, its decrement doesn't come first.
+        #[cfg(feature = "thread-stats")]
+        let live = LIVE_THREADS.fetch_add(1, SeqCst) + 1;
+
         let clone_res = clone(
             flags.bits(),
             stack.cast(),
@@ -591,10 +1335,46 @@ pub unsafe fn create(
             core::mem::transmute(fn_),
             args.len(),
         );
+
+        // Restore our own signal mask and CPU affinity.
+        if let Some(old_mask) = old_mask {
+            sigprocmask(How::SETMASK, Some(&old_mask)).ok();
+        }
+        if let Some(old_affinity) = old_affinity {
+            numa::leave_node(&old_affinity);
+        }
+        #[cfg(feature = "thread-stats")]
         if clone_res >= 0 {
-            #[cfg(feature = "log")]
-            {
-                let id = current_id();
+            PEAK_THREADS.fetch_max(live, SeqCst);
+            TOTAL_THREADS.fetch_add(1, SeqCst);
+        } else {
+            LIVE_THREADS.fetch_sub(1, SeqCst);
+        }
+
+        if clone_res >= 0 {
+            // A VM worker outside our thread group becomes a zombie child
+            // process when it exits, so remember its id so that `join` can
+            // reap it, even after the kernel has cleared `thread_id`.
+            if (*metadata).thread.vm_worker {
+                (*metadata).thread.worker_id.store(clone_res as i32, SeqCst);
+            }
+
+            // Linux stores the tid before `clone` returns, because of
+            // `PARENT_SETTID`, but some kernels and emulators may order the
+            // store more weakly. Wait for it to land, so that `id` is valid
+            // as soon as we return. If the thread has already started
+            // exiting, its tid may already have been cleared, but it will
+            // have changed its `detached` state first, so stop waiting then.
+            let thread_data = &(*metadata).thread;
+            while thread_data.thread_id.load(SeqCst) == 0
+                && thread_data.detached.load(SeqCst) == INITIAL
+            {
+                yield_current();
+            }
+
+            #[cfg(feature = "log")]
+            {
+                let id = current_id();
                 log::trace!(
                     "Thread[{:?}] launched thread Thread[{:?}] with stack_size={} and guard_size={}",
                     id.as_raw_nonzero(),
@@ -609,6 +1389,9 @@ pub unsafe fn create(
 
             Ok(Thread(NonNull::from(&mut (*metadata).thread)))
         } else {
+            // No thread was created, so free its memory.
+            drop_in_place(&mut (*metadata).thread);
+            rustix::mm::munmap(map.cast(), map_size).unwrap();
             Err(io::Errno::from_raw_os_error(-clone_res as i32))
         }
     }
@@ -669,10 +1452,32 @@ pub(super) unsafe extern "C" fn entry(
             debug_assert_eq!(builtin_sponentry().addr() & 0xf, 0);
         }
 
+        // Check that the thread pointer leads to the metadata our parent set
+        // up for us, by checking that it describes the stack our arguments
+        // were stored on, which `clone` passed to us independently of the
+        // thread pointer. This catches `CLONE_SETTLS` not taking effect, or
+        // an architecture's thread pointer pointing somewhere other than
+        // where `initialize_tls` expects.
+        let data = current().0.as_ref();
+        let stack_top = data.stack_addr.addr() + round_up(data.stack_size, 16);
+        debug_assert!(
+            data.stack_addr.addr() <= args.addr() && args.addr() <= stack_top,
+            "the thread pointer doesn't point to the new thread's metadata"
+        );
+        #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+        debug_assert_eq!((*current_metadata()).abi.this, thread_pointer());
+
         // Check that `clone` stored our thread id as we expected.
         debug_assert_eq!(current_id(), gettid());
     }
 
+    // Add ourselves to the registry of threads. VM workers outside our thread
+    // group can't be signaled like threads, so they aren't registered.
+    #[cfg(feature = "thread-registry")]
+    if !current().0.as_ref().vm_worker {
+        THREAD_REGISTRY.lock().push(current_id());
+    }
+
     // Call the user thread function. In `std`, this is `thread_start`. Ignore
     // the return value for now, as `std` doesn't need it.
     let fn_: unsafe fn(&mut [*mut c_void]) -> Option<NonNull<c_void>> = core::mem::transmute(fn_);
@@ -695,9 +1500,45 @@ unsafe fn exit(return_value: Option<NonNull<c_void>>) -> ! {
         );
     }
 
-    // Call functions registered with `at_exit`.
+    // Call functions registered with `at_exit`, and then functions
+    // registered with `register_global_exit_hook`.
     #[cfg(feature = "thread-at-exit")]
-    call_dtors(current);
+    {
+        call_dtors(current);
+        call_global_exit_hooks(current, &AtomicUsize::new(0));
+    }
+
+    // If the thread was created with `create_with_clear_tid`, clear and wake
+    // the user's address, as `CHILD_CLEARTID` would.
+    let clear_addr = current.0.as_ref().clear_addr;
+    if !clear_addr.is_null() {
+        use rustix::thread::futex;
+
+        let clear_addr = AtomicU32::from_ptr(clear_addr);
+        clear_addr.store(0, SeqCst);
+        let _ = futex::wake(clear_addr, futex::Flags::empty(), 1);
+    }
+
+    #[cfg(feature = "thread-stats")]
+    LIVE_THREADS.fetch_sub(1, SeqCst);
+
+    // Remove ourselves from the registry of threads, and wake
+    // `quiesce_others` if it's waiting for us.
+    #[cfg(feature = "thread-registry")]
+    {
+        let id = current_id();
+        let mut registry = THREAD_REGISTRY.lock();
+        if let Some(index) = registry.iter().position(|other| *other == id) {
+            registry.swap_remove(index);
+        }
+    }
+    #[cfg(feature = "quiesce-threads-before-exit")]
+    {
+        use rustix::thread::futex;
+
+        REGISTRY_CHANGES.fetch_add(1, SeqCst);
+        let _ = futex::wake(&REGISTRY_CHANGES, futex::Flags::PRIVATE, 1);
+    }
 
     // Read the thread's state, and set it to `ABANDONED` if it was `INITIAL`,
     // which tells `join_thread` to free the memory. Otherwise, it's in the
@@ -733,17 +1574,7 @@ unsafe fn exit(return_value: Option<NonNull<c_void>>) -> ! {
             // In preparation for freeing the stack, block all signals, so that
             // no signals for the process are delivered to this thread.
             #[cfg(feature = "signal")]
-            {
-                #[cfg(any(target_arch = "arm", target_arch = "x86"))]
-                let all = Sigset { sig: [!0, !0] };
-                #[cfg(any(
-                    target_arch = "aarch64",
-                    target_arch = "riscv64",
-                    target_arch = "x86_64"
-                ))]
-                let all = Sigset { sig: [!0] };
-                sigprocmask(How::BLOCK, Some(&all)).ok();
-            }
+            sigprocmask(How::BLOCK, Some(&all_signals())).ok();
 
             // `munmap` the memory, which also frees the stack we're currently
             // on, and do an `exit` carefully without touching the stack.
@@ -761,6 +1592,15 @@ unsafe fn exit(return_value: Option<NonNull<c_void>>) -> ! {
             );
         }
 
+        // A value passed to `set_return_value`, including by the functions
+        // called above, takes precedence over the thread's own return value.
+        let return_value = current
+            .0
+            .as_ref()
+            .set_return_value
+            .get()
+            .unwrap_or(return_value);
+
         // Convert `return_value` into a `*mut c_void` so that we can store it
         // in an `AtomicPtr`.
         let return_value = match return_value {
@@ -782,7 +1622,9 @@ pub(crate) fn call_dtors(current: Thread) {
     let mut current = current;
 
     // Run the `dtors`, in reverse order of registration. Note that destructors
-    // may register new destructors.
+    // may register new destructors, which are pushed onto the end and so are
+    // run next. This runs before any of the thread's state is torn down; see
+    // the documentation for `at_exit` for the guarantees.
     //
     // SAFETY: `current` points to thread-local data which is valid as long as
     // the thread is alive.
@@ -835,7 +1677,8 @@ pub unsafe fn detach(thread: Thread) {
 /// Waits for a thread to finish.
 ///
 /// The return value is the value returned from the call to the `fn_` passed to
-/// `create_thread`.
+/// `create_thread`, or the value passed to [`set_return_value`] if the thread
+/// called it.
 ///
 /// # Safety
 ///
@@ -874,6 +1717,130 @@ pub unsafe fn join(thread: Thread) -> Option<NonNull<c_void>> {
     NonNull::new(return_value)
 }
 
+/// Waits for any of several threads to finish.
+///
+/// This returns the index in `threads` of a thread that has exited, and that
+/// thread's return value, as [`join`] would. That thread is joined; the others
+/// are left as they were, and still need to be joined or detached.
+///
+/// This waits for all the threads at once with `futex_waitv`, which Linux has
+/// had since version 5.16. If `futex_waitv` isn't available, or there are
+/// more than 128 threads, which is the most it can wait for, this instead
+/// waits for each of the threads in turn, with a short timeout, and it may
+/// take up to that long to notice that a thread other than the one being
+/// waited for has exited.
+///
+/// # Panics
+///
+/// This panics if `threads` is empty.
+///
+/// # Safety
+///
+/// Each element of `threads` must point to a valid thread record that has not
+/// already been detached or joined, and `threads` must not contain duplicates.
+pub unsafe fn join_any(threads: &[Thread]) -> (usize, Option<NonNull<c_void>>) {
+    use core::sync::atomic::AtomicBool;
+    use linux_raw_sys::general::FUTEX_WAITV_MAX;
+    use rustix::thread::futex;
+
+    /// Set if `futex_waitv` has failed with an error other than one that
+    /// just means we should check the threads again, such as `NOSYS` on
+    /// Linux versions before 5.16, so that we don't try it again.
+    static NO_FUTEX_WAITV: AtomicBool = AtomicBool::new(false);
+
+    // How long to wait for each thread before checking the others, when not
+    // using `futex_waitv`.
+    const TIMEOUT: futex::Timespec = futex::Timespec {
+        tv_sec: 0,
+        tv_nsec: 1_000_000,
+    };
+
+    assert!(
+        !threads.is_empty(),
+        "`join_any` requires at least one thread"
+    );
+
+    let mut next = 0;
+    loop {
+        for (index, thread) in threads.iter().enumerate() {
+            if thread.0.as_ref().thread_id.load(SeqCst) == 0 {
+                return (index, join(*thread));
+            }
+        }
+
+        // Whether the wait ends because a thread exited, or for any other
+        // reason, we check all the threads again above.
+        if threads.len() <= FUTEX_WAITV_MAX as usize && !NO_FUTEX_WAITV.load(SeqCst) {
+            match futex_waitv_any(threads) {
+                Ok(()) | Err(io::Errno::AGAIN) | Err(io::Errno::INTR) => {}
+                Err(_) => NO_FUTEX_WAITV.store(true, SeqCst),
+            }
+            continue;
+        }
+
+        // As in `wait_for_exit`, we can't use `futex::Flags::PRIVATE` because
+        // the wake when the thread exits comes from Linux.
+        let thread_id = &threads[next].0.as_ref().thread_id;
+        let id_value = thread_id.load(SeqCst);
+        if id_value != 0 {
+            let _ = futex::wait(
+                AtomicU32::from_ptr(thread_id.as_ptr().cast()),
+                futex::Flags::empty(),
+                id_value as u32,
+                Some(TIMEOUT),
+            );
+        }
+
+        next = (next + 1) % threads.len();
+    }
+}
+
+/// Wait with `futex_waitv` until any of `threads`, of which there are at most
+/// `FUTEX_WAITV_MAX`, may have exited.
+unsafe fn futex_waitv_any(threads: &[Thread]) -> io::Result<()> {
+    use linux_raw_sys::general::{__NR_futex_waitv, futex_waitv, FUTEX_32, FUTEX_WAITV_MAX};
+
+    let mut waiters = [futex_waitv {
+        val: 0,
+        uaddr: 0,
+        flags: 0,
+        __reserved: 0,
+    }; FUTEX_WAITV_MAX as usize];
+    for (waiter, thread) in waiters.iter_mut().zip(threads) {
+        let thread_id = &thread.0.as_ref().thread_id;
+        let id_value = thread_id.load(SeqCst);
+
+        // If the thread has exited since we checked, waiting for its id to
+        // change from 0 would never end.
+        if id_value == 0 {
+            return Ok(());
+        }
+
+        // As in `wait_for_exit`, the wake comes from Linux, so this doesn't
+        // use `FUTEX_PRIVATE_FLAG`.
+        *waiter = futex_waitv {
+            val: id_value as u32 as u64,
+            uaddr: thread_id.as_ptr() as usize as u64,
+            flags: FUTEX_32,
+            __reserved: 0,
+        };
+    }
+
+    let r0 = crate::arch::syscall5(
+        __NR_futex_waitv,
+        waiters.as_ptr() as usize,
+        threads.len(),
+        0,
+        0,
+        0,
+    );
+    if r0 < 0 {
+        Err(io::Errno::from_raw_os_error(-r0 as i32))
+    } else {
+        Ok(())
+    }
+}
+
 /// Wait until `thread` has exited.
 ///
 /// `thread` must point to a valid thread record that has not already been
@@ -925,6 +1892,7 @@ unsafe fn free_memory(thread: Thread) {
     let map_size = thread.0.as_ref().map_size;
     let stack_addr = thread.0.as_ref().stack_addr;
     let guard_size = thread.0.as_ref().guard_size;
+    let worker_id = thread.0.as_ref().worker_id.load(SeqCst);
 
     // Deallocate the `ThreadData`.
     drop_in_place(thread.0.as_ptr());
@@ -934,9 +1902,49 @@ unsafe fn free_memory(thread: Thread) {
         let map = stack_addr.byte_sub(guard_size);
         munmap(map, map_size).unwrap();
     }
+
+    // If it was a VM worker outside our thread group, it's a zombie child
+    // process now; reap it.
+    if worker_id != 0 {
+        reap_worker(worker_id);
+    }
+}
+
+/// Wait for the VM worker with id `worker_id`, which has exited or is
+/// exiting, to become a zombie, and reap it.
+///
+/// The worker was created with no exit signal, so this needs `__WALL`.
+unsafe fn reap_worker(worker_id: i32) {
+    use linux_raw_sys::general::{__NR_waitid, __WALL, P_PID, WEXITED};
+
+    loop {
+        let r0 = crate::arch::syscall5(
+            __NR_waitid,
+            P_PID as usize,
+            worker_id as usize,
+            0,
+            (WEXITED | __WALL) as usize,
+            0,
+        );
+        if r0 != -(io::Errno::INTR.raw_os_error() as isize) {
+            break;
+        }
+    }
 }
 
 /// Registers a function to call when the current thread exits.
+///
+/// Functions are called in the reverse order of their registration. A
+/// function registered while these functions are being called is called
+/// next, before any functions registered earlier, so layers which implement
+/// TLS-key-style destructors on top of this, such as `pthread_key_create`,
+/// can re-register themselves to run another round of destructors.
+///
+/// All of the functions are called while the thread is still fully intact:
+/// its thread-local storage is valid, [`id`] still returns its id, and it
+/// hasn't yet published its return value to [`join`] or cleared the address
+/// passed to [`create_with_clear_tid`]. On the main thread, they are called
+/// by `program::exit` before functions registered with `program::at_exit`.
 #[cfg(feature = "thread-at-exit")]
 pub fn at_exit(func: Box<dyn FnOnce()>) {
     // SAFETY: `current()` points to thread-local data which is valid as long
@@ -946,15 +1954,51 @@ pub fn at_exit(func: Box<dyn FnOnce()>) {
     }
 }
 
+/// Set the value that [`join`] returns for the current thread, in place of
+/// the value returned by the `fn_` passed to [`create`].
+///
+/// This lets code deep in a thread's call stack set the thread's result
+/// without returning it through each caller. If this is called more than
+/// once, the last value is used, and it takes precedence over the value `fn_`
+/// returns, including a `None`. It may also be called from functions
+/// registered with [`at_exit`], which are called before the value is
+/// published.
+///
+/// For detached threads and the main thread, which aren't joined, this has
+/// no effect.
+#[cfg_attr(docsrs, doc(cfg(feature = "take-charge")))]
+pub fn set_return_value(value: Option<NonNull<c_void>>) {
+    // SAFETY: `current()` points to thread-local data which is valid as long
+    // as the thread is alive, and `set_return_value` is only accessed by the
+    // thread itself.
+    unsafe {
+        current().0.as_ref().set_return_value.set(Some(value));
+    }
+}
+
 #[inline]
 #[must_use]
 fn current_metadata() -> *mut Metadata {
-    thread_pointer()
+    let thread_pointer = thread_pointer();
+
+    // SAFETY: `MAIN_THREAD_POINTER` and `MAIN_METADATA` are only written
+    // during startup.
+    #[cfg(feature = "keep-main-thread-pointer")]
+    unsafe {
+        if thread_pointer == MAIN_THREAD_POINTER {
+            return MAIN_METADATA;
+        }
+    }
+
+    thread_pointer
         .wrapping_byte_sub(offset_of!(Metadata, abi) + offset_of!(Abi, thread_pointee))
         .cast()
 }
 
 /// Return a raw pointer to the data associated with the current thread.
+///
+/// This is stable for the lifetime of the thread, and on a thread created by
+/// [`create`], it is equal to the `Thread` that `create` returned.
 #[inline]
 #[must_use]
 pub fn current() -> Thread {
@@ -1010,15 +2054,100 @@ pub unsafe fn set_current_id_after_a_fork(tid: ThreadId) {
         .store(tid.as_raw_nonzero().get(), SeqCst);
 }
 
+/// Reset origin's view of the program's threads in the child of a `fork`.
+///
+/// The child of a `fork` has only one thread, a copy of the thread which
+/// called `fork`, but it inherits the parent's memory, including origin's
+/// records of the parent's other threads. This updates the current thread's
+/// id, which [`set_current_id_after_a_fork`] also does, and with the
+/// "thread-registry" feature, resets the registry of threads to contain only
+/// the current thread, or nothing if the current thread is the main thread,
+/// and with the "thread-stats" feature, resets the count of running threads
+/// to one. The total and peak counts are left as they were, since they
+/// describe the program's history.
+///
+/// The memory of the parent's other threads is copied into the child, and
+/// isn't freed, since the threads can't be joined.
+///
+/// # Safety
+///
+/// This must only be called in the child of a `fork`, before any other
+/// threads are created.
+pub unsafe fn reset_after_fork() {
+    let current = current();
+    let new_id = gettid();
+
+    #[cfg(feature = "thread-registry")]
+    {
+        let old_id = current.0.as_ref().thread_id.load(SeqCst);
+
+        // If another thread in the parent held the lock when `fork` was
+        // called, it's still held, but that thread doesn't exist here, so
+        // release it on that thread's behalf.
+        let mut registry = match THREAD_REGISTRY.try_lock() {
+            Some(registry) => registry,
+            None => {
+                THREAD_REGISTRY.force_unlock();
+                THREAD_REGISTRY.lock()
+            }
+        };
+        let was_registered = registry
+            .iter()
+            .any(|id| id.as_raw_nonzero().get() == old_id);
+        registry.clear();
+        if was_registered {
+            registry.push(new_id);
+        }
+    }
+
+    current
+        .0
+        .as_ref()
+        .thread_id
+        .store(new_id.as_raw_nonzero().get(), SeqCst);
+
+    #[cfg(feature = "thread-stats")]
+    LIVE_THREADS.store(1, SeqCst);
+}
+
 /// Return the address of the thread-local `errno` state.
 ///
 /// This is equivalent to `__errno_location()` in glibc and musl.
+///
+/// Each thread's `errno` starts out as zero. Origin itself doesn't set it,
+/// since rustix reports errors by returning [`io::Errno`] values instead, so
+/// C-ABI code which follows the `errno` convention, such as a libc shim
+/// layer, is responsible for setting it, for example with [`set_errno`] and
+/// [`io::Errno::raw_os_error`].
 #[cfg(feature = "unstable-errno")]
 #[inline]
 pub fn errno_location() -> *mut i32 {
     unsafe { core::ptr::addr_of_mut!((*current_metadata()).thread.errno_val).cast::<i32>() }
 }
 
+/// Return the current thread's `errno` value.
+///
+/// This reads the value at [`errno_location`].
+#[cfg(feature = "unstable-errno")]
+#[inline]
+#[must_use]
+pub fn errno() -> i32 {
+    // SAFETY: `errno_location` returns a valid pointer for the current
+    // thread.
+    unsafe { *errno_location() }
+}
+
+/// Set the current thread's `errno` value.
+///
+/// This writes the value at [`errno_location`].
+#[cfg(feature = "unstable-errno")]
+#[inline]
+pub fn set_errno(value: i32) {
+    // SAFETY: `errno_location` returns a valid pointer for the current
+    // thread.
+    unsafe { *errno_location() = value }
+}
+
 /// Return the TLS address for the given `module` and `offset` for the current
 /// thread.
 #[inline]
@@ -1028,30 +2157,175 @@ pub fn current_tls_addr(module: usize, offset: usize) -> *mut c_void {
     // so we should only ever see 1 here.
     assert_eq!(module, 1);
 
+    thread_pointer()
+        .wrapping_byte_offset(current_tls_block_offset())
+        .wrapping_byte_add(TLS_OFFSET)
+        .wrapping_byte_add(offset)
+}
+
+/// Return the address of the thread-local data at `offset` within the TLS
+/// segment, in `thread`.
+///
+/// This lets debuggers and other introspection tools find a thread-local
+/// variable in a thread other than the current one. The `offset` of a
+/// variable can be computed on any thread, as the address of the variable
+/// minus `tls_addr_of(current(), 0)`, and it's the same in all threads
+/// created with the executable's TLS template.
+///
+/// # Panics
+///
+/// With "keep-main-thread-pointer", the main thread's thread-local variables
+/// are the ones libc set up, which origin doesn't know the location of, so
+/// this panics if `thread` is the main thread.
+///
+/// # Safety
+///
+/// `thread` must point to a valid thread record, and `offset` must be within
+/// the thread's TLS data. The returned pointer is only valid while the thread
+/// is running, and `thread` may be reading and writing the data at the same
+/// time, so accessing it must be synchronized with `thread` by other means.
+#[cfg_attr(docsrs, doc(cfg(feature = "take-charge")))]
+#[inline]
+#[must_use]
+pub unsafe fn tls_addr_of(thread: Thread, offset: usize) -> *mut c_void {
+    #[cfg(feature = "keep-main-thread-pointer")]
+    assert!(
+        !core::ptr::eq(
+            thread.0.as_ptr(),
+            core::ptr::addr_of_mut!((*MAIN_METADATA).thread)
+        ),
+        "`tls_addr_of` doesn't support the main thread with \"keep-main-thread-pointer\""
+    );
+
+    let thread = thread.0.as_ref();
+    debug_assert!(offset <= thread.tls_mem_size);
+    thread.tls_base.byte_add(offset)
+}
+
+/// Return the offset of the current thread's TLS data from the thread
+/// pointer.
+#[inline]
+fn current_tls_block_offset() -> isize {
     // Platforms where TLS data goes after the ABI-exposed fields.
     #[cfg(any(target_arch = "aarch64", target_arch = "arm", target_arch = "riscv64"))]
     {
-        thread_pointer()
-            .wrapping_byte_add(size_of::<Abi>() - offset_of!(Abi, thread_pointee))
-            .wrapping_byte_add(TLS_OFFSET)
-            .wrapping_byte_add(offset)
+        (size_of::<Abi>() - offset_of!(Abi, thread_pointee)) as isize
     }
 
-    // Platforms where TLS data goes before the ABI-exposed fields.
+    // Platforms where TLS data goes before the ABI-exposed fields. The TLS
+    // data ends at the ABI-exposed fields, padded to its alignment, as laid
+    // out by `calculate_tls_size`. The current thread may have been created
+    // with a `TlsTemplate` of a different size than the executable's, so use
+    // its own size.
     //
-    // SAFETY: `STARTUP_TLS_INFO` has already been initialized by
-    // [`initialize_startup_info`].
+    // SAFETY: All threads have been initialized, including the main thread
+    // with `initialize_main`, so `current_metadata()` returns a valid
+    // pointer.
     #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
     unsafe {
-        thread_pointer()
-            .wrapping_byte_sub(STARTUP_TLS_INFO.mem_size)
-            .wrapping_byte_add(TLS_OFFSET)
-            .wrapping_byte_add(offset)
+        let thread = &(*current_metadata()).thread;
+        -(round_up(thread.tls_mem_size, thread.tls_align) as isize)
+    }
+}
+
+/// The two ELF TLS layouts.
+///
+/// See [ELF Handling For Thread-Local Storage] for details.
+///
+/// [ELF Handling For Thread-Local Storage]: https://www.akkadia.org/drepper/tls.pdf
+#[cfg_attr(docsrs, doc(cfg(feature = "take-charge")))]
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum TlsVariant {
+    /// The TLS data follows the thread control block, at or after the
+    /// address the thread pointer points to. This is used on aarch64, ARM,
+    /// and RISC-V.
+    I,
+
+    /// The TLS data precedes the thread control block, ending at the address
+    /// the thread pointer points to. This is used on x86 and x86-64.
+    II,
+}
+
+/// The layout of thread-local data relative to the thread pointer, returned
+/// by [`tls_layout`].
+#[cfg_attr(docsrs, doc(cfg(feature = "take-charge")))]
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+#[non_exhaustive]
+pub struct TlsLayout {
+    /// Which ELF TLS layout the architecture uses.
+    pub variant: TlsVariant,
+
+    /// The offset, in bytes, of the start of the TLS data from the address
+    /// the thread pointer points to. This is negative with
+    /// [`TlsVariant::II`].
+    pub block_offset: isize,
+
+    /// The bias the architecture's ABI subtracts from offsets passed to
+    /// [`current_tls_addr`], and `__tls_get_addr`, which is added back to
+    /// find the variable. This is 0x800 on RISC-V and 0 elsewhere.
+    pub dtv_offset: usize,
+}
+
+/// Return the layout of the current thread's thread-local data.
+///
+/// This is for code generators, such as JITs, which need to emit
+/// thread-pointer-relative accesses to thread-local variables. A variable at
+/// offset `offset` within the executable's TLS segment is at
+/// `block_offset + offset` from the thread pointer.
+///
+/// On [`TlsVariant::II`] architectures, `block_offset` depends on the size of
+/// the thread's TLS data, so it differs for threads created by
+/// [`create_with_tls_template`] with a different template.
+#[cfg_attr(docsrs, doc(cfg(feature = "take-charge")))]
+#[must_use]
+pub fn tls_layout() -> TlsLayout {
+    TlsLayout {
+        #[cfg(any(target_arch = "aarch64", target_arch = "arm", target_arch = "riscv64"))]
+        variant: TlsVariant::I,
+        #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+        variant: TlsVariant::II,
+        block_offset: current_tls_block_offset(),
+        dtv_offset: TLS_OFFSET,
+    }
+}
+
+/// Lock the current thread's TLS data and metadata into memory.
+///
+/// This `mlock`s the memory holding the current thread's thread-local
+/// variables and origin's metadata for the thread, which includes the stack
+/// canary, so that it's never swapped out to disk. It doesn't lock the stack.
+///
+/// This fails with [`io::Errno::PERM`] or [`io::Errno::NOMEM`] if the process
+/// isn't permitted to lock that much memory.
+#[cfg_attr(docsrs, doc(cfg(feature = "take-charge")))]
+pub fn lock_tls_in_memory() -> io::Result<()> {
+    use rustix::mm::mlock;
+
+    // The TLS data and metadata are allocated together in a region with the
+    // layout computed by `calculate_tls_size`, and the region starts at
+    // `metadata_align`, so we can compute its bounds from the metadata.
+    //
+    // SAFETY: The region is part of the current thread's allocation, which
+    // stays live as long as the thread is running.
+    unsafe {
+        let metadata = current_metadata();
+        let mut size = 0;
+        let (_tls_data_bottom, header) = calculate_tls_size(
+            &mut size,
+            (*metadata).thread.tls_mem_size,
+            (*metadata).thread.tls_align,
+        );
+
+        let region = metadata.cast::<u8>().sub(header);
+        mlock(region.cast(), size)
     }
 }
 
 /// Return the id of a thread, or `None` if the thread has exited.
 ///
+/// For a thread created by [`create`], this is valid as soon as `create`
+/// returns.
+///
 /// # Safety
 ///
 /// `thread` must point to a valid thread record.
@@ -1075,6 +2349,384 @@ pub unsafe fn stack(thread: Thread) -> (*mut c_void, usize, usize) {
     (data.stack_addr, data.stack_size, data.guard_size)
 }
 
+/// Compute the address just past the end of the main thread's stack.
+fn main_stack_base() -> *mut u8 {
+    // Linux puts the `AT_EXECFN` string at the top, so find the end of that,
+    // and then round up to the page size. See
+    // <https://lwn.net/Articles/631631/> for details.
+    let execfn = crate::program::exec_path().to_bytes_with_nul();
+    execfn
+        .as_ptr_range()
+        .end
+        .map_addr(|ptr| round_up(ptr, page_size()))
+        .cast_mut()
+}
+
+/// Return the address (lowest address) and size of the memory which is
+/// currently mapped for a thread's stack, not including its guard region.
+///
+/// For threads created by [`create`], this is the stack region origin mapped
+/// for the thread, the same as the address and size returned by [`stack`].
+///
+/// For the main thread, `stack` reports the extent the stack may grow to,
+/// computed from the stack size limit. The kernel only maps the main
+/// thread's stack as it grows, so this instead asks the kernel how much is
+/// currently mapped, which is what tools such as conservative garbage
+/// collectors which scan the stack need to avoid touching unmapped memory.
+/// It does this with a binary search using `msync` with `MS_ASYNC`, which
+/// fails with `NOMEM` on ranges which aren't fully mapped, and otherwise has
+/// no effect. Other errors, such as when a sandbox doesn't allow `msync`,
+/// are returned. The stack may grow after this returns, so the result only
+/// describes a moment in time.
+///
+/// # Safety
+///
+/// `thread` must point to a valid thread record.
+#[cfg_attr(docsrs, doc(cfg(feature = "take-charge")))]
+pub unsafe fn stack_committed_range(thread: Thread) -> io::Result<(*mut c_void, usize)> {
+    use rustix::mm::{msync, MsyncFlags};
+
+    let data = thread.0.as_ref();
+
+    if data.map_size != 0 {
+        return Ok((data.stack_addr, data.stack_size));
+    }
+
+    let page_size = page_size();
+    let stack_base = main_stack_base();
+    let is_mapped = |addr: usize| -> io::Result<bool> {
+        let ptr = stack_base.with_addr(addr);
+        match msync(ptr.cast(), stack_base.addr() - addr, MsyncFlags::ASYNC) {
+            Ok(()) => Ok(true),
+            Err(io::Errno::NOMEM) => Ok(false),
+            Err(err) => Err(err),
+        }
+    };
+
+    // The stack can't extend below the stack size limit. If the limit is
+    // unlimited, search all the way down to the lowest page.
+    let mut low = match getrlimit(Resource::Stack).current {
+        Some(limit) => stack_base.addr().saturating_sub(limit as usize),
+        None => 0,
+    };
+    low = max(round_up(low, page_size), page_size);
+
+    // The page containing the initial stack pointer is mapped.
+    let mut high = MAIN_STACK_POINTER.addr() & !(page_size - 1);
+
+    // If the limit has been lowered since the program started, the stack may
+    // already extend below it.
+    if low >= high {
+        low = page_size;
+    }
+
+    // Find the lowest page `p` such that everything from `p` up to the base
+    // is mapped.
+    if is_mapped(low)? {
+        high = low;
+    }
+    while high - low > page_size {
+        let mid = low + (high - low) / page_size / 2 * page_size;
+        if is_mapped(mid)? {
+            high = mid;
+        } else {
+            low = mid;
+        }
+    }
+
+    Ok((stack_base.with_addr(high).cast(), stack_base.addr() - high))
+}
+
+/// The byte that [`Builder::measure_stack_usage`] fills stacks with.
+const STACK_SENTINEL: u8 = 0xa5;
+
+/// Return an estimate of the most stack space, in bytes, that a thread has
+/// used so far, or `None` if it wasn't created with
+/// [`Builder::measure_stack_usage`].
+///
+/// This scans up from the bottom of the thread's stack for the first byte
+/// which no longer holds the sentinel pattern, so the estimate is low if the
+/// thread happened to store the sentinel byte at the deepest point it
+/// reached, and it includes space used by signal handlers which ran on the
+/// stack. Scanning takes time proportional to the amount of the stack that
+/// hasn't been used.
+///
+/// This may be called from the thread itself, or from another thread while
+/// the thread is running, in which case the result may be out of date by the
+/// time it's returned. A thread's memory is freed when it's joined, or when
+/// it exits if it's detached, so this can't be used after that.
+///
+/// # Safety
+///
+/// `thread` must point to a valid thread record.
+#[cfg_attr(docsrs, doc(cfg(feature = "take-charge")))]
+#[must_use]
+pub unsafe fn peak_stack_usage(thread: Thread) -> Option<usize> {
+    let data = thread.0.as_ref();
+    if !data.stack_measured {
+        return None;
+    }
+
+    // `create_impl` filled the stack from its lowest address up to its
+    // size rounded up to its alignment.
+    let stack = data.stack_addr.cast::<u8>();
+    let len = round_up(data.stack_size, 16);
+
+    // Read the bytes with volatile reads, since the thread may be writing to
+    // its stack concurrently.
+    let unused = (0..len)
+        .position(|i| stack.add(i).read_volatile() != STACK_SENTINEL)
+        .unwrap_or(len);
+    Some(len - unused)
+}
+
+/// The total number of threads that have been started, including the main
+/// thread.
+#[cfg(feature = "thread-stats")]
+static TOTAL_THREADS: AtomicUsize = AtomicUsize::new(0);
+
+/// The number of threads currently running.
+#[cfg(feature = "thread-stats")]
+static LIVE_THREADS: AtomicUsize = AtomicUsize::new(0);
+
+/// The highest value `LIVE_THREADS` has had.
+#[cfg(feature = "thread-stats")]
+static PEAK_THREADS: AtomicUsize = AtomicUsize::new(0);
+
+/// Thread counters returned by [`stats`].
+#[cfg(feature = "thread-stats")]
+#[cfg_attr(
+    docsrs,
+    doc(cfg(all(feature = "take-charge", feature = "thread-stats")))
+)]
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+#[non_exhaustive]
+pub struct ThreadStats {
+    /// The total number of threads that have been started, including the
+    /// main thread.
+    pub total_created: usize,
+
+    /// The number of threads currently running, including the main thread.
+    ///
+    /// A thread stops counting as running once it has called its
+    /// [`at_exit`] functions, even if it hasn't yet been joined.
+    pub live: usize,
+
+    /// The highest number of threads that have been running at the same time.
+    pub peak: usize,
+}
+
+/// Return whether creating a new thread is expected to succeed, given the
+/// limit on the number of processes and threads the user may have,
+/// `RLIMIT_NPROC`.
+///
+/// This is a best-effort check, useful as a soft gate for thread pools which
+/// would rather back off than have [`create`] fail with
+/// [`io::Errno::AGAIN`]. It compares the number of threads origin has
+/// created that are still running against the limit, so it doesn't account
+/// for other processes and threads belonging to the same user, or for
+/// privileged processes being exempt from the limit. And it's racy, since
+/// another thread may be created between this check and a call to
+/// [`create`], so callers must still handle failure.
+///
+/// With the "single-threaded" feature, this always returns `false`.
+#[cfg(feature = "thread-stats")]
+#[cfg_attr(
+    docsrs,
+    doc(cfg(all(feature = "take-charge", feature = "thread-stats")))
+)]
+#[must_use]
+pub fn can_spawn() -> bool {
+    if cfg!(feature = "single-threaded") {
+        return false;
+    }
+
+    match getrlimit(Resource::Nproc).current {
+        Some(limit) => (LIVE_THREADS.load(SeqCst) as u64) < limit,
+        None => true,
+    }
+}
+
+/// Return counters describing the threads the program has created.
+///
+/// Only threads created by origin are counted. The counters are updated
+/// independently, so a snapshot taken while threads are being created or are
+/// exiting may be slightly inconsistent.
+#[cfg(feature = "thread-stats")]
+#[cfg_attr(
+    docsrs,
+    doc(cfg(all(feature = "take-charge", feature = "thread-stats")))
+)]
+#[must_use]
+pub fn stats() -> ThreadStats {
+    ThreadStats {
+        total_created: TOTAL_THREADS.load(SeqCst),
+        live: LIVE_THREADS.load(SeqCst),
+        peak: PEAK_THREADS.load(SeqCst),
+    }
+}
+
+/// Log a warning if any threads other than the current thread are still
+/// running, for `program::exit` to report threads the program didn't join
+/// before exiting.
+///
+/// With the "thread-registry" feature, this also lists the ids of the threads
+/// created by origin which are still running, unless the registry is locked.
+#[cfg(all(debug_assertions, feature = "thread-stats", feature = "log"))]
+pub(crate) fn warn_outstanding() {
+    let others = LIVE_THREADS.load(SeqCst).saturating_sub(1);
+    if others == 0 {
+        return;
+    }
+
+    log::warn!(
+        "Exiting with {} other thread(s) still running, which will be terminated",
+        others
+    );
+
+    #[cfg(feature = "thread-registry")]
+    if let Some(registry) = THREAD_REGISTRY.try_lock() {
+        let current_id = current_id();
+        for id in registry.iter().filter(|id| **id != current_id) {
+            log::warn!("Thread[{:?}] is still running", id.as_raw_nonzero());
+        }
+    }
+}
+
+/// The ids of the threads created by origin which are running, not including
+/// the main thread.
+#[cfg(feature = "thread-registry")]
+static THREAD_REGISTRY: rustix_futex_sync::Mutex<alloc::vec::Vec<ThreadId>> =
+    rustix_futex_sync::Mutex::new(alloc::vec::Vec::new());
+
+/// Lock the registry of the threads created by origin which are running, not
+/// including the main thread.
+///
+/// While the registry is locked, threads which are starting or exiting block
+/// when they try to add or remove themselves.
+#[cfg(all(feature = "thread-registry", feature = "signal"))]
+pub(crate) fn lock_registry() -> rustix_futex_sync::MutexGuard<'static, alloc::vec::Vec<ThreadId>> {
+    THREAD_REGISTRY.lock()
+}
+
+/// Whether [`program::exit`] has been called, for [`exit_requested`].
+///
+/// [`program::exit`]: crate::program::exit
+#[cfg(feature = "quiesce-threads-before-exit")]
+static EXIT_REQUESTED: AtomicU32 = AtomicU32::new(0);
+
+/// Test whether the program has started exiting.
+///
+/// With the "quiesce-threads-before-exit" feature, [`program::exit`] sets
+/// this, and then waits for the other threads created by origin to return
+/// before calling any exit functions. Long-running threads should check this
+/// at points where they hold no locks and have no work in progress, and
+/// return when it's set.
+///
+/// [`program::exit`]: crate::program::exit
+#[cfg(feature = "quiesce-threads-before-exit")]
+#[cfg_attr(docsrs, doc(cfg(feature = "quiesce-threads-before-exit")))]
+#[inline]
+pub fn exit_requested() -> bool {
+    EXIT_REQUESTED.load(SeqCst) != 0
+}
+
+/// Ask all other threads created by origin to stop, and wait for them, so
+/// that the program's exit handlers can run as if the program were
+/// single-threaded.
+///
+/// This sets the flag that [`exit_requested`] reports, and waits for the
+/// other threads in the registry to return. Threads are never interrupted;
+/// if some thread doesn't return within about a second, for example because
+/// it never checks `exit_requested`, we give up and continue with it still
+/// running.
+#[cfg(feature = "quiesce-threads-before-exit")]
+pub(crate) fn quiesce_others() {
+    use rustix::thread::futex;
+
+    // If `exit` is called again from an exit function, we're already done.
+    if EXIT_REQUESTED.swap(1, SeqCst) != 0 {
+        return;
+    }
+
+    let current_id = current_id();
+    let others = || {
+        THREAD_REGISTRY
+            .lock()
+            .iter()
+            .filter(|id| **id != current_id)
+            .count()
+    };
+
+    // Wait for the other threads to return. They wake us each time one of
+    // them removes itself from the registry.
+    const TIMEOUT: futex::Timespec = futex::Timespec {
+        tv_sec: 0,
+        tv_nsec: 10_000_000,
+    };
+    let mut remaining = others();
+    let mut timeouts = 0;
+    while remaining != 0 && timeouts < 100 {
+        let changes = REGISTRY_CHANGES.load(SeqCst);
+        let now = others();
+        if now != remaining {
+            remaining = now;
+            continue;
+        }
+        match futex::wait(
+            &REGISTRY_CHANGES,
+            futex::Flags::PRIVATE,
+            changes,
+            Some(TIMEOUT),
+        ) {
+            Err(io::Errno::TIMEDOUT) => timeouts += 1,
+            _ => remaining = others(),
+        }
+    }
+
+    #[cfg(feature = "log")]
+    if remaining != 0 {
+        log::trace!(
+            "{} other threads are still running after waiting for them to exit",
+            remaining
+        );
+    }
+}
+
+/// A counter which is incremented, with a futex wake, each time a thread
+/// removes itself from the registry, for [`quiesce_others`] to wait on.
+#[cfg(feature = "quiesce-threads-before-exit")]
+static REGISTRY_CHANGES: AtomicU32 = AtomicU32::new(0);
+
+/// Return a `Sigset` with all signals set.
+fn all_signals() -> Sigset {
+    #[cfg(target_pointer_width = "32")]
+    let all = Sigset { sig: [!0, !0] };
+    #[cfg(target_pointer_width = "64")]
+    let all = Sigset { sig: [!0] };
+    all
+}
+
+/// A default stack size for new threads, known at compile time.
+///
+/// This is the minimum that [`default_stack_size`] returns. Unlike
+/// `default_stack_size`, it doesn't take into account a larger stack size
+/// requested by the executable's `PT_GNU_STACK` header.
+pub const DEFAULT_STACK_SIZE: usize = 0x20000;
+
+/// The smallest stack size [`create`] accepts.
+///
+/// This is 16 KiB, the same as `PTHREAD_STACK_MIN` on many platforms. It
+/// doesn't include the thread's TLS data, which is allocated separately.
+pub const MIN_STACK_SIZE: usize = 0x4000;
+
+/// A default guard size for new threads, known at compile time.
+///
+/// This is four 4 KiB pages. Unlike [`default_guard_size`], it doesn't take
+/// into account the actual page size, however [`create`] rounds guard sizes up
+/// to a multiple of the page size, so it's always at least one page.
+pub const DEFAULT_GUARD_SIZE: usize = 4 * 4096;
+
 /// Return the default stack size for new threads.
 #[inline]
 #[must_use]
@@ -1083,7 +2735,7 @@ pub fn default_stack_size() -> usize {
     //
     // SAFETY: `STARTUP_STACK_SIZE` has already been initialized by
     // [`initialize_startup_info`].
-    unsafe { max(0x20000, STARTUP_STACK_SIZE) }
+    unsafe { max(DEFAULT_STACK_SIZE, STARTUP_STACK_SIZE) }
 }
 
 /// Return the default guard size for new threads.
@@ -1100,6 +2752,52 @@ pub fn yield_current() {
     rustix::process::sched_yield()
 }
 
+/// Yield the current thread, hinting that `thread` should run instead.
+///
+/// This is meant for spin-loop backoff in locks which know which thread holds
+/// them. `sched_yield`, used by [`yield_current`], often doesn't let other
+/// threads run when using the default scheduling policy, so instead this
+/// briefly sleeps in a futex wait on `thread`'s id, which frees up the current
+/// CPU and which wakes up early if `thread` exits. Linux has no way to
+/// directly donate the remainder of the current time slice to a specific
+/// thread, so this is only a hint; callers must not depend on `thread`
+/// having made progress when this returns.
+///
+/// If `thread` is the current thread, or has exited, this just calls
+/// [`yield_current`].
+///
+/// # Safety
+///
+/// `thread` must point to a valid thread record.
+#[cfg_attr(docsrs, doc(cfg(feature = "take-charge")))]
+pub unsafe fn yield_to(thread: Thread) {
+    use rustix::thread::futex;
+
+    // How long to sleep for. A zero timeout would return without sleeping, so
+    // use a very short one. The kernel's timer slack typically extends this.
+    const TIMEOUT: futex::Timespec = futex::Timespec {
+        tv_sec: 0,
+        tv_nsec: 1000,
+    };
+
+    let thread_id = &thread.0.as_ref().thread_id;
+    let id_value = thread_id.load(SeqCst);
+    if id_value == 0 || thread == current() {
+        yield_current();
+        return;
+    }
+
+    // As in `wait_for_exit`, we can't use `futex::Flags::PRIVATE` because the
+    // wake when the thread exits comes from Linux. We don't care why the wait
+    // ends, so ignore the result.
+    let _ = futex::wait(
+        AtomicU32::from_ptr(thread_id.as_ptr().cast()),
+        futex::Flags::empty(),
+        id_value as u32,
+        Some(TIMEOUT),
+    );
+}
+
 /// The ARM ABI expects this to be defined.
 #[cfg(target_arch = "arm")]
 #[no_mangle]
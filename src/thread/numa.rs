@@ -0,0 +1,200 @@
+//! Placing a new thread's memory and CPUs on a NUMA node, for
+//! [`Builder::numa_node`].
+//!
+//! [`Builder::numa_node`]: super::Builder::numa_node
+
+use core::ffi::{c_void, CStr};
+use linux_raw_sys::ctypes::c_ulong;
+use rustix::io;
+use rustix::process::{sched_getaffinity, sched_setaffinity, CpuSet};
+
+/// The largest node number Linux supports, plus one.
+const MAX_NODES: usize = 1024;
+
+/// `MPOL_BIND` from `<linux/mempolicy.h>`, which linux-raw-sys doesn't
+/// provide.
+const MPOL_BIND: usize = 2;
+
+/// Return whether `node` is the only node, so that binding to it has no
+/// effect.
+pub(super) fn is_only_node(node: u32) -> bool {
+    let mut buf = [0_u8; 16];
+    node == 0
+        && matches!(
+            read_file(c"/sys/devices/system/node/online", &mut buf),
+            Some(b"0\n")
+        )
+}
+
+/// Bind the memory at `addr`, which hasn't been touched yet, to `node`.
+///
+/// If Linux was built without NUMA support, this does nothing.
+///
+/// # Safety
+///
+/// `addr` and `len` must describe memory mapped by the caller.
+pub(super) unsafe fn bind_memory(addr: *mut c_void, len: usize, node: u32) -> io::Result<()> {
+    use linux_raw_sys::general::__NR_mbind;
+
+    const BITS: usize = c_ulong::BITS as usize;
+
+    let node = node as usize;
+    if node >= MAX_NODES {
+        return Err(io::Errno::INVAL);
+    }
+    let mut mask = [0 as c_ulong; MAX_NODES / BITS];
+    mask[node / BITS] |= 1 << (node % BITS);
+
+    // Linux reads one less than `maxnode` bits from the mask.
+    let r0 = crate::arch::syscall6(
+        __NR_mbind,
+        addr as usize,
+        len,
+        MPOL_BIND,
+        mask.as_ptr() as usize,
+        node + 2,
+        0,
+    );
+    match r0 {
+        0.. => Ok(()),
+        _ if r0 == -(io::Errno::NOSYS.raw_os_error() as isize) => Ok(()),
+        _ => Err(io::Errno::from_raw_os_error(-r0 as i32)),
+    }
+}
+
+/// Restrict the current thread to the CPUs on `node` that it's allowed to
+/// run on, and return its previous affinity, so that a thread created
+/// before it's restored inherits the restriction.
+///
+/// If the node's CPUs can't be determined, or none of them are allowed, this
+/// leaves the affinity alone and returns `None`.
+pub(super) fn enter_node(node: u32) -> Option<CpuSet> {
+    let old = sched_getaffinity(None).ok()?;
+    let cpus = node_cpus(node)?;
+
+    let mut new = CpuSet::new();
+    for cpu in 0..CpuSet::MAX_CPU {
+        if old.is_set(cpu) && cpus.is_set(cpu) {
+            new.set(cpu);
+        }
+    }
+    if new.count() == 0 {
+        return None;
+    }
+
+    sched_setaffinity(None, &new).ok()?;
+    Some(old)
+}
+
+/// Restore the affinity returned by [`enter_node`].
+pub(super) fn leave_node(old: &CpuSet) {
+    sched_setaffinity(None, old).ok();
+}
+
+/// Read the CPUs on `node` from sysfs.
+fn node_cpus(node: u32) -> Option<CpuSet> {
+    const PREFIX: &[u8] = b"/sys/devices/system/node/node";
+    const SUFFIX: &[u8] = b"/cpulist\0";
+
+    // Format the path without allocating.
+    let mut path = [0_u8; PREFIX.len() + 10 + SUFFIX.len()];
+    path[..PREFIX.len()].copy_from_slice(PREFIX);
+    let mut len = PREFIX.len();
+    let mut digits = [0_u8; 10];
+    let mut num_digits = 0;
+    let mut n = node;
+    loop {
+        digits[num_digits] = b'0' + (n % 10) as u8;
+        num_digits += 1;
+        n /= 10;
+        if n == 0 {
+            break;
+        }
+    }
+    for digit in digits[..num_digits].iter().rev() {
+        path[len] = *digit;
+        len += 1;
+    }
+    path[len..len + SUFFIX.len()].copy_from_slice(SUFFIX);
+    len += SUFFIX.len();
+    let path = CStr::from_bytes_with_nul(&path[..len]).unwrap();
+
+    // The list is comma-separated ranges, such as "0-3,8-11\n".
+    let mut buf = [0_u8; 4096];
+    let list = read_file(path, &mut buf)?;
+    let mut cpus = CpuSet::new();
+    for range in list.split(|b| *b == b',') {
+        let range = range.strip_suffix(b"\n").unwrap_or(range);
+        if range.is_empty() {
+            continue;
+        }
+        let mut ends = range.splitn(2, |b| *b == b'-');
+        let first = parse(ends.next()?)?;
+        let last = match ends.next() {
+            Some(last) => parse(last)?,
+            None => first,
+        };
+        for cpu in first..=last.min(CpuSet::MAX_CPU - 1) {
+            cpus.set(cpu);
+        }
+    }
+    Some(cpus)
+}
+
+/// Parse a decimal number.
+fn parse(digits: &[u8]) -> Option<usize> {
+    if digits.is_empty() {
+        return None;
+    }
+    digits.iter().try_fold(0_usize, |n, digit| {
+        if !digit.is_ascii_digit() {
+            return None;
+        }
+        n.checked_mul(10)?.checked_add(usize::from(digit - b'0'))
+    })
+}
+
+/// Read the contents of the file at `path` into `buf`, and return them, or
+/// `None` if it can't be read or doesn't fit.
+fn read_file<'buf>(path: &CStr, buf: &'buf mut [u8]) -> Option<&'buf [u8]> {
+    use linux_raw_sys::general::{
+        __NR_close, __NR_openat, __NR_read, AT_FDCWD, O_CLOEXEC, O_RDONLY,
+    };
+
+    // SAFETY: `path` is a NUL-terminated string, which the kernel only
+    // reads, and it writes at most `buf.len()` bytes into `buf`.
+    unsafe {
+        let fd = crate::arch::syscall4(
+            __NR_openat,
+            AT_FDCWD as usize,
+            path.as_ptr() as usize,
+            (O_RDONLY | O_CLOEXEC) as usize,
+            0,
+        );
+        if fd < 0 {
+            return None;
+        }
+
+        let mut len = 0;
+        let result = loop {
+            if len == buf.len() {
+                break None;
+            }
+            let r0 = crate::arch::syscall3(
+                __NR_read,
+                fd as usize,
+                buf[len..].as_mut_ptr() as usize,
+                buf.len() - len,
+            );
+            match r0 {
+                0 => break Some(len),
+                1.. => len += r0 as usize,
+                _ if r0 == -(io::Errno::INTR.raw_os_error() as isize) => continue,
+                _ => break None,
+            }
+        };
+
+        crate::arch::syscall1(__NR_close, fd as usize);
+        result.map(|len| &buf[..len])
+    }
+}
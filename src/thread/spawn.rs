@@ -0,0 +1,89 @@
+//! Spawning threads with closures.
+
+use super::{create, default_guard_size, default_stack_size, join, Thread};
+use alloc::boxed::Box;
+use core::ffi::c_void;
+use core::marker::PhantomData;
+use core::ptr::NonNull;
+use rustix::io;
+
+/// A handle to a thread created by [`spawn_unchecked`], which can be joined
+/// to obtain the closure's return value.
+///
+/// Like [`Thread`], this does not detach or free resources on drop. It just
+/// leaks the thread and its return value. To free them, call
+/// [`JoinHandle::join`].
+pub struct JoinHandle<T> {
+    thread: Thread,
+    _phantom: PhantomData<T>,
+}
+
+impl<T> JoinHandle<T> {
+    /// Return the underlying [`Thread`].
+    #[inline]
+    #[must_use]
+    pub fn thread(&self) -> Thread {
+        self.thread
+    }
+
+    /// Wait for the thread to finish, and return the value its closure
+    /// returned.
+    pub fn join(self) -> T {
+        // SAFETY: `self.thread` was created by `spawn_unchecked`, and since
+        // we consume `self`, it hasn't been joined yet. `spawn_unchecked`
+        // requires that it not be detached or joined by other means.
+        unsafe {
+            let result = join(self.thread).unwrap();
+            *Box::from_raw(result.as_ptr().cast::<T>())
+        }
+    }
+}
+
+/// Creates a new thread which calls `f`, without requiring `f` and its return
+/// value to be `'static`.
+///
+/// This is like `std::thread::Builder::spawn_unchecked`. It's the primitive
+/// which scoped threads can be built on, letting threads borrow data from
+/// the creating thread without the overhead of `Arc` or cloning.
+///
+/// The thread uses the [`default_stack_size`] and [`default_guard_size`].
+///
+/// # Safety
+///
+/// The caller must ensure that the thread is joined, using
+/// [`JoinHandle::join`], before any data borrowed by `f` or by its return
+/// value is invalidated. The thread must not be detached or joined through
+/// [`JoinHandle::thread`].
+pub unsafe fn spawn_unchecked<F, T>(f: F) -> io::Result<JoinHandle<T>>
+where
+    F: FnOnce() -> T + Send,
+    T: Send,
+{
+    /// Unpack the closure, call it, and box up its return value.
+    unsafe fn call<F: FnOnce() -> T, T>(
+        args: &mut [Option<NonNull<c_void>>],
+    ) -> Option<NonNull<c_void>> {
+        let f = Box::from_raw(args[0].unwrap().as_ptr().cast::<F>());
+        let result = Box::new(f());
+        Some(NonNull::new_unchecked(Box::into_raw(result).cast()))
+    }
+
+    let f = Box::into_raw(Box::new(f));
+
+    match create(
+        call::<F, T>,
+        &[Some(NonNull::new_unchecked(f.cast()))],
+        default_stack_size(),
+        default_guard_size(),
+    ) {
+        Ok(thread) => Ok(JoinHandle {
+            thread,
+            _phantom: PhantomData,
+        }),
+        Err(err) => {
+            // The thread didn't start, so free the closure.
+            drop(Box::from_raw(f));
+            Err(err)
+        }
+    }
+}
@@ -0,0 +1,40 @@
+//! Test that `program::exit` calls `.fini_array` functions without "alloc".
+
+#![no_std]
+#![no_main]
+
+use core::fmt::Write;
+use core::sync::atomic::{AtomicU32, Ordering};
+use origin::program::{self, Stdout};
+
+/// The number of functions which have run so far, to check their order.
+static STEP: AtomicU32 = AtomicU32::new(0);
+
+fn step(expected: u32, name: &str) {
+    assert_eq!(STEP.fetch_add(1, Ordering::Relaxed), expected);
+    writeln!(Stdout, "{}", name).unwrap();
+}
+
+#[no_mangle]
+unsafe fn origin_main(_argc: usize, _argv: *mut *mut u8, _envp: *mut *mut u8) -> i32 {
+    step(1, "main");
+    program::exit(182)
+}
+
+#[link_section = ".init_array"]
+#[used]
+static INIT: extern "C" fn() = {
+    extern "C" fn init() {
+        step(0, "init");
+    }
+    init
+};
+
+#[link_section = ".fini_array"]
+#[used]
+static FINI: extern "C" fn() = {
+    extern "C" fn fini() {
+        step(2, "fini");
+    }
+    fini
+};
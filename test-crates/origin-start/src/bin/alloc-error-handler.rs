@@ -0,0 +1,21 @@
+//! Test that the "origin-alloc-error-handler" feature's default handler
+//! prints a message and traps when an allocation fails.
+
+#![no_std]
+#![no_main]
+
+extern crate alloc;
+
+use origin::program;
+
+#[global_allocator]
+static GLOBAL_ALLOCATOR: rustix_dlmalloc::GlobalDlmalloc = rustix_dlmalloc::GlobalDlmalloc;
+
+#[no_mangle]
+unsafe fn origin_main(_argc: usize, _argv: *mut *mut u8, _envp: *mut *mut u8) -> i32 {
+    // This is too big for the allocator to satisfy.
+    let v = alloc::vec![0_u8; 1 << 46];
+    core::hint::black_box(v);
+
+    program::exit(1);
+}
@@ -0,0 +1,45 @@
+//! Test that `signal::assert_not_in_handler` traps when called from a
+//! handler installed with `signal::set_handler`, here by a global allocator
+//! which checks it.
+
+#![no_std]
+#![no_main]
+
+extern crate alloc;
+
+use alloc::boxed::Box;
+use core::alloc::{GlobalAlloc, Layout};
+use origin::program;
+use origin::signal::{self, Handler, Signal};
+
+struct CheckedAllocator;
+
+unsafe impl GlobalAlloc for CheckedAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        signal::assert_not_in_handler();
+        rustix_dlmalloc::GlobalDlmalloc.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        signal::assert_not_in_handler();
+        rustix_dlmalloc::GlobalDlmalloc.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static GLOBAL_ALLOCATOR: CheckedAllocator = CheckedAllocator;
+
+unsafe extern "C" fn handler(_sig: i32) {
+    core::hint::black_box(Box::new(0_u32));
+}
+
+#[no_mangle]
+unsafe fn origin_main(_argc: usize, _argv: *mut *mut u8, _envp: *mut *mut u8) -> i32 {
+    // Allocating outside of a handler is fine.
+    core::hint::black_box(Box::new(0_u32));
+
+    signal::set_handler(Signal::Usr1, Handler::new(Some(handler))).unwrap();
+    rustix::runtime::tkill(rustix::thread::gettid(), Signal::Usr1).unwrap();
+
+    program::exit(198);
+}
@@ -0,0 +1,63 @@
+//! Test `ArcThread`, joining and detaching through clones.
+
+#![no_std]
+#![no_main]
+
+extern crate alloc;
+
+use core::ptr::NonNull;
+use origin::{program, thread};
+
+#[global_allocator]
+static GLOBAL_ALLOCATOR: rustix_dlmalloc::GlobalDlmalloc = rustix_dlmalloc::GlobalDlmalloc;
+
+#[no_mangle]
+unsafe fn origin_main(_argc: usize, _argv: *mut *mut u8, _envp: *mut *mut u8) -> i32 {
+    // Join the thread through two clones; both see the return value.
+    let thread = thread::ArcThread::new(
+        thread::create(
+            |_args| Some(NonNull::new_unchecked(42 as *mut _)),
+            &[],
+            thread::default_stack_size(),
+            thread::default_guard_size(),
+        )
+        .unwrap(),
+    );
+    let other = thread.clone();
+    assert!(thread.thread() == other.thread());
+    assert_eq!(thread.join().unwrap().as_ptr() as usize, 42);
+    assert_eq!(other.id(), None);
+    assert_eq!(other.join().unwrap().as_ptr() as usize, 42);
+
+    // Detach one clone while the thread is running, then join the other.
+    let thread = thread::ArcThread::new(
+        thread::create(
+            |_args| {
+                thread::yield_current();
+                None
+            },
+            &[],
+            thread::default_stack_size(),
+            thread::default_guard_size(),
+        )
+        .unwrap(),
+    );
+    let other = thread.clone();
+    thread.detach();
+    assert_eq!(other.join(), None);
+
+    // Drop all the handles while the thread may still be running.
+    let thread = thread::ArcThread::new(
+        thread::create(
+            |_args| None,
+            &[],
+            thread::default_stack_size(),
+            thread::default_guard_size(),
+        )
+        .unwrap(),
+    );
+    drop(thread.clone());
+    drop(thread);
+
+    program::exit(235);
+}
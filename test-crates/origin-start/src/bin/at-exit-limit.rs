@@ -0,0 +1,34 @@
+//! Test that functions registered with `at_exit` while exiting may register
+//! up to the limit set by `set_max_exit_registrations`.
+
+#![no_std]
+#![no_main]
+
+extern crate alloc;
+
+use alloc::boxed::Box;
+use core::fmt::Write;
+use core::sync::atomic::{AtomicUsize, Ordering};
+use origin::program;
+
+#[global_allocator]
+static GLOBAL_ALLOCATOR: rustix_dlmalloc::GlobalDlmalloc = rustix_dlmalloc::GlobalDlmalloc;
+
+static COUNT: AtomicUsize = AtomicUsize::new(0);
+
+fn handler() {
+    // Register another handler, until we reach the limit.
+    if COUNT.fetch_add(1, Ordering::Relaxed) < 4 {
+        program::at_exit(Box::new(handler));
+    } else {
+        writeln!(program::Stdout, "{}", COUNT.load(Ordering::Relaxed)).unwrap();
+    }
+}
+
+#[no_mangle]
+unsafe fn origin_main(_argc: usize, _argv: *mut *mut u8, _envp: *mut *mut u8) -> i32 {
+    program::set_max_exit_registrations(4);
+    program::at_exit(Box::new(handler));
+
+    program::exit(236);
+}
@@ -0,0 +1,24 @@
+//! Test that a function registered with `at_exit` which always registers
+//! another function doesn't keep `exit` from finishing.
+
+#![no_std]
+#![no_main]
+
+extern crate alloc;
+
+use alloc::boxed::Box;
+use origin::program;
+
+#[global_allocator]
+static GLOBAL_ALLOCATOR: rustix_dlmalloc::GlobalDlmalloc = rustix_dlmalloc::GlobalDlmalloc;
+
+fn handler() {
+    program::at_exit(Box::new(handler));
+}
+
+#[no_mangle]
+unsafe fn origin_main(_argc: usize, _argv: *mut *mut u8, _envp: *mut *mut u8) -> i32 {
+    program::at_exit(Box::new(handler));
+
+    program::exit(0);
+}
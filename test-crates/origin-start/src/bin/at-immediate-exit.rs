@@ -0,0 +1,40 @@
+//! Test that `program::exit_immediately` calls functions registered with
+//! `program::at_immediate_exit`, but not `program::at_exit`.
+
+#![no_std]
+#![no_main]
+
+extern crate alloc;
+
+use alloc::boxed::Box;
+use core::fmt::Write;
+use origin::program::{self, Stdout};
+
+#[global_allocator]
+static GLOBAL_ALLOCATOR: rustix_dlmalloc::GlobalDlmalloc = rustix_dlmalloc::GlobalDlmalloc;
+
+#[no_mangle]
+unsafe fn origin_main(_argc: usize, _argv: *mut *mut u8, _envp: *mut *mut u8) -> i32 {
+    program::at_exit(Box::new(|| {
+        writeln!(Stdout, "at_exit").unwrap();
+    }));
+    program::at_immediate_exit(|| writeln!(Stdout, "first").unwrap()).unwrap();
+    program::at_immediate_exit(|| {
+        writeln!(Stdout, "second").unwrap();
+
+        // Functions that have already been called aren't called again.
+        program::exit_immediately(251);
+    })
+    .unwrap();
+
+    // Only `MAX_IMMEDIATE_EXIT_FUNCTIONS` functions may be registered.
+    for _ in 2..program::MAX_IMMEDIATE_EXIT_FUNCTIONS {
+        program::at_immediate_exit(|| {}).unwrap();
+    }
+    assert_eq!(
+        program::at_immediate_exit(|| {}),
+        Err(rustix::io::Errno::NOMEM)
+    );
+
+    program::exit_immediately(252);
+}
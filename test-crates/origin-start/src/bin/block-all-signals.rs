@@ -0,0 +1,55 @@
+//! Test that `thread::Builder::block_all_signals` starts a thread with all
+//! signals blocked, and that threads otherwise inherit the creator's mask.
+
+#![no_std]
+#![no_main]
+
+extern crate alloc;
+
+use core::ffi::c_void;
+use core::mem::size_of_val;
+use core::ptr::NonNull;
+use origin::signal::Signal;
+use origin::{program, thread};
+use rustix::runtime::{sigprocmask, How};
+
+#[global_allocator]
+static GLOBAL_ALLOCATOR: rustix_dlmalloc::GlobalDlmalloc = rustix_dlmalloc::GlobalDlmalloc;
+
+/// Return whether `SIGUSR1` is blocked in the current thread.
+fn usr1_blocked() -> bool {
+    let mask = unsafe { sigprocmask(How::BLOCK, None).unwrap() };
+    let word_bits = 8 * size_of_val(&mask.sig[0]);
+    let bit = Signal::Usr1 as usize - 1;
+    mask.sig[bit / word_bits] & (1 << (bit % word_bits)) != 0
+}
+
+/// A thread function which returns non-null if `SIGUSR1` is blocked.
+fn check(_args: &mut [Option<NonNull<c_void>>]) -> Option<NonNull<c_void>> {
+    if usr1_blocked() {
+        Some(NonNull::dangling())
+    } else {
+        None
+    }
+}
+
+#[no_mangle]
+unsafe fn origin_main(_argc: usize, _argv: *mut *mut u8, _envp: *mut *mut u8) -> i32 {
+    assert!(!usr1_blocked());
+
+    // By default, new threads inherit our mask, which doesn't block `SIGUSR1`.
+    let child = thread::Builder::new().create(check, &[]).unwrap();
+    assert_eq!(thread::join(child), None);
+
+    // With `block_all_signals`, the new thread starts with it blocked.
+    let child = thread::Builder::new()
+        .block_all_signals(true)
+        .create(check, &[])
+        .unwrap();
+    assert_ne!(thread::join(child), None);
+
+    // Our own mask is unchanged.
+    assert!(!usr1_blocked());
+
+    program::exit(211);
+}
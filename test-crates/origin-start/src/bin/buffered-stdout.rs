@@ -0,0 +1,39 @@
+//! Test that `BufferedStdout` holds output until it's flushed, and that
+//! `exit` flushes it after calling the functions registered with `at_exit`.
+
+#![no_std]
+#![no_main]
+
+extern crate alloc;
+
+use alloc::boxed::Box;
+use core::fmt::Write;
+use origin::program::{self, BufferedStdout, Stdout};
+
+#[global_allocator]
+static GLOBAL_ALLOCATOR: rustix_dlmalloc::GlobalDlmalloc = rustix_dlmalloc::GlobalDlmalloc;
+
+#[no_mangle]
+unsafe fn origin_main(_argc: usize, _argv: *mut *mut u8, _envp: *mut *mut u8) -> i32 {
+    program::at_exit(Box::new(|| {
+        writeln!(BufferedStdout, "at_exit").unwrap();
+    }));
+
+    writeln!(BufferedStdout, "flushed").unwrap();
+    BufferedStdout.flush().unwrap();
+    writeln!(Stdout, "unbuffered").unwrap();
+
+    // This stays in the buffer until `exit`, so it comes after the
+    // unbuffered output written below.
+    for i in 0..3 {
+        write!(BufferedStdout, "{}", i).unwrap();
+    }
+    writeln!(BufferedStdout).unwrap();
+    writeln!(Stdout, "unbuffered again").unwrap();
+
+    // Writes larger than the buffer go through it.
+    let big = "x".repeat(5000);
+    writeln!(BufferedStdout, "{}", big).unwrap();
+
+    program::exit(243);
+}
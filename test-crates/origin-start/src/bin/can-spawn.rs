@@ -0,0 +1,53 @@
+//! Test `thread::can_spawn`.
+
+#![no_std]
+#![no_main]
+
+extern crate alloc;
+
+use origin::{program, thread};
+use rustix::io;
+use rustix::process::{getrlimit, setrlimit, Resource, Rlimit};
+
+#[global_allocator]
+static GLOBAL_ALLOCATOR: rustix_dlmalloc::GlobalDlmalloc = rustix_dlmalloc::GlobalDlmalloc;
+
+#[no_mangle]
+unsafe fn origin_main(_argc: usize, _argv: *mut *mut u8, _envp: *mut *mut u8) -> i32 {
+    let saved = getrlimit(Resource::Nproc);
+    if saved.current.map_or(true, |limit| limit > 1) {
+        assert!(thread::can_spawn());
+    }
+
+    // Lower the limit to just the main thread.
+    setrlimit(
+        Resource::Nproc,
+        Rlimit {
+            current: Some(1),
+            maximum: saved.maximum,
+        },
+    )
+    .unwrap();
+    assert!(!thread::can_spawn());
+
+    // Privileged processes are exempt from the limit, so `create` may still
+    // succeed, but if it fails, it's with `AGAIN`.
+    match thread::create(
+        |_args| None,
+        &[],
+        thread::default_stack_size(),
+        thread::default_guard_size(),
+    ) {
+        Ok(thread) => {
+            thread::join(thread);
+        }
+        Err(err) => assert_eq!(err, io::Errno::AGAIN),
+    }
+
+    setrlimit(Resource::Nproc, saved).unwrap();
+    if saved.current.map_or(true, |limit| limit > 1) {
+        assert!(thread::can_spawn());
+    }
+
+    program::exit(237);
+}
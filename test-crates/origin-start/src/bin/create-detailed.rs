@@ -0,0 +1,45 @@
+//! Test that `thread::create_detailed` describes the new thread's memory.
+
+#![no_std]
+#![no_main]
+
+use core::ffi::c_void;
+use core::ptr::NonNull;
+use origin::{program, thread};
+
+#[global_allocator]
+static GLOBAL_ALLOCATOR: rustix_dlmalloc::GlobalDlmalloc = rustix_dlmalloc::GlobalDlmalloc;
+
+const STACK_SIZE: usize = 256 * 1024;
+
+#[no_mangle]
+unsafe fn origin_main(_argc: usize, _argv: *mut *mut u8, _envp: *mut *mut u8) -> i32 {
+    let guard_size = rustix::param::page_size();
+    let (child, info) = thread::create_detailed(
+        |_args| {
+            // Return the address of a local variable, to check that it's on
+            // the stack.
+            let local = 0_u8;
+            NonNull::new(core::ptr::addr_of!(local) as *mut c_void)
+        },
+        &[],
+        STACK_SIZE,
+        guard_size,
+    )
+    .unwrap();
+
+    assert_eq!(info.stack_size, STACK_SIZE);
+    assert_eq!(info.guard_size, guard_size);
+    assert_eq!(info.map_addr, info.stack_addr.byte_sub(guard_size));
+    assert!(info.map_size >= guard_size + STACK_SIZE);
+    assert_eq!(
+        thread::stack(child),
+        (info.stack_addr, info.stack_size, guard_size)
+    );
+
+    let local = thread::join(child).unwrap().as_ptr();
+    assert!(local > info.stack_addr);
+    assert!(local < info.stack_addr.byte_add(STACK_SIZE));
+
+    program::exit(187);
+}
@@ -0,0 +1,37 @@
+//! Test that `thread::create` traps when called from a handler installed
+//! with `signal::set_handler`.
+
+#![no_std]
+#![no_main]
+
+use origin::signal::{self, Handler, Signal};
+use origin::{program, thread};
+
+#[global_allocator]
+static GLOBAL_ALLOCATOR: rustix_dlmalloc::GlobalDlmalloc = rustix_dlmalloc::GlobalDlmalloc;
+
+unsafe fn create() {
+    let child = thread::create(
+        |_args| None,
+        &[],
+        thread::default_stack_size(),
+        thread::default_guard_size(),
+    )
+    .unwrap();
+    thread::join(child);
+}
+
+unsafe extern "C" fn handler(_sig: i32) {
+    create();
+}
+
+#[no_mangle]
+unsafe fn origin_main(_argc: usize, _argv: *mut *mut u8, _envp: *mut *mut u8) -> i32 {
+    // Creating a thread outside of a handler is fine.
+    create();
+
+    signal::set_handler(Signal::Usr1, Handler::new(Some(handler))).unwrap();
+    rustix::runtime::tkill(rustix::thread::gettid(), Signal::Usr1).unwrap();
+
+    program::exit(190);
+}
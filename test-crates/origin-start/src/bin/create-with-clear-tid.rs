@@ -0,0 +1,45 @@
+//! Test that `thread::create_with_clear_tid` clears and wakes the given
+//! address when the thread exits.
+
+#![no_std]
+#![no_main]
+
+extern crate alloc;
+
+use core::sync::atomic::{AtomicU32, Ordering};
+use origin::{program, thread};
+use rustix::io;
+use rustix::thread::futex;
+
+#[global_allocator]
+static GLOBAL_ALLOCATOR: rustix_dlmalloc::GlobalDlmalloc = rustix_dlmalloc::GlobalDlmalloc;
+
+static DONE: AtomicU32 = AtomicU32::new(1);
+
+#[no_mangle]
+unsafe fn origin_main(_argc: usize, _argv: *mut *mut u8, _envp: *mut *mut u8) -> i32 {
+    let child = thread::create_with_clear_tid(
+        DONE.as_ptr(),
+        |_args| {
+            // The word isn't cleared until the thread exits.
+            assert_eq!(DONE.load(Ordering::SeqCst), 1);
+            None
+        },
+        &[],
+        thread::default_stack_size(),
+        thread::default_guard_size(),
+    )
+    .unwrap();
+
+    // Wait for the child to clear the word.
+    while DONE.load(Ordering::SeqCst) != 0 {
+        match futex::wait(&DONE, futex::Flags::empty(), 1, None) {
+            Ok(_) | Err(io::Errno::AGAIN) | Err(io::Errno::INTR) => {}
+            Err(err) => panic!("{:?}", err),
+        }
+    }
+
+    thread::join(child);
+
+    program::exit(207);
+}
@@ -0,0 +1,39 @@
+//! Test that `thread::current` on a thread returns the same `Thread` as the
+//! `thread::create` call that created it.
+
+#![no_std]
+#![no_main]
+
+extern crate alloc;
+
+use origin::{program, thread};
+
+#[global_allocator]
+static GLOBAL_ALLOCATOR: rustix_dlmalloc::GlobalDlmalloc = rustix_dlmalloc::GlobalDlmalloc;
+
+#[no_mangle]
+unsafe fn origin_main(_argc: usize, _argv: *mut *mut u8, _envp: *mut *mut u8) -> i32 {
+    // The main thread's identity is stable.
+    assert!(thread::current() == thread::current());
+
+    // Have the child thread return its own identity.
+    let child = thread::create(
+        |_args| {
+            assert!(thread::current() == thread::current());
+            Some(thread::current().to_raw_non_null())
+        },
+        &[],
+        thread::default_stack_size(),
+        thread::default_guard_size(),
+    )
+    .unwrap();
+
+    assert!(child != thread::current());
+
+    // Check that the child saw the same identity that `create` returned.
+    let child_raw = child.to_raw();
+    let returned = thread::join(child).unwrap();
+    assert_eq!(returned.as_ptr(), child_raw);
+
+    program::exit(205);
+}
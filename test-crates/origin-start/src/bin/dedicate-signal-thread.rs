@@ -0,0 +1,97 @@
+//! Test `signal::dedicate_thread_to_signals`.
+
+#![no_std]
+#![no_main]
+
+extern crate alloc;
+
+use core::ffi::{c_int, c_ulong, c_void};
+use core::mem::{size_of_val, zeroed};
+use core::ptr::NonNull;
+use core::sync::atomic::{AtomicBool, AtomicI32, Ordering};
+use origin::signal::{self, Sigaction, Signal, Sigset};
+use origin::{program, thread};
+use rustix::runtime::{sigprocmask, How};
+
+#[global_allocator]
+static GLOBAL_ALLOCATOR: rustix_dlmalloc::GlobalDlmalloc = rustix_dlmalloc::GlobalDlmalloc;
+
+/// Set when the workers should check their masks.
+static CHECK: AtomicBool = AtomicBool::new(false);
+
+/// The id of the thread which handled `SIGUSR1`.
+static HANDLED_BY: AtomicI32 = AtomicI32::new(0);
+
+/// Return the bit for `SIGUSR1` in a `Sigset`, as a word index and mask.
+fn usr1_bit(set: &Sigset) -> (usize, c_ulong) {
+    let word_bits = 8 * size_of_val(&set.sig[0]);
+    let bit = Signal::Usr1 as usize - 1;
+    (bit / word_bits, 1 << (bit % word_bits))
+}
+
+/// Return whether `SIGUSR1` is blocked in the current thread.
+fn usr1_blocked() -> bool {
+    let mask = unsafe { sigprocmask(How::BLOCK, None).unwrap() };
+    let (word, bit) = usr1_bit(&mask);
+    mask.sig[word] & bit != 0
+}
+
+/// A thread function which waits for `CHECK`, and then returns non-null if
+/// `SIGUSR1` is blocked.
+fn worker(_args: &mut [Option<NonNull<c_void>>]) -> Option<NonNull<c_void>> {
+    while !CHECK.load(Ordering::SeqCst) {
+        thread::yield_current();
+    }
+    usr1_blocked().then(NonNull::dangling)
+}
+
+unsafe extern "C" fn handler(_sig: c_int) {
+    HANDLED_BY.store(thread::current_id().as_raw_nonzero().get(), Ordering::SeqCst);
+}
+
+/// A thread function which dedicates itself to `SIGUSR1`, sends `SIGUSR1` to
+/// the process, and returns non-null if it handled it.
+fn dedicated(_args: &mut [Option<NonNull<c_void>>]) -> Option<NonNull<c_void>> {
+    unsafe {
+        let mut action: Sigaction = zeroed();
+        action.sa_handler_kernel = Some(handler);
+        signal::sigaction(Signal::Usr1, Some(action)).unwrap();
+
+        let mut sigs: Sigset = zeroed();
+        let (word, bit) = usr1_bit(&sigs);
+        sigs.sig[word] |= bit;
+        signal::dedicate_thread_to_signals(&sigs).unwrap();
+        assert!(!usr1_blocked());
+
+        rustix::process::kill_process(rustix::process::getpid(), Signal::Usr1).unwrap();
+        while HANDLED_BY.load(Ordering::SeqCst) == 0 {
+            thread::yield_current();
+        }
+    }
+
+    let me = thread::current_id().as_raw_nonzero().get();
+    (HANDLED_BY.load(Ordering::SeqCst) == me).then(NonNull::dangling)
+}
+
+#[no_mangle]
+unsafe fn origin_main(_argc: usize, _argv: *mut *mut u8, _envp: *mut *mut u8) -> i32 {
+    assert!(!usr1_blocked());
+
+    let workers = [
+        thread::Builder::new().create(worker, &[]).unwrap(),
+        thread::Builder::new().create(worker, &[]).unwrap(),
+    ];
+
+    // The dedicated thread handles the signal.
+    let thread = thread::Builder::new().create(dedicated, &[]).unwrap();
+    assert_ne!(thread::join(thread), None);
+
+    // Everyone else has it blocked.
+    assert!(usr1_blocked());
+    CHECK.store(true, Ordering::SeqCst);
+    for worker in workers {
+        assert_ne!(thread::join(worker), None);
+    }
+
+    program::exit(225);
+}
@@ -0,0 +1,104 @@
+//! Test that a detached thread's `thread::at_exit` handlers run while its
+//! `#[thread_local]` data and stack are still valid, before the thread frees
+//! its own memory.
+
+#![no_std]
+#![no_main]
+#![feature(thread_local)]
+
+extern crate alloc;
+
+use alloc::boxed::Box;
+use core::cell::Cell;
+use core::ptr::addr_of;
+use core::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use origin::{program, thread};
+
+#[global_allocator]
+static GLOBAL_ALLOCATOR: rustix_dlmalloc::GlobalDlmalloc = rustix_dlmalloc::GlobalDlmalloc;
+
+#[thread_local]
+static VALUE: Cell<usize> = Cell::new(0);
+
+/// The number of handlers which have checked their thread's state.
+static HANDLERS_RAN: AtomicUsize = AtomicUsize::new(0);
+
+/// Set once the main thread has detached the second thread.
+static DETACHED: AtomicBool = AtomicBool::new(false);
+
+/// Register a handler which checks that this thread's `VALUE` is where it
+/// was, holds `value`, and that the handler is running on this thread's
+/// stack.
+fn register_handler(value: usize) {
+    VALUE.set(value);
+    let addr = addr_of!(VALUE) as usize;
+
+    thread::at_exit(Box::new(move || {
+        assert_eq!(addr_of!(VALUE) as usize, addr);
+        assert_eq!(VALUE.get(), value);
+        VALUE.set(0);
+
+        // SAFETY: The current thread is running, so its memory is valid.
+        let (stack_addr, stack_size, _guard_size) = unsafe { thread::stack(thread::current()) };
+        let local = 0_u8;
+        let local = addr_of!(local) as usize;
+        assert!(local >= stack_addr as usize && local < stack_addr as usize + stack_size);
+
+        HANDLERS_RAN.fetch_add(1, Ordering::AcqRel);
+    }));
+}
+
+#[no_mangle]
+unsafe fn origin_main(_argc: usize, _argv: *mut *mut u8, _envp: *mut *mut u8) -> i32 {
+    // A thread which detaches itself before it exits.
+    let _thread = thread::create(
+        |_args| {
+            register_handler(1);
+            thread::detach(thread::current());
+            None
+        },
+        &[],
+        thread::default_stack_size(),
+        thread::default_guard_size(),
+    )
+    .unwrap();
+
+    // A thread which is detached by the main thread before it exits.
+    let thread = thread::create(
+        |_args| {
+            register_handler(2);
+            while !DETACHED.load(Ordering::Acquire) {
+                thread::yield_current();
+            }
+            None
+        },
+        &[],
+        thread::default_stack_size(),
+        thread::default_guard_size(),
+    )
+    .unwrap();
+    thread::detach(thread);
+    DETACHED.store(true, Ordering::Release);
+
+    while HANDLERS_RAN.load(Ordering::Acquire) != 2 {
+        thread::yield_current();
+    }
+
+    // Create more threads, which may reuse the detached threads' memory once
+    // they've freed it.
+    for _ in 0..4 {
+        let thread = thread::create(
+            |_args| {
+                assert_eq!(VALUE.get(), 0);
+                None
+            },
+            &[],
+            thread::default_stack_size(),
+            thread::default_guard_size(),
+        )
+        .unwrap();
+        thread::join(thread);
+    }
+
+    program::exit(185);
+}
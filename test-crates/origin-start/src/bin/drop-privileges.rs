@@ -0,0 +1,50 @@
+//! Test `program::chroot` and `program::drop_privileges`.
+
+#![no_std]
+#![no_main]
+
+use origin::program::{self, Gid, Uid};
+use rustix::io;
+use rustix::process::{getegid, geteuid, getgid, getuid};
+
+#[global_allocator]
+static GLOBAL_ALLOCATOR: rustix_dlmalloc::GlobalDlmalloc = rustix_dlmalloc::GlobalDlmalloc;
+
+#[no_mangle]
+unsafe fn origin_main(_argc: usize, _argv: *mut *mut u8, _envp: *mut *mut u8) -> i32 {
+    let (uid, gid) = (getuid(), getgid());
+
+    if !uid.is_root() {
+        // Without privileges, both fail, and nothing changes.
+        assert_eq!(program::chroot(c"/"), Err(io::Errno::PERM));
+        assert_eq!(
+            program::drop_privileges(uid, gid, &[]),
+            Err(io::Errno::PERM)
+        );
+        assert_eq!(
+            (getuid(), geteuid(), getgid(), getegid()),
+            (uid, uid, gid, gid)
+        );
+        program::exit(184);
+    }
+
+    // Changing the root to "/" doesn't change what paths refer to.
+    program::chroot(c"/").unwrap();
+
+    let nobody_uid = Uid::from_raw(65534);
+    let nobody_gid = Gid::from_raw(65534);
+    program::drop_privileges(nobody_uid, nobody_gid, &[nobody_gid]).unwrap();
+    assert_eq!(
+        (getuid(), geteuid(), getgid(), getegid()),
+        (nobody_uid, nobody_uid, nobody_gid, nobody_gid)
+    );
+
+    // The privileges can't be regained.
+    assert_eq!(
+        program::drop_privileges(Uid::ROOT, Gid::ROOT, &[]),
+        Err(io::Errno::PERM)
+    );
+    assert_eq!(program::chroot(c"/"), Err(io::Errno::PERM));
+
+    program::exit(184);
+}
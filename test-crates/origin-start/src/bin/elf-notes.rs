@@ -0,0 +1,21 @@
+//! Test `program::build_id` and the "abi-tag" feature.
+
+#![no_std]
+#![no_main]
+
+extern crate alloc;
+
+use origin::program;
+
+#[global_allocator]
+static GLOBAL_ALLOCATOR: rustix_dlmalloc::GlobalDlmalloc = rustix_dlmalloc::GlobalDlmalloc;
+
+#[no_mangle]
+unsafe fn origin_main(_argc: usize, _argv: *mut *mut u8, _envp: *mut *mut u8) -> i32 {
+    // The test links with `--build-id=sha1`, which produces a 20-byte ID.
+    let id = program::build_id().unwrap();
+    assert_eq!(id.len(), 20);
+    assert!(id.iter().any(|b| *b != 0));
+
+    program::exit(233)
+}
@@ -0,0 +1,38 @@
+//! Test that `thread::errno` and `thread::set_errno` access a per-thread
+//! `errno` value.
+
+#![no_std]
+#![no_main]
+
+use origin::{program, thread};
+use rustix::io;
+
+#[global_allocator]
+static GLOBAL_ALLOCATOR: rustix_dlmalloc::GlobalDlmalloc = rustix_dlmalloc::GlobalDlmalloc;
+
+#[no_mangle]
+unsafe fn origin_main(_argc: usize, _argv: *mut *mut u8, _envp: *mut *mut u8) -> i32 {
+    assert_eq!(thread::errno(), 0);
+    thread::set_errno(io::Errno::NOENT.raw_os_error());
+    assert_eq!(thread::errno(), io::Errno::NOENT.raw_os_error());
+    assert_eq!(*thread::errno_location(), io::Errno::NOENT.raw_os_error());
+
+    // Other threads have their own `errno`, which starts out as zero.
+    let child = thread::create(
+        |_args| {
+            assert_eq!(thread::errno(), 0);
+            thread::set_errno(io::Errno::INVAL.raw_os_error());
+            assert_eq!(thread::errno(), io::Errno::INVAL.raw_os_error());
+            None
+        },
+        &[],
+        thread::default_stack_size(),
+        thread::default_guard_size(),
+    )
+    .unwrap();
+    thread::join(child);
+
+    assert_eq!(thread::errno(), io::Errno::NOENT.raw_os_error());
+
+    program::exit(192);
+}
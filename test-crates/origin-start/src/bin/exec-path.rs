@@ -0,0 +1,35 @@
+//! Test `program::exec_path` and `program::current_dir`.
+
+#![no_std]
+#![no_main]
+
+extern crate alloc;
+
+use origin::program;
+use rustix::io::Errno;
+
+#[global_allocator]
+static GLOBAL_ALLOCATOR: rustix_dlmalloc::GlobalDlmalloc = rustix_dlmalloc::GlobalDlmalloc;
+
+#[no_mangle]
+unsafe fn origin_main(_argc: usize, _argv: *mut *mut u8, _envp: *mut *mut u8) -> i32 {
+    assert!(program::exec_path().to_bytes().ends_with(b"exec-path"));
+    assert_eq!(program::exec_path(), program::name());
+
+    let mut buf = [0_u8; 4096];
+    let dir = program::current_dir(&mut buf).unwrap();
+    assert!(dir.to_bytes().starts_with(b"/"));
+    let len = dir.to_bytes_with_nul().len();
+
+    // The buffer needs room for the NUL terminator.
+    assert_eq!(
+        program::current_dir(&mut buf[..len - 1]).unwrap_err(),
+        Errno::RANGE
+    );
+    assert_eq!(program::current_dir(&mut []).unwrap_err(), Errno::RANGE);
+
+    rustix::process::chdir(c"/").unwrap();
+    assert_eq!(program::current_dir(&mut buf).unwrap(), c"/");
+
+    program::exit(180);
+}
@@ -0,0 +1,23 @@
+//! Test that `origin::main!` reports return values with `Termination`, using
+//! `std`'s exit code conventions.
+
+#![no_std]
+#![no_main]
+
+use origin::program::{ExitCode, Termination};
+
+#[global_allocator]
+static GLOBAL_ALLOCATOR: rustix_dlmalloc::GlobalDlmalloc = rustix_dlmalloc::GlobalDlmalloc;
+
+unsafe fn main(_argc: usize, _argv: *mut *mut u8, _envp: *mut *mut u8) -> Result<(), &'static str> {
+    assert_eq!(().report(), 0);
+    assert_eq!(ExitCode::SUCCESS.report(), 0);
+    assert_eq!(ExitCode::FAILURE.report(), 1);
+    assert_eq!(ExitCode::from_raw(193).report(), 193);
+    assert_eq!(ExitCode::from(193).to_i32(), 193);
+    assert_eq!(Ok::<_, ()>(ExitCode::from_raw(193)).report(), 193);
+
+    Err("oops")
+}
+
+origin::main!(main);
@@ -0,0 +1,28 @@
+//! Test that `program::exit_vfork_child` exits without calling any functions
+//! registered with `at_exit` or `.fini_array`.
+
+#![no_std]
+#![no_main]
+
+extern crate alloc;
+
+use alloc::boxed::Box;
+use origin::program;
+
+#[global_allocator]
+static GLOBAL_ALLOCATOR: rustix_dlmalloc::GlobalDlmalloc = rustix_dlmalloc::GlobalDlmalloc;
+
+extern "C" fn fini() {
+    program::exit_immediately(1);
+}
+
+#[used]
+#[link_section = ".fini_array"]
+static FINI_ARRAY: [extern "C" fn(); 1] = [fini];
+
+#[no_mangle]
+unsafe fn origin_main(_argc: usize, _argv: *mut *mut u8, _envp: *mut *mut u8) -> i32 {
+    program::at_exit(Box::new(|| program::exit_immediately(2)));
+
+    program::exit_vfork_child(228)
+}
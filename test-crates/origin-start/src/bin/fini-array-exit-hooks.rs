@@ -0,0 +1,42 @@
+//! Test that a `.fini_array` function calling `exit` doesn't cause the
+//! functions registered with `thread::register_global_exit_hook` to be called
+//! again.
+
+#![no_std]
+#![no_main]
+
+extern crate alloc;
+
+use core::sync::atomic::{AtomicU32, Ordering};
+use origin::program;
+use origin::thread::{self, Thread};
+
+#[global_allocator]
+static GLOBAL_ALLOCATOR: rustix_dlmalloc::GlobalDlmalloc = rustix_dlmalloc::GlobalDlmalloc;
+
+static HOOKS: AtomicU32 = AtomicU32::new(0);
+static FINIS: AtomicU32 = AtomicU32::new(0);
+
+fn hook(_exiting: Thread) {
+    if HOOKS.fetch_add(1, Ordering::SeqCst) != 0 {
+        program::exit_immediately(1);
+    }
+}
+
+/// This is called after the hook, and calls `exit` again.
+extern "C" fn fini() {
+    if FINIS.fetch_add(1, Ordering::SeqCst) != 0 || HOOKS.load(Ordering::SeqCst) != 1 {
+        program::exit_immediately(2);
+    }
+    program::exit(219);
+}
+
+#[used]
+#[link_section = ".fini_array"]
+static FINI_ARRAY: [extern "C" fn(); 1] = [fini];
+
+#[no_mangle]
+unsafe fn origin_main(_argc: usize, _argv: *mut *mut u8, _envp: *mut *mut u8) -> i32 {
+    thread::register_global_exit_hook(hook);
+    0
+}
@@ -0,0 +1,40 @@
+//! Test that a `.fini_array` function calling `exit` doesn't cause earlier
+//! `.fini_array` functions to be called again.
+
+#![no_std]
+#![no_main]
+
+extern crate alloc;
+
+use core::sync::atomic::{AtomicU32, Ordering};
+use origin::program;
+
+#[global_allocator]
+static GLOBAL_ALLOCATOR: rustix_dlmalloc::GlobalDlmalloc = rustix_dlmalloc::GlobalDlmalloc;
+
+static COUNT: AtomicU32 = AtomicU32::new(0);
+
+/// `.fini_array` functions are called in reverse order, so this is called
+/// second, from the nested `exit`.
+extern "C" fn second() {
+    if COUNT.fetch_add(1, Ordering::SeqCst) != 1 {
+        program::exit_immediately(2);
+    }
+}
+
+/// This is called first, and calls `exit` again.
+extern "C" fn first() {
+    if COUNT.fetch_add(1, Ordering::SeqCst) != 0 {
+        program::exit_immediately(1);
+    }
+    program::exit(218);
+}
+
+#[used]
+#[link_section = ".fini_array"]
+static FINI_ARRAY: [extern "C" fn(); 2] = [second, first];
+
+#[no_mangle]
+unsafe fn origin_main(_argc: usize, _argv: *mut *mut u8, _envp: *mut *mut u8) -> i32 {
+    0
+}
@@ -0,0 +1,38 @@
+//! Test the C-compatible `getauxval` function.
+
+#![no_std]
+#![no_main]
+
+extern crate alloc;
+
+use core::ffi::c_ulong;
+use origin::program;
+
+#[global_allocator]
+static GLOBAL_ALLOCATOR: rustix_dlmalloc::GlobalDlmalloc = rustix_dlmalloc::GlobalDlmalloc;
+
+extern "C" {
+    fn getauxval(type_: c_ulong) -> c_ulong;
+}
+
+const AT_PAGESZ: c_ulong = 6;
+const AT_UID: c_ulong = 11;
+const AT_GID: c_ulong = 13;
+
+#[no_mangle]
+unsafe fn origin_main(_argc: usize, _argv: *mut *mut u8, _envp: *mut *mut u8) -> i32 {
+    assert_eq!(getauxval(AT_PAGESZ), 4096);
+    assert_eq!(
+        getauxval(AT_UID),
+        rustix::process::getuid().as_raw() as c_ulong
+    );
+    assert_eq!(
+        getauxval(AT_GID),
+        rustix::process::getgid().as_raw() as c_ulong
+    );
+
+    // Unrecognized types return 0.
+    assert_eq!(getauxval(0xdead_beef), 0);
+
+    program::exit(222);
+}
@@ -0,0 +1,66 @@
+//! Test that functions registered with `thread::register_global_exit_hook`
+//! are called when each thread exits, after its `thread::at_exit` functions,
+//! and on the main thread before `program::at_exit` functions.
+
+#![no_std]
+#![no_main]
+
+extern crate alloc;
+
+use alloc::boxed::Box;
+use core::fmt::Write;
+use core::sync::atomic::{AtomicUsize, Ordering};
+use origin::program::{self, Stdout};
+use origin::thread::{self, Thread};
+
+#[global_allocator]
+static GLOBAL_ALLOCATOR: rustix_dlmalloc::GlobalDlmalloc = rustix_dlmalloc::GlobalDlmalloc;
+
+/// The number of `thread::at_exit` functions that have been called.
+static AT_EXITS: AtomicUsize = AtomicUsize::new(0);
+
+/// The number of times `hook` has been called.
+static HOOKS: AtomicUsize = AtomicUsize::new(0);
+
+fn hook(exiting: Thread) {
+    assert!(exiting == thread::current());
+
+    // The hook for each thread runs after that thread's `at_exit` function.
+    let hooks = HOOKS.fetch_add(1, Ordering::SeqCst);
+    assert_eq!(AT_EXITS.load(Ordering::SeqCst), hooks + 1);
+    writeln!(Stdout, "hook").unwrap();
+}
+
+#[no_mangle]
+unsafe fn origin_main(_argc: usize, _argv: *mut *mut u8, _envp: *mut *mut u8) -> i32 {
+    thread::register_global_exit_hook(hook);
+
+    program::at_exit(Box::new(|| {
+        assert_eq!(HOOKS.load(Ordering::SeqCst), 3);
+        writeln!(Stdout, "program::at_exit").unwrap();
+    }));
+    thread::at_exit(Box::new(|| {
+        AT_EXITS.fetch_add(1, Ordering::SeqCst);
+        writeln!(Stdout, "main thread::at_exit").unwrap();
+    }));
+
+    for _ in 0..2 {
+        let thread = thread::create(
+            |_args| {
+                thread::at_exit(Box::new(|| {
+                    AT_EXITS.fetch_add(1, Ordering::SeqCst);
+                    writeln!(Stdout, "thread::at_exit").unwrap();
+                }));
+                None
+            },
+            &[],
+            thread::default_stack_size(),
+            thread::default_guard_size(),
+        )
+        .unwrap();
+        thread::join(thread);
+    }
+    assert_eq!(HOOKS.load(Ordering::SeqCst), 2);
+
+    program::exit(249)
+}
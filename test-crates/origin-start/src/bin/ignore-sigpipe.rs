@@ -0,0 +1,24 @@
+//! Test that the "ignore-sigpipe" feature ignores `SIGPIPE` at startup.
+
+#![no_std]
+#![no_main]
+
+use origin::program;
+use origin::signal::{self, Signal};
+
+#[global_allocator]
+static GLOBAL_ALLOCATOR: rustix_dlmalloc::GlobalDlmalloc = rustix_dlmalloc::GlobalDlmalloc;
+
+#[no_mangle]
+unsafe fn origin_main(_argc: usize, _argv: *mut *mut u8, _envp: *mut *mut u8) -> i32 {
+    let action = signal::sigaction(Signal::Pipe, None).unwrap();
+    assert_eq!(
+        action.sa_handler_kernel.map(|handler| handler as usize),
+        signal::sig_ign().map(|handler| handler as usize)
+    );
+
+    // Sending ourselves `SIGPIPE` doesn't terminate the program.
+    rustix::runtime::tkill(rustix::thread::gettid(), Signal::Pipe).unwrap();
+
+    program::exit(191);
+}
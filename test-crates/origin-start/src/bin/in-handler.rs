@@ -0,0 +1,60 @@
+//! Test that `signal::in_handler` is true only while a handler installed with
+//! `signal::set_handler` is running, and that the previous action returned
+//! by `set_handler` can be reinstalled with `signal::sigaction`, though it
+//! isn't tracked then.
+
+#![no_std]
+#![no_main]
+
+use core::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+use origin::program;
+use origin::signal::{self, Handler, Signal};
+
+#[global_allocator]
+static GLOBAL_ALLOCATOR: rustix_dlmalloc::GlobalDlmalloc = rustix_dlmalloc::GlobalDlmalloc;
+
+static FIRST: AtomicU32 = AtomicU32::new(0);
+static SECOND: AtomicU32 = AtomicU32::new(0);
+static FIRST_IN_HANDLER: AtomicBool = AtomicBool::new(false);
+
+unsafe extern "C" fn first(_sig: i32) {
+    FIRST_IN_HANDLER.store(signal::in_handler(), Ordering::SeqCst);
+    FIRST.fetch_add(1, Ordering::SeqCst);
+}
+
+unsafe extern "C" fn second(_sig: i32) {
+    assert!(signal::in_handler());
+    SECOND.fetch_add(1, Ordering::SeqCst);
+}
+
+unsafe fn raise() {
+    rustix::runtime::tkill(rustix::thread::gettid(), Signal::Usr1).unwrap();
+}
+
+#[no_mangle]
+unsafe fn origin_main(_argc: usize, _argv: *mut *mut u8, _envp: *mut *mut u8) -> i32 {
+    assert!(!signal::in_handler());
+
+    let default = signal::set_handler(Signal::Usr1, Handler::new(Some(first))).unwrap();
+    raise();
+    assert_eq!(FIRST.load(Ordering::SeqCst), 1);
+    assert!(FIRST_IN_HANDLER.load(Ordering::SeqCst));
+    assert!(!signal::in_handler());
+
+    let old = signal::set_handler(Signal::Usr1, Handler::new(Some(second))).unwrap();
+    raise();
+    assert_eq!(SECOND.load(Ordering::SeqCst), 1);
+
+    // Reinstalling the previous action restores the first handler, which
+    // is called directly now.
+    signal::sigaction(Signal::Usr1, Some(old)).unwrap();
+    raise();
+    assert_eq!(FIRST.load(Ordering::SeqCst), 2);
+    assert_eq!(SECOND.load(Ordering::SeqCst), 1);
+    assert!(!FIRST_IN_HANDLER.load(Ordering::SeqCst));
+
+    signal::sigaction(Signal::Usr1, Some(default)).unwrap();
+    signal::assert_not_in_handler();
+
+    program::exit(199);
+}
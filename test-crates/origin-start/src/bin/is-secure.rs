@@ -0,0 +1,19 @@
+//! Test that `program::is_secure` reports that a normal program isn't in
+//! secure execution mode.
+
+#![no_std]
+#![no_main]
+
+extern crate alloc;
+
+use origin::program;
+
+#[global_allocator]
+static GLOBAL_ALLOCATOR: rustix_dlmalloc::GlobalDlmalloc = rustix_dlmalloc::GlobalDlmalloc;
+
+#[no_mangle]
+unsafe fn origin_main(_argc: usize, _argv: *mut *mut u8, _envp: *mut *mut u8) -> i32 {
+    assert!(!program::is_secure());
+
+    program::exit(230)
+}
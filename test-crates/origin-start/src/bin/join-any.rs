@@ -0,0 +1,62 @@
+//! Test `thread::join_any`.
+
+#![no_std]
+#![no_main]
+
+extern crate alloc;
+
+use core::ffi::c_void;
+use core::ptr::NonNull;
+use core::sync::atomic::{AtomicBool, Ordering};
+use origin::{program, thread};
+
+#[global_allocator]
+static GLOBAL_ALLOCATOR: rustix_dlmalloc::GlobalDlmalloc = rustix_dlmalloc::GlobalDlmalloc;
+
+static RELEASE: AtomicBool = AtomicBool::new(false);
+
+/// Create a thread which returns its argument, after waiting for `RELEASE`
+/// if `wait` is true.
+unsafe fn spawn(value: usize, wait: bool) -> thread::Thread {
+    let fn_ = if wait {
+        |args: &mut [Option<NonNull<c_void>>]| {
+            while !RELEASE.load(Ordering::SeqCst) {
+                thread::yield_current();
+            }
+            args[0]
+        }
+    } else {
+        |args: &mut [Option<NonNull<c_void>>]| args[0]
+    };
+    thread::create(
+        fn_,
+        &[NonNull::new(value as *mut c_void)],
+        thread::default_stack_size(),
+        thread::default_guard_size(),
+    )
+    .unwrap()
+}
+
+#[no_mangle]
+unsafe fn origin_main(_argc: usize, _argv: *mut *mut u8, _envp: *mut *mut u8) -> i32 {
+    // Only the middle thread can exit, so it's the one we get.
+    let mut threads = [spawn(1, true), spawn(2, false), spawn(3, true)];
+    let (index, value) = thread::join_any(&threads);
+    assert_eq!(index, 1);
+    assert_eq!(value.unwrap().as_ptr() as usize, 2);
+
+    // Let the others exit, and join them.
+    RELEASE.store(true, Ordering::SeqCst);
+    threads.swap(1, 2);
+    let mut seen = 0;
+    for remaining in [2, 1] {
+        let (index, value) = thread::join_any(&threads[..remaining]);
+        let value = value.unwrap().as_ptr() as usize;
+        assert!(value == 1 || value == 3);
+        seen += value;
+        threads.swap(index, remaining - 1);
+    }
+    assert_eq!(seen, 1 + 3);
+
+    program::exit(223);
+}
@@ -0,0 +1,73 @@
+//! Test that large TLS segments, which are big enough that initializing them
+//! is lowered to real `memcpy` and `memset` calls, are initialized correctly
+//! on both the main thread and child threads.
+
+#![no_std]
+#![no_main]
+#![feature(thread_local)]
+
+extern crate alloc;
+
+use core::cell::UnsafeCell;
+use origin::{program, thread};
+
+#[global_allocator]
+static GLOBAL_ALLOCATOR: rustix_dlmalloc::GlobalDlmalloc = rustix_dlmalloc::GlobalDlmalloc;
+
+const LEN: usize = 64 * 1024;
+
+/// Initialized TLS data, in `.tdata`.
+#[thread_local]
+static DATA: UnsafeCell<[u8; LEN]> = UnsafeCell::new(pattern());
+
+/// Zero-initialized TLS data, in `.tbss`.
+#[thread_local]
+static ZEROS: UnsafeCell<[u8; LEN]> = UnsafeCell::new([0; LEN]);
+
+const fn pattern() -> [u8; LEN] {
+    let mut data = [0; LEN];
+    let mut i = 0;
+    while i < LEN {
+        data[i] = (i % 251) as u8 + 1;
+        i += 1;
+    }
+    data
+}
+
+unsafe fn check_and_scribble() {
+    let data = &mut *DATA.get();
+    let zeros = &mut *ZEROS.get();
+
+    // Avoid comparing against `pattern()` directly, as that would put a large
+    // array on the stack.
+    for (i, b) in data.iter().enumerate() {
+        assert_eq!(*b, (i % 251) as u8 + 1);
+    }
+    assert!(zeros.iter().all(|b| *b == 0));
+
+    // Scribble over the data, so that other threads can check that they get
+    // their own fresh copies.
+    data.fill(0xa5);
+    zeros.fill(0x5a);
+}
+
+#[no_mangle]
+unsafe fn origin_main(_argc: usize, _argv: *mut *mut u8, _envp: *mut *mut u8) -> i32 {
+    check_and_scribble();
+
+    for _ in 0..4 {
+        let child = thread::create(
+            |_args| {
+                check_and_scribble();
+                None
+            },
+            &[],
+            thread::default_stack_size(),
+            thread::default_guard_size(),
+        )
+        .unwrap();
+        thread::join(child);
+    }
+
+    program::exit(210);
+}
@@ -0,0 +1,44 @@
+//! Test that `thread::lock_tls_in_memory` works on the main thread and on
+//! created threads.
+
+#![no_std]
+#![no_main]
+#![feature(thread_local)]
+
+extern crate alloc;
+
+use core::cell::Cell;
+use origin::{program, thread};
+
+#[global_allocator]
+static GLOBAL_ALLOCATOR: rustix_dlmalloc::GlobalDlmalloc = rustix_dlmalloc::GlobalDlmalloc;
+
+#[thread_local]
+static SECRET: Cell<u64> = Cell::new(0);
+
+#[no_mangle]
+unsafe fn origin_main(_argc: usize, _argv: *mut *mut u8, _envp: *mut *mut u8) -> i32 {
+    thread::lock_tls_in_memory().unwrap();
+    SECRET.set(0x5ec4e7);
+
+    let child = thread::create(
+        |_args| {
+            thread::lock_tls_in_memory().unwrap();
+            SECRET.set(0xc0ffee);
+            assert_eq!(SECRET.get(), 0xc0ffee);
+            None
+        },
+        &[],
+        thread::default_stack_size(),
+        thread::default_guard_size(),
+    )
+    .unwrap();
+
+    thread::join(child);
+
+    // Locking is idempotent.
+    thread::lock_tls_in_memory().unwrap();
+    assert_eq!(SECRET.get(), 0x5ec4e7);
+
+    program::exit(206);
+}
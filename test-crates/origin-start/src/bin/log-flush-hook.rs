@@ -0,0 +1,27 @@
+//! Test that `program::exit` calls the function registered with
+//! `program::set_log_flush_hook`, after functions registered with
+//! `program::at_exit`.
+
+#![no_std]
+#![no_main]
+
+extern crate alloc;
+
+use alloc::boxed::Box;
+use core::fmt::Write;
+use origin::program::{self, Stdout};
+
+#[global_allocator]
+static GLOBAL_ALLOCATOR: rustix_dlmalloc::GlobalDlmalloc = rustix_dlmalloc::GlobalDlmalloc;
+
+fn flush() {
+    writeln!(Stdout, "flushed").unwrap();
+}
+
+#[no_mangle]
+unsafe fn origin_main(_argc: usize, _argv: *mut *mut u8, _envp: *mut *mut u8) -> i32 {
+    program::set_log_flush_hook(flush);
+    program::at_exit(Box::new(|| writeln!(Stdout, "at_exit").unwrap()));
+
+    program::exit(189);
+}
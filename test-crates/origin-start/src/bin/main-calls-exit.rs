@@ -0,0 +1,30 @@
+//! Test that when `origin_main` calls `program::exit` itself and never
+//! returns, functions registered with `at_exit` and `.fini_array` are called
+//! exactly once.
+
+#![no_std]
+#![no_main]
+
+extern crate alloc;
+
+use alloc::boxed::Box;
+use core::fmt::Write;
+use origin::program::{self, Stdout};
+
+#[global_allocator]
+static GLOBAL_ALLOCATOR: rustix_dlmalloc::GlobalDlmalloc = rustix_dlmalloc::GlobalDlmalloc;
+
+extern "C" fn fini() {
+    writeln!(Stdout, "fini").unwrap();
+}
+
+#[used]
+#[link_section = ".fini_array"]
+static FINI_ARRAY: [extern "C" fn(); 1] = [fini];
+
+#[no_mangle]
+unsafe fn origin_main(_argc: usize, _argv: *mut *mut u8, _envp: *mut *mut u8) -> i32 {
+    program::at_exit(Box::new(|| writeln!(Stdout, "at_exit").unwrap()));
+
+    program::exit(5)
+}
@@ -0,0 +1,30 @@
+//! Test that `origin::main!` dispatches on the name the program was invoked
+//! with.
+
+#![no_std]
+#![no_main]
+
+use origin::program;
+
+#[global_allocator]
+static GLOBAL_ALLOCATOR: rustix_dlmalloc::GlobalDlmalloc = rustix_dlmalloc::GlobalDlmalloc;
+
+unsafe fn other_main(_argc: usize, _argv: *mut *mut u8, _envp: *mut *mut u8) -> i32 {
+    program::exit(1);
+}
+
+unsafe fn named_main(argc: usize, argv: *mut *mut u8, _envp: *mut *mut u8) -> i32 {
+    assert!(argc >= 1);
+    assert!(!(*argv).is_null());
+    program::exit(195);
+}
+
+unsafe fn default_main(_argc: usize, _argv: *mut *mut u8, _envp: *mut *mut u8) -> i32 {
+    program::exit(2);
+}
+
+origin::main! {
+    "other" => other_main,
+    "main-macro" => named_main,
+    _ => default_main,
+}
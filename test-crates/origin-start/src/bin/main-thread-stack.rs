@@ -0,0 +1,50 @@
+//! Test `thread::stack` on the main thread, including when the stack size
+//! limit is unlimited.
+
+#![no_std]
+#![no_main]
+
+extern crate alloc;
+
+use origin::{program, thread};
+use rustix::process::{getrlimit, setrlimit, Resource, Rlimit};
+
+#[global_allocator]
+static GLOBAL_ALLOCATOR: rustix_dlmalloc::GlobalDlmalloc = rustix_dlmalloc::GlobalDlmalloc;
+
+/// Check that a local variable is within the main thread's stack.
+unsafe fn check_stack() -> usize {
+    let local = 0_u8;
+    let local = core::ptr::addr_of!(local) as usize;
+    let (addr, size, guard_size) = thread::stack(thread::current());
+    assert!(addr as usize <= local);
+    assert!(local < addr as usize + size);
+    assert_ne!(guard_size, 0);
+    size
+}
+
+#[no_mangle]
+unsafe fn origin_main(_argc: usize, _argv: *mut *mut u8, _envp: *mut *mut u8) -> i32 {
+    let saved = getrlimit(Resource::Stack);
+    let size = check_stack();
+    if let Some(limit) = saved.current {
+        assert!(size < limit as usize);
+    }
+
+    // With an unlimited stack size limit, origin assumes Linux's default.
+    if saved.maximum.is_none() {
+        setrlimit(
+            Resource::Stack,
+            Rlimit {
+                current: None,
+                maximum: None,
+            },
+        )
+        .unwrap();
+        let size = check_stack();
+        assert!(size < 8 * 1024 * 1024);
+        setrlimit(Resource::Stack, saved).unwrap();
+    }
+
+    program::exit(238);
+}
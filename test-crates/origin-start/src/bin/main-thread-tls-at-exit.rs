@@ -0,0 +1,61 @@
+//! Test that the main thread's `#[thread_local]` data remains valid in
+//! `thread::at_exit` and `program::at_exit` handlers, and `.fini_array`
+//! functions, while the program exits.
+
+#![no_std]
+#![no_main]
+#![feature(thread_local)]
+
+extern crate alloc;
+
+use alloc::boxed::Box;
+use core::cell::Cell;
+use core::fmt::Write;
+use core::ptr::addr_of;
+use core::sync::atomic::{AtomicUsize, Ordering};
+use origin::program::{self, Stdout};
+use origin::thread;
+
+#[global_allocator]
+static GLOBAL_ALLOCATOR: rustix_dlmalloc::GlobalDlmalloc = rustix_dlmalloc::GlobalDlmalloc;
+
+#[thread_local]
+static VALUE: Cell<u32> = Cell::new(1);
+
+/// The address of `VALUE` on the main thread.
+static ADDR: AtomicUsize = AtomicUsize::new(0);
+
+/// Check that `VALUE` is where it was in `origin_main` and holds `expected`,
+/// and then change it.
+fn check_and_set(expected: u32, new: u32) {
+    assert_eq!(addr_of!(VALUE) as usize, ADDR.load(Ordering::Relaxed));
+    assert_eq!(VALUE.get(), expected);
+    VALUE.set(new);
+}
+
+#[no_mangle]
+unsafe fn origin_main(_argc: usize, _argv: *mut *mut u8, _envp: *mut *mut u8) -> i32 {
+    ADDR.store(addr_of!(VALUE) as usize, Ordering::Relaxed);
+    check_and_set(1, 2);
+
+    program::at_exit(Box::new(|| {
+        check_and_set(3, 4);
+        writeln!(Stdout, "program::at_exit").unwrap();
+    }));
+    thread::at_exit(Box::new(|| {
+        check_and_set(2, 3);
+        writeln!(Stdout, "thread::at_exit").unwrap();
+    }));
+
+    program::exit(241)
+}
+
+#[link_section = ".fini_array"]
+#[used]
+static FINI: extern "C" fn() = {
+    extern "C" fn fini() {
+        check_and_set(4, 5);
+        writeln!(Stdout, "fini").unwrap();
+    }
+    fini
+};
@@ -0,0 +1,68 @@
+//! Test that a signal handler can redirect execution with
+//! `signal::set_mcontext_pc` and `signal::set_mcontext_sp`.
+
+#![no_std]
+#![no_main]
+
+use core::ffi::c_void;
+use core::sync::atomic::{AtomicUsize, Ordering};
+use linux_raw_sys::general::SA_SIGINFO;
+use origin::program;
+use origin::signal::{self, Siginfo, Signal};
+
+#[global_allocator]
+static GLOBAL_ALLOCATOR: rustix_dlmalloc::GlobalDlmalloc = rustix_dlmalloc::GlobalDlmalloc;
+
+/// A stack for `resume` to run on.
+static mut STACK: [u128; 4096] = [0; 4096];
+
+static INTERRUPTED_SP: AtomicUsize = AtomicUsize::new(0);
+
+unsafe extern "C" fn handler(_sig: i32, _info: *mut Siginfo, ucontext: *mut c_void) {
+    // The interrupted code was running on the main thread's stack.
+    let sp = signal::mcontext_sp(ucontext);
+    INTERRUPTED_SP.store(sp, Ordering::SeqCst);
+    assert_ne!(signal::mcontext_pc(ucontext), 0);
+
+    // Resume at `resume` on `STACK`, with the stack pointer aligned as it
+    // would be just after a call instruction.
+    let mut pc = resume as unsafe extern "C" fn() -> ! as usize;
+    if cfg!(target_arch = "arm") {
+        pc &= !1;
+    }
+    let mut sp = core::ptr::addr_of_mut!(STACK).add(1) as usize;
+    if cfg!(any(target_arch = "x86_64", target_arch = "x86")) {
+        sp -= core::mem::size_of::<usize>();
+    }
+    signal::set_mcontext_pc(ucontext, pc);
+    signal::set_mcontext_sp(ucontext, sp);
+    assert_eq!(signal::mcontext_pc(ucontext), pc);
+    assert_eq!(signal::mcontext_sp(ucontext), sp);
+}
+
+unsafe extern "C" fn resume() -> ! {
+    // We're running on `STACK` now.
+    let local = 0_u8;
+    let addr = core::ptr::addr_of!(local) as usize;
+    let stack = core::ptr::addr_of!(STACK) as usize;
+    assert!(addr > stack && addr < stack + core::mem::size_of::<[u128; 4096]>());
+    assert_ne!(INTERRUPTED_SP.load(Ordering::SeqCst), 0);
+
+    program::exit(188);
+}
+
+#[no_mangle]
+unsafe fn origin_main(_argc: usize, _argv: *mut *mut u8, _envp: *mut *mut u8) -> i32 {
+    let mut action: signal::Sigaction = core::mem::zeroed();
+    action.sa_handler_kernel = Some(core::mem::transmute::<
+        unsafe extern "C" fn(i32, *mut Siginfo, *mut c_void),
+        unsafe extern "C" fn(i32),
+    >(handler));
+    action.sa_flags = SA_SIGINFO.into();
+    signal::sigaction(Signal::Usr1, Some(action)).unwrap();
+
+    rustix::runtime::tkill(rustix::thread::gettid(), Signal::Usr1).unwrap();
+
+    // The handler redirects execution to `resume`, which exits.
+    program::trap();
+}
@@ -0,0 +1,42 @@
+//! Test that `thread::create` rejects stacks smaller than `MIN_STACK_SIZE`.
+
+#![no_std]
+#![no_main]
+
+extern crate alloc;
+
+use core::ptr::NonNull;
+use origin::{program, thread};
+use rustix::io;
+
+#[global_allocator]
+static GLOBAL_ALLOCATOR: rustix_dlmalloc::GlobalDlmalloc = rustix_dlmalloc::GlobalDlmalloc;
+
+#[no_mangle]
+unsafe fn origin_main(_argc: usize, _argv: *mut *mut u8, _envp: *mut *mut u8) -> i32 {
+    let fn_ = |_args: &mut [Option<NonNull<_>>]| Some(NonNull::dangling());
+
+    assert_eq!(
+        thread::create(fn_, &[], 1024, thread::default_guard_size()).err(),
+        Some(io::Errno::INVAL)
+    );
+    assert_eq!(
+        thread::Builder::new()
+            .stack_size(thread::MIN_STACK_SIZE - 1)
+            .create(fn_, &[])
+            .err(),
+        Some(io::Errno::INVAL)
+    );
+
+    // The minimum itself is enough to run a thread.
+    let thread = thread::create(
+        fn_,
+        &[],
+        thread::MIN_STACK_SIZE,
+        thread::default_guard_size(),
+    )
+    .unwrap();
+    assert_eq!(thread::join(thread), Some(NonNull::dangling()));
+
+    program::exit(246);
+}
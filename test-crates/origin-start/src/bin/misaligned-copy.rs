@@ -0,0 +1,75 @@
+//! Test `memcpy` and `memmove` with misaligned buffers across a range of
+//! offsets and lengths, checking against a simple byte-at-a-time reference.
+
+#![no_std]
+#![no_main]
+
+extern crate alloc;
+
+use core::hint::black_box;
+use core::ptr::{copy, copy_nonoverlapping};
+use origin::program;
+
+#[global_allocator]
+static GLOBAL_ALLOCATOR: rustix_dlmalloc::GlobalDlmalloc = rustix_dlmalloc::GlobalDlmalloc;
+
+const SIZE: usize = 128;
+
+/// Fill `buf` with a pattern with no repeating words.
+fn fill(buf: &mut [u8; SIZE]) {
+    for (i, byte) in buf.iter_mut().enumerate() {
+        *byte = (i as u8).wrapping_mul(7).wrapping_add(1);
+    }
+}
+
+#[no_mangle]
+unsafe fn origin_main(_argc: usize, _argv: *mut *mut u8, _envp: *mut *mut u8) -> i32 {
+    let mut src = [0_u8; SIZE];
+    let mut dest = [0_u8; SIZE];
+    let mut expected = [0_u8; SIZE];
+    fill(&mut src);
+
+    for src_offset in 0..16 {
+        for dest_offset in 0..16 {
+            for len in 0..SIZE - 16 {
+                // Use `black_box` so that the compiler calls `memcpy`.
+                let len = black_box(len);
+
+                // Non-overlapping copies.
+                dest.fill(0xff);
+                expected.fill(0xff);
+                for i in 0..len {
+                    expected[dest_offset + i] = src[src_offset + i];
+                }
+                copy_nonoverlapping(
+                    src.as_ptr().add(src_offset),
+                    dest.as_mut_ptr().add(dest_offset),
+                    len,
+                );
+                assert_eq!(dest, expected);
+
+                // Overlapping copies, in both directions.
+                fill(&mut dest);
+                fill(&mut expected);
+                let mut i = 0;
+                while i < len {
+                    let j = if dest_offset > src_offset {
+                        len - 1 - i
+                    } else {
+                        i
+                    };
+                    expected[dest_offset + j] = expected[src_offset + j];
+                    i += 1;
+                }
+                copy(
+                    dest.as_ptr().add(src_offset),
+                    dest.as_mut_ptr().add(dest_offset),
+                    len,
+                );
+                assert_eq!(dest, expected);
+            }
+        }
+    }
+
+    program::exit(213);
+}
@@ -0,0 +1,39 @@
+//! Test that threads work with the "no-tls" feature.
+
+#![no_std]
+#![no_main]
+
+extern crate alloc;
+
+use core::ffi::c_void;
+use core::ptr::{without_provenance_mut, NonNull};
+use origin::{program, thread};
+
+#[global_allocator]
+static GLOBAL_ALLOCATOR: rustix_dlmalloc::GlobalDlmalloc = rustix_dlmalloc::GlobalDlmalloc;
+
+#[no_mangle]
+unsafe fn origin_main(_argc: usize, _argv: *mut *mut u8, _envp: *mut *mut u8) -> i32 {
+    assert_eq!(thread::TlsTemplate::startup().mem_size(), 0);
+
+    let main = thread::current();
+    assert_eq!(thread::current_id(), rustix::thread::gettid());
+
+    let thread = thread::create(
+        |args| {
+            let main = thread::Thread::from_raw(args[0].unwrap().as_ptr());
+            assert!(thread::current() != main);
+            assert_eq!(thread::current_id(), rustix::thread::gettid());
+            NonNull::new(without_provenance_mut::<c_void>(1))
+        },
+        &[Some(main.to_raw_non_null())],
+        thread::default_stack_size(),
+        thread::default_guard_size(),
+    )
+    .unwrap();
+
+    assert!(thread::join(thread).is_some());
+    assert!(thread::current() == main);
+
+    program::exit(232)
+}
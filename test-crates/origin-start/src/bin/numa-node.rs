@@ -0,0 +1,55 @@
+//! Test `thread::Builder::numa_node`.
+
+#![no_std]
+#![no_main]
+
+extern crate alloc;
+
+use core::ffi::c_void;
+use core::ptr::NonNull;
+use origin::{program, thread};
+use rustix::io::Errno;
+use rustix::process::{sched_getaffinity, CpuSet};
+
+#[global_allocator]
+static GLOBAL_ALLOCATOR: rustix_dlmalloc::GlobalDlmalloc = rustix_dlmalloc::GlobalDlmalloc;
+
+#[no_mangle]
+unsafe fn origin_main(_argc: usize, _argv: *mut *mut u8, _envp: *mut *mut u8) -> i32 {
+    let before = sched_getaffinity(None).unwrap();
+
+    // Every Linux system has node 0. The new thread runs on a subset of the
+    // CPUs we can run on, and its stack is usable.
+    let thread = thread::Builder::new()
+        .numa_node(0)
+        .create(
+            |args| {
+                let before = &*args[0].unwrap().as_ptr().cast::<CpuSet>();
+                let affinity = sched_getaffinity(None).unwrap();
+                assert_ne!(affinity.count(), 0);
+                for cpu in 0..CpuSet::MAX_CPU {
+                    assert!(!affinity.is_set(cpu) || before.is_set(cpu));
+                }
+
+                let mut buf = [0_u8; 64 * 1024];
+                core::hint::black_box(&mut buf).fill(1);
+                None
+            },
+            &[NonNull::new(
+                (&before as *const CpuSet).cast_mut().cast::<c_void>(),
+            )],
+        )
+        .unwrap();
+    thread::join(thread);
+
+    // Our own affinity is restored afterward.
+    assert_eq!(sched_getaffinity(None).unwrap(), before);
+
+    // Nodes beyond what Linux supports are rejected.
+    let result = thread::Builder::new()
+        .numa_node(u32::MAX)
+        .create(|_args| None, &[]);
+    assert!(matches!(result, Err(Errno::INVAL)));
+
+    program::exit(178);
+}
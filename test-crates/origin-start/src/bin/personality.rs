@@ -0,0 +1,30 @@
+//! Test that `program::disable_aslr_and_reexec` executes the program again
+//! with `ADDR_NO_RANDOMIZE` set.
+
+#![no_std]
+#![no_main]
+
+use core::ffi::CStr;
+use origin::program;
+
+#[global_allocator]
+static GLOBAL_ALLOCATOR: rustix_dlmalloc::GlobalDlmalloc = rustix_dlmalloc::GlobalDlmalloc;
+
+#[no_mangle]
+unsafe fn origin_main(argc: usize, argv: *mut *mut u8, envp: *mut *mut u8) -> i32 {
+    program::disable_aslr_and_reexec(argv.cast(), envp.cast()).unwrap();
+
+    // We're now running in the new program, or were started with the flag
+    // already set.
+    assert_ne!(program::personality().unwrap() & program::ADDR_NO_RANDOMIZE, 0);
+
+    // The arguments are passed through.
+    assert_eq!(argc, 1);
+    let argv0 = CStr::from_ptr((*argv).cast());
+    assert!(argv0.to_bytes().ends_with(b"personality"));
+
+    // Calling it again returns immediately.
+    program::disable_aslr_and_reexec(argv.cast(), envp.cast()).unwrap();
+
+    program::exit(186);
+}
@@ -0,0 +1,26 @@
+//! Test `program::set_dumpable` and `program::set_no_new_privs`.
+
+#![no_std]
+#![no_main]
+
+extern crate alloc;
+
+use origin::program;
+use rustix::process::{dumpable_behavior, DumpableBehavior};
+use rustix::thread::no_new_privs;
+
+#[global_allocator]
+static GLOBAL_ALLOCATOR: rustix_dlmalloc::GlobalDlmalloc = rustix_dlmalloc::GlobalDlmalloc;
+
+#[no_mangle]
+unsafe fn origin_main(_argc: usize, _argv: *mut *mut u8, _envp: *mut *mut u8) -> i32 {
+    program::set_dumpable(false).unwrap();
+    assert_eq!(dumpable_behavior().unwrap(), DumpableBehavior::NotDumpable);
+    program::set_dumpable(true).unwrap();
+    assert_eq!(dumpable_behavior().unwrap(), DumpableBehavior::Dumpable);
+
+    program::set_no_new_privs().unwrap();
+    assert!(no_new_privs().unwrap());
+
+    program::exit(247);
+}
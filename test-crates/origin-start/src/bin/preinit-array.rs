@@ -0,0 +1,44 @@
+//! Test that `.preinit_array` functions are called once, before
+//! `.init_array` functions, with the same arguments, both when origin calls
+//! them in a statically-linked executable and when the dynamic linker does.
+
+#![no_std]
+#![no_main]
+
+use core::sync::atomic::{AtomicUsize, Ordering};
+use origin::program;
+
+#[global_allocator]
+static GLOBAL_ALLOCATOR: rustix_dlmalloc::GlobalDlmalloc = rustix_dlmalloc::GlobalDlmalloc;
+
+/// The number of initialization functions called so far.
+static CALLS: AtomicUsize = AtomicUsize::new(0);
+
+/// The argc seen by `preinit`.
+static PREINIT_ARGC: AtomicUsize = AtomicUsize::new(0);
+
+extern "C" fn preinit(argc: i32, _argv: *mut *mut u8, _envp: *mut *mut u8) {
+    assert_eq!(CALLS.fetch_add(1, Ordering::SeqCst), 0);
+    PREINIT_ARGC.store(argc as usize, Ordering::SeqCst);
+}
+
+extern "C" fn init(argc: i32, _argv: *mut *mut u8, _envp: *mut *mut u8) {
+    assert_eq!(CALLS.fetch_add(1, Ordering::SeqCst), 1);
+    assert_eq!(PREINIT_ARGC.load(Ordering::SeqCst), argc as usize);
+}
+
+#[used]
+#[link_section = ".preinit_array"]
+static PREINIT_ARRAY: [extern "C" fn(i32, *mut *mut u8, *mut *mut u8); 1] = [preinit];
+
+#[used]
+#[link_section = ".init_array"]
+static INIT_ARRAY: [extern "C" fn(i32, *mut *mut u8, *mut *mut u8); 1] = [init];
+
+#[no_mangle]
+unsafe fn origin_main(argc: usize, _argv: *mut *mut u8, _envp: *mut *mut u8) -> i32 {
+    assert_eq!(CALLS.load(Ordering::SeqCst), 2);
+    assert_eq!(PREINIT_ARGC.load(Ordering::SeqCst), argc);
+
+    program::exit(196);
+}
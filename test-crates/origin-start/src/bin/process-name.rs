@@ -0,0 +1,37 @@
+//! Test `program::name` and `program::set_name`.
+
+#![no_std]
+#![no_main]
+
+extern crate alloc;
+
+use alloc::vec;
+use core::ffi::CStr;
+use origin::program;
+
+#[global_allocator]
+static GLOBAL_ALLOCATOR: rustix_dlmalloc::GlobalDlmalloc = rustix_dlmalloc::GlobalDlmalloc;
+
+#[no_mangle]
+unsafe fn origin_main(_argc: usize, argv: *mut *mut u8, _envp: *mut *mut u8) -> i32 {
+    // The exec filename is the path to this executable.
+    assert!(program::name().to_bytes().ends_with(b"process-name"));
+
+    let argv0 = || CStr::from_ptr((*argv).cast());
+    let original_len = argv0().to_bytes().len();
+
+    // A shorter name replaces `argv[0]`.
+    program::set_name(c"renamed").unwrap();
+    assert_eq!(argv0(), c"renamed");
+
+    // A longer name is truncated to the original length.
+    let mut long = vec![b'x'; original_len + 10];
+    long.push(b'\0');
+    program::set_name(CStr::from_bytes_with_nul(&long).unwrap()).unwrap();
+    assert_eq!(argv0().to_bytes(), &long[..original_len]);
+
+    // `name` is unaffected.
+    assert!(program::name().to_bytes().ends_with(b"process-name"));
+
+    program::exit(215);
+}
@@ -0,0 +1,40 @@
+//! Test `program::initial_brk` and `program::data_end`.
+
+#![no_std]
+#![no_main]
+
+extern crate alloc;
+
+use core::ffi::c_void;
+use core::ptr::addr_of;
+use origin::program;
+
+#[global_allocator]
+static GLOBAL_ALLOCATOR: rustix_dlmalloc::GlobalDlmalloc = rustix_dlmalloc::GlobalDlmalloc;
+
+/// A zero-initialized static, which lives in `.bss` at the end of the data.
+static mut BSS: [u8; 4096] = [0; 4096];
+
+/// An initialized static, which lives in `.data`.
+static DATA: [u8; 4] = *b"data";
+
+#[no_mangle]
+unsafe fn origin_main(_argc: usize, _argv: *mut *mut u8, _envp: *mut *mut u8) -> i32 {
+    let brk = program::initial_brk();
+    let end = program::data_end();
+    assert!(!brk.is_null());
+
+    // The initial break is cached.
+    assert_eq!(program::initial_brk(), brk);
+
+    // Our statics are within the loaded segments.
+    let bss = addr_of!(BSS).cast::<c_void>();
+    assert!((bss as usize) + 4096 <= end as usize);
+    let data = addr_of!(DATA).cast::<c_void>();
+    assert!((data as usize) < end as usize);
+
+    // The kernel places the break after the end of the loaded segments.
+    assert!((end as usize) <= brk as usize);
+
+    program::exit(221);
+}
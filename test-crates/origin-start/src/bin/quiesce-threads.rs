@@ -0,0 +1,52 @@
+//! Test that with "quiesce-threads-before-exit", other threads are asked to
+//! stop, and are waited for, before `at_exit` functions run.
+
+#![no_std]
+#![no_main]
+
+extern crate alloc;
+
+use alloc::boxed::Box;
+use core::sync::atomic::{AtomicU32, Ordering};
+use origin::{program, thread};
+
+#[global_allocator]
+static GLOBAL_ALLOCATOR: rustix_dlmalloc::GlobalDlmalloc = rustix_dlmalloc::GlobalDlmalloc;
+
+static COUNTER: AtomicU32 = AtomicU32::new(0);
+
+#[no_mangle]
+unsafe fn origin_main(_argc: usize, _argv: *mut *mut u8, _envp: *mut *mut u8) -> i32 {
+    // Start some threads which run until the program starts exiting.
+    for _ in 0..4 {
+        let child = thread::create(
+            |_args| {
+                while !thread::exit_requested() {
+                    COUNTER.fetch_add(1, Ordering::SeqCst);
+                }
+                None
+            },
+            &[],
+            thread::default_stack_size(),
+            thread::default_guard_size(),
+        )
+        .unwrap();
+        thread::detach(child);
+    }
+
+    // Wait for them to get going.
+    while COUNTER.load(Ordering::SeqCst) < 1000 {
+        thread::yield_current();
+    }
+
+    // When we exit, the threads should be stopped before this runs.
+    program::at_exit(Box::new(|| {
+        let before = COUNTER.load(Ordering::SeqCst);
+        for _ in 0..1000 {
+            thread::yield_current();
+        }
+        assert_eq!(COUNTER.load(Ordering::SeqCst), before);
+    }));
+
+    program::exit(219);
+}
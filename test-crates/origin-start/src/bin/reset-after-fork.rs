@@ -0,0 +1,71 @@
+//! Test that `thread::reset_after_fork` leaves the child of a `fork` with a
+//! consistent view of its threads.
+
+#![no_std]
+#![no_main]
+
+use core::hint::spin_loop;
+use core::sync::atomic::{AtomicBool, Ordering};
+use origin::{program, thread};
+use rustix::process::{waitpid, WaitOptions};
+use rustix::runtime::{fork, Fork};
+use rustix::thread::gettid;
+
+#[global_allocator]
+static GLOBAL_ALLOCATOR: rustix_dlmalloc::GlobalDlmalloc = rustix_dlmalloc::GlobalDlmalloc;
+
+static RELEASED: AtomicBool = AtomicBool::new(false);
+
+#[no_mangle]
+unsafe fn origin_main(_argc: usize, _argv: *mut *mut u8, _envp: *mut *mut u8) -> i32 {
+    // Start a thread which waits until we release it, so that it's running
+    // when we fork.
+    let child = thread::create(
+        |_args| {
+            while !RELEASED.load(Ordering::SeqCst) {
+                spin_loop();
+            }
+            None
+        },
+        &[],
+        thread::default_stack_size(),
+        thread::default_guard_size(),
+    )
+    .unwrap();
+    assert_eq!(thread::stats().live, 2);
+
+    match fork().unwrap() {
+        Fork::Child(pid) => {
+            thread::reset_after_fork();
+
+            assert_eq!(thread::current_id(), gettid());
+            assert_eq!(thread::current_id(), pid);
+            assert_eq!(thread::stats().live, 1);
+
+            // Threads can be created and joined in the child.
+            let grandchild = thread::create(
+                |_args| None,
+                &[],
+                thread::default_stack_size(),
+                thread::default_guard_size(),
+            )
+            .unwrap();
+            assert_eq!(thread::stats().live, 2);
+            thread::join(grandchild);
+            assert_eq!(thread::stats().live, 1);
+
+            program::exit(255);
+        }
+        Fork::Parent(pid) => {
+            let status = waitpid(Some(pid), WaitOptions::empty()).unwrap().unwrap();
+            assert_eq!(status.exit_status(), Some(255));
+
+            // The parent still has its thread.
+            assert_eq!(thread::stats().live, 2);
+            RELEASED.store(true, Ordering::SeqCst);
+            thread::join(child);
+
+            program::exit(255);
+        }
+    }
+}
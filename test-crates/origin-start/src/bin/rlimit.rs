@@ -0,0 +1,53 @@
+//! Test `program::get_rlimit` and `program::set_rlimit`.
+
+#![no_std]
+#![no_main]
+
+extern crate alloc;
+
+use origin::program::{self, Resource, Rlimit};
+
+#[global_allocator]
+static GLOBAL_ALLOCATOR: rustix_dlmalloc::GlobalDlmalloc = rustix_dlmalloc::GlobalDlmalloc;
+
+#[no_mangle]
+unsafe fn origin_main(_argc: usize, _argv: *mut *mut u8, _envp: *mut *mut u8) -> i32 {
+    // Disable core dumps.
+    let core = program::get_rlimit(Resource::Core);
+    program::set_rlimit(
+        Resource::Core,
+        Rlimit {
+            current: Some(0),
+            maximum: core.maximum,
+        },
+    )
+    .unwrap();
+    assert_eq!(program::get_rlimit(Resource::Core).current, Some(0));
+
+    // Lower the current file descriptor limit, and then raise it back to the
+    // maximum.
+    let nofile = program::get_rlimit(Resource::Nofile);
+    program::set_rlimit(
+        Resource::Nofile,
+        Rlimit {
+            current: Some(16),
+            maximum: nofile.maximum,
+        },
+    )
+    .unwrap();
+    assert_eq!(program::get_rlimit(Resource::Nofile).current, Some(16));
+    program::set_rlimit(
+        Resource::Nofile,
+        Rlimit {
+            current: nofile.maximum,
+            maximum: nofile.maximum,
+        },
+    )
+    .unwrap();
+    assert_eq!(program::get_rlimit(Resource::Nofile), Rlimit {
+        current: nofile.maximum,
+        maximum: nofile.maximum,
+    });
+
+    program::exit(254);
+}
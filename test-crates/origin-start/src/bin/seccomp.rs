@@ -0,0 +1,66 @@
+//! Test `program::seccomp_allow_origin_syscalls` and
+//! `program::install_seccomp_filter`.
+
+#![no_std]
+#![no_main]
+
+extern crate alloc;
+
+use linux_raw_sys::general::__NR_prctl;
+use linux_raw_sys::ptrace::{
+    BPF_ABS, BPF_JEQ, BPF_JMP, BPF_K, BPF_LD, BPF_RET, BPF_W, SECCOMP_RET_ALLOW, SECCOMP_RET_ERRNO,
+};
+use origin::program::{self, SockFilter};
+use origin::thread;
+use rustix::io::Errno;
+
+#[global_allocator]
+static GLOBAL_ALLOCATOR: rustix_dlmalloc::GlobalDlmalloc = rustix_dlmalloc::GlobalDlmalloc;
+
+#[no_mangle]
+unsafe fn origin_main(_argc: usize, _argv: *mut *mut u8, _envp: *mut *mut u8) -> i32 {
+    program::set_no_new_privs().unwrap();
+    program::seccomp_allow_origin_syscalls(&[]).unwrap();
+
+    // Origin's own system calls still work.
+    let thread = thread::create(
+        |_args| None,
+        &[],
+        thread::default_stack_size(),
+        thread::default_guard_size(),
+    )
+    .unwrap();
+    thread::join(thread);
+
+    // Install a second filter which makes `prctl` fail with `EPERM`.
+    let prog = [
+        SockFilter {
+            code: (BPF_LD | BPF_W | BPF_ABS) as u16,
+            jt: 0,
+            jf: 0,
+            k: 0,
+        },
+        SockFilter {
+            code: (BPF_JMP | BPF_JEQ | BPF_K) as u16,
+            jt: 0,
+            jf: 1,
+            k: __NR_prctl,
+        },
+        SockFilter {
+            code: (BPF_RET | BPF_K) as u16,
+            jt: 0,
+            jf: 0,
+            k: SECCOMP_RET_ERRNO | Errno::PERM.raw_os_error() as u32,
+        },
+        SockFilter {
+            code: (BPF_RET | BPF_K) as u16,
+            jt: 0,
+            jf: 0,
+            k: SECCOMP_RET_ALLOW,
+        },
+    ];
+    program::install_seccomp_filter(&prog).unwrap();
+    assert_eq!(program::set_dumpable(false), Err(Errno::PERM));
+
+    program::exit(248);
+}
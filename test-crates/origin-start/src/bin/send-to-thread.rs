@@ -0,0 +1,68 @@
+//! Test that `signal::send_to_thread` delivers a signal to the given thread,
+//! and fails for a thread which has exited.
+
+#![no_std]
+#![no_main]
+
+use core::hint::spin_loop;
+use core::sync::atomic::{AtomicI32, Ordering};
+use origin::signal::{self, Handler, Signal};
+use origin::{program, thread};
+use rustix::io;
+use rustix::thread::gettid;
+
+#[global_allocator]
+static GLOBAL_ALLOCATOR: rustix_dlmalloc::GlobalDlmalloc = rustix_dlmalloc::GlobalDlmalloc;
+
+static HANDLED_TID: AtomicI32 = AtomicI32::new(0);
+
+unsafe extern "C" fn handler(_sig: i32) {
+    HANDLED_TID.store(gettid().as_raw_nonzero().get(), Ordering::SeqCst);
+}
+
+#[no_mangle]
+unsafe fn origin_main(_argc: usize, _argv: *mut *mut u8, _envp: *mut *mut u8) -> i32 {
+    signal::set_handler(Signal::Usr1, Handler::new(Some(handler))).unwrap();
+
+    // Start a thread which waits until it's been signaled.
+    let child = thread::create(
+        |_args| {
+            while HANDLED_TID.load(Ordering::SeqCst) == 0 {
+                spin_loop();
+            }
+            None
+        },
+        &[],
+        thread::default_stack_size(),
+        thread::default_guard_size(),
+    )
+    .unwrap();
+    let child_id = thread::id(child).unwrap();
+
+    signal::send_to_thread(child, Signal::Usr1).unwrap();
+    thread::join(child);
+    assert_eq!(
+        HANDLED_TID.load(Ordering::SeqCst),
+        child_id.as_raw_nonzero().get()
+    );
+
+    // Signaling a thread which has exited, but which hasn't been joined yet,
+    // fails.
+    let child = thread::create(
+        |_args| None,
+        &[],
+        thread::default_stack_size(),
+        thread::default_guard_size(),
+    )
+    .unwrap();
+    while thread::id(child).is_some() {
+        spin_loop();
+    }
+    assert_eq!(
+        signal::send_to_thread(child, Signal::Usr1),
+        Err(io::Errno::SRCH)
+    );
+    thread::join(child);
+
+    program::exit(193);
+}
@@ -0,0 +1,29 @@
+//! Test `program::set_alloc_error_handler`.
+
+#![no_std]
+#![no_main]
+
+extern crate alloc;
+
+use core::alloc::Layout;
+use core::fmt::Write;
+use origin::program::{self, Stdout};
+
+#[global_allocator]
+static GLOBAL_ALLOCATOR: rustix_dlmalloc::GlobalDlmalloc = rustix_dlmalloc::GlobalDlmalloc;
+
+fn handler(layout: Layout) -> ! {
+    writeln!(Stdout, "failed to allocate {} bytes", layout.size()).unwrap();
+    program::exit_immediately(245)
+}
+
+#[no_mangle]
+unsafe fn origin_main(_argc: usize, _argv: *mut *mut u8, _envp: *mut *mut u8) -> i32 {
+    program::set_alloc_error_handler(handler);
+
+    // This is too big for the allocator to satisfy.
+    let v = alloc::vec![0_u8; 1 << 46];
+    core::hint::black_box(v);
+
+    program::exit(1);
+}
@@ -0,0 +1,83 @@
+//! Test that `signal::set_handler` sets `SA_RESTART` by default, so that an
+//! interrupted futex wait is restarted, and that `Handler::no_restart` makes
+//! it fail with `EINTR` instead.
+
+#![no_std]
+#![no_main]
+
+extern crate alloc;
+
+use core::sync::atomic::{AtomicU32, Ordering};
+use origin::signal::{self, Handler, Signal};
+use origin::{program, thread};
+use rustix::io;
+use rustix::thread::{futex, Pid, Timespec};
+
+#[global_allocator]
+static GLOBAL_ALLOCATOR: rustix_dlmalloc::GlobalDlmalloc = rustix_dlmalloc::GlobalDlmalloc;
+
+static HANDLED: AtomicU32 = AtomicU32::new(0);
+static WORD: AtomicU32 = AtomicU32::new(0);
+static MAIN_TID: AtomicU32 = AtomicU32::new(0);
+
+unsafe extern "C" fn handler(sig: i32) {
+    assert_eq!(sig, Signal::Usr1 as i32);
+    HANDLED.fetch_add(1, Ordering::SeqCst);
+}
+
+fn sleep() {
+    let _ = rustix::thread::nanosleep(&Timespec {
+        tv_sec: 0,
+        tv_nsec: 100_000_000,
+    });
+}
+
+/// Wait on `WORD` while another thread sends us a signal and then wakes us.
+unsafe fn interrupted_wait() -> io::Result<()> {
+    WORD.store(0, Ordering::SeqCst);
+    let thread = thread::create(
+        |_args| {
+            sleep();
+            let tid = Pid::from_raw(MAIN_TID.load(Ordering::SeqCst) as _).unwrap();
+            rustix::runtime::tkill(tid, Signal::Usr1).unwrap();
+            sleep();
+            WORD.store(1, Ordering::SeqCst);
+            futex::wake(&WORD, futex::Flags::PRIVATE, 1).unwrap();
+            None
+        },
+        &[],
+        thread::default_stack_size(),
+        thread::default_guard_size(),
+    )
+    .unwrap();
+
+    let result = match futex::wait(&WORD, futex::Flags::PRIVATE, 0, None) {
+        // The restarted wait may see that `WORD` has already changed.
+        Err(io::Errno::AGAIN) => Ok(()),
+        result => result,
+    };
+
+    thread::join(thread);
+    result
+}
+
+#[no_mangle]
+unsafe fn origin_main(_argc: usize, _argv: *mut *mut u8, _envp: *mut *mut u8) -> i32 {
+    MAIN_TID.store(
+        rustix::thread::gettid().as_raw_nonzero().get() as u32,
+        Ordering::SeqCst,
+    );
+
+    // By default, the wait is restarted after the handler runs.
+    signal::set_handler(Signal::Usr1, Handler::new(Some(handler))).unwrap();
+    assert_eq!(interrupted_wait(), Ok(()));
+    assert_eq!(HANDLED.load(Ordering::SeqCst), 1);
+
+    // With `no_restart`, the wait fails with `EINTR`.
+    let old = signal::set_handler(Signal::Usr1, Handler::new(Some(handler)).no_restart()).unwrap();
+    assert_ne!(old.sa_flags & signal::SA_RESTART, 0);
+    assert_eq!(interrupted_wait(), Err(io::Errno::INTR));
+    assert_eq!(HANDLED.load(Ordering::SeqCst), 2);
+
+    program::exit(242);
+}
@@ -0,0 +1,74 @@
+//! Test that `thread::set_return_value` sets the value `join` returns.
+
+#![no_std]
+#![no_main]
+
+extern crate alloc;
+
+use alloc::boxed::Box;
+use core::ffi::c_void;
+use core::ptr::{without_provenance_mut, NonNull};
+use origin::{program, thread};
+
+#[global_allocator]
+static GLOBAL_ALLOCATOR: rustix_dlmalloc::GlobalDlmalloc = rustix_dlmalloc::GlobalDlmalloc;
+
+fn value(addr: usize) -> Option<NonNull<c_void>> {
+    NonNull::new(without_provenance_mut(addr))
+}
+
+#[inline(never)]
+fn deep(depth: usize) {
+    if depth == 0 {
+        thread::set_return_value(value(0x1000));
+        thread::set_return_value(value(0x2000));
+    } else {
+        deep(depth - 1);
+    }
+}
+
+unsafe fn run(fn_: unsafe fn(&mut [Option<NonNull<c_void>>]) -> Option<NonNull<c_void>>) -> usize {
+    let thread = thread::create(
+        fn_,
+        &[],
+        thread::default_stack_size(),
+        thread::default_guard_size(),
+    )
+    .unwrap();
+    thread::join(thread).map_or(0, |value| value.as_ptr() as usize)
+}
+
+#[no_mangle]
+unsafe fn origin_main(_argc: usize, _argv: *mut *mut u8, _envp: *mut *mut u8) -> i32 {
+    // Without `set_return_value`, `join` returns what the thread returned.
+    assert_eq!(run(|_args| value(0x3000)), 0x3000);
+
+    // The last value set takes precedence over what the thread returned.
+    assert_eq!(
+        run(|_args| {
+            deep(8);
+            value(0x3000)
+        }),
+        0x2000
+    );
+
+    // Setting `None` takes precedence too.
+    assert_eq!(
+        run(|_args| {
+            thread::set_return_value(None);
+            value(0x3000)
+        }),
+        0
+    );
+
+    // It can be set from an `at_exit` function.
+    assert_eq!(
+        run(|_args| {
+            thread::at_exit(Box::new(|| thread::set_return_value(value(0x4000))));
+            None
+        }),
+        0x4000
+    );
+
+    program::exit(183);
+}
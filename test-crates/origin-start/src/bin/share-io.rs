@@ -0,0 +1,37 @@
+//! Test `thread::Builder::share_io`.
+
+#![no_std]
+#![no_main]
+
+extern crate alloc;
+
+use core::ffi::c_void;
+use core::ptr::NonNull;
+use origin::{program, thread};
+
+#[global_allocator]
+static GLOBAL_ALLOCATOR: rustix_dlmalloc::GlobalDlmalloc = rustix_dlmalloc::GlobalDlmalloc;
+
+/// A thread function which returns non-null if it's in our process.
+fn check(args: &mut [Option<NonNull<c_void>>]) -> Option<NonNull<c_void>> {
+    let parent = args[0].unwrap().as_ptr() as usize as i32;
+    (rustix::process::getpid().as_raw_nonzero().get() == parent
+        && thread::current_id() != rustix::process::getpid())
+    .then(NonNull::dangling)
+}
+
+#[no_mangle]
+unsafe fn origin_main(_argc: usize, _argv: *mut *mut u8, _envp: *mut *mut u8) -> i32 {
+    let pid = rustix::process::getpid().as_raw_nonzero().get() as usize;
+
+    // A thread sharing our I/O context is an ordinary thread in our process.
+    for share_io in [true, false] {
+        let child = thread::Builder::new()
+            .share_io(share_io)
+            .create(check, &[NonNull::new(pid as *mut c_void)])
+            .unwrap();
+        assert_ne!(thread::join(child), None);
+    }
+
+    program::exit(227);
+}
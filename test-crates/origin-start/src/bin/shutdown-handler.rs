@@ -0,0 +1,49 @@
+//! Test `program::install_shutdown_handler`.
+
+#![no_std]
+#![no_main]
+
+extern crate alloc;
+
+use origin::program;
+use origin::signal::{self, Sigaction, Signal};
+
+#[global_allocator]
+static GLOBAL_ALLOCATOR: rustix_dlmalloc::GlobalDlmalloc = rustix_dlmalloc::GlobalDlmalloc;
+
+unsafe fn raise(sig: Signal) {
+    rustix::runtime::tkill(rustix::thread::gettid(), sig).unwrap();
+}
+
+#[no_mangle]
+unsafe fn origin_main(_argc: usize, _argv: *mut *mut u8, _envp: *mut *mut u8) -> i32 {
+    // Ignore `SIGHUP`, as `nohup` would.
+    let mut ignore: Sigaction = core::mem::zeroed();
+    ignore.sa_handler_kernel = signal::sig_ign();
+    signal::sigaction(Signal::Hup, Some(ignore)).unwrap();
+
+    let token = program::install_shutdown_handler();
+    assert!(!token.is_shutting_down());
+    assert_eq!(token.which_signal(), None);
+
+    // `SIGHUP` is left ignored.
+    let hup = signal::sigaction(Signal::Hup, None).unwrap();
+    assert_eq!(
+        hup.sa_handler_kernel.map(|f| f as usize),
+        signal::sig_ign().map(|f| f as usize)
+    );
+    raise(Signal::Hup);
+    assert!(!token.is_shutting_down());
+
+    raise(Signal::Term);
+    assert!(token.is_shutting_down());
+    assert_eq!(token.which_signal(), Some(Signal::Term));
+
+    // The first signal is kept, and other tokens see the same state.
+    raise(Signal::Int);
+    let other = program::install_shutdown_handler();
+    assert!(other.is_shutting_down());
+    assert_eq!(other.which_signal(), Some(Signal::Term));
+
+    program::exit(179);
+}
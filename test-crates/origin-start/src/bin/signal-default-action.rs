@@ -0,0 +1,42 @@
+//! Test that raising signals whose `signal::default_action` is `Ignore` with
+//! the default handler installed doesn't terminate the process.
+
+#![no_std]
+#![no_main]
+
+extern crate alloc;
+
+use origin::program;
+use origin::signal::{self, DefaultAction, Signal};
+
+#[global_allocator]
+static GLOBAL_ALLOCATOR: rustix_dlmalloc::GlobalDlmalloc = rustix_dlmalloc::GlobalDlmalloc;
+
+#[no_mangle]
+unsafe fn origin_main(_argc: usize, _argv: *mut *mut u8, _envp: *mut *mut u8) -> i32 {
+    assert_eq!(
+        signal::default_action(Signal::Term),
+        DefaultAction::Terminate
+    );
+    assert_eq!(
+        signal::default_action(Signal::Abort),
+        DefaultAction::CoreDump
+    );
+    assert_eq!(signal::default_action(Signal::Tstp), DefaultAction::Stop);
+    assert_eq!(
+        signal::default_action(Signal::Cont),
+        DefaultAction::Continue
+    );
+
+    for sig in [Signal::Child, Signal::Urg, Signal::Winch] {
+        assert_eq!(signal::default_action(sig), DefaultAction::Ignore);
+
+        // Reset the signal to its default handler and raise it.
+        let mut action = signal::sigaction(sig, None).unwrap();
+        action.sa_handler_kernel = signal::SigDfl;
+        signal::sigaction(sig, Some(action)).unwrap();
+        rustix::runtime::tkill(rustix::thread::gettid(), sig).unwrap();
+    }
+
+    program::exit(209);
+}
@@ -0,0 +1,55 @@
+//! Test that control returns correctly after a signal handler returns
+//! normally, with and without `SA_SIGINFO`.
+
+#![no_std]
+#![no_main]
+
+extern crate alloc;
+
+use core::ffi::c_void;
+use core::sync::atomic::{AtomicU32, Ordering};
+use origin::signal::{self, Sigaction, Siginfo, Signal, SA_SIGINFO};
+use origin::{program, thread};
+
+#[global_allocator]
+static GLOBAL_ALLOCATOR: rustix_dlmalloc::GlobalDlmalloc = rustix_dlmalloc::GlobalDlmalloc;
+
+static HANDLED: AtomicU32 = AtomicU32::new(0);
+
+unsafe extern "C" fn handler(sig: i32) {
+    assert_eq!(sig, Signal::Usr1 as i32);
+    HANDLED.fetch_add(1, Ordering::SeqCst);
+}
+
+unsafe extern "C" fn handler_info(sig: i32, info: *mut Siginfo, _context: *mut c_void) {
+    assert_eq!(sig, Signal::Usr2 as i32);
+    assert_eq!((*info).__bindgen_anon_1.__bindgen_anon_1.si_signo, sig);
+    HANDLED.fetch_add(1, Ordering::SeqCst);
+}
+
+#[no_mangle]
+unsafe fn origin_main(_argc: usize, _argv: *mut *mut u8, _envp: *mut *mut u8) -> i32 {
+    let mut action: Sigaction = core::mem::zeroed();
+    action.sa_handler_kernel = Some(handler);
+    signal::sigaction(Signal::Usr1, Some(action)).unwrap();
+
+    let mut action: Sigaction = core::mem::zeroed();
+    action.sa_handler_kernel = Some(core::mem::transmute::<
+        unsafe extern "C" fn(i32, *mut Siginfo, *mut c_void),
+        unsafe extern "C" fn(i32),
+    >(handler_info));
+    action.sa_flags = SA_SIGINFO;
+    signal::sigaction(Signal::Usr2, Some(action)).unwrap();
+
+    // Deliver each signal a few times, checking that local state survives.
+    let mut sum = 0_u32;
+    for i in 0..10 {
+        rustix::runtime::tkill(thread::current_id(), Signal::Usr1).unwrap();
+        rustix::runtime::tkill(thread::current_id(), Signal::Usr2).unwrap();
+        sum += i;
+    }
+    assert_eq!(sum, 45);
+    assert_eq!(HANDLED.load(Ordering::SeqCst), 20);
+
+    program::exit(216);
+}
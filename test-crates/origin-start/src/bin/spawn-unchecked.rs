@@ -0,0 +1,45 @@
+//! Test `thread::spawn_unchecked` with closures that borrow from the stack.
+
+#![no_std]
+#![no_main]
+
+extern crate alloc;
+
+use alloc::vec::Vec;
+use core::sync::atomic::{AtomicU32, Ordering};
+use origin::{program, thread};
+
+#[global_allocator]
+static GLOBAL_ALLOCATOR: rustix_dlmalloc::GlobalDlmalloc = rustix_dlmalloc::GlobalDlmalloc;
+
+#[no_mangle]
+unsafe fn origin_main(_argc: usize, _argv: *mut *mut u8, _envp: *mut *mut u8) -> i32 {
+    let data = (0..1000_u64).collect::<Vec<_>>();
+    let calls = AtomicU32::new(0);
+
+    // Sum chunks of `data` on separate threads, borrowing it and `calls`.
+    let handles = data
+        .chunks(100)
+        .map(|chunk| {
+            let calls = &calls;
+            thread::spawn_unchecked(move || {
+                calls.fetch_add(1, Ordering::SeqCst);
+                chunk.iter().sum::<u64>()
+            })
+            .unwrap()
+        })
+        .collect::<Vec<_>>();
+
+    let total: u64 = handles.into_iter().map(|handle| handle.join()).sum();
+    assert_eq!(total, 999 * 1000 / 2);
+    assert_eq!(calls.load(Ordering::SeqCst), 10);
+
+    // Closures returning `()` and non-`Copy` values work too.
+    thread::spawn_unchecked(|| ()).unwrap().join();
+    let v = thread::spawn_unchecked(|| data[..3].to_vec())
+        .unwrap()
+        .join();
+    assert_eq!(v, [0, 1, 2]);
+
+    program::exit(220);
+}
@@ -0,0 +1,67 @@
+//! Test `thread::stack_committed_range`.
+
+#![no_std]
+#![no_main]
+
+extern crate alloc;
+
+use core::hint::black_box;
+use core::ptr::addr_of;
+use origin::{program, thread};
+
+#[global_allocator]
+static GLOBAL_ALLOCATOR: rustix_dlmalloc::GlobalDlmalloc = rustix_dlmalloc::GlobalDlmalloc;
+
+/// Check that the current thread's committed stack range contains a local
+/// variable, and return it.
+fn check_current() -> (usize, usize) {
+    let local = 0_u8;
+    let (addr, size) = unsafe { thread::stack_committed_range(thread::current()).unwrap() };
+    let (addr, local) = (addr as usize, addr_of!(local) as usize);
+    assert!(addr <= local && local < addr + size);
+    // Page sizes are multiples of 4 KiB.
+    assert_eq!(addr % 4096, 0);
+    assert_eq!(size % 4096, 0);
+    (addr, size)
+}
+
+/// Use about `depth` times 64 KiB of stack, and then check the range.
+#[inline(never)]
+fn grow(depth: usize) -> (usize, usize) {
+    let buf = black_box([0_u8; 65536]);
+    let result = if depth == 0 {
+        check_current()
+    } else {
+        grow(depth - 1)
+    };
+    black_box(&buf);
+    result
+}
+
+#[no_mangle]
+unsafe fn origin_main(_argc: usize, _argv: *mut *mut u8, _envp: *mut *mut u8) -> i32 {
+    // The main thread's mapped stack grows as it's used.
+    let (addr, size) = check_current();
+    let (deeper_addr, deeper_size) = grow(16);
+    assert!(deeper_addr < addr);
+    assert_eq!(deeper_addr + deeper_size, addr + size);
+    assert!(deeper_size >= 16 * 65536);
+
+    // For other threads, it's the stack that origin allocated.
+    let thread = thread::create(
+        |_args| {
+            let (addr, size) = check_current();
+            let (stack_addr, stack_size, _guard_size) = thread::stack(thread::current());
+            assert_eq!(addr, stack_addr as usize);
+            assert_eq!(size, stack_size);
+            None
+        },
+        &[],
+        thread::default_stack_size(),
+        thread::default_guard_size(),
+    )
+    .unwrap();
+    thread::join(thread);
+
+    program::exit(244);
+}
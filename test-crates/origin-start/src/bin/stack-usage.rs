@@ -0,0 +1,66 @@
+//! Test that `thread::peak_stack_usage` measures how much stack a thread has
+//! used.
+
+#![no_std]
+#![no_main]
+
+use core::hint::black_box;
+use core::sync::atomic::{AtomicUsize, Ordering};
+use origin::{program, thread};
+
+#[global_allocator]
+static GLOBAL_ALLOCATOR: rustix_dlmalloc::GlobalDlmalloc = rustix_dlmalloc::GlobalDlmalloc;
+
+const STACK_SIZE: usize = 1024 * 1024;
+const USED: usize = 64 * 1024;
+
+static BEFORE: AtomicUsize = AtomicUsize::new(0);
+static AFTER: AtomicUsize = AtomicUsize::new(0);
+
+#[inline(never)]
+fn use_stack() {
+    let buf = [0x5a_u8; USED];
+    black_box(&buf);
+}
+
+#[no_mangle]
+unsafe fn origin_main(_argc: usize, _argv: *mut *mut u8, _envp: *mut *mut u8) -> i32 {
+    // The main thread's stack isn't measured.
+    assert_eq!(thread::peak_stack_usage(thread::current()), None);
+
+    let child = thread::Builder::new()
+        .stack_size(STACK_SIZE)
+        .measure_stack_usage(true)
+        .create(
+            |_args| {
+                let current = thread::current();
+                BEFORE.store(thread::peak_stack_usage(current).unwrap(), Ordering::SeqCst);
+                use_stack();
+                AFTER.store(thread::peak_stack_usage(current).unwrap(), Ordering::SeqCst);
+                None
+            },
+            &[],
+        )
+        .unwrap();
+    thread::join(child);
+
+    let before = BEFORE.load(Ordering::SeqCst);
+    let after = AFTER.load(Ordering::SeqCst);
+    assert!(before < USED, "{}", before);
+    assert!(after >= USED, "{}", after);
+    assert!(after < STACK_SIZE, "{}", after);
+
+    // Threads not created with `measure_stack_usage` aren't measured.
+    let child = thread::Builder::new()
+        .create(
+            |_args| {
+                assert_eq!(thread::peak_stack_usage(thread::current()), None);
+                None
+            },
+            &[],
+        )
+        .unwrap();
+    thread::join(child);
+
+    program::exit(194);
+}
@@ -0,0 +1,49 @@
+//! Test that `program::stderr_lock` keeps a thread's output together.
+
+#![no_std]
+#![no_main]
+
+extern crate alloc;
+
+use core::fmt::Write;
+use core::sync::atomic::{AtomicBool, Ordering};
+use origin::program::{self, stderr_lock};
+use origin::thread;
+
+#[global_allocator]
+static GLOBAL_ALLOCATOR: rustix_dlmalloc::GlobalDlmalloc = rustix_dlmalloc::GlobalDlmalloc;
+
+static STARTED: AtomicBool = AtomicBool::new(false);
+
+#[no_mangle]
+unsafe fn origin_main(_argc: usize, _argv: *mut *mut u8, _envp: *mut *mut u8) -> i32 {
+    let mut stderr = stderr_lock();
+    writeln!(stderr, "main: first").unwrap();
+
+    let thread = thread::create(
+        |_args| {
+            STARTED.store(true, Ordering::SeqCst);
+            writeln!(stderr_lock(), "thread").unwrap();
+            None
+        },
+        &[],
+        thread::default_stack_size(),
+        thread::default_guard_size(),
+    )
+    .unwrap();
+
+    // Give the thread a chance to try to write while we hold the lock.
+    while !STARTED.load(Ordering::SeqCst) {
+        thread::yield_current();
+    }
+    for _ in 0..100 {
+        thread::yield_current();
+    }
+
+    writeln!(stderr, "main: second").unwrap();
+    drop(stderr);
+
+    thread::join(thread);
+
+    program::exit(250);
+}
@@ -0,0 +1,21 @@
+//! Test formatted output with `program::Stdout` and `program::Stderr`.
+
+#![no_std]
+#![no_main]
+
+extern crate alloc;
+
+use core::fmt::Write;
+use origin::program::{self, Stderr, Stdout};
+
+#[global_allocator]
+static GLOBAL_ALLOCATOR: rustix_dlmalloc::GlobalDlmalloc = rustix_dlmalloc::GlobalDlmalloc;
+
+#[no_mangle]
+unsafe fn origin_main(_argc: usize, _argv: *mut *mut u8, _envp: *mut *mut u8) -> i32 {
+    writeln!(Stdout, "Hello, {}!", "stdout").unwrap();
+    write!(Stderr, "Hello, ").unwrap();
+    writeln!(Stderr, "{}!", "stderr").unwrap();
+
+    program::exit(229)
+}
@@ -0,0 +1,34 @@
+//! Test that `program::terminate_by_signal` terminates the process with the
+//! given signal, even if it has a handler installed and is blocked.
+
+#![no_std]
+#![no_main]
+
+extern crate alloc;
+
+use origin::program;
+use origin::signal::{self, Sigaction, Signal};
+use rustix::runtime::{sigprocmask, How, Sigset};
+
+#[global_allocator]
+static GLOBAL_ALLOCATOR: rustix_dlmalloc::GlobalDlmalloc = rustix_dlmalloc::GlobalDlmalloc;
+
+/// A handler which exits, so that the test fails if it's called.
+unsafe extern "C" fn handler(_sig: i32) {
+    program::exit_immediately(13);
+}
+
+#[no_mangle]
+unsafe fn origin_main(_argc: usize, _argv: *mut *mut u8, _envp: *mut *mut u8) -> i32 {
+    // Install a handler for `Signal::Term`.
+    let mut action: Sigaction = core::mem::zeroed();
+    action.sa_handler_kernel = Some(handler);
+    signal::sigaction(Signal::Term, Some(action)).unwrap();
+
+    // Block all signals.
+    let mut all: Sigset = core::mem::zeroed();
+    all.sig.fill(!0);
+    sigprocmask(How::SETMASK, Some(&all)).unwrap();
+
+    program::terminate_by_signal(Signal::Term)
+}
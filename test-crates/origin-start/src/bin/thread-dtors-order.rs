@@ -0,0 +1,55 @@
+//! Test that thread dtors run before the thread's state is torn down, and
+//! that the main thread's dtors run before the program's dtors.
+
+#![no_std]
+#![no_main]
+
+extern crate alloc;
+
+use alloc::boxed::Box;
+use core::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+use origin::{program, thread};
+
+#[global_allocator]
+static GLOBAL_ALLOCATOR: rustix_dlmalloc::GlobalDlmalloc = rustix_dlmalloc::GlobalDlmalloc;
+
+static DONE: AtomicU32 = AtomicU32::new(1);
+static CHILD_DTOR_RAN: AtomicBool = AtomicBool::new(false);
+static MAIN_DTOR_RAN: AtomicBool = AtomicBool::new(false);
+
+#[no_mangle]
+unsafe fn origin_main(_argc: usize, _argv: *mut *mut u8, _envp: *mut *mut u8) -> i32 {
+    let child = thread::create_with_clear_tid(
+        DONE.as_ptr(),
+        |_args| {
+            thread::at_exit(Box::new(|| {
+                // The thread is still intact.
+                assert!(thread::id(thread::current()).is_some());
+                assert_eq!(thread::current_id(), rustix::thread::gettid());
+                assert_eq!(DONE.load(Ordering::SeqCst), 1);
+                CHILD_DTOR_RAN.store(true, Ordering::SeqCst);
+            }));
+            None
+        },
+        &[],
+        thread::default_stack_size(),
+        thread::default_guard_size(),
+    )
+    .unwrap();
+
+    // Once the child has been joined, its dtors have run.
+    thread::join(child);
+    assert!(CHILD_DTOR_RAN.load(Ordering::SeqCst));
+    assert_eq!(DONE.load(Ordering::SeqCst), 0);
+
+    // Register a program dtor before a main-thread dtor, and check that the
+    // main-thread dtor runs first anyway.
+    program::at_exit(Box::new(|| {
+        assert!(MAIN_DTOR_RAN.load(Ordering::SeqCst));
+    }));
+    thread::at_exit(Box::new(|| {
+        MAIN_DTOR_RAN.store(true, Ordering::SeqCst);
+    }));
+
+    program::exit(214);
+}
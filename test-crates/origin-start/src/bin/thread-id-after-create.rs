@@ -0,0 +1,45 @@
+//! Test that `thread::id` is valid as soon as `thread::create` returns.
+
+#![no_std]
+#![no_main]
+
+extern crate alloc;
+
+use core::hint::spin_loop;
+use core::sync::atomic::{AtomicBool, Ordering};
+use origin::{program, thread};
+
+#[global_allocator]
+static GLOBAL_ALLOCATOR: rustix_dlmalloc::GlobalDlmalloc = rustix_dlmalloc::GlobalDlmalloc;
+
+static RELEASED: AtomicBool = AtomicBool::new(false);
+
+#[no_mangle]
+unsafe fn origin_main(_argc: usize, _argv: *mut *mut u8, _envp: *mut *mut u8) -> i32 {
+    for _ in 0..100 {
+        RELEASED.store(false, Ordering::SeqCst);
+
+        // Have the child wait until we've checked its id, so that it can't
+        // exit and clear its id first.
+        let child = thread::create(
+            |_args| {
+                while !RELEASED.load(Ordering::SeqCst) {
+                    spin_loop();
+                }
+                None
+            },
+            &[],
+            thread::default_stack_size(),
+            thread::default_guard_size(),
+        )
+        .unwrap();
+
+        let id = thread::id(child).unwrap();
+        assert_ne!(id, thread::current_id());
+
+        RELEASED.store(true, Ordering::SeqCst);
+        thread::join(child);
+    }
+
+    program::exit(208);
+}
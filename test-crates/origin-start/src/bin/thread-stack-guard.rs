@@ -0,0 +1,38 @@
+//! Test that a thread's stack guard region is inaccessible, by reading the
+//! byte just below the stack, which should crash with `SIGSEGV`.
+
+#![no_std]
+#![no_main]
+
+extern crate alloc;
+
+use origin::{program, thread};
+
+#[global_allocator]
+static GLOBAL_ALLOCATOR: rustix_dlmalloc::GlobalDlmalloc = rustix_dlmalloc::GlobalDlmalloc;
+
+#[no_mangle]
+unsafe fn origin_main(_argc: usize, _argv: *mut *mut u8, _envp: *mut *mut u8) -> i32 {
+    let thread = thread::create(
+        |_args| {
+            let (addr, size, guard_size) = thread::stack(thread::current());
+            assert_ne!(size, 0);
+            assert_ne!(guard_size, 0);
+
+            // The bottom of the stack is accessible.
+            let addr = addr.cast::<u8>();
+            addr.write_volatile(addr.read_volatile());
+
+            // The byte below it is in the guard region.
+            addr.sub(1).read_volatile();
+            None
+        },
+        &[],
+        thread::default_stack_size(),
+        thread::default_guard_size(),
+    )
+    .unwrap();
+    thread::join(thread);
+
+    program::exit(0);
+}
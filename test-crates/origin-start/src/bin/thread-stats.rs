@@ -0,0 +1,61 @@
+//! Test `thread::stats`.
+
+#![no_std]
+#![no_main]
+
+extern crate alloc;
+
+use alloc::vec::Vec;
+use core::hint::spin_loop;
+use core::sync::atomic::{AtomicBool, Ordering};
+use origin::{program, thread};
+
+#[global_allocator]
+static GLOBAL_ALLOCATOR: rustix_dlmalloc::GlobalDlmalloc = rustix_dlmalloc::GlobalDlmalloc;
+
+static RELEASED: AtomicBool = AtomicBool::new(false);
+
+#[no_mangle]
+unsafe fn origin_main(_argc: usize, _argv: *mut *mut u8, _envp: *mut *mut u8) -> i32 {
+    // Just the main thread.
+    let stats = thread::stats();
+    assert_eq!(stats.total_created, 1);
+    assert_eq!(stats.live, 1);
+    assert_eq!(stats.peak, 1);
+
+    // Start four threads which wait until we release them.
+    let children = (0..4)
+        .map(|_| {
+            thread::create(
+                |_args| {
+                    while !RELEASED.load(Ordering::SeqCst) {
+                        spin_loop();
+                    }
+                    None
+                },
+                &[],
+                thread::default_stack_size(),
+                thread::default_guard_size(),
+            )
+            .unwrap()
+        })
+        .collect::<Vec<_>>();
+
+    let stats = thread::stats();
+    assert_eq!(stats.total_created, 5);
+    assert_eq!(stats.live, 5);
+    assert_eq!(stats.peak, 5);
+
+    RELEASED.store(true, Ordering::SeqCst);
+    for child in children {
+        thread::join(child);
+    }
+
+    // The threads have exited, but the peak and total remain.
+    let stats = thread::stats();
+    assert_eq!(stats.total_created, 5);
+    assert_eq!(stats.live, 1);
+    assert_eq!(stats.peak, 5);
+
+    program::exit(217);
+}
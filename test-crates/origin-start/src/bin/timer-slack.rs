@@ -0,0 +1,43 @@
+//! Test `program::set_timer_slack` and `program::timer_slack`.
+
+#![no_std]
+#![no_main]
+
+extern crate alloc;
+
+use core::ffi::c_void;
+use core::ptr::{without_provenance_mut, NonNull};
+use origin::{program, thread};
+
+#[global_allocator]
+static GLOBAL_ALLOCATOR: rustix_dlmalloc::GlobalDlmalloc = rustix_dlmalloc::GlobalDlmalloc;
+
+#[no_mangle]
+unsafe fn origin_main(_argc: usize, _argv: *mut *mut u8, _envp: *mut *mut u8) -> i32 {
+    let initial = program::timer_slack().unwrap();
+    assert_ne!(initial, 0);
+
+    program::set_timer_slack(1000).unwrap();
+    assert_eq!(program::timer_slack().unwrap(), 1000);
+
+    // Threads created afterward inherit it.
+    let thread = thread::create(
+        |_args| {
+            NonNull::new(without_provenance_mut::<c_void>(
+                program::timer_slack().unwrap() as usize,
+            ))
+        },
+        &[],
+        thread::default_stack_size(),
+        thread::default_guard_size(),
+    )
+    .unwrap();
+    let slack = thread::join(thread).map_or(0, |value| value.as_ptr() as usize);
+    assert_eq!(slack, 1000);
+
+    // Zero restores the initial value.
+    program::set_timer_slack(0).unwrap();
+    assert_eq!(program::timer_slack().unwrap(), initial);
+
+    program::exit(177);
+}
@@ -0,0 +1,26 @@
+//! Test that `program::timestamp_counter` advances.
+
+#![no_std]
+#![no_main]
+
+use origin::program;
+
+#[global_allocator]
+static GLOBAL_ALLOCATOR: rustix_dlmalloc::GlobalDlmalloc = rustix_dlmalloc::GlobalDlmalloc;
+
+#[no_mangle]
+unsafe fn origin_main(_argc: usize, _argv: *mut *mut u8, _envp: *mut *mut u8) -> i32 {
+    let start = program::timestamp_counter();
+
+    // Sleep, so that the counter has time to advance, even on systems where
+    // it runs at a low frequency.
+    let _ = rustix::thread::nanosleep(&rustix::thread::Timespec {
+        tv_sec: 0,
+        tv_nsec: 10_000_000,
+    });
+
+    let end = program::timestamp_counter();
+    assert!(end > start);
+
+    program::exit(197);
+}
@@ -0,0 +1,81 @@
+//! Test `thread::tls_addr_of`, reading `#[thread_local]` data in another
+//! thread.
+
+#![no_std]
+#![no_main]
+#![feature(thread_local)]
+
+extern crate alloc;
+
+use core::cell::Cell;
+use core::ptr::addr_of;
+use core::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use origin::{program, thread};
+
+#[global_allocator]
+static GLOBAL_ALLOCATOR: rustix_dlmalloc::GlobalDlmalloc = rustix_dlmalloc::GlobalDlmalloc;
+
+#[thread_local]
+static VALUE: Cell<u32> = Cell::new(1);
+
+/// The offset of `VALUE` computed on the main thread.
+static OFFSET: AtomicUsize = AtomicUsize::new(0);
+
+static READY: AtomicBool = AtomicBool::new(false);
+static DONE: AtomicBool = AtomicBool::new(false);
+
+/// Return the offset of `VALUE` within the TLS segment.
+fn value_offset() -> usize {
+    let base = unsafe { thread::tls_addr_of(thread::current(), 0) };
+    addr_of!(VALUE) as usize - base as usize
+}
+
+#[no_mangle]
+unsafe fn origin_main(_argc: usize, _argv: *mut *mut u8, _envp: *mut *mut u8) -> i32 {
+    let offset = value_offset();
+    assert_eq!(
+        thread::tls_addr_of(thread::current(), offset),
+        addr_of!(VALUE) as *mut _
+    );
+    OFFSET.store(offset, Ordering::SeqCst);
+    VALUE.set(2);
+
+    let main = thread::current();
+    let thread = thread::create(
+        |args| {
+            let main = thread::Thread::from_raw_non_null(args[0].unwrap());
+            let offset = OFFSET.load(Ordering::SeqCst);
+
+            // The offset is the same in this thread.
+            assert_eq!(value_offset(), offset);
+
+            // Read the main thread's value, and check that ours is separate.
+            let main_value = thread::tls_addr_of(main, offset).cast::<u32>();
+            assert_eq!(main_value.read(), 2);
+            assert_eq!(VALUE.get(), 1);
+
+            VALUE.set(3);
+            READY.store(true, Ordering::SeqCst);
+            while !DONE.load(Ordering::SeqCst) {
+                thread::yield_current();
+            }
+            None
+        },
+        &[Some(main.to_raw_non_null())],
+        thread::default_stack_size(),
+        thread::default_guard_size(),
+    )
+    .unwrap();
+
+    // Read the new thread's value while it's running.
+    while !READY.load(Ordering::SeqCst) {
+        thread::yield_current();
+    }
+    let value = thread::tls_addr_of(thread, offset).cast::<u32>();
+    assert_eq!(value.read(), 3);
+    assert_eq!(VALUE.get(), 2);
+    DONE.store(true, Ordering::SeqCst);
+
+    thread::join(thread);
+    program::exit(253);
+}
@@ -0,0 +1,109 @@
+//! Test `thread::tls_layout`, by finding data in a thread's TLS using
+//! thread-pointer-relative addressing, as generated code would.
+
+#![no_std]
+#![no_main]
+
+extern crate alloc;
+
+use alloc::vec::Vec;
+use core::arch::asm;
+use core::cmp::max;
+use core::ffi::c_void;
+use core::ptr::{without_provenance_mut, NonNull};
+use origin::{program, thread};
+
+#[global_allocator]
+static GLOBAL_ALLOCATOR: rustix_dlmalloc::GlobalDlmalloc = rustix_dlmalloc::GlobalDlmalloc;
+
+/// Our own TLS initializer data.
+const DATA: &[u8] = b"origin tls layout";
+
+const fn round_up(addr: usize, boundary: usize) -> usize {
+    (addr + (boundary - 1)) & boundary.wrapping_neg()
+}
+
+/// Read the thread pointer, as generated code would.
+fn thread_pointer() -> *mut u8 {
+    let ptr;
+    unsafe {
+        #[cfg(target_arch = "x86_64")]
+        asm!("mov {}, fs:0", out(reg) ptr, options(nostack, preserves_flags, readonly));
+        #[cfg(target_arch = "x86")]
+        asm!("mov {}, gs:0", out(reg) ptr, options(nostack, preserves_flags, readonly));
+        #[cfg(target_arch = "aarch64")]
+        asm!("mrs {}, tpidr_el0", out(reg) ptr, options(nostack, preserves_flags, readonly));
+        #[cfg(target_arch = "arm")]
+        asm!("mrc p15, 0, {}, c13, c0, 3", out(reg) ptr, options(nostack, preserves_flags, readonly));
+        #[cfg(target_arch = "riscv64")]
+        asm!("mv {}, tp", out(reg) ptr, options(nostack, preserves_flags, readonly));
+    }
+    ptr
+}
+
+#[no_mangle]
+unsafe fn origin_main(_argc: usize, _argv: *mut *mut u8, _envp: *mut *mut u8) -> i32 {
+    let layout = thread::tls_layout();
+    if cfg!(any(target_arch = "x86", target_arch = "x86_64")) {
+        assert_eq!(layout.variant, thread::TlsVariant::II);
+        assert!(layout.block_offset <= 0);
+    } else {
+        assert_eq!(layout.variant, thread::TlsVariant::I);
+        assert!(layout.block_offset >= 0);
+    }
+    assert_eq!(
+        thread::current_tls_addr(1, 0usize.wrapping_sub(layout.dtv_offset)),
+        thread_pointer()
+            .wrapping_offset(layout.block_offset)
+            .cast::<c_void>()
+    );
+
+    // Create a thread with a template containing the executable's TLS data
+    // and some data of our own, as in the tls-template test.
+    let startup = thread::TlsTemplate::startup();
+    let align = max(startup.align(), 16);
+    let exe_size = round_up(startup.mem_size(), startup.align());
+    let mut image = Vec::new();
+    let ours;
+    let mem_size;
+    if layout.variant == thread::TlsVariant::II {
+        mem_size = round_up(round_up(DATA.len(), align) + exe_size, align);
+        ours = 0;
+        image.extend_from_slice(DATA);
+        image.resize(mem_size - exe_size, 0);
+        image.extend_from_slice(startup.data());
+    } else {
+        image.extend_from_slice(startup.data());
+        image.resize(round_up(exe_size, align), 0);
+        ours = image.len();
+        image.extend_from_slice(DATA);
+        mem_size = image.len();
+    }
+    let template = thread::TlsTemplate::new(&image, mem_size, align).unwrap();
+
+    let thread = thread::create_with_tls_template(
+        &template,
+        |args| {
+            // The offset is passed plus one, so that it's never null.
+            let ours = args[0].unwrap().as_ptr().addr() - 1;
+            let layout = thread::tls_layout();
+            let addr = thread_pointer()
+                .wrapping_offset(layout.block_offset)
+                .wrapping_add(ours);
+            let found = core::slice::from_raw_parts(addr, DATA.len());
+            if found == DATA {
+                NonNull::new(without_provenance_mut::<c_void>(1))
+            } else {
+                None
+            }
+        },
+        &[NonNull::new(without_provenance_mut(ours + 1))],
+        thread::default_stack_size(),
+        thread::default_guard_size(),
+    )
+    .unwrap();
+
+    assert!(thread::join(thread).is_some());
+
+    program::exit(239)
+}
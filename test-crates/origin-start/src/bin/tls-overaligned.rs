@@ -0,0 +1,68 @@
+//! Test that TLS data with an alignment greater than the page size is
+//! aligned and initialized correctly on both the main thread and child
+//! threads.
+
+#![no_std]
+#![no_main]
+#![feature(thread_local)]
+
+extern crate alloc;
+
+use core::cell::Cell;
+use core::ptr::addr_of;
+use origin::{program, thread};
+
+#[global_allocator]
+static GLOBAL_ALLOCATOR: rustix_dlmalloc::GlobalDlmalloc = rustix_dlmalloc::GlobalDlmalloc;
+
+/// More than any page size we run on.
+const ALIGN: usize = 0x10000;
+
+#[repr(C, align(0x10000))]
+struct OverAligned(Cell<u32>);
+
+#[repr(C, align(64))]
+struct CacheAligned(Cell<u32>);
+
+#[thread_local]
+static OVER_ALIGNED: OverAligned = OverAligned(Cell::new(0x1234_5678));
+
+#[thread_local]
+static CACHE_ALIGNED: CacheAligned = CacheAligned(Cell::new(0x9abc_def0));
+
+#[thread_local]
+static PLAIN: Cell<u8> = Cell::new(7);
+
+fn check() {
+    assert_eq!(addr_of!(OVER_ALIGNED) as usize % ALIGN, 0);
+    assert_eq!(addr_of!(CACHE_ALIGNED) as usize % 64, 0);
+
+    assert_eq!(OVER_ALIGNED.0.get(), 0x1234_5678);
+    assert_eq!(CACHE_ALIGNED.0.get(), 0x9abc_def0);
+    assert_eq!(PLAIN.get(), 7);
+
+    OVER_ALIGNED.0.set(0);
+    CACHE_ALIGNED.0.set(0);
+    PLAIN.set(0);
+}
+
+#[no_mangle]
+unsafe fn origin_main(_argc: usize, _argv: *mut *mut u8, _envp: *mut *mut u8) -> i32 {
+    check();
+
+    for _ in 0..4 {
+        let thread = thread::create(
+            |_args| {
+                check();
+                None
+            },
+            &[],
+            thread::default_stack_size(),
+            thread::default_guard_size(),
+        )
+        .unwrap();
+        thread::join(thread);
+    }
+
+    program::exit(224);
+}
@@ -0,0 +1,81 @@
+//! Test creating a thread with `thread::create_with_tls_template`, using a
+//! template composed of the executable's TLS data and some data of our own.
+
+#![no_std]
+#![no_main]
+
+extern crate alloc;
+
+use alloc::vec::Vec;
+use core::cmp::max;
+use core::ffi::c_void;
+use core::ptr::{without_provenance_mut, NonNull};
+use origin::{program, thread};
+
+#[global_allocator]
+static GLOBAL_ALLOCATOR: rustix_dlmalloc::GlobalDlmalloc = rustix_dlmalloc::GlobalDlmalloc;
+
+/// Our own TLS initializer data.
+const DATA: &[u8] = b"origin tls template";
+
+/// The bias in TLS offsets passed to `current_tls_addr`.
+const DTP_OFFSET: usize = if cfg!(target_arch = "riscv64") { 0x800 } else { 0 };
+
+const fn round_up(addr: usize, boundary: usize) -> usize {
+    (addr + (boundary - 1)) & boundary.wrapping_neg()
+}
+
+#[no_mangle]
+unsafe fn origin_main(_argc: usize, _argv: *mut *mut u8, _envp: *mut *mut u8) -> i32 {
+    // Invalid templates are rejected.
+    assert!(thread::TlsTemplate::new(DATA, DATA.len(), 3).is_err());
+    assert!(thread::TlsTemplate::new(DATA, DATA.len() - 1, 16).is_err());
+
+    let startup = thread::TlsTemplate::startup();
+    let align = max(startup.align(), 16);
+    let exe_size = round_up(startup.mem_size(), startup.align());
+
+    // Place the executable's TLS data where it expects it, and ours next to
+    // it. On x86 and x86_64, TLS data goes before the thread pointer, so the
+    // executable's data goes at the end.
+    let mut image = Vec::new();
+    let ours;
+    let mem_size;
+    if cfg!(any(target_arch = "x86", target_arch = "x86_64")) {
+        mem_size = round_up(round_up(DATA.len(), align) + exe_size, align);
+        ours = 0;
+        image.extend_from_slice(DATA);
+        image.resize(mem_size - exe_size, 0);
+        image.extend_from_slice(startup.data());
+    } else {
+        image.extend_from_slice(startup.data());
+        image.resize(round_up(exe_size, align), 0);
+        ours = image.len();
+        image.extend_from_slice(DATA);
+        mem_size = image.len();
+    }
+    let template = thread::TlsTemplate::new(&image, mem_size, align).unwrap();
+
+    let thread = thread::create_with_tls_template(
+        &template,
+        |args| {
+            // The offset is passed plus one, so that it's never null.
+            let ours = args[0].unwrap().as_ptr().addr() - 1;
+            let addr = thread::current_tls_addr(1, ours.wrapping_sub(DTP_OFFSET));
+            let found = core::slice::from_raw_parts(addr.cast::<u8>(), DATA.len());
+            if found == DATA {
+                NonNull::new(without_provenance_mut::<c_void>(1))
+            } else {
+                None
+            }
+        },
+        &[NonNull::new(without_provenance_mut(ours + 1))],
+        thread::default_stack_size(),
+        thread::default_guard_size(),
+    )
+    .unwrap();
+
+    assert!(thread::join(thread).is_some());
+
+    program::exit(231)
+}
@@ -0,0 +1,64 @@
+//! Test that `program::exit` warns about threads which are still running in
+//! debug builds with "thread-stats" and "log".
+
+#![no_std]
+#![no_main]
+
+extern crate alloc;
+
+use atomic_dbg::eprintln;
+use core::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+use origin::{program, thread};
+use rustix::thread::futex;
+
+#[global_allocator]
+static GLOBAL_ALLOCATOR: rustix_dlmalloc::GlobalDlmalloc = rustix_dlmalloc::GlobalDlmalloc;
+
+/// A logger which prints messages to stderr.
+struct Logger;
+
+impl log::Log for Logger {
+    fn enabled(&self, metadata: &log::Metadata<'_>) -> bool {
+        metadata.level() <= log::Level::Warn
+    }
+
+    fn log(&self, record: &log::Record<'_>) {
+        if self.enabled(record.metadata()) {
+            eprintln!("{}", record.args());
+        }
+    }
+
+    fn flush(&self) {}
+}
+
+static LOGGER: Logger = Logger;
+
+/// Set when the thread has started.
+static STARTED: AtomicBool = AtomicBool::new(false);
+
+#[no_mangle]
+unsafe fn origin_main(_argc: usize, _argv: *mut *mut u8, _envp: *mut *mut u8) -> i32 {
+    log::set_logger(&LOGGER).unwrap();
+    log::set_max_level(log::LevelFilter::Warn);
+
+    // Start a thread which never finishes, and exit without joining it.
+    let _thread = thread::create(
+        |_args| {
+            STARTED.store(true, Ordering::SeqCst);
+            let never = AtomicU32::new(0);
+            loop {
+                let _ = futex::wait(&never, futex::Flags::PRIVATE, 0, None);
+            }
+        },
+        &[],
+        thread::default_stack_size(),
+        thread::default_guard_size(),
+    )
+    .unwrap();
+
+    while !STARTED.load(Ordering::SeqCst) {
+        thread::yield_current();
+    }
+
+    program::exit(226);
+}
@@ -0,0 +1,41 @@
+//! Test that `program::vdso` finds the vDSO's time functions.
+
+#![no_std]
+#![no_main]
+
+use core::ptr::null_mut;
+use linux_raw_sys::general::{__kernel_old_timeval, __kernel_timespec, CLOCK_MONOTONIC};
+use origin::program;
+
+#[global_allocator]
+static GLOBAL_ALLOCATOR: rustix_dlmalloc::GlobalDlmalloc = rustix_dlmalloc::GlobalDlmalloc;
+
+#[no_mangle]
+unsafe fn origin_main(_argc: usize, _argv: *mut *mut u8, _envp: *mut *mut u8) -> i32 {
+    // Some emulators don't provide a vDSO.
+    let Some(vdso) = program::vdso() else {
+        program::exit(186);
+    };
+
+    let clock_gettime = vdso.clock_gettime.unwrap();
+    let mut a: __kernel_timespec = core::mem::zeroed();
+    let mut b: __kernel_timespec = core::mem::zeroed();
+    assert_eq!(clock_gettime(CLOCK_MONOTONIC as _, &mut a), 0);
+    assert_eq!(clock_gettime(CLOCK_MONOTONIC as _, &mut b), 0);
+    assert!((a.tv_sec, a.tv_nsec) <= (b.tv_sec, b.tv_nsec));
+    assert!(a.tv_sec != 0 || a.tv_nsec != 0);
+
+    // An invalid clock id fails with `EINVAL`.
+    assert_eq!(
+        clock_gettime(-1, &mut a),
+        -rustix::io::Errno::INVAL.raw_os_error()
+    );
+
+    let gettimeofday = vdso.gettimeofday.unwrap();
+    let mut tv: __kernel_old_timeval = core::mem::zeroed();
+    assert_eq!(gettimeofday(&mut tv, null_mut()), 0);
+    // Some time after 2020.
+    assert!(tv.tv_sec > 1_600_000_000);
+
+    program::exit(186);
+}
@@ -0,0 +1,43 @@
+//! Test `program::vfork_exec`.
+
+#![no_std]
+#![no_main]
+
+extern crate alloc;
+
+use core::ptr::null;
+use origin::program;
+use rustix::io;
+use rustix::process::{waitpid, WaitOptions};
+
+#[global_allocator]
+static GLOBAL_ALLOCATOR: rustix_dlmalloc::GlobalDlmalloc = rustix_dlmalloc::GlobalDlmalloc;
+
+#[no_mangle]
+unsafe fn origin_main(_argc: usize, _argv: *mut *mut u8, envp: *mut *mut u8) -> i32 {
+    let argv = [
+        c"/bin/sh".as_ptr().cast::<u8>(),
+        c"-c".as_ptr().cast(),
+        c"exit 7".as_ptr().cast(),
+        null(),
+    ];
+
+    // Launch a program, and check that it ran.
+    let pid = program::vfork_exec(c"/bin/sh", argv.as_ptr(), envp.cast()).unwrap();
+    let status = waitpid(Some(pid), WaitOptions::empty()).unwrap().unwrap();
+    assert_eq!(status.exit_status(), Some(7));
+
+    // Launch a program that doesn't exist, and check that we get the error.
+    assert_eq!(
+        program::vfork_exec(c"/nonexistent", argv.as_ptr(), envp.cast()),
+        Err(io::Errno::NOENT)
+    );
+
+    // The failed child should already have been reaped.
+    assert_eq!(
+        waitpid(None, WaitOptions::NOHANG).map(|status| status.is_some()),
+        Err(io::Errno::CHILD)
+    );
+
+    program::exit(204);
+}
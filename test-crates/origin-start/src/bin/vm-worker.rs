@@ -0,0 +1,71 @@
+//! Test `thread::create_vm_worker`.
+
+#![no_std]
+#![no_main]
+
+extern crate alloc;
+
+use core::ffi::c_void;
+use core::ptr::{without_provenance_mut, NonNull};
+use core::sync::atomic::{AtomicI32, Ordering::SeqCst};
+use origin::program;
+use origin::thread::{self, VmWorkerFlags};
+use rustix::io::Errno;
+use rustix::process::{getpid, test_kill_process, Pid};
+
+#[global_allocator]
+static GLOBAL_ALLOCATOR: rustix_dlmalloc::GlobalDlmalloc = rustix_dlmalloc::GlobalDlmalloc;
+
+/// The process id the worker saw, which it stores in our shared memory.
+static WORKER_PID: AtomicI32 = AtomicI32::new(0);
+
+unsafe fn run(flags: VmWorkerFlags) -> (Pid, usize) {
+    WORKER_PID.store(0, SeqCst);
+    let worker = thread::create_vm_worker(
+        flags,
+        |_args| {
+            WORKER_PID.store(getpid().as_raw_nonzero().get(), SeqCst);
+            NonNull::new(without_provenance_mut::<c_void>(0x1234))
+        },
+        &[],
+        thread::default_stack_size(),
+        thread::default_guard_size(),
+    )
+    .unwrap();
+    let id = thread::id(worker).unwrap();
+    let value = thread::join(worker).map_or(0, |value| value.as_ptr() as usize);
+    (Pid::from_raw(id.as_raw_nonzero().get()).unwrap(), value)
+}
+
+#[no_mangle]
+unsafe fn origin_main(_argc: usize, _argv: *mut *mut u8, _envp: *mut *mut u8) -> i32 {
+    let pid = getpid();
+
+    // Without `THREAD`, the worker is in its own thread group, but it
+    // shares our memory, and `join` works and reaps it.
+    for flags in [VmWorkerFlags::empty(), VmWorkerFlags::SIGHAND] {
+        let (id, value) = run(flags);
+        assert_eq!(value, 0x1234);
+        assert_eq!(WORKER_PID.load(SeqCst), id.as_raw_nonzero().get());
+        assert_ne!(id, pid);
+        assert_eq!(test_kill_process(id), Err(Errno::SRCH));
+    }
+
+    // With all the flags, it's an ordinary thread.
+    let (id, value) = run(VmWorkerFlags::all());
+    assert_eq!(value, 0x1234);
+    assert_eq!(WORKER_PID.load(SeqCst), pid.as_raw_nonzero().get());
+    assert_ne!(id, pid);
+
+    // `THREAD` requires `SIGHAND`.
+    let result = thread::create_vm_worker(
+        VmWorkerFlags::THREAD,
+        |_args| None,
+        &[],
+        thread::default_stack_size(),
+        thread::default_guard_size(),
+    );
+    assert!(matches!(result, Err(Errno::INVAL)));
+
+    program::exit(181);
+}
@@ -0,0 +1,44 @@
+//! Test that `signal::with_handler` installs a handler only while its closure
+//! runs.
+
+#![no_std]
+#![no_main]
+
+extern crate alloc;
+
+use core::sync::atomic::{AtomicU32, Ordering};
+use origin::program;
+use origin::signal::{self, Sigaction, Signal};
+
+#[global_allocator]
+static GLOBAL_ALLOCATOR: rustix_dlmalloc::GlobalDlmalloc = rustix_dlmalloc::GlobalDlmalloc;
+
+static HANDLED: AtomicU32 = AtomicU32::new(0);
+
+unsafe extern "C" fn handler(sig: i32) {
+    assert_eq!(sig, Signal::Pipe as i32);
+    HANDLED.fetch_add(1, Ordering::SeqCst);
+}
+
+#[no_mangle]
+unsafe fn origin_main(_argc: usize, _argv: *mut *mut u8, _envp: *mut *mut u8) -> i32 {
+    // `SigDfl` is represented as `None`.
+    let before = signal::sigaction(Signal::Pipe, None).unwrap();
+    assert!(before.sa_handler_kernel.is_none());
+
+    let mut action: Sigaction = core::mem::zeroed();
+    action.sa_handler_kernel = Some(handler);
+
+    let result = signal::with_handler(Signal::Pipe, action, || {
+        rustix::runtime::tkill(rustix::thread::gettid(), Signal::Pipe).unwrap();
+        HANDLED.load(Ordering::SeqCst)
+    })
+    .unwrap();
+    assert_eq!(result, 1);
+
+    // The default action is restored afterward.
+    let after = signal::sigaction(Signal::Pipe, None).unwrap();
+    assert!(after.sa_handler_kernel.is_none());
+
+    program::exit(234);
+}
@@ -0,0 +1,49 @@
+//! Test `thread::yield_to` as a spin-loop backoff.
+
+#![no_std]
+#![no_main]
+
+extern crate alloc;
+
+use core::sync::atomic::{AtomicU32, Ordering};
+use origin::{program, thread};
+
+#[global_allocator]
+static GLOBAL_ALLOCATOR: rustix_dlmalloc::GlobalDlmalloc = rustix_dlmalloc::GlobalDlmalloc;
+
+static COUNTER: AtomicU32 = AtomicU32::new(0);
+
+#[no_mangle]
+unsafe fn origin_main(_argc: usize, _argv: *mut *mut u8, _envp: *mut *mut u8) -> i32 {
+    // Yielding to ourselves is just a yield.
+    thread::yield_to(thread::current());
+
+    // Have the child make some progress, and yield to it until it's done.
+    let child = thread::create(
+        |_args| {
+            for _ in 0..100 {
+                COUNTER.fetch_add(1, Ordering::SeqCst);
+                thread::yield_current();
+            }
+            None
+        },
+        &[],
+        thread::default_stack_size(),
+        thread::default_guard_size(),
+    )
+    .unwrap();
+
+    while COUNTER.load(Ordering::SeqCst) != 100 {
+        thread::yield_to(child);
+    }
+
+    // Yielding to a thread which has exited, but hasn't been joined, returns.
+    while thread::id(child).is_some() {
+        thread::yield_to(child);
+    }
+    thread::yield_to(child);
+
+    thread::join(child);
+
+    program::exit(212);
+}
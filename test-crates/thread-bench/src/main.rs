@@ -0,0 +1,111 @@
+//! A benchmark of thread creation and joining.
+//!
+//! Run it with `cargo run --release`, optionally followed by `--`, the total
+//! number of threads to create, and the number of threads to have running at
+//! the same time in the throughput measurement. It reports the average time
+//! to create and join a thread one at a time, and the number of threads per
+//! second that can be created and joined in batches.
+
+#![no_std]
+#![no_main]
+
+extern crate alloc;
+
+use core::ffi::CStr;
+use core::fmt::Write;
+use core::ptr::NonNull;
+use origin::program::{self, Stdout};
+use origin::thread::{self, Thread};
+use rustix::time::{clock_gettime, ClockId, Timespec};
+
+#[global_allocator]
+static GLOBAL_ALLOCATOR: rustix_dlmalloc::GlobalDlmalloc = rustix_dlmalloc::GlobalDlmalloc;
+
+/// The default total number of threads to create.
+const DEFAULT_THREADS: usize = 10_000;
+
+/// The default number of threads to create at a time, for throughput.
+const DEFAULT_BATCH: usize = 64;
+
+/// The largest batch size we support.
+const MAX_BATCH: usize = 1024;
+
+/// The trivial work each thread does.
+unsafe fn work(
+    args: &mut [Option<NonNull<core::ffi::c_void>>],
+) -> Option<NonNull<core::ffi::c_void>> {
+    args[0]
+}
+
+/// Create a thread which does trivial work.
+unsafe fn create() -> Thread {
+    thread::create(
+        work,
+        &[Some(NonNull::dangling())],
+        thread::default_stack_size(),
+        thread::default_guard_size(),
+    )
+    .unwrap()
+}
+
+/// Return the number of nanoseconds since `start`.
+fn elapsed_nanos(start: Timespec) -> u64 {
+    let now = clock_gettime(ClockId::Monotonic);
+    (now.tv_sec - start.tv_sec) as u64 * 1_000_000_000 + now.tv_nsec as u64 - start.tv_nsec as u64
+}
+
+/// Parse the command-line argument at `index`, or return `default`.
+unsafe fn arg(argc: usize, argv: *mut *mut u8, index: usize, default: usize) -> usize {
+    if index >= argc {
+        return default;
+    }
+    let arg = CStr::from_ptr((*argv.add(index)).cast());
+    match arg.to_str().ok().and_then(|arg| arg.parse().ok()) {
+        Some(value) if value > 0 => value,
+        _ => {
+            writeln!(program::Stderr, "invalid argument: {:?}", arg).unwrap();
+            program::exit(2)
+        }
+    }
+}
+
+#[no_mangle]
+unsafe fn origin_main(argc: usize, argv: *mut *mut u8, _envp: *mut *mut u8) -> i32 {
+    let threads = arg(argc, argv, 1, DEFAULT_THREADS);
+    let batch = arg(argc, argv, 2, DEFAULT_BATCH).min(MAX_BATCH);
+
+    writeln!(Stdout, "threads: {}", threads).unwrap();
+
+    // Latency: create and join one thread at a time.
+    let start = clock_gettime(ClockId::Monotonic);
+    for _ in 0..threads {
+        assert!(thread::join(create()).is_some());
+    }
+    let nanos = elapsed_nanos(start);
+    writeln!(Stdout, "create+join latency: {} ns", nanos / threads as u64).unwrap();
+
+    // Throughput: create `batch` threads, then join them all.
+    let mut handles = [None; MAX_BATCH];
+    let start = clock_gettime(ClockId::Monotonic);
+    let mut remaining = threads;
+    while remaining != 0 {
+        let n = remaining.min(batch);
+        for handle in &mut handles[..n] {
+            *handle = Some(create());
+        }
+        for handle in &mut handles[..n] {
+            assert!(thread::join(handle.take().unwrap()).is_some());
+        }
+        remaining -= n;
+    }
+    let nanos = elapsed_nanos(start).max(1);
+    writeln!(
+        Stdout,
+        "throughput (batches of {}): {} threads/sec",
+        batch,
+        threads as u64 * 1_000_000_000 / nanos
+    )
+    .unwrap();
+
+    0
+}
@@ -37,6 +37,23 @@ fn example_crate_basic_relocate() {
     );
 }
 
+#[test]
+fn example_crate_cdylib() {
+    test_crate(
+        "cdylib",
+        &[],
+        &[],
+        "",
+        "Hello from host\n\
+         Hello from the library\n\
+         Hello from child thread\n\
+         Hello from child thread's `thread::at_exit` handler\n\
+         Goodbye from the library\n\
+         Goodbye from host\n",
+        None,
+    );
+}
+
 #[test]
 fn example_crate_no_std() {
     test_crate("no-std", &[], &[], "", COMMON_STDERR, None);
@@ -73,6 +90,20 @@ fn example_crate_external_start_relocate() {
     );
 }
 
+/// Like `example_crate_external_start` but leave libc's thread pointer in
+/// place on the main thread.
+#[test]
+fn example_crate_external_start_keep_main_thread_pointer() {
+    test_crate(
+        "external-start",
+        &["--features=origin/keep-main-thread-pointer"],
+        &[],
+        "",
+        COMMON_STDERR,
+        None,
+    );
+}
+
 #[test]
 fn example_crate_origin_start() {
     // Use a dynamic linker.
@@ -358,3 +389,15 @@ fn example_crate_origin_start_panic_abort() {
 fn example_crate_tiny() {
     test_crate("tiny", &["--release"], &[], "", "", Some(42));
 }
+
+/// Check that the tiny example stays tiny. With no `at_exit` or `.fini_array`
+/// support, `exit` should be nothing more than an `exit_group` syscall.
+#[cfg(target_arch = "x86_64")]
+#[test]
+fn example_crate_tiny_size() {
+    test_crate("tiny", &["--release"], &[], "", "", Some(42));
+
+    let path = format!("example-crates/tiny/target/{}/release/tiny", utils::arch());
+    let size = std::fs::metadata(path).unwrap().len();
+    assert!(size <= 1024, "tiny example is {size} bytes");
+}
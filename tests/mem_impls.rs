@@ -0,0 +1,108 @@
+//! Test the portable `memcmp` implementation in src/mem/impls.rs against a
+//! simple bytewise loop.
+//!
+//! The implementation is only used by origin on targets without their own,
+//! but this test includes it directly, so that it's tested on every target.
+
+// impls.rs is written for origin's own build configuration, which differs
+// from this test's.
+#[allow(warnings)]
+#[path = "../src/mem/impls.rs"]
+mod impls;
+#[path = "../src/ptr.rs"]
+mod ptr;
+
+/// Stand-ins for the functions in src/arch/* which impls.rs uses.
+#[allow(dead_code)]
+mod arch {
+    /// Load a `usize` which may extend past the end of the bytes being
+    /// compared. The buffers in this test have enough slack that this stays
+    /// in bounds.
+    pub(crate) unsafe fn oob_load(ptr: *const usize) -> usize {
+        ptr.read_volatile()
+    }
+}
+
+const WORD_SIZE: usize = core::mem::size_of::<usize>();
+
+/// The same value as in impls.rs, above which it compares words at a time.
+const WORD_COPY_THRESHOLD: usize = if 2 * WORD_SIZE > 16 {
+    2 * WORD_SIZE
+} else {
+    16
+};
+
+/// A buffer with room for every misalignment and length we test, plus a
+/// word of slack on each side.
+#[repr(C, align(16))]
+struct Buffer([u8; 128]);
+
+/// Compare `a` and `b` one byte at a time.
+fn compare_bytewise(a: &[u8], b: &[u8]) -> i32 {
+    for (x, y) in a.iter().zip(b) {
+        if x != y {
+            return *x as i32 - *y as i32;
+        }
+    }
+    0
+}
+
+fn check(a: &[u8], b: &[u8]) {
+    let expected = compare_bytewise(a, b);
+    let actual = unsafe { impls::compare_bytes(a.as_ptr(), b.as_ptr(), a.len()) };
+    assert_eq!(
+        actual.signum(),
+        expected.signum(),
+        "a={a:?} b={b:?} a_align={} b_align={}",
+        a.as_ptr() as usize % WORD_SIZE,
+        b.as_ptr() as usize % WORD_SIZE,
+    );
+}
+
+#[test]
+fn test_compare_bytes() {
+    let mut buf1 = Buffer([0; 128]);
+    let mut buf2 = Buffer([0; 128]);
+
+    let lens = (0..4).chain(WORD_COPY_THRESHOLD - 3..=WORD_COPY_THRESHOLD + 2 * WORD_SIZE + 1);
+    for len in lens {
+        for off1 in 0..WORD_SIZE {
+            for off2 in 0..WORD_SIZE {
+                let start1 = WORD_SIZE + off1;
+                let start2 = WORD_SIZE + off2;
+
+                // Fill the buffers with the same bytes in the compared ranges
+                // and different bytes outside them.
+                buf1.0.fill(0x00);
+                buf2.0.fill(0xff);
+                for i in 0..len {
+                    buf1.0[start1 + i] = (i as u8).wrapping_mul(7).wrapping_add(0x41);
+                    buf2.0[start2 + i] = (i as u8).wrapping_mul(7).wrapping_add(0x41);
+                }
+                check(&buf1.0[start1..start1 + len], &buf2.0[start2..start2 + len]);
+
+                // Make the bytes differ at each position, in each direction.
+                // Make the following byte, if any, differ in the opposite
+                // direction, so that a result determined by a later byte
+                // has the wrong sign.
+                for pos in 0..len {
+                    for (x, y) in [(0x10, 0x90), (0x90, 0x10)] {
+                        let saved1 = buf1.0[start1..start1 + len].to_vec();
+                        let saved2 = buf2.0[start2..start2 + len].to_vec();
+
+                        buf1.0[start1 + pos] = x;
+                        buf2.0[start2 + pos] = y;
+                        if pos + 1 < len {
+                            buf1.0[start1 + pos + 1] = y;
+                            buf2.0[start2 + pos + 1] = x;
+                        }
+                        check(&buf1.0[start1..start1 + len], &buf2.0[start2..start2 + len]);
+
+                        buf1.0[start1..start1 + len].copy_from_slice(&saved1);
+                        buf2.0[start2..start2 + len].copy_from_slice(&saved2);
+                    }
+                }
+            }
+        }
+    }
+}
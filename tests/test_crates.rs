@@ -90,6 +90,1013 @@ fn test_canary() {
     test_crate("origin-start", &["--bin=canary"], &[], "", "", Some(203));
 }
 
+#[test]
+fn test_current_identity() {
+    test_crate(
+        "origin-start",
+        &["--bin=current-identity"],
+        &[],
+        "",
+        "",
+        Some(205),
+    );
+}
+
+#[test]
+fn test_lock_tls() {
+    test_crate("origin-start", &["--bin=lock-tls"], &[], "", "", Some(206));
+}
+
+#[test]
+fn test_create_with_clear_tid() {
+    test_crate(
+        "origin-start",
+        &["--bin=create-with-clear-tid"],
+        &[],
+        "",
+        "",
+        Some(207),
+    );
+}
+
+#[test]
+fn test_thread_id_after_create() {
+    test_crate(
+        "origin-start",
+        &["--bin=thread-id-after-create"],
+        &[],
+        "",
+        "",
+        Some(208),
+    );
+}
+
+#[test]
+fn test_block_all_signals() {
+    test_crate(
+        "origin-start",
+        &["--bin=block-all-signals"],
+        &[],
+        "",
+        "",
+        Some(211),
+    );
+}
+
+#[test]
+fn test_yield_to() {
+    test_crate("origin-start", &["--bin=yield-to"], &[], "", "", Some(212));
+}
+
+#[test]
+fn test_misaligned_copy() {
+    test_crate(
+        "origin-start",
+        &["--bin=misaligned-copy"],
+        &[],
+        "",
+        "",
+        Some(213),
+    );
+}
+
+#[test]
+fn test_process_name() {
+    test_crate(
+        "origin-start",
+        &["--bin=process-name"],
+        &[],
+        "",
+        "",
+        Some(215),
+    );
+}
+
+#[test]
+fn test_signal_return() {
+    test_crate(
+        "origin-start",
+        &["--bin=signal-return"],
+        &[],
+        "",
+        "",
+        Some(216),
+    );
+}
+
+#[test]
+fn test_thread_stats() {
+    test_crate(
+        "origin-start",
+        &["--bin=thread-stats", "--features=origin/thread-stats"],
+        &[],
+        "",
+        "",
+        Some(217),
+    );
+}
+
+#[test]
+fn test_fini_array_exit() {
+    test_crate(
+        "origin-start",
+        &["--bin=fini-array-exit", "--features=origin/fini-array"],
+        &[],
+        "",
+        "",
+        Some(218),
+    );
+}
+
+#[test]
+fn test_fini_array_exit_hooks() {
+    test_crate(
+        "origin-start",
+        &[
+            "--bin=fini-array-exit-hooks",
+            "--features=origin/fini-array,origin/thread-at-exit",
+        ],
+        &[],
+        "",
+        "",
+        Some(219),
+    );
+}
+
+#[test]
+fn test_quiesce_threads() {
+    test_crate(
+        "origin-start",
+        &[
+            "--bin=quiesce-threads",
+            "--features=origin/quiesce-threads-before-exit",
+        ],
+        &[],
+        "",
+        "",
+        Some(219),
+    );
+}
+
+#[test]
+fn test_spawn_unchecked() {
+    test_crate(
+        "origin-start",
+        &["--bin=spawn-unchecked"],
+        &[],
+        "",
+        "",
+        Some(220),
+    );
+}
+
+#[test]
+fn test_program_break() {
+    test_crate(
+        "origin-start",
+        &["--bin=program-break"],
+        &[],
+        "",
+        "",
+        Some(221),
+    );
+}
+
+#[test]
+fn test_getauxval() {
+    test_crate(
+        "origin-start",
+        &["--bin=getauxval", "--features=origin/getauxval"],
+        &[],
+        "",
+        "",
+        Some(222),
+    );
+}
+
+#[test]
+fn test_join_any() {
+    test_crate("origin-start", &["--bin=join-any"], &[], "", "", Some(223));
+}
+
+#[test]
+fn test_tls_overaligned() {
+    test_crate(
+        "origin-start",
+        &["--bin=tls-overaligned"],
+        &[],
+        "",
+        "",
+        Some(224),
+    );
+}
+
+#[test]
+fn test_dedicate_signal_thread() {
+    test_crate(
+        "origin-start",
+        &[
+            "--bin=dedicate-signal-thread",
+            "--features=origin/thread-registry",
+        ],
+        &[],
+        "",
+        "",
+        Some(225),
+    );
+}
+
+#[test]
+fn test_unjoined_threads() {
+    let mut command = utils::run_test(
+        "test",
+        "run",
+        "origin-start",
+        &[
+            "--bin=unjoined-threads",
+            "--features=origin/thread-stats,origin/thread-registry,origin/log",
+        ],
+        &[],
+    );
+    let output = command.output().unwrap();
+    assert_eq!(output.status.code(), Some(226));
+
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    let mut lines = stderr.lines();
+    assert_eq!(
+        lines.next(),
+        Some("Exiting with 1 other thread(s) still running, which will be terminated")
+    );
+    let line = lines.next().unwrap();
+    assert!(line.starts_with("Thread[") && line.ends_with("] is still running"));
+    assert_eq!(lines.next(), None);
+}
+
+#[test]
+fn test_share_io() {
+    test_crate("origin-start", &["--bin=share-io"], &[], "", "", Some(227));
+}
+
+#[test]
+fn test_exit_vfork_child() {
+    test_crate(
+        "origin-start",
+        &["--bin=exit-vfork-child", "--features=origin/fini-array"],
+        &[],
+        "",
+        "",
+        Some(228),
+    );
+}
+
+#[test]
+fn test_stdio() {
+    test_crate(
+        "origin-start",
+        &["--bin=stdio"],
+        &[],
+        "Hello, stdout!\n",
+        "Hello, stderr!\n",
+        Some(229),
+    );
+}
+
+#[test]
+fn test_is_secure() {
+    test_crate(
+        "origin-start",
+        &["--bin=is-secure", "--features=origin/param"],
+        &[],
+        "",
+        "",
+        Some(230),
+    );
+}
+
+#[test]
+fn test_tls_template() {
+    test_crate(
+        "origin-start",
+        &["--bin=tls-template", "--features=origin/thread"],
+        &[],
+        "",
+        "",
+        Some(231),
+    );
+}
+
+#[test]
+fn test_no_tls() {
+    test_crate(
+        "origin-start",
+        &["--bin=no-tls", "--features=origin/no-tls"],
+        &[],
+        "",
+        "",
+        Some(232),
+    );
+}
+
+#[test]
+fn test_main_calls_exit() {
+    test_crate(
+        "origin-start",
+        &["--bin=main-calls-exit", "--features=origin/fini-array"],
+        &[],
+        "at_exit\nfini\n",
+        "",
+        Some(5),
+    );
+}
+
+#[test]
+fn test_elf_notes() {
+    test_crate(
+        "origin-start",
+        &["--bin=elf-notes", "--features=origin/abi-tag"],
+        &[("RUSTFLAGS", "-C link-arg=-Wl,--build-id=sha1")],
+        "",
+        "",
+        Some(233),
+    );
+}
+
+#[test]
+fn test_with_handler() {
+    test_crate(
+        "origin-start",
+        &["--bin=with-handler"],
+        &[],
+        "",
+        "",
+        Some(234),
+    );
+}
+
+#[test]
+fn test_arc_thread() {
+    test_crate(
+        "origin-start",
+        &["--bin=arc-thread"],
+        &[],
+        "",
+        "",
+        Some(235),
+    );
+}
+
+#[test]
+fn test_at_exit_limit() {
+    test_crate(
+        "origin-start",
+        &["--bin=at-exit-limit"],
+        &[],
+        "5\n",
+        "",
+        Some(236),
+    );
+}
+
+#[test]
+#[ignore] // TODO: This test isn't handled well by qemu.
+fn test_at_exit_unbounded() {
+    let mut command = utils::run_test(
+        "test",
+        "run",
+        "origin-start",
+        &["--bin=at-exit-unbounded"],
+        &[],
+    );
+    let output = command.output().unwrap();
+    assert_eq!(
+        output.status.signal(),
+        Some(origin::signal::Signal::Ill as i32)
+    );
+    assert_eq!(
+        core::str::from_utf8(&output.stderr).unwrap(),
+        "origin: too many functions registered with `at_exit` while exiting; \
+         see `origin::program::set_max_exit_registrations`\n"
+    );
+}
+
+#[test]
+fn test_can_spawn() {
+    test_crate(
+        "origin-start",
+        &["--bin=can-spawn", "--features=origin/thread-stats"],
+        &[],
+        "",
+        "",
+        Some(237),
+    );
+}
+
+#[test]
+fn test_main_thread_stack() {
+    test_crate(
+        "origin-start",
+        &["--bin=main-thread-stack"],
+        &[],
+        "",
+        "",
+        Some(238),
+    );
+}
+
+#[test]
+fn test_tls_layout() {
+    test_crate(
+        "origin-start",
+        &["--bin=tls-layout", "--features=origin/thread"],
+        &[],
+        "",
+        "",
+        Some(239),
+    );
+}
+
+#[test]
+fn test_exit_silent() {
+    test_crate(
+        "origin-start",
+        &["--bin=exit-silent", "--features=origin/log"],
+        &[],
+        "",
+        "",
+        Some(240),
+    );
+}
+
+#[test]
+fn test_thread_bench() {
+    let mut command = utils::run_test("test", "run", "thread-bench", &["--", "10", "4"], &[]);
+    let output = command.output().unwrap();
+    assert!(output.status.success());
+
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let mut lines = stdout.lines();
+    assert_eq!(lines.next(), Some("threads: 10"));
+    assert!(lines.next().unwrap().starts_with("create+join latency: "));
+    assert!(lines
+        .next()
+        .unwrap()
+        .starts_with("throughput (batches of 4): "));
+    assert_eq!(lines.next(), None);
+}
+
+#[test]
+fn test_main_thread_tls_at_exit() {
+    test_crate(
+        "origin-start",
+        &[
+            "--bin=main-thread-tls-at-exit",
+            "--release",
+            "--features=origin/fini-array",
+        ],
+        &[],
+        "thread::at_exit\nprogram::at_exit\nfini\n",
+        "",
+        Some(241),
+    );
+}
+
+#[test]
+fn test_set_handler() {
+    test_crate(
+        "origin-start",
+        &["--bin=set-handler", "--features=origin/thread"],
+        &[],
+        "",
+        "",
+        Some(242),
+    );
+}
+
+#[test]
+fn test_buffered_stdout() {
+    test_crate(
+        "origin-start",
+        &["--bin=buffered-stdout"],
+        &[],
+        format!(
+            "flushed\nunbuffered\nunbuffered again\n012\n{}\nat_exit\n",
+            "x".repeat(5000)
+        )
+        .leak(),
+        "",
+        Some(243),
+    );
+}
+
+#[test]
+fn test_stack_committed_range() {
+    test_crate(
+        "origin-start",
+        &["--bin=stack-committed-range", "--features=origin/thread"],
+        &[],
+        "",
+        "",
+        Some(244),
+    );
+}
+
+#[test]
+#[ignore] // TODO: This test isn't handled well by qemu.
+fn test_alloc_error_handler() {
+    let mut command = utils::run_test(
+        "test",
+        "run",
+        "origin-start",
+        &[
+            "--bin=alloc-error-handler",
+            "--features=origin/origin-alloc-error-handler",
+        ],
+        &[],
+    );
+    let output = command.output().unwrap();
+    assert_eq!(
+        output.status.signal(),
+        Some(origin::signal::Signal::Ill as i32)
+    );
+    assert_eq!(
+        core::str::from_utf8(&output.stderr).unwrap(),
+        "origin: memory allocation of 70368744177664 bytes failed\n"
+    );
+}
+
+#[test]
+fn test_set_alloc_error_handler() {
+    test_crate(
+        "origin-start",
+        &[
+            "--bin=set-alloc-error-handler",
+            "--features=origin/origin-alloc-error-handler",
+        ],
+        &[],
+        "failed to allocate 70368744177664 bytes\n",
+        "",
+        Some(245),
+    );
+}
+
+#[test]
+fn test_min_stack_size() {
+    test_crate(
+        "origin-start",
+        &["--bin=min-stack-size", "--features=origin/thread"],
+        &[],
+        "",
+        "",
+        Some(246),
+    );
+}
+
+#[test]
+fn test_prctl_security() {
+    test_crate(
+        "origin-start",
+        &["--bin=prctl-security"],
+        &[],
+        "",
+        "",
+        Some(247),
+    );
+}
+
+#[test]
+fn test_seccomp() {
+    test_crate(
+        "origin-start",
+        &["--bin=seccomp", "--features=origin/seccomp,origin/thread"],
+        &[],
+        "",
+        "",
+        Some(248),
+    );
+}
+
+#[test]
+fn test_global_exit_hook() {
+    test_crate(
+        "origin-start",
+        &["--bin=global-exit-hook", "--features=origin/thread"],
+        &[],
+        "thread::at_exit\nhook\nthread::at_exit\nhook\nmain thread::at_exit\nhook\nprogram::at_exit\n",
+        "",
+        Some(249),
+    );
+}
+
+#[test]
+fn test_stderr_lock() {
+    test_crate(
+        "origin-start",
+        &["--bin=stderr-lock", "--features=origin/thread"],
+        &[],
+        "",
+        "main: first\nmain: second\nthread\n",
+        Some(250),
+    );
+}
+
+#[test]
+#[ignore] // TODO: This test isn't handled well by qemu.
+fn test_thread_stack_guard() {
+    let mut command = utils::run_test(
+        "test",
+        "run",
+        "origin-start",
+        &["--bin=thread-stack-guard", "--features=origin/thread"],
+        &[],
+    );
+    let output = command.output().unwrap();
+    assert_eq!(
+        output.status.signal(),
+        Some(origin::signal::Signal::Segv as i32)
+    );
+}
+
+#[test]
+fn test_at_immediate_exit() {
+    test_crate(
+        "origin-start",
+        &[
+            "--bin=at-immediate-exit",
+            "--features=origin/program-at-immediate-exit",
+        ],
+        &[],
+        "second\nfirst\n",
+        "",
+        Some(251),
+    );
+}
+
+#[test]
+fn test_tls_addr_of() {
+    test_crate(
+        "origin-start",
+        &["--bin=tls-addr-of", "--release", "--features=origin/thread"],
+        &[],
+        "",
+        "",
+        Some(253),
+    );
+}
+
+#[test]
+fn test_rlimit() {
+    test_crate("origin-start", &["--bin=rlimit"], &[], "", "", Some(254));
+}
+
+#[test]
+fn test_reset_after_fork() {
+    test_crate(
+        "origin-start",
+        &[
+            "--bin=reset-after-fork",
+            "--features=origin/thread-stats,origin/thread-registry",
+        ],
+        &[],
+        "",
+        "",
+        Some(255),
+    );
+}
+
+#[test]
+fn test_in_handler() {
+    test_crate(
+        "origin-start",
+        &["--bin=in-handler"],
+        &[],
+        "",
+        "",
+        Some(199),
+    );
+}
+
+#[test]
+#[ignore] // TODO: This test isn't handled well by qemu.
+fn test_alloc_in_handler() {
+    let mut command = utils::run_test(
+        "test",
+        "run",
+        "origin-start",
+        &["--bin=alloc-in-handler"],
+        &[],
+    );
+    let output = command.output().unwrap();
+    assert_eq!(
+        output.status.signal(),
+        Some(origin::signal::Signal::Ill as i32)
+    );
+}
+
+#[test]
+fn test_timestamp_counter() {
+    test_crate(
+        "origin-start",
+        &[
+            "--bin=timestamp-counter",
+            "--features=origin/timestamp-counter",
+        ],
+        &[],
+        "",
+        "",
+        Some(197),
+    );
+}
+
+#[test]
+fn test_preinit_array() {
+    test_crate(
+        "origin-start",
+        &[
+            "--bin=preinit-array",
+            "--features=origin/preinit-array,origin/init-array",
+        ],
+        &[],
+        "",
+        "",
+        Some(196),
+    );
+}
+
+#[test]
+fn test_preinit_array_crt_static() {
+    test_crate(
+        "origin-start",
+        &[
+            "--bin=preinit-array",
+            "--features=origin/preinit-array,origin/init-array,origin/experimental-relocate",
+        ],
+        &[("RUSTFLAGS", "-C target-feature=+crt-static")],
+        "",
+        "",
+        Some(196),
+    );
+}
+
+#[test]
+fn test_main_macro() {
+    test_crate(
+        "origin-start",
+        &["--bin=main-macro"],
+        &[],
+        "",
+        "",
+        Some(195),
+    );
+}
+
+#[test]
+fn test_stack_usage() {
+    test_crate(
+        "origin-start",
+        &["--bin=stack-usage"],
+        &[],
+        "",
+        "",
+        Some(194),
+    );
+}
+
+#[test]
+fn test_exit_code() {
+    test_crate(
+        "origin-start",
+        &["--bin=exit-code"],
+        &[],
+        "",
+        "Error: \"oops\"\n",
+        Some(1),
+    );
+}
+
+#[test]
+fn test_send_to_thread() {
+    test_crate(
+        "origin-start",
+        &["--bin=send-to-thread"],
+        &[],
+        "",
+        "",
+        Some(193),
+    );
+}
+
+#[test]
+fn test_errno() {
+    test_crate(
+        "origin-start",
+        &["--bin=errno", "--features=origin/unstable-errno"],
+        &[],
+        "",
+        "",
+        Some(192),
+    );
+}
+
+#[test]
+fn test_ignore_sigpipe() {
+    test_crate(
+        "origin-start",
+        &["--bin=ignore-sigpipe", "--features=origin/ignore-sigpipe"],
+        &[],
+        "",
+        "",
+        Some(191),
+    );
+}
+
+#[test]
+#[ignore] // TODO: This test isn't handled well by qemu.
+fn test_create_in_handler() {
+    let mut command = utils::run_test(
+        "test",
+        "run",
+        "origin-start",
+        &["--bin=create-in-handler", "--features=origin/thread"],
+        &[],
+    );
+    let output = command.output().unwrap();
+    assert_eq!(
+        output.status.signal(),
+        Some(origin::signal::Signal::Ill as i32)
+    );
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains("create-in-handler.rs:14:"), "{}", stderr);
+}
+
+#[test]
+fn test_log_flush_hook() {
+    test_crate(
+        "origin-start",
+        &["--bin=log-flush-hook", "--features=origin/log"],
+        &[],
+        "at_exit\nflushed\n",
+        "",
+        Some(189),
+    );
+}
+
+#[test]
+fn test_mcontext() {
+    test_crate("origin-start", &["--bin=mcontext"], &[], "", "", Some(188));
+}
+
+#[test]
+fn test_create_detailed() {
+    test_crate(
+        "origin-start",
+        &["--bin=create-detailed"],
+        &[],
+        "",
+        "",
+        Some(187),
+    );
+}
+
+#[test]
+fn test_personality() {
+    test_crate(
+        "origin-start",
+        &["--bin=personality"],
+        &[],
+        "",
+        "",
+        Some(186),
+    );
+}
+
+#[test]
+fn test_vdso() {
+    test_crate(
+        "origin-start",
+        &["--bin=vdso", "--features=origin/vdso"],
+        &[],
+        "",
+        "",
+        Some(186),
+    );
+}
+
+#[test]
+fn test_detached_at_exit() {
+    test_crate(
+        "origin-start",
+        &["--bin=detached-at-exit"],
+        &[],
+        "",
+        "",
+        Some(185),
+    );
+}
+
+#[test]
+fn test_drop_privileges() {
+    test_crate(
+        "origin-start",
+        &["--bin=drop-privileges"],
+        &[],
+        "",
+        "",
+        Some(184),
+    );
+}
+
+#[test]
+fn test_set_return_value() {
+    test_crate(
+        "origin-start",
+        &["--bin=set-return-value"],
+        &[],
+        "",
+        "",
+        Some(183),
+    );
+}
+
+#[test]
+fn test_no_alloc_fini_array() {
+    test_crate(
+        "origin-start-no-alloc",
+        &["--bin=fini-array"],
+        &[],
+        "init\nmain\nfini\n",
+        "",
+        Some(182),
+    );
+}
+
+#[test]
+fn test_vm_worker() {
+    test_crate("origin-start", &["--bin=vm-worker"], &[], "", "", Some(181));
+}
+
+#[test]
+fn test_exec_path() {
+    test_crate("origin-start", &["--bin=exec-path"], &[], "", "", Some(180));
+}
+
+#[test]
+fn test_shutdown_handler() {
+    test_crate(
+        "origin-start",
+        &["--bin=shutdown-handler"],
+        &[],
+        "",
+        "",
+        Some(179),
+    );
+}
+
+#[test]
+fn test_numa_node() {
+    test_crate("origin-start", &["--bin=numa-node"], &[], "", "", Some(178));
+}
+
+#[test]
+fn test_timer_slack() {
+    test_crate(
+        "origin-start",
+        &["--bin=timer-slack"],
+        &[],
+        "",
+        "",
+        Some(177),
+    );
+}
+
+#[test]
+fn test_signal_default_action() {
+    test_crate(
+        "origin-start",
+        &["--bin=signal-default-action"],
+        &[],
+        "",
+        "",
+        Some(209),
+    );
+}
+
+#[test]
+fn test_large_tls() {
+    test_crate("origin-start", &["--bin=large-tls"], &[], "", "", Some(210));
+}
+
+#[test]
+fn test_vfork_exec() {
+    test_crate(
+        "origin-start",
+        &["--bin=vfork-exec"],
+        &[],
+        "",
+        "",
+        Some(204),
+    );
+}
+
 #[test]
 fn test_program_dtors_adding_dtors() {
     test_crate(
@@ -114,6 +1121,18 @@ fn test_thread_dtors_adding_dtors() {
     );
 }
 
+#[test]
+fn test_thread_dtors_order() {
+    test_crate(
+        "origin-start",
+        &["--bin=thread-dtors-order"],
+        &[],
+        "",
+        "",
+        Some(214),
+    );
+}
+
 #[test]
 fn test_main_thread_dtors_adding_dtors() {
     test_crate(
@@ -150,3 +1169,18 @@ fn test_abort_via_raise() {
         Some(origin::signal::Signal::Abort as i32)
     );
 }
+
+#[test]
+fn test_terminate_by_signal() {
+    let mut command = utils::run_test(
+        "test",
+        "run",
+        "origin-start",
+        &["--bin=terminate-by-signal"],
+        &[],
+    );
+    assert_eq!(
+        command.output().unwrap().status.signal(),
+        Some(origin::signal::Signal::Term as i32)
+    );
+}